@@ -1,10 +1,20 @@
 extern crate chrono;
 extern crate clap;
 
+use crate::batch::add_events;
 use crate::configure::Configuration;
+use crate::hook::notify;
 use crate::log::{Item, LogController};
-use crate::util::{check_for_ongoing_event, describe, some_nws};
+use crate::util::{
+    check_new_tags, check_vacation, close_at_argument, close_forgotten_event, describe, fatal,
+    interactive, new_tag_arguments, parse_duration, prompt, require_tags,
+    shorten_vacation_argument, some_nws, valid_duration, warn, yes_or_no,
+};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::collections::BTreeSet;
+
+// how many of the tags most recently used are offered when prompting for tags
+const RECENT_TAGS: usize = 8;
 
 fn after_help() -> &'static str {
     "\
@@ -22,6 +32,69 @@ Tags facilitate categorizing and searching for events. When you use the summary
 subcommand to view the events in a particular period the time is shown aggregated \
 by tag as well.
 
+If the last event in the log was begun on a previous day and never ended -- you went home \
+without typing `job done` -- the add subcommand asks when it ended and adds a DONE line at \
+that time before adding the new event. Leave the answer blank to leave the event ongoing. \
+To give the time without being asked, use --close-at:
+
+  job add --close-at 5:30pm checking email
+
+To catch misspelled tags, set `job configure --warn-new-tags true`. You are then warned of any \
+tag found nowhere in the log, along with the known tags like it. With --strict-tags the event \
+is not added at all. Give a tag you mean to be new with --new-tag rather than --tag:
+
+  job add --strict-tags --new-tag retro --tag meetings sprint retrospective
+
+If you never want an untagged event, set `job configure --require-tags true`. The add \
+subcommand then asks for tags when you give none, or, if it is not run in a terminal, leaves \
+the log unchanged.
+
+To record how long you expect the task to take, give an estimate. The summary and statistics \
+subcommands compare the time spent on events with estimates to what was estimated:
+
+  job add --estimate 2h --tag auth fixing the login bug
+
+The estimate is kept in the log line, after the timestamp, so the Perl client cannot read it; \
+if you have set `job configure --perl-compatible true` estimates are refused.
+
+If an ordinary vacation record covers today you are warned that you are working on a vacation \
+day, since a summary would count both the vacation and the work. With --shorten-vacation the \
+record is made flex for today, so the vacation only fills out the hours you do not work:
+
+  job add --shorten-vacation fixing the outage
+
+If you give no description, or --interactive, the add subcommand asks for the description and \
+tags, offering the tags you have used recently, and shows the event for you to confirm before \
+adding it. This spares you typing long lists of arguments, on a phone, say. A tag ending in '*' \
+is completed to the known tag beginning with what precedes it:
+
+  > job add
+  description: fixing the login bug
+  recent tags: auth, meetings, plugh, review
+  tags (separate tags with commas): au*, bugs
+  add 'fixing the login bug' (auth, bugs)? [Yn]
+
+Anything given on the command line is offered as the default answer.
+
+To add many events at once -- a week's worth kept on paper while you were offline, say -- give \
+--stdin and write them to the standard input, one per line, the start, end, tags, and \
+description separated by tabs and the tags separated by commas:
+
+  > job add --stdin < week.tsv
+
+  2024-03-04 09:00\t2024-03-04 10:30\temail\treading mail
+  2024-03-04 10:30\t\tplugh, review\treviewing the plugh PR
+  2024-03-04 12:30\t2024-03-04 13:00\t\tlunch
+
+An event without an end lasts until the next begins. A line may instead be a JSON object with \
+start, end, tags, and description properties, such as the summary subcommand writes with \
+--json. Blank lines and lines beginning with # are ignored. Times may be any expression the \
+parse-time subcommand understands. The events are sorted by start and interleaved with the \
+events already in the log, and any tags given with --tag are added to each. As with the batch \
+subcommand, nothing is written unless every event is valid and none overlaps another, or an \
+event in the log, unless you give --force. The log is first copied to log.pre-add.bak in the \
+job log directory, and webhooks are not notified.
+
 All prefixes of 'add' (so just 'a' and 'ad') are aliases for the add subcommand."
 }
 
@@ -52,6 +125,43 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .long_help("Copy to this event all the tags of the immediately preceding event. These tags will be in addition to any tags added via --tag.")
                 .display_order(2)
             )
+            .arg(close_at_argument().display_order(3))
+            .arg(
+                Arg::with_name("estimate")
+                .long("estimate")
+                .help("how long you expect the task to take")
+                .long_help("How long you expect the task to take, such as '2h', '90 min', or '1h 15m'. The estimate is recorded in the log line.")
+                .value_name("duration")
+                .validator(valid_duration)
+                .display_order(4)
+            )
+            .arg(shorten_vacation_argument().display_order(5))
+            .arg(
+                Arg::with_name("interactive")
+                .short("i")
+                .long("interactive")
+                .help("asks for the description and tags")
+                .long_help("Asks for the description and tags of the event, completing tags, and for confirmation before adding it. \
+                This is what happens when no description is given.")
+                .display_order(6)
+            )
+            .arg(
+                Arg::with_name("stdin")
+                .long("stdin")
+                .help("adds the events read from the standard input")
+                .long_help("Reads events from the standard input, one per line, the start, end, tags, and description \
+                separated by tabs, or as JSON objects, and adds them all to the log in order of time.")
+                .conflicts_with_all(&["description", "interactive", "copy-tags", "close-at", "estimate", "shorten-vacation"])
+                .display_order(7)
+            )
+            .arg(
+                Arg::with_name("force")
+                .long("force")
+                .help("adds the events read with --stdin despite conflicts")
+                .long_help("Adds the events read with --stdin even if they overlap events in the log.")
+                .requires("stdin")
+                .display_order(8)
+            )
             .setting(AppSettings::TrailingVarArg)
             .arg(
                 Arg::with_name("description")
@@ -60,9 +170,9 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                         "All the <description> arguments are concatenated to produce a description of the event.",
                     )
                     .value_name("description")
-                    .required(true)
                     .multiple(true)
             )
+            .args(&new_tag_arguments())
             .display_order(display_order)
     )
 }
@@ -70,17 +180,17 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
     let mut reader = LogController::new(None, &conf).expect("could not read log");
-    check_for_ongoing_event(&mut reader, &conf);
-    let description = matches
-        .values_of("description")
-        .unwrap()
-        .collect::<Vec<&str>>()
-        .join(" ");
-    let mut tags: Vec<String> = if let Some(values) = matches.values_of("tag") {
-        values.map(|s| s.to_owned()).collect()
-    } else {
-        vec![]
-    };
+    let mut tags: Vec<String> = matches
+        .values_of("tag")
+        .into_iter()
+        .chain(matches.values_of("new-tag"))
+        .flatten()
+        .map(|s| s.to_owned())
+        .collect();
+    if matches.is_present("stdin") {
+        add_events(&conf.normalize_tags(&tags), matches, &conf);
+        return;
+    }
     if matches.is_present("copy-tags") {
         if let Some(event) = reader.last_event() {
             for t in event.tags {
@@ -88,6 +198,174 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
-    let (event, offset) = reader.append_event(description, tags);
-    describe("starting", None, Item::Event(event, offset), &conf);
+    let mut description = matches
+        .values_of("description")
+        .map(|values| values.collect::<Vec<&str>>().join(" "))
+        .unwrap_or_default();
+    if description.is_empty() || matches.is_present("interactive") {
+        if !interactive() {
+            fatal(
+                "the log is unchanged; give a description or run the add subcommand in a terminal",
+                &conf,
+            );
+        }
+        match ask_for_event(&description, &tags, &mut reader, &conf) {
+            Some((d, t)) => {
+                description = d;
+                tags = t;
+            }
+            None => {
+                warn("no change to log", &conf);
+                return;
+            }
+        }
+    }
+    tags = conf.normalize_tags(&tags);
+    require_tags(&mut tags, &conf);
+    check_new_tags(&tags, &reader, matches, &conf);
+    close_forgotten_event(&mut reader, matches, &conf);
+    let estimate = matches.value_of("estimate").and_then(parse_duration);
+    if estimate.is_some() && conf.perl_compatible {
+        fatal(
+            "the Perl client cannot read estimates; see `job configure --perl-compatible`",
+            &conf,
+        );
+    }
+    let (event, offset) = reader.append_estimated_event(description, tags, estimate);
+    check_vacation(matches, &conf);
+    let json = event.to_json(&event.start, &conf);
+    notify("add", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else {
+        describe("starting", None, Item::Event(event, offset), &conf);
+    }
+}
+
+// asks for the description and tags of the event, offering those given as defaults, and for
+// confirmation of the event; None if it is not confirmed
+fn ask_for_event(
+    description: &str,
+    tags: &[String],
+    reader: &mut LogController,
+    conf: &Configuration,
+) -> Option<(String, Vec<String>)> {
+    let mut description = description.to_owned();
+    loop {
+        description = prompt("description", &description);
+        if !description.is_empty() {
+            break;
+        }
+    }
+    let known = reader.tag_inventory();
+    let recent = recent_tags(reader);
+    if !recent.is_empty() {
+        println!("recent tags: {}", recent.join(", "));
+    }
+    let tags = loop {
+        let answer = prompt("tags (separate tags with commas)", &tags.join(", "));
+        match complete_tags(&answer, &known) {
+            Ok(tags) => break conf.normalize_tags(&tags),
+            Err(e) => println!("{}", e),
+        }
+    };
+    let confirmed = yes_or_no(format!(
+        "add '{}'{}?",
+        description,
+        if tags.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", tags.join(", "))
+        }
+    ));
+    if confirmed {
+        Some((description, tags))
+    } else {
+        None
+    }
+}
+
+// the distinct tags of the most recent events, the most recent first
+fn recent_tags(reader: &mut LogController) -> Vec<String> {
+    let mut recent: Vec<String> = vec![];
+    for event in reader.events_from_the_end() {
+        for tag in event.tags {
+            if !recent.contains(&tag) {
+                recent.push(tag);
+            }
+        }
+        if recent.len() >= RECENT_TAGS {
+            break;
+        }
+    }
+    recent.truncate(RECENT_TAGS);
+    recent.sort();
+    recent
+}
+
+// the comma-separated tags, those ending in '*' completed to the only known tag beginning with
+// what precedes the '*'
+fn complete_tags(answer: &str, known: &BTreeSet<String>) -> Result<Vec<String>, String> {
+    let mut tags = vec![];
+    for tag in answer
+        .split(',')
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+    {
+        let prefix = match tag.strip_suffix('*') {
+            Some(prefix) => prefix,
+            None => {
+                tags.push(tag.to_owned());
+                continue;
+            }
+        };
+        let candidates = known
+            .iter()
+            .filter(|k| k.starts_with(prefix))
+            .collect::<Vec<_>>();
+        match candidates.len() {
+            0 => return Err(format!("no known tag begins with '{}'", prefix)),
+            1 => tags.push(candidates[0].clone()),
+            _ => {
+                return Err(format!(
+                    "'{}' could be {}",
+                    tag,
+                    candidates
+                        .iter()
+                        .map(|c| c.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }
+    }
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_tags() {
+        let known = ["plugh", "plover", "review"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            vec!["review", "bugs", "plugh"],
+            complete_tags(" re*, bugs,, plu* ", &known).unwrap()
+        );
+        assert_eq!(
+            vec!["pl"],
+            complete_tags("pl", &known).unwrap(),
+            "only tags ending in * are completed"
+        );
+        assert_eq!(
+            "'pl*' could be plover, plugh",
+            complete_tags("pl*", &known).unwrap_err()
+        );
+        assert!(complete_tags("xyzzy*", &known).is_err());
+        assert!(complete_tags("", &known).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,150 @@
+extern crate clap;
+
+use crate::backup;
+use crate::cancel::ongoing_event;
+use crate::configure::Configuration;
+use crate::hook::notify;
+use crate::log::{parse_line, Item};
+use crate::merge::tmp_path;
+use crate::util::{describe, fatal, info, log_path, warn};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::{read_to_string, rename, write};
+
+fn after_help() -> &'static str {
+    "\
+As you work on a task it often grows. Rather than begin a new event, you can add to the \
+description of the one under way:
+
+  > job add fixing the login bug
+  > job append and also the tests
+  continuing fixing the login bug and also the tests (no tags)
+
+Only the description of the ongoing event is changed; its start time, tags, and the rest of \
+the log are left as they were. The text is joined to the description with a space unless you \
+give some other --separator:
+
+  > job append --separator '; ' reviewed by Jane
+
+If no event is ongoing, the log is not changed. Before the log is changed it is backed up as \
+by the backup subcommand.
+
+'ap', 'app', 'appe', and 'appen' are aliases of the subcommand. 'a' and 'ad' are aliases of \
+add."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("append")
+            .aliases(&["ap", "app", "appe", "appen"])
+            .about("Adds to the description of the ongoing task")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("separator")
+                    .long("separator")
+                    .short("s")
+                    .help("What to put between the description and the text")
+                    .value_name("text")
+                    .default_value(" "),
+            )
+            .arg(
+                Arg::with_name("text")
+                    .help("what to add")
+                    .long_help(
+                        "All the <text> arguments are concatenated to produce the text added to the description.",
+                    )
+                    .value_name("text")
+                    .required(true)
+                    .multiple(true),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let text = matches
+        .values_of("text")
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let separator = matches.value_of("separator").unwrap();
+    if text.contains(['\n', '\r']) || separator.contains(['\n', '\r']) {
+        fatal("a description cannot contain a line break", &conf);
+    }
+    let path = log_path(conf.directory());
+    let log = read_to_string(&path).expect("could not read log");
+    let mut lines = log.lines().map(|l| l.to_owned()).collect::<Vec<_>>();
+    let offset = match ongoing_event(&lines.iter().map(|l| l.as_str()).collect::<Vec<_>>()) {
+        Some((_, offset)) => offset,
+        None => {
+            warn("there is no ongoing event to append to", &conf);
+            return;
+        }
+    };
+    lines[offset] = appended(&lines[offset], &text, separator);
+    let event = match parse_line(&lines[offset], offset) {
+        Item::Event(e, _) => e,
+        _ => unreachable!(),
+    };
+    if let Some(backup) = backup::create(&conf) {
+        info(
+            format!("backed up the log to {}", backup.to_str().unwrap()),
+            &conf,
+        );
+    }
+    let mut log = lines.join("\n");
+    log.push('\n');
+    let tmp = tmp_path(&conf);
+    write(&tmp, log).expect("could not write temporary log");
+    rename(&tmp, &path).expect("failed to move the new log into place");
+    let json = event.to_json(&event.start, &conf);
+    notify("append", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else {
+        describe("continuing", None, Item::Event(event, offset), &conf);
+    }
+}
+
+// the event line with the text added to the end of its description, which is the end of the line
+fn appended(line: &str, text: &str, separator: &str) -> String {
+    match parse_line(line, 0) {
+        Item::Event(e, _) if e.description.is_empty() => format!("{}{}", line, text),
+        Item::Event(_, _) => format!("{}{}{}", line, separator, text),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appended() {
+        let line = appended(
+            "2020  1  2  9  0  0 +0100 ~1h @bob:foo:fixing",
+            "tests",
+            " ",
+        );
+        assert_eq!("2020  1  2  9  0  0 +0100 ~1h @bob:foo:fixing tests", line);
+        match parse_line(&line, 0) {
+            Item::Event(e, _) => {
+                assert_eq!("fixing tests", e.description);
+                assert_eq!(vec!["foo"], e.tags, "tags survive");
+                assert_eq!(Some("bob"), e.author.as_deref(), "author survives");
+                assert_eq!(Some(60 * 60), e.estimate, "estimate survives");
+            }
+            _ => assert!(false, "failed to parse appended line"),
+        }
+        assert_eq!(
+            "2020  1  2  9  0  0:foo:fixing; and tests",
+            appended("2020  1  2  9  0  0:foo:fixing", "and tests", "; ")
+        );
+        assert_eq!(
+            "2020  1  2  9  0  0:foo:tests",
+            appended("2020  1  2  9  0  0:foo:", "tests", " "),
+            "no separator after an empty description"
+        );
+    }
+}
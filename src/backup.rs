@@ -0,0 +1,316 @@
+extern crate chrono;
+extern crate clap;
+extern crate colonnade;
+extern crate serde_json;
+
+use crate::configure::Configuration;
+use crate::util::{base_dir, fatal, info, success, warn, Style};
+use chrono::{Duration, Local, NaiveDateTime};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+use serde_json::json;
+use std::fs::{copy, create_dir_all, read_dir, remove_dir_all};
+use std::path::{Path, PathBuf};
+
+// the files in the job log directory a backup preserves
+pub(crate) const FILES: [&str; 3] = ["log", "vacation", "config.ini"];
+// the format of the names of backup directories; the milliseconds make it easy to keep
+// backups made in quick succession distinct
+const FORMAT: &str = "%Y%m%dT%H%M%S%.3f";
+
+fn after_help() -> &'static str {
+    "\
+The backup subcommand keeps copies of the log, vacation file, and configuration in the \
+backups directory of the job log directory, each backup in a subdirectory named for the \
+time it was made. By itself it makes a new backup:
+
+  > job backup
+  > job backup --list
+  > job backup --restore 2
+
+Backups are numbered from the most recent, so --restore 1 restores the last backup made. \
+Before restoring a backup the subcommand backs up the current files, so a restoration can \
+itself be undone.
+
+The edit subcommand also makes a backup before it opens the editor. Once there are more \
+backups than the configured number -- 10 by default; see `job configure --backups` -- the \
+oldest are deleted.
+
+All prefixes of 'backup' after 'ba' -- 'bac', 'back', and 'backu' -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("backup")
+            .aliases(&["bac", "back", "backu"])
+            .about("Makes, lists, and restores backups of the job log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("create")
+                    .long("create")
+                    .short("c")
+                    .help("Makes a new backup")
+                    .long_help("Copies the log, vacation file, and configuration into a new backup. This is what happens if you give no option.")
+                    .conflicts_with_all(&["list", "restore"])
+                    .display_order(0),
+            )
+            .arg(
+                Arg::with_name("list")
+                    .long("list")
+                    .short("l")
+                    .help("Lists the backups, most recent first")
+                    .conflicts_with("restore")
+                    .display_order(1),
+            )
+            .arg(
+                Arg::with_name("restore")
+                    .long("restore")
+                    .short("r")
+                    .help("Restores the backup with this number")
+                    .long_help("Replaces the log, vacation file, and configuration with those in the backup with \
+                    the given number, as shown by --list. The current files are backed up first.")
+                    .value_name("n")
+                    .validator(|v| match v.parse::<usize>() {
+                        Ok(n) if n > 0 => Ok(()),
+                        _ => Err(format!("backups are numbered from 1; '{}' is not such a number", v)),
+                    })
+                    .display_order(2),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    if matches.is_present("list") {
+        list(&conf, matches);
+    } else if let Some(n) = matches.value_of("restore") {
+        restore(n.parse().unwrap(), &conf);
+    } else if let Some(backup) = create(&conf) {
+        success(
+            format!("backed up the job log to {}", backup.to_str().unwrap()),
+            &conf,
+        );
+    } else {
+        warn(
+            "no backup made because the configured number of backups is 0",
+            &conf,
+        );
+    }
+}
+
+fn list(conf: &Configuration, matches: &ArgMatches) {
+    let backups = backups(conf);
+    if backups.is_empty() {
+        warn("no backups", conf);
+        return;
+    }
+    if matches.is_present("json") {
+        for (i, (time, path)) in backups.iter().enumerate() {
+            println!(
+                "{}",
+                json!({
+                    "number": i + 1,
+                    "time": time.format("%F %T").to_string(),
+                    "path": path.to_str().unwrap(),
+                    "files": files(path),
+                })
+            );
+        }
+        return;
+    }
+    let style = Style::new(conf);
+    let mut table =
+        Colonnade::new(3, conf.width()).expect("could not create table to display backups");
+    table.columns[0].alignment(Alignment::Right);
+    let data = backups
+        .iter()
+        .enumerate()
+        .map(|(i, (time, path))| {
+            vec![
+                (i + 1).to_string(),
+                time.format("%F %T").to_string(),
+                files(path).join(", "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    for (i, line) in table
+        .tabulate(&data)
+        .expect("could not lay out backups")
+        .iter()
+        .enumerate()
+    {
+        println!(
+            "{}",
+            if i % 2 == 0 {
+                style.paint("odd", line)
+            } else {
+                style.paint("even", line)
+            }
+        );
+    }
+}
+
+fn restore(n: usize, conf: &Configuration) {
+    let backups = backups(conf);
+    if n > backups.len() {
+        fatal(
+            format!(
+                "there is no backup {}; there {} {}",
+                n,
+                if backups.len() == 1 { "is" } else { "are" },
+                backups.len()
+            ),
+            conf,
+        );
+    }
+    let (time, source) = &backups[n - 1];
+    // we prune only after restoring lest the backup being restored be the one pruned
+    if let Some(backup) = snapshot(conf) {
+        info(
+            format!(
+                "backed up the current files to {}",
+                backup.to_str().unwrap()
+            ),
+            conf,
+        );
+    }
+    let dir = base_dir(conf.directory());
+    for file in files(source) {
+        let mut from = source.clone();
+        from.push(file);
+        let mut to = dir.clone();
+        to.push(file);
+        info(
+            format!(
+                "copying {} to {}",
+                from.to_str().unwrap(),
+                to.to_str().unwrap()
+            ),
+            conf,
+        );
+        copy(&from, &to).expect("could not restore file from backup");
+    }
+    prune(conf);
+    success(
+        format!("restored the backup made {}", time.format("%F %T")),
+        conf,
+    );
+}
+
+// backs up the log, vacation file, and configuration, returning the backup's directory;
+// if no backups are to be kept, does nothing
+pub fn create(conf: &Configuration) -> Option<PathBuf> {
+    let backup = snapshot(conf);
+    prune(conf);
+    backup
+}
+
+// makes a backup without deleting old ones; follow this with either prune or discard
+pub fn snapshot(conf: &Configuration) -> Option<PathBuf> {
+    if conf.backups == 0 {
+        return None;
+    }
+    let mut time = Local::now().naive_local();
+    let mut dir = backup_dir(conf);
+    dir.push(time.format(FORMAT).to_string());
+    while dir.as_path().exists() {
+        time += Duration::milliseconds(1);
+        dir.set_file_name(time.format(FORMAT).to_string());
+    }
+    create_dir_all(&dir).expect("could not create backup directory");
+    let base = base_dir(conf.directory());
+    for file in FILES.iter() {
+        let mut from = base.clone();
+        from.push(file);
+        if from.as_path().exists() {
+            let mut to = dir.clone();
+            to.push(file);
+            copy(&from, &to).expect("could not back up file");
+        }
+    }
+    info(format!("made backup {}", dir.to_str().unwrap()), conf);
+    Some(dir)
+}
+
+// deletes all but the most recent backups
+pub fn prune(conf: &Configuration) {
+    for (_, old) in backups(conf).iter().skip(conf.backups) {
+        info(
+            format!("deleting old backup {}", old.to_str().unwrap()),
+            conf,
+        );
+        remove_dir_all(old).expect("could not delete old backup");
+    }
+}
+
+// deletes a backup made needlessly
+pub fn discard(backup: PathBuf, conf: &Configuration) {
+    info(
+        format!("deleting backup {}", backup.to_str().unwrap()),
+        conf,
+    );
+    remove_dir_all(backup).expect("could not delete backup");
+}
+
+fn backup_dir(conf: &Configuration) -> PathBuf {
+    let mut dir = base_dir(conf.directory());
+    dir.push("backups");
+    dir
+}
+
+// the backups with the times they were made, most recent first
+fn backups(conf: &Configuration) -> Vec<(NaiveDateTime, PathBuf)> {
+    let mut backups = vec![];
+    if let Ok(entries) = read_dir(backup_dir(conf)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let time = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| NaiveDateTime::parse_from_str(n, FORMAT).ok());
+            if let Some(time) = time {
+                backups.push((time, path));
+            }
+        }
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse(b.0));
+    backups
+}
+
+// the files a backup holds
+fn files(backup: &Path) -> Vec<&'static str> {
+    FILES
+        .iter()
+        .filter(|f| backup.join(f).exists())
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+    use std::fs::write;
+
+    #[test]
+    fn test_create_and_prune() {
+        let dir = TestDirectory::new("test_create_and_prune");
+        write(dir.path.join("log"), "# job log\n").expect("could not write log");
+        let mut conf = dir.configuration();
+        conf.backups = 2;
+        let first = create(&conf).unwrap();
+        assert_eq!(vec!["log", "config.ini"], files(&first));
+        create(&conf);
+        create(&conf);
+        let remaining = backups(&conf);
+        assert_eq!(2, remaining.len());
+        assert!(remaining.iter().all(|(_, p)| p != &first), "oldest pruned");
+        conf.backups = 0;
+        assert!(create(&conf).is_none());
+    }
+}
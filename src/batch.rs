@@ -0,0 +1,758 @@
+extern crate chrono;
+extern crate clap;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::log::{check_line_parts, log_line, parse_line, timestamp, Done, Event, Item, Note};
+use crate::merge::{
+    conflicts, drop_orphaned_dones, merge, read_blocks, replace_log, tmp_path, Block, LogState,
+    Source,
+};
+use crate::util::{clap_error_message, current_time, fatal, log_path, some_nws, success, warn};
+use chrono::{NaiveDateTime, Timelike};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::Value;
+use std::io::{stdin, BufRead};
+use two_timer::{parsable, parse};
+
+fn after_help() -> &'static str {
+    "\
+The batch subcommand reads operations from the standard input, one per line, and applies them \
+to the log all at once. This is much faster than invoking job once per operation when you are \
+importing events from another time tracker or correcting the log with a script:
+
+  > job batch <<END
+  add --at '2024-03-01 09:00' --tag email reading mail
+  add --at '2024-03-01 09:30' -t plugh -t review 'reviewing the plugh PR'
+  note --at '2024-03-01 10:15' -t plugh the PR needs tests
+  done --at '2024-03-01 12:00'
+  END
+
+An operation is either a command -- add, note, or done, with the options shown above, where \
+--at gives the time of the line and defaults to the present -- or a JSON object:
+
+  {\"op\": \"add\", \"time\": \"2024-03-01 09:00\", \"tags\": [\"email\"], \"description\": \"reading mail\"}
+
+Arguments containing spaces or quotation marks must be quoted as they would be in the shell. \
+Blank lines and lines beginning with # are ignored.
+
+The operations must be in chronological order, but they need not follow the last line in the \
+log: their lines will be interleaved with those already in it by time. As with the merge \
+subcommand, if an event in the batch begins while an event in the log is ongoing, or vice versa, \
+the batch is rejected unless you give it --force.
+
+Nothing is written unless every operation is valid. The new log is validated before it replaces \
+the old one, and the old one is first copied to log.pre-batch.bak in the job log directory. \
+If anything else writes to the log while the batch is applied, the batch is abandoned and the \
+log left as it is, so nothing is lost. A DONE which would end no event is dropped, with a \
+warning. Unlike the add, done, and note subcommands, batch does not notify webhooks.
+
+All prefixes of 'batch' after 'b' -- 'ba', 'bat', and 'batc' -- are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("batch")
+            .aliases(&["ba", "bat", "batc"])
+            .about("Applies operations read from the standard input to the log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("force")
+                .long("force")
+                .short("f")
+                .help("Applies the batch despite conflicts")
+                .long_help("Applies the batch even if its events overlap ongoing events in the log or vice versa.")
+                .conflicts_with("dry-run")
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Validates the operations without applying them")
+                .display_order(1)
+            )
+            .display_order(display_order),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Add,
+    Note,
+    Done,
+}
+
+#[derive(Debug)]
+struct Operation {
+    kind: Kind,
+    time: Option<NaiveDateTime>,
+    description: String,
+    tags: Vec<String>,
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let mut operations = vec![];
+    let mut errors = vec![];
+    for (i, line) in stdin().lock().lines().enumerate() {
+        let line = line.expect("could not read the standard input");
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        match operation(&line, &conf) {
+            Ok(op) => operations.push((i + 1, op)),
+            Err(e) => errors.push(format!("line {}: {}", i + 1, e)),
+        }
+    }
+    // lines without an explicit time happen now
+    let now = current_time().with_nanosecond(0).unwrap();
+    let mut last: Option<NaiveDateTime> = None;
+    for (i, op) in operations.iter() {
+        let time = op.time.unwrap_or(now);
+        if let Some(l) = last {
+            if l > time {
+                errors.push(format!(
+                    "line {}: {} precedes the time of the previous operation, {}",
+                    i,
+                    timestamp(&time),
+                    timestamp(&l)
+                ));
+            }
+        }
+        last = Some(time);
+    }
+    if !errors.is_empty() {
+        for e in errors.iter() {
+            warn(e, &conf);
+        }
+        fatal(
+            format!(
+                "found {} invalid operation{}; the log is unchanged",
+                errors.len(),
+                if errors.len() == 1 { "" } else { "s" }
+            ),
+            &conf,
+        );
+    }
+    apply(
+        operations,
+        now,
+        "the batch",
+        "log.pre-batch.bak",
+        matches,
+        &conf,
+    );
+}
+
+// adds the lines of the operations to the log, interleaving them with those already in it by
+// time, after first copying the log to the backup file in the job log directory
+fn apply(
+    operations: Vec<(usize, Operation)>,
+    now: NaiveDateTime,
+    what: &str,
+    backup: &str,
+    matches: &ArgMatches,
+    conf: &Configuration,
+) {
+    if operations.is_empty() {
+        success(format!("{} is empty; the log is unchanged", what), conf);
+        return;
+    }
+    let read = LogState::of(conf);
+    let ours = match read_blocks(log_path(conf.directory()).to_str().unwrap(), Source::Ours) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            fatal(
+                format!("{}; fix this with `job edit --validate` first", e),
+                conf,
+            );
+            unreachable!()
+        }
+    };
+    let count = |kind| operations.iter().filter(|(_, op)| op.kind == kind).count();
+    let (events, notes, dones) = (count(Kind::Add), count(Kind::Note), count(Kind::Done));
+    let theirs: Vec<Block> = operations
+        .into_iter()
+        .map(|(_, op)| block(op, now, conf))
+        .collect();
+    let merged = merge(ours, theirs);
+    let conflicts = conflicts(&merged, what);
+    for c in conflicts.iter() {
+        warn(c, conf);
+    }
+    // a DONE with no event to end is dropped; say so rather than count it as added
+    let new_dones = |blocks: &[Block]| {
+        blocks
+            .iter()
+            .filter(|b| b.source != Source::Ours && matches!(b.item, Some(Item::Done(_, _))))
+            .count()
+    };
+    let before = new_dones(&merged);
+    let merged = drop_orphaned_dones(merged);
+    let dropped = before - new_dones(&merged);
+    if dropped > 0 {
+        warn(
+            format!(
+                "{} would end no event and so {} dropped",
+                describe(0, 0, dropped),
+                if dropped == 1 { "is" } else { "are" }
+            ),
+            conf,
+        );
+    }
+    let summary = describe(events, notes, dones - dropped);
+    if matches.is_present("dry-run") {
+        success(format!("{} would add {}", what, summary), conf);
+        return;
+    }
+    if !conflicts.is_empty() && !matches.is_present("force") {
+        fatal(
+            format!(
+                "found {} conflict{}; fix {} or apply {} with --force",
+                conflicts.len(),
+                if conflicts.len() == 1 { "" } else { "s" },
+                if conflicts.len() == 1 { "it" } else { "them" },
+                what,
+            ),
+            conf,
+        );
+    }
+    // stdin is spoken for, so we cannot ask whether to overwrite a stray log.tmp
+    let tmp = tmp_path(conf);
+    if tmp.as_path().exists() {
+        fatal(
+            format!(
+                "the temporary log file {} already exists; remove it and try again",
+                tmp.to_str().unwrap()
+            ),
+            conf,
+        );
+    }
+    let backup = replace_log(&merged, backup, &read, conf);
+    success(
+        format!(
+            "added {}; your original log is in {}",
+            summary,
+            backup.to_str().unwrap()
+        ),
+        conf,
+    );
+}
+
+// an event read by add --stdin
+#[derive(Debug)]
+struct Entry {
+    line: usize,
+    start: NaiveDateTime,
+    end: Option<NaiveDateTime>,
+    tags: Vec<String>,
+    description: String,
+}
+
+// reads the events of add --stdin, one per line, and adds them to the log in order of time;
+// the tags are added to every event
+pub(crate) fn add_events(tags: &[String], matches: &ArgMatches, conf: &Configuration) {
+    let mut entries = vec![];
+    let mut errors = vec![];
+    for (i, line) in stdin().lock().lines().enumerate() {
+        let line = line.expect("could not read the standard input");
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        match entry(&line, i + 1, conf) {
+            Ok(mut e) => {
+                e.tags = conf.normalize_tags(&[tags, &e.tags].concat());
+                entries.push(e)
+            }
+            Err(e) => errors.push(format!("line {}: {}", i + 1, e)),
+        }
+    }
+    let mut operations = vec![];
+    if errors.is_empty() {
+        match self::operations(entries) {
+            Ok(ops) => operations = ops,
+            Err(mut e) => errors.append(&mut e),
+        }
+    }
+    if !errors.is_empty() {
+        for e in errors.iter() {
+            warn(e, conf);
+        }
+        fatal(
+            format!(
+                "found {} invalid event{}; the log is unchanged",
+                errors.len(),
+                if errors.len() == 1 { "" } else { "s" }
+            ),
+            conf,
+        );
+    }
+    let now = current_time().with_nanosecond(0).unwrap();
+    apply(
+        operations,
+        now,
+        "the input",
+        "log.pre-add.bak",
+        matches,
+        conf,
+    );
+}
+
+// interprets a line of add --stdin: either the start, end, tags, and description of the event
+// separated by tabs, the tags separated by commas, or a JSON object with these properties,
+// such as the summary subcommand writes; an event without an end lasts until the next
+fn entry(line: &str, number: usize, conf: &Configuration) -> Result<Entry, String> {
+    let (start, end, tags, description) = if line.trim_start().starts_with('{') {
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| format!("invalid JSON: {}", e))?;
+        let text = |key: &str| match value.get(key) {
+            None | Some(Value::Null) => Ok(String::new()),
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(v) => Err(format!("{} is not a valid {}", v, key)),
+        };
+        let tags = match value.get("tags") {
+            None | Some(Value::Null) => vec![],
+            Some(Value::Array(tags)) => tags
+                .iter()
+                .map(|t| match t.as_str() {
+                    Some(t) => Ok(t.to_owned()),
+                    None => Err(format!("{} is not a tag", t)),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(t) => return Err(format!("{} is not a list of tags", t)),
+        };
+        (text("start")?, text("end")?, tags, text("description")?)
+    } else {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != 4 {
+            return Err(format!(
+                "expected four fields separated by tabs -- start, end, tags, and description -- but found {}",
+                fields.len()
+            ));
+        }
+        let tags = fields[2]
+            .split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_owned())
+            .collect();
+        (
+            fields[0].to_owned(),
+            fields[1].to_owned(),
+            tags,
+            fields[3].to_owned(),
+        )
+    };
+    if start.trim().is_empty() {
+        return Err(String::from("the event has no start"));
+    }
+    let start = time(&start, conf)?;
+    let end = match end.trim() {
+        "" => None,
+        end => Some(time(end, conf)?),
+    };
+    if end.is_some_and(|end| end <= start) {
+        return Err(String::from("the event ends before it starts"));
+    }
+    let op = validate(Operation {
+        kind: Kind::Add,
+        time: Some(start),
+        description,
+        tags,
+    })?;
+    Ok(Entry {
+        line: number,
+        start,
+        end,
+        tags: op.tags,
+        description: op.description,
+    })
+}
+
+// the operations adding the events in order of time, each followed by a DONE unless the next
+// begins when it ends, or the errors if any events overlap
+fn operations(mut entries: Vec<Entry>) -> Result<Vec<(usize, Operation)>, Vec<String>> {
+    entries.sort_by_key(|e| e.start);
+    let mut operations = vec![];
+    let mut errors = vec![];
+    for (i, e) in entries.iter().enumerate() {
+        let next = entries.get(i + 1);
+        if let (Some(end), Some(next)) = (e.end, next) {
+            if end > next.start {
+                errors.push(format!(
+                    "line {}: the event overlaps the one on line {}",
+                    e.line, next.line
+                ));
+            }
+        }
+        operations.push((
+            e.line,
+            Operation {
+                kind: Kind::Add,
+                time: Some(e.start),
+                description: e.description.clone(),
+                tags: e.tags.clone(),
+            },
+        ));
+        if let Some(end) = e.end {
+            if next.is_none_or(|n| n.start != end) {
+                operations.push((
+                    e.line,
+                    Operation {
+                        kind: Kind::Done,
+                        time: Some(end),
+                        description: String::new(),
+                        tags: vec![],
+                    },
+                ));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(operations)
+    } else {
+        Err(errors)
+    }
+}
+
+// the log line an operation adds
+fn block(op: Operation, now: NaiveDateTime, conf: &Configuration) -> Block {
+    let time = op.time.unwrap_or(now);
+    let line = match op.kind {
+        // the operation has been validated
+        Kind::Add => {
+            let event = Event::builder()
+                .start(time)
+                .description(op.description)
+                .tags(&op.tags)
+                .author(conf.user_name.clone())
+                .build()
+                .unwrap();
+            log_line(&event, conf)
+        }
+        Kind::Note => {
+            let note = Note::builder()
+                .time(time)
+                .description(op.description)
+                .tags(&op.tags)
+                .author(conf.user_name.clone())
+                .build()
+                .unwrap();
+            log_line(&note, conf)
+        }
+        Kind::Done => log_line(&Done(time), conf),
+    };
+    Block {
+        time: Some(time),
+        item: Some(parse_line(&line, 0)),
+        line: Some(line),
+        comments: vec![],
+        source: Source::Theirs,
+    }
+}
+
+// e.g., "3 events, 1 note, and 1 DONE"
+fn describe(events: usize, notes: usize, dones: usize) -> String {
+    let parts: Vec<String> = [(events, "event"), (notes, "note"), (dones, "DONE")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|&(n, name)| format!("{} {}{}", n, name, if n == 1 { "" } else { "s" }))
+        .collect();
+    match parts.len() {
+        0 => String::from("nothing"),
+        1 => parts[0].clone(),
+        2 => format!("{} and {}", parts[0], parts[1]),
+        _ => format!("{}, {}, and {}", parts[0], parts[1], parts[2]),
+    }
+}
+
+// interprets a line of the batch
+fn operation(line: &str, conf: &Configuration) -> Result<Operation, String> {
+    if line.trim_start().starts_with('{') {
+        json_operation(line, conf)
+    } else {
+        command_operation(line, conf)
+    }
+}
+
+fn json_operation(line: &str, conf: &Configuration) -> Result<Operation, String> {
+    let value: Value = serde_json::from_str(line).map_err(|e| format!("invalid JSON: {}", e))?;
+    let kind = match value.get("op").and_then(|v| v.as_str()) {
+        Some("add") => Kind::Add,
+        Some("note") => Kind::Note,
+        Some("done") => Kind::Done,
+        Some(op) => return Err(format!("unknown op: {}", op)),
+        None => return Err(String::from("the object has no op")),
+    };
+    let time = match value.get("time") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(t)) => Some(time(t, conf)?),
+        Some(t) => return Err(format!("{} is not a time expression", t)),
+    };
+    let description = match value.get("description") {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(d)) => d.clone(),
+        Some(d) => return Err(format!("{} is not a description", d)),
+    };
+    let tags = match value.get("tags") {
+        None | Some(Value::Null) => vec![],
+        Some(Value::Array(tags)) => {
+            let mut strings = vec![];
+            for t in tags {
+                match t.as_str() {
+                    Some(t) => strings.push(t.to_owned()),
+                    None => return Err(format!("{} is not a tag", t)),
+                }
+            }
+            strings
+        }
+        Some(t) => return Err(format!("{} is not a list of tags", t)),
+    };
+    validate(Operation {
+        kind,
+        time,
+        description,
+        tags,
+    })
+}
+
+fn command_operation(line: &str, conf: &Configuration) -> Result<Operation, String> {
+    let mut args = vec![String::from("batch")];
+    args.append(&mut words(line)?);
+    let at = || {
+        Arg::with_name("at")
+            .long("at")
+            .value_name("time")
+            .validator(|v| {
+                if parsable(&v) {
+                    Ok(())
+                } else {
+                    Err(format!("cannot parse '{}' as a time expression", v))
+                }
+            })
+    };
+    let tag = || {
+        Arg::with_name("tag")
+            .short("t")
+            .long("tag")
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("tag")
+    };
+    let description = || Arg::with_name("description").multiple(true).required(true);
+    let app = App::new("batch")
+        .setting(AppSettings::ColorNever)
+        .setting(AppSettings::DisableVersion)
+        .setting(AppSettings::DisableHelpSubcommand)
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            SubCommand::with_name("add")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(at())
+                .arg(tag())
+                .arg(description()),
+        )
+        .subcommand(
+            SubCommand::with_name("note")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(at())
+                .arg(tag())
+                .arg(description()),
+        )
+        .subcommand(SubCommand::with_name("done").arg(at()));
+    let matches = app
+        .get_matches_from_safe(args)
+        .map_err(|e| clap_error_message(&e))?;
+    let (kind, m) = match matches.subcommand() {
+        ("add", Some(m)) => (Kind::Add, m),
+        ("note", Some(m)) => (Kind::Note, m),
+        ("done", Some(m)) => (Kind::Done, m),
+        _ => unreachable!(),
+    };
+    let time = match m.value_of("at") {
+        Some(t) => Some(time(t, conf)?),
+        None => None,
+    };
+    validate(Operation {
+        kind,
+        time,
+        description: m
+            .values_of("description")
+            .map(|values| values.collect::<Vec<_>>().join(" "))
+            .unwrap_or_default(),
+        tags: m
+            .values_of("tag")
+            .map(|values| values.map(|t| t.to_owned()).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn time(expression: &str, conf: &Configuration) -> Result<NaiveDateTime, String> {
+    match parse(expression, conf.two_timer_config()) {
+        Ok((t, _, _)) => Ok(t.with_nanosecond(0).unwrap()),
+        Err(_) => Err(format!(
+            "cannot parse '{}' as a time expression",
+            expression
+        )),
+    }
+}
+
+fn validate(op: Operation) -> Result<Operation, String> {
+    if op.kind == Kind::Done {
+        if !op.description.is_empty() || !op.tags.is_empty() {
+            return Err(String::from("done takes neither a description nor tags"));
+        }
+        return Ok(op);
+    }
+    if !some_nws(&op.description) || op.description.contains('\n') {
+        return Err(String::from(
+            "the description must be a single line with some non-whitespace character",
+        ));
+    }
+    for t in op.tags.iter() {
+        if !some_nws(t) || t.contains('\n') {
+            return Err(format!("{:?} is not a suitable tag", t));
+        }
+    }
+    check_line_parts(&op.description, &op.tags)?;
+    Ok(op)
+}
+
+// splits a line into words as the shell would, respecting quotes and backslash escapes
+fn words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.push(c),
+                        None => return Err(String::from("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => w.push(c),
+                            None => return Err(String::from("unterminated double quote")),
+                        },
+                        Some(c) => w.push(c),
+                        None => return Err(String::from("unterminated double quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(String::from("trailing backslash")),
+            },
+            c if c.is_whitespace() => {
+                if let Some(w) = word.take() {
+                    words.push(w);
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(w) = word {
+        words.push(w);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_words() {
+        assert_eq!(
+            vec!["add", "-t", "a b", "it's", "\"quoted\"", "x y"],
+            words(r#"add -t 'a b' it\'s "\"quoted\"" x\ y"#).unwrap()
+        );
+        assert_eq!(vec!["", "a"], words("'' a").unwrap());
+        assert!(words("'open").is_err());
+    }
+
+    #[test]
+    fn test_operation() {
+        let dir = TestDirectory::new("test_batch_operation");
+        let conf = dir.configuration();
+        let op = operation("add --at '2024-03-01 09:00' -t a -t b foo bar", &conf).unwrap();
+        assert_eq!(Kind::Add, op.kind);
+        assert_eq!("2024-03-01 09:00:00", format!("{}", op.time.unwrap()));
+        assert_eq!(vec!["a", "b"], op.tags);
+        assert_eq!("foo bar", op.description);
+        let op = operation(
+            r#"{"op":"note","time":"2024-03-01 10:00","tags":["c"],"description":"baz"}"#,
+            &conf,
+        )
+        .unwrap();
+        assert_eq!(Kind::Note, op.kind);
+        assert_eq!(vec!["c"], op.tags);
+        assert_eq!(Kind::Done, operation("done", &conf).unwrap().kind);
+        assert!(operation("done foo", &conf).is_err());
+        assert!(operation("add", &conf).is_err());
+        assert!(operation("plugh foo", &conf).is_err());
+        assert!(operation(r#"{"op":"add"}"#, &conf).is_err());
+        assert!(operation(r#"{"op":"done","description":"x"}"#, &conf).is_err());
+    }
+
+    #[test]
+    fn test_entries() {
+        let dir = TestDirectory::new("test_batch_entries");
+        let conf = dir.configuration();
+        let entries = vec![
+            entry(
+                "2024-03-01 10:00\t2024-03-01 11:00\tplugh, review\treviewing the PR",
+                1,
+                &conf,
+            )
+            .unwrap(),
+            entry(
+                r#"{"type":"Event","start":"2024-03-01 09:00:00","end":"2024-03-01 10:00:00","duration":1.00,"tags":["email"],"description":"reading mail"}"#,
+                2,
+                &conf,
+            )
+            .unwrap(),
+            entry("2024-03-01 13:00\t\t\tlunch", 3, &conf).unwrap(),
+        ];
+        assert_eq!(vec!["plugh", "review"], entries[0].tags);
+        let kinds = operations(entries)
+            .unwrap()
+            .iter()
+            .map(|(i, op)| (*i, op.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (2, Kind::Add),
+                (1, Kind::Add),
+                (1, Kind::Done),
+                (3, Kind::Add)
+            ],
+            kinds,
+            "sorted, with a DONE only where the next event does not begin"
+        );
+        assert!(entry("2024-03-01 10:00\t2024-03-01 9:00\t\tfoo", 1, &conf).is_err());
+        assert!(entry("2024-03-01 10:00\tfoo", 1, &conf).is_err());
+        assert!(entry("\t\t\tfoo", 1, &conf).is_err());
+        assert!(entry("2024-03-01 10:00\t\t\t", 1, &conf).is_err());
+        let overlapping = vec![
+            entry("2024-03-01 10:00\t2024-03-01 12:00\t\tfoo", 1, &conf).unwrap(),
+            entry("2024-03-01 11:00\t\t\tbar", 2, &conf).unwrap(),
+        ];
+        assert_eq!(
+            vec!["line 1: the event overlaps the one on line 2"],
+            operations(overlapping).unwrap_err()
+        );
+    }
+}
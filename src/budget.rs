@@ -0,0 +1,342 @@
+extern crate chrono;
+extern crate clap;
+extern crate colonnade;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::configure::{BudgetPeriod, Configuration};
+use crate::log::{Event, LogController};
+use crate::util::{current_time, duration_string, expand_period, fatal, warn, Style};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use clap::{App, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+use serde_json::json;
+use two_timer::parse;
+
+fn after_help() -> &'static str {
+    "\
+If you have set time budgets for certain tags -- e.g., `job configure --budget meetings 5h/week` \
+-- the budget subcommand shows how much of each budget you have used in its current period, \
+the current day, week, month, or pay period, and how much remains:
+
+  > job budget
+  budget    per         budgeted  used  remaining  runs out
+  meetings  week            5.00  6.25      -1.25  exceeded
+  support   pay period     20.00  8.50      11.50  Thursday, 12 March
+
+  warning: the meetings budget of 5 hours a week is exceeded by 1.25 hours
+  warning: you are on pace to exceed the support budget by Thursday, 12 March
+
+When a budget will run out is projected from the rate at which you have spent it over the last \
+two weeks: the hours logged with its tag divided by the number of work days. If at this rate \
+the budget will last the period, the column is blank.
+
+The time used is the time logged on events with the budget's tag. When you summarize a period \
+overlapping the current period of a budget you have exceeded, the summary also warns you. See \
+also the when subcommand, which shows pay period budgets as well.
+
+All prefixes of 'budget' after 'b' -- 'bu', 'bud', 'budg', and 'budge' -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("budget")
+            .aliases(&["bu", "bud", "budg", "budge"])
+            .about("Shows how much of each time budget you have used")
+            .after_help(after_help())
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    if conf.budgets.is_none() {
+        fatal(
+            "no time budgets are configured; see `job configure --budget`",
+            &conf,
+        );
+    }
+    let now = current_time();
+    let usages = usages(&now, &conf);
+    if matches.is_present("json") {
+        for u in usages.iter() {
+            println!(
+                "{}",
+                json!({
+                    "tag": u.tag,
+                    "per": u.per.name(),
+                    "start": format!("{}", u.start),
+                    "end": format!("{}", u.end),
+                    "budgeted": u.budgeted / (60.0 * 60.0),
+                    "used": u.used / (60.0 * 60.0),
+                    "remaining": u.remaining() / (60.0 * 60.0),
+                    "daily-rate": u.rate / (60.0 * 60.0),
+                    "runs-out": u.runs_out.map(|d| format!("{}", d)),
+                })
+            );
+        }
+        return;
+    }
+    let style = Style::new(&conf);
+    let mut data = vec![vec![
+        String::from("budget"),
+        String::from("per"),
+        String::from("budgeted"),
+        String::from("used"),
+        String::from("remaining"),
+        String::from("runs out"),
+    ]];
+    for u in usages.iter() {
+        data.push(vec![
+            u.tag.clone(),
+            u.per.name().to_owned(),
+            duration_string(u.budgeted, &conf),
+            duration_string(u.used, &conf),
+            duration_string(u.remaining(), &conf),
+            if u.exceeded() {
+                String::from("exceeded")
+            } else {
+                u.runs_out
+                    .map(|d| d.format("%A, %e %B").to_string())
+                    .unwrap_or_default()
+            },
+        ]);
+    }
+    let mut table = Colonnade::new(6, conf.width()).expect("could not build the budget table");
+    for i in 1..6 {
+        table.columns[i].left_margin(2);
+    }
+    for i in 2..5 {
+        table.columns[i].alignment(Alignment::Right);
+    }
+    for (i, line) in table
+        .tabulate(&data)
+        .expect("could not tabulate data")
+        .iter()
+        .enumerate()
+    {
+        if i == 0 {
+            println!("{}", style.paint("header", line));
+        } else if usages[i - 1].exceeded() {
+            println!("{}", style.paint("alert", line));
+        } else {
+            println!("{}", line);
+        }
+    }
+    for u in usages.iter() {
+        if u.exceeded() {
+            warn(u.describe_excess(&conf), &conf);
+        } else if let Some(d) = u.runs_out {
+            warn(
+                format!(
+                    "you are on pace to exceed the {} budget by {}",
+                    u.tag,
+                    d.format("%A, %e %B")
+                ),
+                &conf,
+            );
+        }
+    }
+}
+
+// warns of any exceeded budget whose current period overlaps the given range
+pub fn warn_of_exceeded_budgets(
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) {
+    if conf.budgets.is_none() {
+        return;
+    }
+    for u in usages(now, conf)
+        .iter()
+        .filter(|u| u.exceeded() && u.start < *end && u.end > *start)
+    {
+        warn(u.describe_excess(conf), conf);
+    }
+}
+
+// how much of a budget has been used in its current period; times are in seconds
+pub struct Usage {
+    pub tag: String,
+    pub per: BudgetPeriod,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub budgeted: f32,
+    pub used: f32,
+    pub rate: f32,                   // the recent rate of use per work day
+    pub runs_out: Option<NaiveDate>, // the day within the period the budget is projected to run out
+}
+
+impl Usage {
+    pub fn remaining(&self) -> f32 {
+        self.budgeted - self.used
+    }
+    pub fn exceeded(&self) -> bool {
+        self.used > self.budgeted
+    }
+    fn describe_excess(&self, conf: &Configuration) -> String {
+        format!(
+            "the {} budget of {} hours a {} is exceeded by {} hours",
+            self.tag,
+            self.budgeted / (60.0 * 60.0),
+            self.per.name(),
+            duration_string(-self.remaining(), conf)
+        )
+    }
+}
+
+// the usage of every budget in its current period
+pub fn usages(now: &NaiveDateTime, conf: &Configuration) -> Vec<Usage> {
+    let mut reader = LogController::new(None, conf).expect("could not read log");
+    let mut usages = vec![];
+    for (tag, hours, per) in conf.budgets.clone().unwrap_or_default() {
+        let expression = expand_period(per.expression(), false, conf);
+        let (start, end) = match parse(&expression, conf.logical_two_timer_config()) {
+            Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+            Err(_) => {
+                warn(
+                    format!(
+                        "cannot find the current {} for the {} budget",
+                        per.name(),
+                        tag
+                    ),
+                    conf,
+                );
+                continue;
+            }
+        };
+        let events = reader
+            .events_in_range(&start, &end)
+            .into_iter()
+            .filter(|e| e.tags.contains(&tag))
+            .collect();
+        let used = Event::gather_by_day(events, &end, conf)
+            .iter()
+            .filter(|e| e.start >= start && e.start < end)
+            .map(|e| e.duration(now))
+            // an empty f32 sum is -0.0, which would be shown as -0.00
+            .fold(0.0, |acc, d| acc + d);
+        let rate = daily_rate(&mut reader, &tag, now, conf);
+        let budgeted = hours * 60.0 * 60.0;
+        let runs_out = projection(
+            budgeted - used,
+            rate,
+            &conf.logical_date(now),
+            &conf.logical_date(&end),
+            conf,
+        );
+        usages.push(Usage {
+            tag,
+            per,
+            start,
+            end,
+            budgeted,
+            used,
+            rate,
+            runs_out,
+        });
+    }
+    usages
+}
+
+// the number of days over which the rate at which a budget is spent is measured
+const RATE_WINDOW: i64 = 14;
+
+// the seconds per work day spent on events with this tag over the last RATE_WINDOW days
+fn daily_rate(
+    reader: &mut LogController,
+    tag: &str,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> f32 {
+    let today = conf.logical_date(now);
+    let first = today - Duration::days(RATE_WINDOW - 1);
+    let start = conf.start_of_day(&first);
+    let workdays = (0..RATE_WINDOW)
+        .filter(|i| conf.is_workday(&(first + Duration::days(*i))))
+        .count();
+    if workdays == 0 {
+        return 0.0;
+    }
+    let events = reader
+        .events_in_range(&start, now)
+        .into_iter()
+        .filter(|e| e.tags.iter().any(|t| t == tag))
+        .collect();
+    let used: f32 = Event::gather_by_day(events, now, conf)
+        .iter()
+        .filter(|e| e.start >= start)
+        .map(|e| e.duration(now))
+        .fold(0.0, |acc, d| acc + d);
+    used / workdays as f32
+}
+
+// the work day on which, spending the given seconds per work day from today on, the remaining
+// budget runs out, if that happens before the end date
+fn projection(
+    remaining: f32,
+    rate: f32,
+    today: &NaiveDate,
+    end: &NaiveDate,
+    conf: &Configuration,
+) -> Option<NaiveDate> {
+    if remaining < 0.0 || rate <= 0.0 {
+        return None;
+    }
+    let mut left = remaining;
+    let mut date = *today;
+    while date < *end {
+        if conf.is_workday(&date) {
+            left -= rate;
+            if left < 0.0 {
+                return Some(date);
+            }
+        }
+        date += Duration::days(1);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_projection() {
+        let dir = TestDirectory::new("test_budget_projection");
+        let conf = dir.configuration();
+        // Monday, 2 March 2020, with the default Monday to Friday work week
+        let monday = NaiveDate::from_ymd(2020, 3, 2);
+        let next_monday = monday + Duration::days(7);
+        let hour = 60.0 * 60.0;
+        assert_eq!(
+            Some(monday + Duration::days(2)),
+            projection(5.0 * hour, 2.0 * hour, &monday, &next_monday, &conf),
+            "runs out Wednesday"
+        );
+        assert_eq!(
+            Some(next_monday),
+            projection(
+                5.0 * hour,
+                2.0 * hour,
+                &(monday + Duration::days(3)),
+                &(next_monday + Duration::days(7)),
+                &conf
+            ),
+            "the weekend is skipped"
+        );
+        assert_eq!(
+            None,
+            projection(20.0 * hour, 2.0 * hour, &monday, &next_monday, &conf),
+            "lasts the period"
+        );
+        assert_eq!(
+            None,
+            projection(5.0 * hour, 0.0, &monday, &next_monday, &conf)
+        );
+    }
+}
@@ -0,0 +1,263 @@
+extern crate clap;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::ics;
+use crate::log::{Filter, LogController};
+use crate::util::{
+    base_dir, common_search_or_filter_arguments, current_time, expand_period, fatal, info,
+    remainder, success, warn,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::env;
+use std::fs::{remove_file, write};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use two_timer::parse;
+
+// the seconds allowed for each upload
+const TIMEOUT: usize = 30;
+
+fn after_help() -> &'static str {
+    "\
+The push-calendar subcommand uploads the events of a period to a CalDAV calendar, so the time \
+you have tracked appears on a shared calendar:
+
+  > job configure --caldav-url https://dav.example.com/calendars/me/work/ --caldav-user me
+  > job push-calendar last week
+  uploaded 23 events to https://dav.example.com/calendars/me/work/
+
+Each event is uploaded as its own calendar resource, named after the moment it began, so \
+pushing the same period again updates the calendar events rather than duplicating them. \
+Ongoing events are not uploaded. The filtering options of the summary subcommand -- --tag, \
+--rx, and so forth -- select which events are uploaded.
+
+The password is taken from the JOBLOG_CALDAV_PASSWORD environment variable or, if that is \
+unset, from your ~/.netrc file. Uploading uses curl, which must be installed. With --dry-run \
+the subcommand prints the iCalendar data it would upload instead of uploading it.
+
+All prefixes of 'push-calendar' after 'pu' are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(common_search_or_filter_arguments(
+        SubCommand::with_name("push-calendar")
+            .aliases(&[
+                "pu",
+                "pus",
+                "push",
+                "push-",
+                "push-c",
+                "push-ca",
+                "push-cal",
+                "push-cale",
+                "push-calen",
+                "push-calend",
+                "push-calenda",
+            ])
+            .about("Uploads events to a CalDAV calendar")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .help("Prints the calendar data rather than uploading it"),
+            )
+            .arg(
+                Arg::with_name("period")
+                    .help("time expression")
+                    .long_help(
+                        "All the <period> arguments are concatenated to produce a time expression.",
+                    )
+                    .value_name("period")
+                    .default_value("today")
+                    .multiple(true),
+            )
+            .display_order(display_order),
+        Some(true),
+    ))
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let dry_run = matches.is_present("dry-run");
+    let url = match &conf.caldav_url {
+        Some(url) => url.clone(),
+        None if dry_run => String::new(),
+        None => {
+            fatal(
+                "no calendar is configured; see `job configure --caldav-url`",
+                &conf,
+            );
+            unreachable!()
+        }
+    };
+    let phrase = remainder("period", matches);
+    let (start, end) = match parse(
+        &expand_period(&phrase, false, &conf),
+        conf.logical_two_timer_config(),
+    ) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), &conf);
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        &conf,
+    );
+    let now = current_time();
+    let filter = Filter::new(matches);
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let events = reader
+        .events_in_range(&start, &end)
+        .into_iter()
+        .filter(|e| !e.ongoing() && e.start >= start && filter.matches(e))
+        .collect::<Vec<_>>();
+    if events.is_empty() {
+        warn("no event found", &conf);
+        return;
+    }
+    if dry_run {
+        for e in events.iter() {
+            print!("{}", ics::calendar(e, &now));
+        }
+        return;
+    }
+    let mut uploaded = 0;
+    for e in events.iter() {
+        match upload(
+            "PUT",
+            &resource(&url, &ics::uid(e)),
+            "text/calendar; charset=utf-8",
+            &ics::calendar(e, &now),
+            &conf.caldav_user,
+            "JOBLOG_CALDAV_PASSWORD",
+            &conf,
+        ) {
+            Ok(()) => uploaded += 1,
+            Err(err) => warn(
+                format!(
+                    "could not upload '{}', begun {}: {}",
+                    e.description,
+                    e.start.format("%F %T"),
+                    err
+                ),
+                &conf,
+            ),
+        }
+    }
+    success(
+        format!(
+            "uploaded {} event{} to {}",
+            uploaded,
+            if uploaded == 1 { "" } else { "s" },
+            url
+        ),
+        &conf,
+    );
+}
+
+// the URL of the calendar resource with the given UID
+fn resource(url: &str, uid: &str) -> String {
+    let encoded = uid.replace('@', "%40");
+    if url.ends_with('/') {
+        format!("{}{}.ics", url, encoded)
+    } else {
+        format!("{}/{}.ics", url, encoded)
+    }
+}
+
+// sends the body to the URL with curl by the given method; the credentials, the user and the
+// secret in the environment variable or else those in ~/.netrc, go to curl through its standard
+// input so they don't appear among the arguments of a running process
+pub(crate) fn upload(
+    method: &str,
+    url: &str,
+    content_type: &str,
+    body: &str,
+    user: &Option<String>,
+    secret: &str,
+    conf: &Configuration,
+) -> Result<(), String> {
+    let mut tmp = base_dir(conf.directory());
+    tmp.push("upload.tmp");
+    write(&tmp, body).map_err(|e| format!("could not write temporary file: {}", e))?;
+    let mut config = String::new();
+    match (user, env::var(secret)) {
+        (Some(user), Ok(secret)) => {
+            config += &format!("user = \"{}\"\n", quote(&format!("{}:{}", user, secret)))
+        }
+        (user, _) => {
+            config += "netrc-optional\n";
+            if let Some(user) = user {
+                config += &format!("user = \"{}\"\n", quote(user));
+            }
+        }
+    }
+    let result = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--output",
+            "/dev/null",
+            "--max-time",
+            &TIMEOUT.to_string(),
+            "--request",
+            method,
+            "--header",
+            &format!("Content-Type: {}", content_type),
+            "--data-binary",
+            &format!("@{}", tmp.to_str().unwrap()),
+            "--config",
+            "-",
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {}", e))
+        .and_then(|mut child| {
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(config.as_bytes())
+                .map_err(|e| format!("could not configure curl: {}", e))?;
+            child
+                .wait_with_output()
+                .map_err(|e| format!("curl failed: {}", e))
+        });
+    remove_file(&tmp).ok();
+    let output = result?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+// escapes a value for a curl configuration file
+pub(crate) fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource() {
+        assert_eq!(
+            "https://example.com/cal/20200302T090000%40jobrog.ics",
+            resource("https://example.com/cal/", "20200302T090000@jobrog")
+        );
+        assert_eq!(
+            "https://example.com/cal/20200302T090000%40jobrog.ics",
+            resource("https://example.com/cal", "20200302T090000@jobrog")
+        );
+    }
+}
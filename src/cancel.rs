@@ -0,0 +1,176 @@
+extern crate clap;
+extern crate regex;
+
+use crate::backup;
+use crate::configure::Configuration;
+use crate::hook::notify;
+use crate::log::{parse_line, Event, Item};
+use crate::merge::tmp_path;
+use crate::util::{fatal, info, interactive, log_path, success, warn, yes_or_no};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use std::fs::{read_to_string, rename, write};
+
+fn after_help() -> &'static str {
+    "\
+If you have just begun an event you didn't mean to -- you mistyped the description or \
+started tracking the wrong thing -- the cancel subcommand removes the ongoing event from \
+the log as though it had never been begun. The event before it, if it was ongoing when the \
+cancelled event began, is then ongoing again.
+
+  > job add lunch
+  > job cancel
+  cancel the event 'lunch' begun at 12:01:03? [Yn]
+
+Unless you give it --force, the subcommand asks for confirmation, and if it cannot ask, \
+because it is not being run in a terminal, it does nothing. Before the log is changed it is \
+backed up as by the backup subcommand.
+
+All prefixes of 'cancel' after 'c' -- 'ca', 'can', 'canc', and 'cance' -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("cancel")
+            .aliases(&["ca", "can", "canc", "cance"])
+            .about("Removes the ongoing task from the log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("force")
+                    .long("force")
+                    .short("f")
+                    .help("Cancels the event without asking for confirmation"),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let path = log_path(conf.directory());
+    let text = read_to_string(&path).expect("could not read log");
+    let lines = text.lines().collect::<Vec<_>>();
+    let (event, offset) = match ongoing_event(&lines) {
+        Some(found) => found,
+        None => {
+            warn("there is no ongoing event to cancel", &conf);
+            return;
+        }
+    };
+    if !matches.is_present("force") {
+        if !interactive() {
+            fatal(
+                "cannot ask for confirmation; use --force to cancel the event without it",
+                &conf,
+            );
+        }
+        if !yes_or_no(format!(
+            "cancel the event '{}' begun at {}?",
+            event.description,
+            event.start.format("%T")
+        )) {
+            warn("no change to log", &conf);
+            return;
+        }
+    }
+    if let Some(backup) = backup::create(&conf) {
+        info(
+            format!("backed up the log to {}", backup.to_str().unwrap()),
+            &conf,
+        );
+    }
+    let mut text = without_line(&lines, offset).join("\n");
+    text.push('\n');
+    let tmp = tmp_path(&conf);
+    write(&tmp, text).expect("could not write temporary log");
+    rename(&tmp, &path).expect("failed to move the new log into place");
+    let json = event.to_json(&event.start, &conf);
+    notify("cancel", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else {
+        success(format!("cancelled {}", event.description), &conf);
+    }
+}
+
+// the last event in the log and its line offset, if it is ongoing
+pub(crate) fn ongoing_event(lines: &[&str]) -> Option<(Event, usize)> {
+    for (offset, line) in lines.iter().enumerate().rev() {
+        match parse_line(line, offset) {
+            Item::Event(e, _) => return Some((e, offset)),
+            Item::Done(_, _) => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+// the lines without the one at the offset and, if nothing but blank lines and comments
+// follows it, without the date comment immediately before it
+fn without_line<'a>(lines: &[&'a str], offset: usize) -> Vec<&'a str> {
+    lazy_static! {
+        static ref DATE_COMMENT: Regex =
+            Regex::new(r"\A\s*#\s*\d{4}/\d{1,2}/\d{1,2}\s*\z").unwrap();
+    }
+    let last = lines[offset + 1..].iter().enumerate().all(|(i, l)| {
+        matches!(
+            parse_line(l, offset + 1 + i),
+            Item::Blank(_) | Item::Comment(_)
+        )
+    });
+    let first = if last && offset > 0 && DATE_COMMENT.is_match(lines[offset - 1]) {
+        offset - 1
+    } else {
+        offset
+    };
+    lines[..first]
+        .iter()
+        .chain(lines[offset + 1..].iter())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ongoing_event() {
+        let lines = vec![
+            "# 2020/1/2",
+            "2020  1  2  9  0  0::foo",
+            "2020  1  2 10  0  0<NOTE>::a note",
+        ];
+        let (event, offset) = ongoing_event(&lines).unwrap();
+        assert_eq!("foo", event.description);
+        assert_eq!(1, offset);
+        assert!(ongoing_event(&["2020  1  2  9  0  0::foo", "2020  1  2 10  0  0:DONE"]).is_none());
+        assert!(ongoing_event(&["# just a comment"]).is_none());
+    }
+
+    #[test]
+    fn test_without_line() {
+        let lines = vec![
+            "# 2020/1/2",
+            "2020  1  2  9  0  0::foo",
+            "# 2020/1/3",
+            "2020  1  3  9  0  0::bar",
+        ];
+        assert_eq!(
+            vec!["# 2020/1/2", "2020  1  2  9  0  0::foo"],
+            without_line(&lines, 3),
+            "date comment dropped"
+        );
+        let lines = vec![
+            "# 2020/1/3",
+            "2020  1  3  9  0  0::bar",
+            "2020  1  3  9  5  0<NOTE>::a note",
+        ];
+        assert_eq!(
+            vec!["# 2020/1/3", "2020  1  3  9  5  0<NOTE>::a note"],
+            without_line(&lines, 1),
+            "date comment kept"
+        );
+    }
+}
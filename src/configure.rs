@@ -1,33 +1,67 @@
 extern crate chrono;
+#[cfg(feature = "cli")]
 extern crate clap;
+#[cfg(feature = "cli")]
 extern crate colonnade;
 extern crate ini;
 extern crate regex;
+extern crate serde_json;
+#[cfg(feature = "cli")]
 extern crate term_size;
 extern crate two_timer;
 
-use crate::util::{base_dir, fatal, success, warn, Style, STYLE_MATCHER};
-use chrono::{Datelike, Duration, NaiveDate};
+use crate::util::{base_dir, current_time};
+#[cfg(feature = "cli")]
+use crate::util::{
+    fatal, known_locale, prompt_until_eof, some_nws, success, warn, Style, LOCALES, STYLE_MATCHER,
+};
+#[cfg(feature = "cli")]
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+#[cfg(feature = "cli")]
 use clap::{App, Arg, ArgMatches, SubCommand};
+#[cfg(feature = "cli")]
 use colonnade::{Alignment, Colonnade};
 use ini::Ini;
 use regex::Regex;
-use std::collections::BTreeMap;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
+#[cfg(feature = "cli")]
+use std::fmt::Write;
 use std::fs::File;
 use std::path::PathBuf;
-use two_timer::{parsable, parse, Config};
+use two_timer::Config;
+#[cfg(feature = "cli")]
+use two_timer::{parsable, parse};
 
 pub const PRECISION: &str = "2";
 pub const SUNDAY_BEGINS_WEEK: &str = "true";
 pub const LENGTH_PAY_PERIOD: &str = "14";
+pub const PAY_PERIOD: &str = "fixed";
 pub const DAY_LENGTH: &str = "8";
 pub const BEGINNING_WORK_DAY: (usize, usize) = (9, 0);
+pub const DAY_BOUNDARY: (usize, usize) = (0, 0);
 pub const WORKDAYS: &str = "MTWHF";
 pub const COLOR: &str = "true";
 pub const TRUNCATION: &str = "round";
 pub const CLOCK: &str = "12";
-pub const STYLES: &'static [[&'static str; 4]; 10] = &[
+pub const UTC_OFFSETS: &str = "false";
+pub const WEEK_NUMBERS: &str = "false";
+pub const PAGER: &str = "true";
+pub const BACKUPS: &str = "10";
+pub const PERL_COMPATIBLE: &str = "false";
+pub const WARN_NEW_TAGS: &str = "false";
+pub const FOLD_TAG_CASE: &str = "false";
+pub const REQUIRE_TAGS: &str = "false";
+pub const SLACK_EMOJI: &str = ":computer:";
+pub const STYLES: &'static [[&'static str; 4]; 16] = &[
+    [
+        "added",
+        "green",
+        "line added to the log",
+        "the diff shown after edit",
+    ],
     [
         "alert",
         "purple",
@@ -52,19 +86,49 @@ pub const STYLES: &'static [[&'static str; 4]; 10] = &[
         "even row in a striped table",
         "configure --list",
     ],
+    [
+        "gap",
+        "yellow",
+        "untracked time between events",
+        "summary --show-gaps",
+    ],
     [
         "header",
         "bold blue",
         "header row in vacation table",
         "vacation --list",
     ],
+    [
+        "hunk",
+        "cyan",
+        "the location of a change",
+        "the diff shown after edit",
+    ],
+    [
+        "info",
+        "cyan",
+        "diagnostic detail",
+        "messages shown with --verbose",
+    ],
     [
         "important",
         "red",
         "important information",
         "TOTAL_HOURS in summary",
     ],
+    [
+        "note",
+        "italic",
+        "note shown among events",
+        "summary --with-notes",
+    ],
     ["odd", "", "odd row in a striped table", "configure --list"],
+    [
+        "removed",
+        "red",
+        "line removed from the log",
+        "the diff shown after edit",
+    ],
     [
         "success",
         "bold green",
@@ -80,6 +144,7 @@ pub const STYLES: &'static [[&'static str; 4]; 10] = &[
     ],
 ];
 
+#[cfg(feature = "cli")]
 fn after_help() -> &'static str {
     lazy_static! {
         static ref INTRO: &'static str = "\
@@ -126,6 +191,7 @@ All prefixes of 'configure' are aliases of the subcommand.
     &TEXT
 }
 
+#[cfg(feature = "cli")]
 fn describe_styles() -> String {
     let mut data = vec![["IDENTIFIER", "DEFAULT STYLE", "DESCRIPTION", "EXAMPLE"]
         .iter()
@@ -152,6 +218,16 @@ fn describe_styles() -> String {
         + "\n"
 }
 
+#[cfg(feature = "cli")]
+fn valid_backups(v: String) -> Result<(), String> {
+    if v.parse::<usize>().is_ok() {
+        Ok(())
+    } else {
+        Err(String::from("a whole number of backups expected"))
+    }
+}
+
+#[cfg(feature = "cli")]
 fn valid_length_pay_period(v: String) -> Result<(), String> {
     let n = v.parse::<u32>();
     if n.is_ok() {
@@ -166,6 +242,7 @@ fn valid_length_pay_period(v: String) -> Result<(), String> {
     }
 }
 
+#[cfg(feature = "cli")]
 fn valid_day_length(v: String) -> Result<(), String> {
     let n = v.parse::<f32>();
     if n.is_ok() {
@@ -184,6 +261,7 @@ fn valid_day_length(v: String) -> Result<(), String> {
     }
 }
 
+#[cfg(feature = "cli")]
 fn valid_max_width(v: String) -> Result<(), String> {
     let n = v.parse::<usize>();
     if n.is_ok() {
@@ -199,6 +277,107 @@ fn valid_max_width(v: String) -> Result<(), String> {
     }
 }
 
+#[cfg(feature = "cli")]
+fn valid_day_boundary(v: String) -> Result<(), String> {
+    parse_day_boundary(&v).map(|_| ())
+}
+
+// parses a day boundary of the form hours[:minutes], which must be before noon
+fn parse_day_boundary(v: &str) -> Result<(usize, usize), String> {
+    let rx = Regex::new(r"\A(\d\d?)(?::([0-5]\d))?\z").unwrap();
+    if let Some(captures) = rx.captures(v.trim()) {
+        let hours = captures[1].parse::<usize>().unwrap();
+        let minutes = captures
+            .get(2)
+            .map(|m| m.as_str().parse::<usize>().unwrap())
+            .unwrap_or(0);
+        if hours < 12 {
+            Ok((hours, minutes))
+        } else {
+            Err(format!("the day boundary '{}' must be before noon", v))
+        }
+    } else {
+        Err(format!("'{}' is not of the form hours[:minutes]", v))
+    }
+}
+
+#[cfg(feature = "cli")]
+fn valid_auto_close_at(v: String) -> Result<(), String> {
+    if AutoClose::parse_at(&v).is_some() {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a time of day of the form hours[:minutes]",
+            v
+        ))
+    }
+}
+
+#[cfg(feature = "cli")]
+fn valid_auto_close_after(v: String) -> Result<(), String> {
+    if AutoClose::parse_after(&v).is_some() {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is neither 'day-length' nor a positive number of hours less than 24",
+            v
+        ))
+    }
+}
+
+#[cfg(feature = "cli")]
+fn valid_fiscal_year_start(v: String) -> Result<(), String> {
+    if Configuration::parse_month_day(&v).is_some() {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a month and day of the form <month>-<day>; e.g., 7-1 for the 1st of July",
+            v
+        ))
+    }
+}
+
+#[cfg(feature = "cli")]
+fn valid_strftime(v: String) -> Result<(), String> {
+    if StrftimeItems::new(&v).any(|i| i == Item::Error) {
+        Err(format!("'{}' is not a valid strftime pattern", v))
+    } else {
+        Ok(())
+    }
+}
+
+// a pattern may parse yet fail to format what it is given, e.g., %H:%M with a date
+#[cfg(feature = "cli")]
+fn valid_date_format(v: String) -> Result<(), String> {
+    valid_strftime(v.clone())?;
+    let mut s = String::new();
+    if write!(s, "{}", NaiveDate::from_ymd(2020, 4, 10).format(&v)).is_err() {
+        Err(format!("'{}' cannot be used to display a date", v))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cli")]
+fn valid_time_format(v: String) -> Result<(), String> {
+    valid_strftime(v.clone())?;
+    let mut s = String::new();
+    if write!(
+        s,
+        "{}",
+        NaiveDate::from_ymd(2020, 4, 10)
+            .and_hms(13, 5, 0)
+            .format(&v)
+    )
+    .is_err()
+    {
+        Err(format!("'{}' cannot be used to display a time", v))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cli")]
 fn valid_beginning_work_day(v: String) -> Result<(), String> {
     let rx = Regex::new(r"\A([1-9]\d?)(?::([0-6]\d))?\z").unwrap();
     if let Some(captures) = rx.captures(&v) {
@@ -229,6 +408,7 @@ fn valid_beginning_work_day(v: String) -> Result<(), String> {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
     mast.subcommand(
         SubCommand::with_name("configure")
@@ -266,6 +446,16 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .validator(|v| if parsable(&v) {Ok(())} else {Err(format!("cannot parse '{}' as a time expression", v))} )
                 .value_name("date")
             )
+            .arg(
+                Arg::with_name("fiscal-year-start")
+                .long("fiscal-year-start")
+                .help("Sets the month and day on which the fiscal year begins")
+                .long_help("The first day of the fiscal year, given as <month>-<day>; e.g., 10-1 for the 1st of October. \
+                If this is set, the expressions 'this fiscal year', 'last fiscal year', and 'next fiscal year' become available, and \
+                with the --fiscal option of summary and statistics 'this year', 'last year', and 'next year' mean the fiscal year.")
+                .validator(valid_fiscal_year_start)
+                .value_name("month-day")
+            )
             .arg(
                 Arg::with_name("sunday-begins-week")
                 .long("sunday-begins-week")
@@ -280,6 +470,56 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .possible_values(&["12", "24"])
                 .value_name("type")
             )
+            .arg(
+                Arg::with_name("time-format")
+                .long("time-format")
+                .help("Sets the strftime pattern used to display times")
+                .long_help("A strftime pattern such as '%H:%M:%S' used to display times of day in summaries, \
+                in the output of the first and last subcommands, and in vacation listings. If no pattern is set, \
+                times are displayed according to the --clock parameter. See https://docs.rs/chrono/0.4.10/chrono/format/strftime/index.html.")
+                .validator(valid_time_format)
+                .value_name("pattern")
+            )
+            .arg(
+                Arg::with_name("date-format")
+                .long("date-format")
+                .help("Sets the strftime pattern used to display dates")
+                .long_help("A strftime pattern such as '%Y-%m-%d' used to display dates in the day headers of summaries, \
+                in the output of the first and last subcommands, and in vacation listings. If no pattern is set, \
+                day headers look like 'Friday, 10 April' and vacation listings use %F.")
+                .validator(valid_date_format)
+                .value_name("pattern")
+            )
+            .arg(
+                Arg::with_name("locale")
+                .long("locale")
+                .help("Sets the language used for the names of days and months")
+                .long_help("A locale such as 'de' or 'fr_FR.UTF-8' determining the language of the weekday and month \
+                names in summaries, the when subcommand, and parse-time. Only the language portion of the locale matters. \
+                Unless this is set, names are in English.")
+                .validator(|v| if known_locale(&v) {Ok(())} else {Err(format!("unknown locale '{}'; known languages: {}", v, LOCALES.join(", ")))})
+                .value_name("locale")
+            )
+            .arg(
+                Arg::with_name("week-numbers")
+                .long("week-numbers")
+                .help("Sets whether multi-week summaries show ISO week numbers; default value: false")
+                .long_help("If this is true, when a summary covers more than one week, each day header is followed by the ISO 8601 \
+                number of the week it belongs to. This can be turned on for a particular summary with summary --week-numbers.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("utc-offsets")
+                .long("utc-offsets")
+                .help("Sets whether new log lines record the local UTC offset; default value: false")
+                .long_help("If you move between time zones, naive local timestamps will distort durations. \
+                If this is true, each new line in the log will record the UTC offset in force when it was written, \
+                and times will be converted into the current local time zone for display. See the zone subcommand \
+                for migrating existing logs.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
             .arg(
                 Arg::with_name("length-pay-period")
                 .long("length-pay-period")
@@ -287,6 +527,17 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .validator(valid_length_pay_period)
                 .value_name("int")
             )
+            .arg(
+                Arg::with_name("pay-period")
+                .long("pay-period")
+                .help("Sets how pay periods are reckoned; default value: fixed")
+                .long_help("Fixed pay periods are some number of days long, each beginning where the last ended; \
+                see --start-pay-period and --length-pay-period. Monthly pay periods are calendar months. Semimonthly \
+                pay periods run from the 1st to the 15th and from the 16th to the end of the month. Neither of \
+                these last requires a start or length.")
+                .possible_values(&["fixed", "monthly", "semimonthly"])
+                .value_name("cadence")
+            )
             .arg(
                 Arg::with_name("day-length")
                 .long("day-length")
@@ -294,6 +545,18 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .validator(valid_day_length)
                 .value_name("num")
             )
+            .arg(
+                Arg::with_name("day-length-for")
+                .long("day-length-for")
+                .help("Sets expected number of hours in a workday for a particular day of the week")
+                .long_help("If you work a compressed schedule or part time, your expected hours may vary by day of the week. \
+                This sets the hours expected on a particular day, identified by a letter from SMTWHFA, where S is Sunday and A is Saturday, etc. \
+                Days without a particular length use the --day-length value. A length of 0 makes the day no longer a workday. \
+                E.g., --day-length-for M 10 --day-length-for F 0")
+                .value_name("day hours")
+                .multiple(true)
+                .number_of_values(2)
+            )
             .arg(
                 Arg::with_name("beginning-work-day")
                 .long("beginning-work-day")
@@ -301,6 +564,57 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .validator(valid_beginning_work_day)
                 .value_name("hours[:minutes]")
             )
+            .arg(
+                Arg::with_name("day-boundary")
+                .long("day-boundary")
+                .help("Sets when one day ends and the next begins; default value: 0:00")
+                .long_help("If you work past midnight, you may not want the work after midnight attributed to the following day. \
+                This sets the moment, before noon, at which one day ends and the next begins for the purposes of summaries, \
+                the when subcommand, and vacation time. E.g., with --day-boundary 4 work done at 2 am on Tuesday belongs to Monday. \
+                Period expressions such as 'yesterday' or 'last week' are also shifted to begin and end at this time.")
+                .validator(valid_day_boundary)
+                .value_name("hours[:minutes]")
+            )
+            .arg(
+                Arg::with_name("auto-close-at")
+                .long("auto-close-at")
+                .help("Sets when events left open overnight are deemed to have ended")
+                .long_help("If you forget to end the last event of the day, summaries show it running until you begin \
+                the next one, perhaps the next morning. With this set, an event that runs past the day boundary is treated \
+                as having ended at this time on the day it began, or, if it began later than this, at the day boundary. \
+                `job doctor --fix` adds the corresponding DONE lines to the log. E.g., --auto-close-at 18:00.")
+                .validator(valid_auto_close_at)
+                .value_name("hours[:minutes]")
+                .conflicts_with("auto-close-after")
+            )
+            .arg(
+                Arg::with_name("auto-close-after")
+                .long("auto-close-after")
+                .help("Sets how long events left open overnight are deemed to have lasted")
+                .long_help("Like --auto-close-at, but an event that runs past the day boundary is treated as having \
+                ended this many hours after it began, or after the length of the work day if the value is 'day-length', \
+                though never later than the day boundary. E.g., --auto-close-after 2 or --auto-close-after day-length.")
+                .validator(valid_auto_close_after)
+                .value_name("hours|day-length")
+            )
+            .arg(
+                Arg::with_name("default-profile")
+                .long("default-profile")
+                .help("Sets the profile used when neither --profile nor --directory is given")
+                .long_help("Sets the profile used when neither --profile nor --directory is given. \
+                Profile settings are kept in the base configuration whatever profile is in use.")
+                .validator(valid_profile_name)
+                .value_name("name")
+            )
+            .arg(
+                Arg::with_name("profile-directory")
+                .long("profile-directory")
+                .help("Sets the directory in which a profile's log is kept")
+                .long_help("By default a profile's log is kept in a subdirectory of the profiles directory \
+                in the base directory. This lets you keep it elsewhere. E.g., --profile-directory work ~/Dropbox/work-log")
+                .value_names(&["name", "dir"])
+                .number_of_values(2)
+            )
             .arg(
                 Arg::with_name("workdays")
                 .long("workdays")
@@ -310,6 +624,17 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 where S means Sunday and A, Saturday, etc."))})
                 .value_name("days")
             )
+            .arg(
+                Arg::with_name("schedule-change")
+                .long("schedule-change")
+                .help("Records that your work schedule changed as of a particular date")
+                .long_help("If your workdays, day length, or the beginning of your work day change, you don't want this \
+                to alter summaries of earlier periods. This option preserves the current schedule as the one in force before the \
+                given date. Combine it with the options that set the new schedule; e.g., \
+                --schedule-change 2024-03-01 --workdays MTWH --day-length 10. Schedule changes must be recorded in chronological order.")
+                .validator(|v| if parsable(&v) {Ok(())} else {Err(format!("cannot parse '{}' as a time expression", v))} )
+                .value_name("date")
+            )
             .arg(
                 Arg::with_name("editor")
                 .long("editor")
@@ -329,6 +654,218 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .validator(valid_max_width)
                 .value_name("num")
             )
+            .arg(
+                Arg::with_name("backups")
+                .long("backups")
+                .help("Sets the number of backups kept; default value: 10")
+                .long_help("The backup subcommand, and the edit subcommand before it opens the editor, \
+                save copies of the log, vacation file, and configuration. Once there are more than this many, \
+                the oldest are deleted. If this is 0, no backups are kept.")
+                .validator(valid_backups)
+                .value_name("int")
+            )
+            .arg(
+                Arg::with_name("perl-compatible")
+                .long("perl-compatible")
+                .help("Sets whether the log and vacation file are kept readable by the Perl client; default value: false")
+                .long_help("The Perl App::JobLog cannot read some of what this version of Job Log writes: UTC offsets and \
+                user names in log lines and the times at which repeating vacations take effect or cease. If this is true, \
+                new log lines will have neither UTC offsets nor user names, vacation records will be saved without the \
+                extra times, and the vacation subcommand will refuse to set them, so you can go on using both clients.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("warn-new-tags")
+                .long("warn-new-tags")
+                .help("Sets whether add and note warn of tags not yet in the log; default value: false")
+                .long_help("If this is true, the add and note subcommands warn you when you give a tag found nowhere in \
+                the log, suggesting any similar tags, so you catch a typo like 'meetign' as you make it. With --strict-tags \
+                they refuse such tags unless they are given with --new-tag.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("fold-tag-case")
+                .long("fold-tag-case")
+                .help("Sets whether new tags are put in lower case; default value: false")
+                .long_help("If this is true, the tags given to the add, note, resume, and switch subcommands are put in \
+                lower case, so 'Meeting' and 'meeting' are one tag. To apply this to the tags already in the log, use \
+                tag --normalize.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("require-tags")
+                .long("require-tags")
+                .help("Sets whether the add subcommand refuses untagged events; default value: false")
+                .long_help("If this is true, the add subcommand will not add an event without at least one tag. When \
+                it is run in a terminal it asks for the missing tags; otherwise it leaves the log unchanged.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("tag-alias")
+                .long("tag-alias")
+                .help("Sets a tag to be replaced by another when written")
+                .long_help("Makes the add, note, resume, and switch subcommands write the second tag wherever they are \
+                given the first, so a shorthand or a common misspelling becomes the tag you report on. To apply this to \
+                the tags already in the log, use tag --normalize. You may configure any number of aliases. \
+                E.g., --tag-alias mtg meeting")
+                .value_names(&["alias", "tag"])
+                .multiple(true)
+                .number_of_values(2)
+                .validator(|v| if some_nws(&v) {Ok(())} else {Err(format!("{:?} is not a suitable tag: it has no non-whitespace character", v))} )
+            )
+            .arg(
+                Arg::with_name("user-name")
+                .long("user-name")
+                .help("Sets the author recorded in new events and notes")
+                .long_help("If several people append to a shared log, say over a network share, setting a user-name \
+                causes each new event and note to record its author. Summaries show the authors of events and notes \
+                and the --author option filters by author.")
+                .validator(|v| if v.trim().is_empty() || v.contains('\n') {
+                    Err(String::from("a user-name must be a single line with some non-whitespace character"))
+                } else {
+                    Ok(())
+                })
+                .value_name("name")
+            )
+            .arg(
+                Arg::with_name("sync-remote")
+                .long("sync-remote")
+                .help("Sets the git repository the sync subcommand pulls from and pushes to")
+                .long_help("The URL or path of a git repository. The sync subcommand will pull changes to the job log directory from \
+                this repository and push local changes to it. E.g., --sync-remote git@example.com:me/joblog.git")
+                .validator(|v| if v.trim().is_empty() || v.contains(char::is_whitespace) {
+                    Err(format!("'{}' is not a plausible git remote", v))
+                } else {
+                    Ok(())
+                })
+                .value_name("url")
+            )
+            .arg(
+                Arg::with_name("caldav-url")
+                .long("caldav-url")
+                .help("Sets the CalDAV calendar the push-calendar subcommand uploads events to")
+                .long_help("The URL of a CalDAV calendar collection. The push-calendar subcommand will upload events to it, \
+                one resource per event. E.g., --caldav-url https://dav.example.com/calendars/me/work/")
+                .validator(|v| if v.starts_with("http://") || v.starts_with("https://") {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not an http or https URL", v))
+                })
+                .value_name("url")
+            )
+            .arg(
+                Arg::with_name("caldav-user")
+                .long("caldav-user")
+                .help("Sets the user name for the CalDAV calendar")
+                .long_help("The user name the push-calendar subcommand gives the CalDAV server. The password is taken from \
+                the JOBLOG_CALDAV_PASSWORD environment variable or, if that is unset, from your ~/.netrc file; it is never \
+                kept in the configuration.")
+                .validator(|v| if v.trim().is_empty() || v.contains(char::is_whitespace) {
+                    Err(format!("'{}' is not a plausible user name", v))
+                } else {
+                    Ok(())
+                })
+                .value_name("name")
+            )
+            .arg(
+                Arg::with_name("jira-url")
+                .long("jira-url")
+                .help("Sets the JIRA server the export subcommand posts worklogs to")
+                .long_help("The base URL of a JIRA server. `job export --jira --post` will add worklog entries to the \
+                issues on this server. E.g., --jira-url https://example.atlassian.net")
+                .validator(|v| if v.starts_with("http://") || v.starts_with("https://") {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not an http or https URL", v))
+                })
+                .value_name("url")
+            )
+            .arg(
+                Arg::with_name("jira-user")
+                .long("jira-user")
+                .help("Sets the user name for the JIRA server")
+                .long_help("The user name, typically an email address, the export subcommand gives the JIRA server. The \
+                API token is taken from the JOBLOG_JIRA_TOKEN environment variable or, if that is unset, from your \
+                ~/.netrc file; it is never kept in the configuration.")
+                .validator(|v| if v.trim().is_empty() || v.contains(char::is_whitespace) {
+                    Err(format!("'{}' is not a plausible user name", v))
+                } else {
+                    Ok(())
+                })
+                .value_name("name")
+            )
+            .arg(
+                Arg::with_name("slack-emoji")
+                .long("slack-emoji")
+                .help("Sets the emoji of the Slack status set for an event; default value: :computer:")
+                .long_help("The emoji shown beside the description of the ongoing event in your Slack status. \
+                Your status is kept only if the JOBLOG_SLACK_TOKEN environment variable holds a Slack user token with the \
+                users.profile:write scope. The token is not a configuration parameter, so sync cannot share it; a token left \
+                in the slack section of config.ini by an earlier version is ignored, and job complains until you remove it \
+                with --unset slack-token. \
+                When it is set, adding, resuming, or switching to an event sets your Slack status to the event's description, \
+                and ending or cancelling it clears your status. The global --no-hooks option suppresses this. \
+                E.g., --slack-emoji :hammer_and_wrench:")
+                .validator(|v| if v.len() > 2 && v.starts_with(':') && v.ends_with(':') && !v.contains(char::is_whitespace) {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not an emoji code such as :computer:", v))
+                })
+                .value_name("emoji")
+            )
+            .arg(
+                Arg::with_name("calendar-tag")
+                .long("calendar-tag")
+                .help("Sets the tag given events created by the import-calendar subcommand")
+                .long_help("The tag the import-calendar subcommand gives the events it creates for calendar meetings \
+                when no --tag is given. E.g., --calendar-tag meetings")
+                .validator(|v| if some_nws(&v) {Ok(())} else {Err(format!("{:?} is not a suitable tag: it has no non-whitespace character", v))} )
+                .value_name("tag")
+            )
+            .arg(
+                Arg::with_name("webhook")
+                .long("webhook")
+                .help("Sets a URL notified when you add, resume, or end an event or add a note")
+                .long_help("Names a URL that will receive an HTTP POST whenever the add, done, note, or resume \
+                subcommands change the log. The body is a JSON object whose \"action\" property is the subcommand \
+                and whose \"item\" property is the JSON representation of what was added to the log. Delivery \
+                uses curl, which must be installed. It happens in the background, so a slow or unreachable webhook \
+                does not hold up the command; curl retries a few times before it gives up. \
+                You may configure any number of webhooks. The global --no-hooks option suppresses notification. \
+                E.g., --webhook dashboard https://example.com/joblog")
+                .value_names(&["name", "url"])
+                .multiple(true)
+                .number_of_values(2)
+            )
+            .arg(
+                Arg::with_name("issue-pattern")
+                .long("issue-pattern")
+                .help("Sets a pattern recognizing issue references in descriptions")
+                .long_help("Names a regular expression that finds references to issues in the descriptions of events \
+                and a template for the URL of the issue referred to. In the template $0 stands for the whole reference, \
+                $1 for the first parenthesized group in the pattern, and so forth. `job summary --links` makes references \
+                into hyperlinks and `job statistics --by-issue` totals the time spent on each issue. You may configure any \
+                number of patterns. E.g., --issue-pattern github '#(\\d+)' 'https://github.com/me/repo/issues/$1'")
+                .value_names(&["name", "pattern", "url"])
+                .multiple(true)
+                .number_of_values(3)
+            )
+            .arg(
+                Arg::with_name("lint")
+                .long("lint")
+                .help("Sets the severity and threshold of a rule of the lint subcommand")
+                .long_help("Names a rule of the lint subcommand and gives its severity -- off, warning, or error -- \
+                optionally followed by a threshold. The rules, with their defaults, are untagged (warning), long-event \
+                (warning 10, in hours), short-description (warning 3, in characters), gap (off 60, in minutes), and \
+                missing-done (warning). E.g., --lint gap 'error 90'")
+                .value_names(&["rule", "setting"])
+                .multiple(true)
+                .number_of_values(2)
+            )
             .arg(
                 Arg::with_name("color")
                 .long("color")
@@ -339,6 +876,29 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .possible_values(&["true", "false"])
                 .value_name("bool")
             )
+            .arg(
+                Arg::with_name("pager")
+                .long("pager")
+                .help("Sets whether to page long output; default value: true")
+                .long_help("If this is true and the standard output is a terminal, long summaries, statistics, and \
+                vacation listings are shown through the pager named by the PAGER environment variable, or less if it is unset. \
+                It can be turned off for a particular command with the global --no-pager option.")
+                .possible_values(&["true", "false"])
+                .value_name("bool")
+            )
+            .arg(
+                Arg::with_name("vacation-precedence")
+                .long("vacation-precedence")
+                .help("Sets the order of the rules deciding which of overlapping vacation records counts; default value: kind, repeating, earlier, narrower")
+                .long_help("When vacation records overlap, a moment of vacation is counted only once, for the record that takes \
+                precedence. The rules are kind -- ordinary records over fixed and fixed over flex --, repeating -- repeating \
+                records over non-repeating --, earlier -- records beginning earlier over those beginning later --, and \
+                narrower -- shorter records over longer. A '-' before a rule reverses it. The rules you list are applied first, \
+                in the order given, and those you leave out after them, in the default order. E.g., \
+                --vacation-precedence 'narrower, -kind'")
+                .validator(|v| parse_vacation_precedence(&v).map(|_| ()))
+                .value_name("rules")
+            )
             .arg(
                 Arg::with_name("style")
                 .long("style")
@@ -353,8 +913,9 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .short("b")
                 .long("budget")
                 .help("Sets the time budget for a particular tag")
-                .long_help("Sets the time budget within the pay period for a particular tag. See the \"when\" command. \
-                E.g., --budget foo 12.5")
+                .long_help("Sets the time budget for a particular tag. A bare number of hours is a budget for the pay period; \
+                otherwise give the period after a slash: per day, week, month, or pay period. E.g., --budget foo 12.5 or \
+                --budget meetings 5h/week. See the \"budget\" and \"when\" commands.")
                 .value_name("tag hours")
                 .multiple(true)
                 .number_of_values(2)
@@ -366,11 +927,40 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .help("Returns a configurable parameter to its default; to unset styles you need to provide both \
                 'style' and the parameter you wish to unset; e.g., --unset 'style even'. \
                 Likewise for time budgets you need to provide both 'budget' and a tag identifying a particular \
-                budget; e.g., --unset 'budget foo'")
+                budget; e.g., --unset 'budget foo'. --unset schedule-history forgets all earlier work schedules. To unset the day length for a particular day of the week \
+                provide both 'day-length-for' and the day; e.g., --unset 'day-length-for F'. Likewise, --unset 'profile-directory work' \
+                returns the work profile to the default directory, --unset 'webhook dashboard' removes the dashboard webhook, \
+                --unset 'tag-alias mtg' removes the alias mtg, and --unset 'lint gap' restores the default setting of the gap rule.")
                 .value_name("param")
                 .multiple(true)
                 .number_of_values(1)
             )
+            .arg(
+                Arg::with_name("init")
+                .long("init")
+                .help("Walks you through setting the principal parameters")
+                .long_help("Prompts you for workdays, day length, pay period, clock, editor, and color, validating each answer, \
+                and writes the configuration once all the questions have been answered. Pressing enter keeps the value shown \
+                in brackets. Any other options are applied after the wizard has finished.")
+            )
+            .arg(
+                Arg::with_name("export")
+                .long("export")
+                .help("Saves the configuration to the given file")
+                .long_help("Writes the non-default configuration parameters, including styles, to the given file \
+                so that they may be imported on another machine via --import. The export occurs after any other \
+                changes have been made.")
+                .value_name("file")
+            )
+            .arg(
+                Arg::with_name("import")
+                .long("import")
+                .help("Merges in the configuration from the given file")
+                .long_help("Merges the parameters in the given file, typically one produced with --export, into \
+                the current configuration, reporting each change. Parameters not mentioned in the file are left as they are. \
+                The import occurs before any other changes are made.")
+                .value_name("file")
+            )
             .arg(
                 Arg::with_name("list")
                 .short("l")
@@ -382,10 +972,325 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
     )
 }
 
+// asks the question until it receives a valid answer
+#[cfg(feature = "cli")]
+fn ask(
+    question: &str,
+    default: &str,
+    validator: fn(String) -> Result<(), String>,
+    conf: &Configuration,
+) -> String {
+    loop {
+        let answer = match prompt_until_eof(question, default) {
+            Some(answer) => answer,
+            // with no more input, asking again would loop forever
+            None => {
+                fatal("no more input; the configuration is unchanged", conf);
+                unreachable!()
+            }
+        };
+        match validator(answer.clone()) {
+            Ok(()) => return answer,
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+// the answer to a wizard question which clears an optional setting
+#[cfg(feature = "cli")]
+const NONE: &str = "-";
+
+// the interactive configuration of configure --init
+#[cfg(feature = "cli")]
+fn wizard(conf: &mut Configuration) {
+    println!("Press enter to accept the value in brackets or - to clear an optional value.\n");
+    let workdays = ask(
+        "workdays, as a subset of SMTWHFA, where S is Sunday and A is Saturday",
+        &conf.serialize_workdays(),
+        |v| {
+            if Regex::new(r"\A[SMTWHFA]+\z").unwrap().is_match(&v) {
+                Ok(())
+            } else {
+                Err(String::from("please use only the letters SMTWHFA"))
+            }
+        },
+        conf,
+    );
+    conf.workdays(&workdays);
+    conf.day_length = ask(
+        "hours in a workday",
+        &conf.day_length.to_string(),
+        valid_day_length,
+        conf,
+    )
+    .parse()
+    .unwrap();
+    conf.length_pay_period = ask(
+        "days in a pay period",
+        &conf.length_pay_period.to_string(),
+        valid_length_pay_period,
+        conf,
+    )
+    .parse()
+    .unwrap();
+    let start_pay_period = ask(
+        "the first day of some pay period; e.g., 2020-01-06; - for none",
+        &conf
+            .start_pay_period
+            .map(|d| d.format("%F").to_string())
+            .unwrap_or_else(|| String::from("")),
+        |v| {
+            if v.is_empty() || v == NONE || parsable(&v) {
+                Ok(())
+            } else {
+                Err(format!("cannot parse '{}' as a time expression", v))
+            }
+        },
+        conf,
+    );
+    conf.start_pay_period = if start_pay_period.is_empty() || start_pay_period == NONE {
+        None
+    } else {
+        let (t, _, _) = parse(&start_pay_period, conf.two_timer_config()).unwrap();
+        Some(t.date())
+    };
+    conf.h12 = ask(
+        "clock, 12 or 24",
+        if conf.h12 { "12" } else { "24" },
+        |v| {
+            if v == "12" || v == "24" {
+                Ok(())
+            } else {
+                Err(String::from("please answer 12 or 24"))
+            }
+        },
+        conf,
+    ) == "12";
+    let editor = ask(
+        "text editor; - to use VISUAL or EDITOR",
+        &conf
+            .editor
+            .as_ref()
+            .map(|e| e.join(" "))
+            .unwrap_or_else(|| String::from("")),
+        |_| Ok(()),
+        conf,
+    );
+    if editor.is_empty() || editor == NONE {
+        conf.editor = None;
+    } else {
+        conf.editor(&editor);
+    }
+    let (effective_color, _) = conf.effective_color();
+    let color = ask(
+        "use color, true or false",
+        &effective_color.to_string(),
+        |v| {
+            if v == "true" || v == "false" {
+                Ok(())
+            } else {
+                Err(String::from("please answer true or false"))
+            }
+        },
+        conf,
+    );
+    // leave the color unset, letting NO_COLOR decide, unless it has changed
+    if (color == "true") != effective_color {
+        conf.color = Some(color == "true");
+    }
+    conf.write();
+    println!();
+    success("wrote the configuration", conf);
+}
+
+// merges the configuration in the given file into the current configuration file
+#[cfg(feature = "cli")]
+fn import(file: &str, conf: &Configuration) {
+    let imported = match Ini::load_from_file(file) {
+        Ok(ini) => ini,
+        Err(e) => {
+            fatal(format!("could not read {}: {}", file, e), conf);
+            unreachable!()
+        }
+    };
+    let path = Configuration::config_file(conf.directory());
+    let mut ini = Ini::load_from_file(&path).unwrap_or_else(|_| conf.ini());
+    let mut changes = vec![];
+    for (section, properties) in imported.iter() {
+        for (key, value) in properties.iter() {
+            let name = match section {
+                Some("time") | Some("summary") | Some("external") | Some("color") | Some("log")
+                | None => key.to_owned(),
+                Some(section) => format!("{} {}", section, key),
+            };
+            if ini.get_from(section, key) == Some(value) {
+                continue;
+            }
+            changes.push(format!("setting {} to {}!", name, value));
+            ini.with_section(section).set(key, value);
+        }
+    }
+    if changes.is_empty() {
+        warn(format!("{} does not change the configuration", file), conf);
+    } else {
+        // confirm what we imported is parsable before touching config.ini
+        if let Err(e) = check_ini(&ini) {
+            fatal(
+                format!("{} does not make a valid configuration: {}", file, e),
+                conf,
+            );
+        }
+        let tmp = path.with_file_name("config.ini.tmp");
+        ini.write_to_file(&tmp)
+            .expect("could not write config.ini.tmp");
+        std::fs::rename(&tmp, &path).expect("could not replace config.ini");
+        for change in changes {
+            success(change, conf);
+        }
+    }
+}
+
+// finds any value Configuration::read would be unable to parse
+#[cfg(feature = "cli")]
+fn check_ini(ini: &Ini) -> Result<(), String> {
+    fn number<T: std::str::FromStr>(section: &str, key: &str, v: &str) -> Result<T, String> {
+        v.parse::<T>()
+            .map_err(|_| format!("{} {}: '{}' is not a valid number", section, key, v))
+    }
+    fn time(section: &str, key: &str, v: &str) -> Result<(), String> {
+        let parts = v.split(":").collect::<Vec<&str>>();
+        if parts.len() != 2 {
+            return Err(format!("{} {}: '{}' is not hours:minutes", section, key, v));
+        }
+        for part in parts {
+            number::<usize>(section, key, part)?;
+        }
+        Ok(())
+    }
+    fn date(section: &str, key: &str, v: &str, separator: &str) -> Result<(), String> {
+        let parts = v.split(separator).collect::<Vec<&str>>();
+        if parts.len() == 3 {
+            if let (Ok(y), Ok(m), Ok(d)) = (parts[0].parse(), parts[1].parse(), parts[2].parse()) {
+                if NaiveDate::from_ymd_opt(y, m, d).is_some() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(format!("{} {}: '{}' is not a valid date", section, key, v))
+    }
+    for (section, properties) in ini.iter() {
+        let name = section.unwrap_or("general");
+        for (key, value) in properties.iter() {
+            match (section, key) {
+                (Some("time"), "day-length") => {
+                    number::<f32>(name, key, value)?;
+                }
+                (Some("time"), "pay-period-length") => {
+                    number::<u32>(name, key, value)?;
+                }
+                (Some("time"), "start-pay-period") => date(name, key, value, " ")?,
+                (Some("time"), "beginning-work-day") => time(name, key, value)?,
+                (Some("time"), "day-boundary") => {
+                    parse_day_boundary(value).map_err(|e| format!("{} {}: {}", name, key, e))?;
+                }
+                (Some("summary"), "max-width") => {
+                    number::<usize>(name, key, value)?;
+                }
+                (Some("summary"), "precision") => {
+                    let precisions = [
+                        "0", "1", "2", "3", "half", "third", "quarter", "sixth", "twelfth",
+                        "sixtieth",
+                    ];
+                    if !precisions.contains(&value) {
+                        return Err(format!("{} {}: '{}' is not a precision", name, key, value));
+                    }
+                }
+                (Some("summary"), "truncation")
+                    if !["round", "floor", "ceiling"].contains(&value) =>
+                {
+                    return Err(format!("{} {}: '{}' is not a truncation", name, key, value));
+                }
+                (Some("summary"), "date-format") => valid_date_format(value.to_owned())?,
+                (Some("summary"), "time-format") => valid_time_format(value.to_owned())?,
+                (Some("log"), "backups") => {
+                    number::<usize>(name, key, value)?;
+                }
+                (Some("schedule"), _) if Configuration::day_index(key).is_some() => {
+                    number::<f32>(name, key, value)?;
+                }
+                (Some("budget"), _) if parse_budget(value).is_none() => {
+                    return Err(format!("{} {}: '{}' is not a budget", name, key, value));
+                }
+                (Some(s), _) if s.starts_with("schedule before ") => {
+                    if key == "day-length" || Configuration::day_index(key).is_some() {
+                        number::<f32>(name, key, value)?;
+                    } else if key == "beginning-work-day" {
+                        time(name, key, value)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        if let Some(d) = section.and_then(|s| s.strip_prefix("schedule before ")) {
+            date(name, "date", d, "-")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn valid_profile_name(v: String) -> Result<(), String> {
+    if v == "default" {
+        Err(String::from("'default' cannot be the name of a profile"))
+    } else if !v.is_empty()
+        && v.chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a valid profile name; use only letters, numbers, hyphens, and underscores",
+            v
+        ))
+    }
+}
+
+#[cfg(feature = "cli")]
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let mut did_something = false;
     let mut write = false;
     let mut conf = Configuration::read(None, directory);
+    // profile settings live in the base configuration whatever profile is in use
+    let mut base = if conf.is_base() {
+        None
+    } else {
+        Some(Configuration::base())
+    };
+    let mut write_base = false;
+    if matches.is_present("init") {
+        did_something = true;
+        wizard(&mut conf);
+    }
+    if let Some(file) = matches.value_of("import") {
+        did_something = true;
+        import(file, &conf);
+        conf = Configuration::read(None, directory);
+    }
+    // this must precede any changes to the current schedule
+    if let Some(v) = matches.value_of("schedule-change") {
+        did_something = true;
+        let (date, _, _) = parse(v, conf.two_timer_config()).unwrap();
+        let date = date.date();
+        match conf.record_schedule_change(date) {
+            Ok(()) => {
+                success(
+                    format!("recorded the current schedule as in force before {}", date),
+                    &conf,
+                );
+                write = true;
+            }
+            Err(s) => fatal(s, &conf),
+        }
+    }
     if let Some(v) = matches.value_of("start-pay-period") {
         did_something = true;
         let tt_conf = Config::new()
@@ -409,6 +1314,23 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             write = true;
         }
     }
+    if let Some(v) = matches.value_of("fiscal-year-start") {
+        did_something = true;
+        let (month, day) = Configuration::parse_month_day(v).unwrap();
+        if conf.fiscal_year_start == Some((month, day)) {
+            warn(
+                format!("fiscal-year-start is already {}-{}!", month, day),
+                &conf,
+            );
+        } else {
+            success(
+                format!("setting fiscal-year-start to {}-{}!", month, day),
+                &conf,
+            );
+            conf.fiscal_year_start = Some((month, day));
+            write = true;
+        }
+    }
     if matches.is_present("sunday-begins-week") {
         did_something = true;
         if let Some(v) = matches.value_of("sunday-begins-week") {
@@ -434,6 +1356,58 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
+    if let Some(v) = matches.value_of("time-format") {
+        did_something = true;
+        if conf.time_format.as_deref() == Some(v) {
+            warn(format!("time-format is already {}!", v), &conf);
+        } else {
+            success(format!("setting time-format to {}!", v), &conf);
+            conf.time_format = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("date-format") {
+        did_something = true;
+        if conf.date_format.as_deref() == Some(v) {
+            warn(format!("date-format is already {}!", v), &conf);
+        } else {
+            success(format!("setting date-format to {}!", v), &conf);
+            conf.date_format = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("locale") {
+        did_something = true;
+        if conf.locale.as_deref() == Some(v) {
+            warn(format!("locale is already {}!", v), &conf);
+        } else {
+            success(format!("setting locale to {}!", v), &conf);
+            conf.locale = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("week-numbers") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.week_numbers {
+            warn(format!("week-numbers is already {}!", v), &conf);
+        } else {
+            success(format!("setting week-numbers to {}!", v), &conf);
+            conf.week_numbers = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("utc-offsets") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.utc_offsets {
+            warn(format!("utc-offsets is already {}!", v), &conf);
+        } else {
+            success(format!("setting utc-offsets to {}!", v), &conf);
+            conf.utc_offsets = v;
+            write = true;
+        }
+    }
     if matches.is_present("color") {
         did_something = true;
         if let Some(v) = matches.value_of("color") {
@@ -444,6 +1418,72 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             write = true;
         }
     }
+    if let Some(v) = matches.value_of("pager") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.pager {
+            warn(format!("pager is already {}!", v), &conf);
+        } else {
+            success(format!("setting pager to {}!", v), &conf);
+            conf.pager = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("backups") {
+        did_something = true;
+        let v: usize = v.parse().unwrap();
+        if v == conf.backups {
+            warn(format!("backups is already {}!", v), &conf);
+        } else {
+            success(format!("setting backups to {}!", v), &conf);
+            conf.backups = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("perl-compatible") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.perl_compatible {
+            warn(format!("perl-compatible is already {}!", v), &conf);
+        } else {
+            success(format!("setting perl-compatible to {}!", v), &conf);
+            conf.perl_compatible = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("warn-new-tags") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.warn_new_tags {
+            warn(format!("warn-new-tags is already {}!", v), &conf);
+        } else {
+            success(format!("setting warn-new-tags to {}!", v), &conf);
+            conf.warn_new_tags = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("fold-tag-case") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.fold_tag_case {
+            warn(format!("fold-tag-case is already {}!", v), &conf);
+        } else {
+            success(format!("setting fold-tag-case to {}!", v), &conf);
+            conf.fold_tag_case = v;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("require-tags") {
+        did_something = true;
+        let v: bool = v.parse().unwrap();
+        if v == conf.require_tags {
+            warn(format!("require-tags is already {}!", v), &conf);
+        } else {
+            success(format!("setting require-tags to {}!", v), &conf);
+            conf.require_tags = v;
+            write = true;
+        }
+    }
     if matches.is_present("length-pay-period") {
         did_something = true;
         if let Some(v) = matches.value_of("length-pay-period") {
@@ -483,6 +1523,40 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             write = true;
         }
     }
+    if let Some(v) = matches.value_of("day-boundary") {
+        did_something = true;
+        let boundary = parse_day_boundary(v).unwrap();
+        if conf.day_boundary == boundary {
+            warn(
+                format!("day-boundary is already {}:{:02}!", boundary.0, boundary.1),
+                &conf,
+            );
+        } else {
+            success(
+                format!("setting day-boundary to {}:{:02}!", boundary.0, boundary.1),
+                &conf,
+            );
+            conf.day_boundary = boundary;
+            write = true;
+        }
+    }
+    for key in &["auto-close-at", "auto-close-after"] {
+        if let Some(v) = matches.value_of(key) {
+            did_something = true;
+            let policy = if *key == "auto-close-at" {
+                AutoClose::parse_at(v)
+            } else {
+                AutoClose::parse_after(v)
+            };
+            if conf.auto_close == policy {
+                warn(format!("{} is already {}!", key, v), &conf);
+            } else {
+                success(format!("setting {} to {}!", key, v), &conf);
+                conf.auto_close = policy;
+                write = true;
+            }
+        }
+    }
     if matches.is_present("day-length") {
         did_something = true;
         if let Some(v) = matches.value_of("day-length") {
@@ -496,6 +1570,48 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
+    if let Some(vs) = matches.values_of("day-length-for") {
+        let values = vs.map(|s| s.to_string()).collect::<Vec<_>>();
+        for v in values.chunks(2) {
+            let day = v[0].clone();
+            let index = if let Some(i) = Configuration::day_index(&day) {
+                i
+            } else {
+                fatal(
+                    format!(
+                        "'{}' is not a day of the week; expected one of the letters SMTWHFA",
+                        day
+                    ),
+                    &conf,
+                );
+                unreachable!()
+            };
+            let hours = match v[1].parse::<f32>() {
+                Ok(h) if (0.0..=24.0).contains(&h) => h,
+                _ => {
+                    fatal(
+                        format!("cannot parse \"{}\" as a number of hours in a day", v[1]),
+                        &conf,
+                    );
+                    unreachable!()
+                }
+            };
+            did_something = true;
+            if conf.day_lengths[index] == Some(hours) {
+                warn(
+                    format!("day-length for {} is already {}!", day, hours),
+                    &conf,
+                );
+            } else {
+                success(
+                    format!("setting day-length for {} to {}!", day, hours),
+                    &conf,
+                );
+                conf.day_lengths[index] = Some(hours);
+                write = true;
+            }
+        }
+    }
     if matches.is_present("precision") {
         did_something = true;
         if let Some(v) = matches.value_of("precision") {
@@ -509,6 +1625,17 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
+    if let Some(v) = matches.value_of("pay-period") {
+        did_something = true;
+        let v = PayPeriod::from_s(v);
+        if v == conf.pay_period {
+            warn(format!("pay-period is already {}!", v.to_s()), &conf);
+        } else {
+            success(format!("setting pay-period to {}!", v.to_s()), &conf);
+            conf.pay_period = v;
+            write = true;
+        }
+    }
     if matches.is_present("truncation") {
         did_something = true;
         if let Some(v) = matches.value_of("truncation") {
@@ -534,6 +1661,41 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
+    if let Some(v) = matches.value_of("default-profile") {
+        did_something = true;
+        let target = base.as_mut().unwrap_or(&mut conf);
+        if target.default_profile.as_deref() == Some(v) {
+            warn(format!("default-profile is already {}!", v), target);
+        } else {
+            success(format!("setting default-profile to {}!", v), target);
+            target.default_profile = Some(v.to_owned());
+            write_base = true;
+        }
+    }
+    if let Some(vs) = matches.values_of("profile-directory") {
+        did_something = true;
+        let vs: Vec<&str> = vs.collect();
+        let (name, dir) = (vs[0], vs[1]);
+        let target = base.as_mut().unwrap_or(&mut conf);
+        if let Err(e) = valid_profile_name(name.to_owned()) {
+            fatal(e, target);
+        }
+        if target.profile_directories.get(name).map(|d| d.as_str()) == Some(dir) {
+            warn(
+                format!("the directory of profile {} is already {}!", name, dir),
+                target,
+            );
+        } else {
+            success(
+                format!("setting the directory of profile {} to {}!", name, dir),
+                target,
+            );
+            target
+                .profile_directories
+                .insert(name.to_owned(), dir.to_owned());
+            write_base = true;
+        }
+    }
     if let Some(v) = matches.value_of("editor") {
         did_something = true;
         if conf.editor.is_some() && v == conf.editor.as_ref().unwrap().join(" ") {
@@ -544,6 +1706,192 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             write = true;
         }
     }
+    if let Some(v) = matches.value_of("user-name") {
+        did_something = true;
+        let v = v.trim();
+        if conf.user_name.as_deref() == Some(v) {
+            warn(format!("user-name is already {}!", v), &conf);
+        } else {
+            success(format!("setting user-name to {}!", v), &conf);
+            conf.user_name = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("sync-remote") {
+        did_something = true;
+        if conf.sync_remote.as_deref() == Some(v) {
+            warn(format!("sync-remote is already {}!", v), &conf);
+        } else {
+            success(format!("setting sync-remote to {}!", v), &conf);
+            conf.sync_remote = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("caldav-url") {
+        did_something = true;
+        if conf.caldav_url.as_deref() == Some(v) {
+            warn(format!("caldav-url is already {}!", v), &conf);
+        } else {
+            success(format!("setting caldav-url to {}!", v), &conf);
+            conf.caldav_url = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("caldav-user") {
+        did_something = true;
+        if conf.caldav_user.as_deref() == Some(v) {
+            warn(format!("caldav-user is already {}!", v), &conf);
+        } else {
+            success(format!("setting caldav-user to {}!", v), &conf);
+            conf.caldav_user = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("jira-url") {
+        did_something = true;
+        if conf.jira_url.as_deref() == Some(v) {
+            warn(format!("jira-url is already {}!", v), &conf);
+        } else {
+            success(format!("setting jira-url to {}!", v), &conf);
+            conf.jira_url = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("jira-user") {
+        did_something = true;
+        if conf.jira_user.as_deref() == Some(v) {
+            warn(format!("jira-user is already {}!", v), &conf);
+        } else {
+            success(format!("setting jira-user to {}!", v), &conf);
+            conf.jira_user = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("vacation-precedence") {
+        did_something = true;
+        let rules = parse_vacation_precedence(v).unwrap();
+        if conf.vacation_precedence == rules {
+            warn(format!("vacation-precedence is already {}!", v), &conf);
+        } else {
+            success(format!("setting vacation-precedence to {}!", v), &conf);
+            conf.vacation_precedence = rules;
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("slack-emoji") {
+        did_something = true;
+        if conf.slack_emoji == v {
+            warn(format!("slack-emoji is already {}!", v), &conf);
+        } else {
+            success(format!("setting slack-emoji to {}!", v), &conf);
+            conf.slack_emoji = v.to_owned();
+            write = true;
+        }
+    }
+    if let Some(v) = matches.value_of("calendar-tag") {
+        did_something = true;
+        if conf.calendar_tag.as_deref() == Some(v) {
+            warn(format!("calendar-tag is already {}!", v), &conf);
+        } else {
+            success(format!("setting calendar-tag to {}!", v), &conf);
+            conf.calendar_tag = Some(v.to_owned());
+            write = true;
+        }
+    }
+    if let Some(vs) = matches.values_of("webhook") {
+        did_something = true;
+        let vs: Vec<&str> = vs.collect();
+        for pair in vs.chunks(2) {
+            let (name, url) = (pair[0], pair[1]);
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                fatal(
+                    format!("webhook {} is not an http or https URL: {}", name, url),
+                    &conf,
+                );
+            }
+            if conf.webhooks.get(name).map(|u| u.as_str()) == Some(url) {
+                warn(format!("webhook {} is already {}!", name, url), &conf);
+            } else {
+                success(format!("setting webhook {} to {}!", name, url), &conf);
+                conf.webhooks.insert(name.to_owned(), url.to_owned());
+                write = true;
+            }
+        }
+    }
+    if let Some(vs) = matches.values_of("tag-alias") {
+        did_something = true;
+        let vs: Vec<&str> = vs.collect();
+        for pair in vs.chunks(2) {
+            let (alias, tag) = (pair[0], pair[1]);
+            if alias == tag {
+                fatal(format!("tag {} cannot be an alias of itself", tag), &conf);
+            }
+            if conf.tag_aliases.get(alias).map(|t| t.as_str()) == Some(tag) {
+                warn(format!("tag-alias {} is already {}!", alias, tag), &conf);
+            } else {
+                success(format!("setting tag-alias {} to {}!", alias, tag), &conf);
+                conf.tag_aliases.insert(alias.to_owned(), tag.to_owned());
+                write = true;
+            }
+        }
+    }
+    if let Some(vs) = matches.values_of("issue-pattern") {
+        did_something = true;
+        let vs: Vec<&str> = vs.collect();
+        for triple in vs.chunks(3) {
+            let (name, pattern, url) = (triple[0], triple[1], triple[2]);
+            if let Err(e) = Regex::new(pattern) {
+                fatal(
+                    format!(
+                        "issue-pattern {} is not a valid regular expression: {}",
+                        name, e
+                    ),
+                    &conf,
+                );
+            }
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                fatal(
+                    format!("issue-pattern {} has no http or https URL: {}", name, url),
+                    &conf,
+                );
+            }
+            let value = (pattern.to_owned(), url.to_owned());
+            if conf.issue_patterns.get(name) == Some(&value) {
+                warn(
+                    format!("issue-pattern {} is already {} {}!", name, pattern, url),
+                    &conf,
+                );
+            } else {
+                success(
+                    format!("setting issue-pattern {} to {} {}!", name, pattern, url),
+                    &conf,
+                );
+                conf.issue_patterns.insert(name.to_owned(), value);
+                write = true;
+            }
+        }
+    }
+    if let Some(vs) = matches.values_of("lint") {
+        did_something = true;
+        let vs: Vec<&str> = vs.collect();
+        for pair in vs.chunks(2) {
+            let (rule, setting) = (pair[0], pair[1]);
+            let setting = match LintRule::parse(rule, setting) {
+                Ok(setting) => setting,
+                Err(e) => {
+                    fatal(e, &conf);
+                    unreachable!()
+                }
+            };
+            if conf.lint_rule(rule) == setting {
+                warn(format!("lint {} is already {}!", rule, setting), &conf);
+            } else {
+                success(format!("setting lint {} to {}!", rule, setting), &conf);
+                conf.lint_rules.insert(rule.to_owned(), setting);
+                write = true;
+            }
+        }
+    }
     if let Some(v) = matches.value_of("max-width") {
         did_something = true;
         let v = v.parse::<usize>().unwrap();
@@ -565,78 +1913,85 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     format!("cannot parse \"{}\" as a style specification", style),
                     &conf,
                 );
-            }
-            if conf.style_map.contains_key(&identifier) {
-                conf.style_map.insert(identifier, style);
+            }
+            if conf.style_map.contains_key(&identifier) {
+                conf.style_map.insert(identifier, style);
+            } else {
+                fatal(
+                    format!("there is no configurable style named '{}'", identifier),
+                    &conf,
+                );
+            }
+            success(format!("set {} to {}", v[0], v[1]), &conf);
+            did_something = true;
+            write = true;
+        }
+    }
+    if let Some(vs) = matches.values_of("budget") {
+        let mut budgets = conf.budgets.clone().unwrap_or_default();
+        let values = vs.map(|s| s.to_string()).collect::<Vec<_>>();
+        for v in values.chunks(2) {
+            let tag = v[0].clone();
+            if let Some((h, per)) = parse_budget(&v[1]) {
+                if per == BudgetPeriod::PayPeriod {
+                    match conf.hours_in_pay_period() {
+                        None => fatal(
+                            "cannot set pay period time budgets without an established pay period"
+                                .to_owned(),
+                            &conf,
+                        ),
+                        Some(0.0) => fatal(
+                            "cannot set time budgets if there are no expected work hours in pay period"
+                                .to_owned(),
+                            &conf,
+                        ),
+                        _ => (),
+                    }
+                }
+                if let Some(budget) = budgets.iter_mut().find(|p| p.0 == tag) {
+                    budget.1 = h;
+                    budget.2 = per;
+                } else {
+                    budgets.push((tag, h, per))
+                }
+                success(
+                    format!(
+                        "set time budget for \"{}\" to {} hours a {}",
+                        v[0],
+                        h,
+                        per.name()
+                    ),
+                    &conf,
+                );
+                did_something = true;
+                write = true;
             } else {
                 fatal(
-                    format!("there is no configurable style named '{}'", identifier),
+                    format!(
+                        "cannot parse \"{}\" as a budget; try something like 12.5 or 5h/week",
+                        v[1]
+                    ),
                     &conf,
                 );
             }
-            success(format!("set {} to {}", v[0], v[1]), &conf);
-            did_something = true;
-            write = true;
         }
-    }
-    if let Some(vs) = matches.values_of("budget") {
         if let Some(total_hours) = conf.hours_in_pay_period() {
-            if total_hours == 0.0 {
-                fatal(
-                    "cannot set time budgets if there are no expected work hours in pay period"
-                        .to_owned(),
+            let budgeted_hours: f32 = budgets
+                .iter()
+                .filter(|b| b.2 == BudgetPeriod::PayPeriod)
+                .map(|b| b.1)
+                .sum();
+            if budgeted_hours > total_hours {
+                warn(
+                    format!(
+                        "hours budgeted: {}; hours in pay period: {}",
+                        budgeted_hours, total_hours
+                    ),
                     &conf,
-                );
-            } else {
-                let mut budgets: Vec<(String, f32)> = conf
-                    .budgets
-                    .clone()
-                    .or_else(|| Some(vec![]))
-                    .unwrap()
-                    .iter()
-                    .map(|(tag, hours)| (tag.clone(), hours.clone()))
-                    .collect();
-                let values = vs.map(|s| s.to_string()).collect::<Vec<_>>();
-                for v in values.windows(2) {
-                    let tag = v[0].clone();
-                    let hours = v[1].clone();
-                    if let Ok(h) = hours.parse::<f32>() {
-                        if let Some(pair) = budgets.iter_mut().find(|p| p.0 == tag) {
-                            pair.1 = h;
-                        } else {
-                            budgets.push((tag, h))
-                        }
-                        success(
-                            format!("set time budget for \"{}\" to {} hours", v[0], v[1]),
-                            &conf,
-                        );
-                        did_something = true;
-                        write = true;
-                    } else {
-                        fatal(
-                            format!("cannot parse \"{}\" as a number of hours", hours),
-                            &conf,
-                        );
-                    }
-                }
-                let budgeted_hours: f32 = budgets.iter().map(|p| p.1).sum();
-                conf.budgets = Some(budgets);
-                if budgeted_hours > total_hours {
-                    warn(
-                        format!(
-                            "hours budgeted: {}; hours in pay period: {}",
-                            budgeted_hours, total_hours
-                        ),
-                        &conf,
-                    )
-                }
+                )
             }
-        } else {
-            fatal(
-                "cannot set time budgets without an established pay period".to_owned(),
-                &conf,
-            )
         }
+        conf.budgets = Some(budgets);
     }
     if let Some(vs) = matches.values_of("unset") {
         for v in vs {
@@ -656,14 +2011,102 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     conf.color = None;
                     write = true;
                 }
+                "pager" => {
+                    conf.pager = PAGER == "true";
+                    write = true;
+                }
                 "clock" => {
                     conf.h12 = "12" == CLOCK;
                     write = true;
                 }
+                "time-format" => {
+                    conf.time_format = None;
+                    write = true;
+                }
+                "date-format" => {
+                    conf.date_format = None;
+                    write = true;
+                }
+                "locale" => {
+                    conf.locale = None;
+                    write = true;
+                }
+                "week-numbers" => {
+                    conf.week_numbers = WEEK_NUMBERS == "true";
+                    write = true;
+                }
+                "utc-offsets" => {
+                    conf.utc_offsets = UTC_OFFSETS == "true";
+                    write = true;
+                }
                 "length-pay-period" => {
                     conf.length_pay_period = LENGTH_PAY_PERIOD.parse().unwrap();
                     write = true;
                 }
+                "pay-period" => {
+                    conf.pay_period = PayPeriod::from_s(PAY_PERIOD);
+                    write = true;
+                }
+                "sync-remote" => {
+                    conf.sync_remote = None;
+                    write = true;
+                }
+                "caldav-url" => {
+                    conf.caldav_url = None;
+                    write = true;
+                }
+                "caldav-user" => {
+                    conf.caldav_user = None;
+                    write = true;
+                }
+                "calendar-tag" => {
+                    conf.calendar_tag = None;
+                    write = true;
+                }
+                "slack-token" => {
+                    conf.stale_slack_token = false;
+                    write = true;
+                }
+                "slack-emoji" => {
+                    conf.slack_emoji = SLACK_EMOJI.to_owned();
+                    write = true;
+                }
+                "jira-url" => {
+                    conf.jira_url = None;
+                    write = true;
+                }
+                "jira-user" => {
+                    conf.jira_user = None;
+                    write = true;
+                }
+                "vacation-precedence" => {
+                    conf.vacation_precedence = vec![];
+                    write = true;
+                }
+                "backups" => {
+                    conf.backups = BACKUPS.parse().unwrap();
+                    write = true;
+                }
+                "perl-compatible" => {
+                    conf.perl_compatible = PERL_COMPATIBLE == "true";
+                    write = true;
+                }
+                "warn-new-tags" => {
+                    conf.warn_new_tags = WARN_NEW_TAGS == "true";
+                    write = true;
+                }
+                "fold-tag-case" => {
+                    conf.fold_tag_case = FOLD_TAG_CASE == "true";
+                    write = true;
+                }
+                "require-tags" => {
+                    conf.require_tags = REQUIRE_TAGS == "true";
+                    write = true;
+                }
+                "user-name" => {
+                    conf.user_name = None;
+                    write = true;
+                }
                 "max-width" => {
                     conf.max_width = None;
                     write = true;
@@ -680,6 +2123,10 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     conf.start_pay_period = None;
                     write = true;
                 }
+                "fiscal-year-start" => {
+                    conf.fiscal_year_start = None;
+                    write = true;
+                }
                 "sunday-begins-week" => {
                     conf.sunday_begins_week = SUNDAY_BEGINS_WEEK.parse().unwrap();
                     write = true;
@@ -688,9 +2135,78 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     conf.workdays(WORKDAYS);
                     write = true;
                 }
+                "day-boundary" => {
+                    conf.day_boundary = DAY_BOUNDARY;
+                    write = true;
+                }
+                "auto-close-at" | "auto-close-after" => {
+                    conf.auto_close = None;
+                    write = true;
+                }
+                "schedule-history" => {
+                    conf.schedule_history.clear();
+                    write = true;
+                }
+                "default-profile" => {
+                    base.as_mut().unwrap_or(&mut conf).default_profile = None;
+                    write_base = true;
+                }
                 _ => {
                     let parts = v.split_whitespace().collect::<Vec<_>>();
-                    if parts.len() == 2 && parts[0] == "style" {
+                    if parts.len() == 2 && parts[0] == "day-length-for" {
+                        if let Some(i) = Configuration::day_index(parts[1]) {
+                            write = true;
+                            set = true;
+                            conf.day_lengths[i] = None;
+                        } else {
+                            warning = Some(format!("unknown day of the week: \"{}\"", parts[1]));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "webhook" {
+                        if conf.webhooks.remove(parts[1]).is_some() {
+                            write = true;
+                            set = true;
+                        } else {
+                            warning = Some(format!("unknown webhook: \"{}\"", parts[1]));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "lint" {
+                        if conf.lint_rules.remove(parts[1]).is_some() {
+                            write = true;
+                            set = true;
+                        } else {
+                            warning = Some(format!("lint {} is already the default", parts[1]));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "tag-alias" {
+                        if conf.tag_aliases.remove(parts[1]).is_some() {
+                            write = true;
+                            set = true;
+                        } else {
+                            warning = Some(format!("unknown tag-alias: \"{}\"", parts[1]));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "issue-pattern" {
+                        if conf.issue_patterns.remove(parts[1]).is_some() {
+                            write = true;
+                            set = true;
+                        } else {
+                            warning = Some(format!("unknown issue-pattern: \"{}\"", parts[1]));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "profile-directory" {
+                        let target = base.as_mut().unwrap_or(&mut conf);
+                        if target.profile_directories.remove(parts[1]).is_some() {
+                            write_base = true;
+                            set = true;
+                        } else {
+                            warning = Some(format!(
+                                "no directory is configured for profile \"{}\"",
+                                parts[1]
+                            ));
+                            set = false;
+                        }
+                    } else if parts.len() == 2 && parts[0] == "style" {
                         if conf.style_map.contains_key(parts[1]) {
                             write = true;
                             set = true;
@@ -702,14 +2218,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                         }
                     } else if parts.len() > 1 && parts[0] == "budget" {
                         let tag = parts[1..parts.len()].join(" ");
-                        let mut budgets: Vec<(String, f32)> = conf
-                            .budgets
-                            .clone()
-                            .or_else(|| Some(vec![]))
-                            .unwrap()
-                            .iter()
-                            .map(|(tag, hours)| (tag.clone(), hours.clone()))
-                            .collect();
+                        let mut budgets = conf.budgets.clone().unwrap_or_default();
                         if let Some(i) = budgets.iter().position(|p| {
                             p.0.split_whitespace().collect::<Vec<_>>().join(" ") == tag
                         }) {
@@ -740,10 +2249,27 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     }
+    if write_base {
+        match &base {
+            Some(base) => base.write(),
+            None => write = true,
+        }
+    }
     if write {
         conf.write()
     }
-    if matches.is_present("list") {
+    if let Some(file) = matches.value_of("export") {
+        did_something = true;
+        conf.ini()
+            .write_to_file(file)
+            .expect("could not write the exported configuration");
+        success(format!("exported the configuration to {}", file), &conf);
+    }
+    // the global --json option implies --list
+    if matches.is_present("json") {
+        did_something = true;
+        println!("{}", conf.to_json());
+    } else if matches.is_present("list") {
         let mut footnotes: Vec<String> = Vec::new();
         if did_something {
             println!("");
@@ -767,10 +2293,68 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     String::from("")
                 },
             ],
+            vec![String::from("backups"), format!("{}", conf.backups)],
+            vec![
+                String::from("perl-compatible"),
+                format!("{}", conf.perl_compatible),
+            ],
+            vec![
+                String::from("warn-new-tags"),
+                format!("{}", conf.warn_new_tags),
+            ],
+            vec![
+                String::from("fold-tag-case"),
+                format!("{}", conf.fold_tag_case),
+            ],
+            vec![
+                String::from("require-tags"),
+                format!("{}", conf.require_tags),
+            ],
+            vec![
+                String::from("user-name"),
+                conf.user_name.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("sync-remote"),
+                conf.sync_remote.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("caldav-url"),
+                conf.caldav_url.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("caldav-user"),
+                conf.caldav_user.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("calendar-tag"),
+                conf.calendar_tag.clone().unwrap_or_default(),
+            ],
+            vec![String::from("slack-emoji"), conf.slack_emoji.clone()],
+            vec![
+                String::from("jira-url"),
+                conf.jira_url.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("jira-user"),
+                conf.jira_user.clone().unwrap_or_default(),
+            ],
+            vec![
+                String::from("vacation-precedence"),
+                conf.vacation_precedence_rules()
+                    .iter()
+                    .map(|(rule, reversed)| format!("{}{}", if *reversed { "-" } else { "" }, rule))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ],
             vec![
                 String::from("length-pay-period"),
                 format!("{}", conf.length_pay_period),
             ],
+            vec![
+                String::from("pay-period"),
+                conf.pay_period.to_s().to_owned(),
+            ],
             vec![
                 String::from("start-pay-period"),
                 format!(
@@ -783,6 +2367,14 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     }
                 ),
             ],
+            vec![
+                String::from("fiscal-year-start"),
+                if let Some((month, day)) = conf.fiscal_year_start {
+                    format!("{}-{}", month, day)
+                } else {
+                    String::from("")
+                },
+            ],
             vec![
                 String::from("sunday-begins-week"),
                 format!("{}", conf.sunday_begins_week),
@@ -791,6 +2383,23 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 String::from("clock"),
                 format!("{}", if conf.h12 { "12" } else { "24" }),
             ],
+            vec![
+                String::from("time-format"),
+                conf.time_format.clone().unwrap_or_else(|| String::from("")),
+            ],
+            vec![
+                String::from("date-format"),
+                conf.date_format.clone().unwrap_or_else(|| String::from("")),
+            ],
+            vec![
+                String::from("locale"),
+                conf.locale.clone().unwrap_or_else(|| String::from("")),
+            ],
+            vec![
+                String::from("week-numbers"),
+                format!("{}", conf.week_numbers),
+            ],
+            vec![String::from("utc-offsets"), format!("{}", conf.utc_offsets)],
             vec![String::from("workdays"), conf.serialize_workdays()],
             vec![
                 String::from("beginning-work-day"),
@@ -800,6 +2409,29 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 ),
             ],
             vec![String::from("day-length"), format!("{}", conf.day_length)],
+            vec![
+                String::from("day-boundary"),
+                format!("{}:{:02}", conf.day_boundary.0, conf.day_boundary.1),
+            ],
+            vec![
+                String::from("auto-close-at"),
+                conf.auto_close
+                    .and_then(|a| a.at())
+                    .unwrap_or_else(|| String::from("")),
+            ],
+            vec![
+                String::from("auto-close-after"),
+                conf.auto_close
+                    .and_then(|a| a.after())
+                    .unwrap_or_else(|| String::from("")),
+            ],
+        ];
+        for (i, c) in "SMTWHFA".chars().enumerate() {
+            if let Some(l) = conf.day_lengths[i] {
+                attributes.push(vec![format!("day-length for {}", c), format!("{}", l)]);
+            }
+        }
+        attributes.append(&mut vec![
             vec![String::from("editor"), {
                 match conf.effective_editor() {
                     Some((editor, source)) => {
@@ -826,7 +2458,43 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 }
                 color
             }],
-        ];
+            vec![String::from("pager"), format!("{}", conf.pager)],
+        ]);
+        let profile_settings = base.as_ref().unwrap_or(&conf);
+        attributes.push(vec![
+            String::from("default-profile"),
+            profile_settings.default_profile.clone().unwrap_or_default(),
+        ]);
+        for (name, dir) in profile_settings.profiles() {
+            attributes.push(vec![
+                format!("profile {}", name),
+                dir.to_str().unwrap().to_owned(),
+            ]);
+        }
+        for (name, url) in &conf.webhooks {
+            attributes.push(vec![format!("webhook {}", name), url.clone()]);
+        }
+        for (alias, tag) in &conf.tag_aliases {
+            attributes.push(vec![format!("tag-alias {}", alias), tag.clone()]);
+        }
+        for (rule, _) in LINT_RULES.iter() {
+            attributes.push(vec![
+                format!("lint {}", rule),
+                conf.lint_rule(rule).to_string(),
+            ]);
+        }
+        for (name, (pattern, url)) in &conf.issue_patterns {
+            attributes.push(vec![
+                format!("issue-pattern {}", name),
+                format!("{} {}", pattern, url),
+            ]);
+        }
+        for schedule in &conf.schedule_history {
+            attributes.push(vec![
+                format!("schedule before {}", schedule.until),
+                schedule.describe(),
+            ]);
+        }
         for style in &conf.style_map {
             attributes.push(vec![style.0.clone(), style.1.clone()]);
         }
@@ -835,7 +2503,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             for budget in budgets.iter() {
                 attributes.push(vec![
                     format!("\u{00A0}\u{00A0}{}", budget.0),
-                    format!("{}", budget.1),
+                    budget_string(budget.1, budget.2),
                 ])
             }
         }
@@ -871,6 +2539,31 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     }
 }
 
+// how pay periods are reckoned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayPeriod {
+    Fixed,       // some number of days from a known start
+    Monthly,     // calendar months
+    Semimonthly, // the 1st to the 15th and the 16th to the end of the month
+}
+
+impl PayPeriod {
+    fn to_s(&self) -> &str {
+        match self {
+            PayPeriod::Fixed => "fixed",
+            PayPeriod::Monthly => "monthly",
+            PayPeriod::Semimonthly => "semimonthly",
+        }
+    }
+    fn from_s(s: &str) -> PayPeriod {
+        match s {
+            "monthly" => PayPeriod::Monthly,
+            "semimonthly" => PayPeriod::Semimonthly,
+            _ => PayPeriod::Fixed,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Truncation {
     Round,
@@ -1050,23 +2743,335 @@ impl PartialEq for Precision {
     }
 }
 
+// a work schedule that was in force until some date
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub until: NaiveDate,
+    pub workdays: u8,
+    pub day_length: f32,
+    pub day_lengths: [Option<f32>; 7],
+    pub beginning_work_day: (usize, usize),
+}
+
+impl Schedule {
+    fn section_name(&self) -> String {
+        format!("schedule before {}", self.until.format("%F"))
+    }
+    #[cfg(feature = "cli")]
+    fn describe(&self) -> String {
+        let mut s = format!(
+            "{} {}h {}:{:02}",
+            Configuration::serialize_workday_flags(self.workdays),
+            self.day_length,
+            self.beginning_work_day.0,
+            self.beginning_work_day.1
+        );
+        for (i, c) in "SMTWHFA".chars().enumerate() {
+            if let Some(l) = self.day_lengths[i] {
+                s.push_str(&format!(" {}={}h", c, l));
+            }
+        }
+        s
+    }
+}
+
+// when an event left open past the day boundary is deemed to have ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoClose {
+    At(usize, usize),   // this time on the day it began
+    After(Option<f32>), // this many hours after it began or, if None, the length of the work day
+}
+
+impl AutoClose {
+    fn parse_at(v: &str) -> Option<AutoClose> {
+        let rx = Regex::new(r"\A(\d\d?)(?::([0-5]\d))?\z").unwrap();
+        let captures = rx.captures(v)?;
+        let hour = captures[1].parse::<usize>().unwrap();
+        let minute = captures
+            .get(2)
+            .map(|m| m.as_str().parse::<usize>().unwrap())
+            .unwrap_or(0);
+        if hour < 24 {
+            Some(AutoClose::At(hour, minute))
+        } else {
+            None
+        }
+    }
+    fn parse_after(v: &str) -> Option<AutoClose> {
+        if v == "day-length" {
+            return Some(AutoClose::After(None));
+        }
+        match v.parse::<f32>() {
+            Ok(h) if h > 0.0 && h < 24.0 => Some(AutoClose::After(Some(h))),
+            _ => None,
+        }
+    }
+    // the value of auto-close-at
+    fn at(&self) -> Option<String> {
+        match self {
+            AutoClose::At(h, m) => Some(format!("{}:{:02}", h, m)),
+            _ => None,
+        }
+    }
+    // the value of auto-close-after
+    fn after(&self) -> Option<String> {
+        match self {
+            AutoClose::After(Some(h)) => Some(format!("{}", h)),
+            AutoClose::After(None) => Some(String::from("day-length")),
+            _ => None,
+        }
+    }
+}
+
+// the period over which a time budget is spent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetPeriod {
+    PayPeriod,
+    Day,
+    Week,
+    Month,
+}
+
+impl BudgetPeriod {
+    fn parse(v: &str) -> Option<BudgetPeriod> {
+        match v.to_lowercase().as_str() {
+            "pp" | "pay period" | "pay-period" => Some(BudgetPeriod::PayPeriod),
+            "d" | "day" => Some(BudgetPeriod::Day),
+            "w" | "wk" | "week" => Some(BudgetPeriod::Week),
+            "mo" | "month" => Some(BudgetPeriod::Month),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            BudgetPeriod::PayPeriod => "pay period",
+            BudgetPeriod::Day => "day",
+            BudgetPeriod::Week => "week",
+            BudgetPeriod::Month => "month",
+        }
+    }
+    // the time expression for the current period of this sort
+    pub fn expression(&self) -> &'static str {
+        match self {
+            BudgetPeriod::PayPeriod => "this pay period",
+            BudgetPeriod::Day => "today",
+            BudgetPeriod::Week => "this week",
+            BudgetPeriod::Month => "this month",
+        }
+    }
+}
+
+// parses a budget such as '12.5', hours per pay period, or '5h/week'
+pub fn parse_budget(v: &str) -> Option<(f32, BudgetPeriod)> {
+    lazy_static! {
+        static ref BUDGET: Regex = Regex::new(
+            r"(?i)\A\s*(\d+(?:\.\d*)?|\.\d+)\s*(?:h|hrs?|hours?)?\s*(?:/\s*([a-z][a-z -]*?)\s*)?\z"
+        )
+        .unwrap();
+    }
+    let captures = BUDGET.captures(v)?;
+    let hours = captures[1].parse::<f32>().ok()?;
+    let per = match captures.get(2) {
+        Some(m) => BudgetPeriod::parse(m.as_str())?,
+        None => BudgetPeriod::PayPeriod,
+    };
+    Some((hours, per))
+}
+
+// the inverse of parse_budget
+pub fn budget_string(hours: f32, per: BudgetPeriod) -> String {
+    match per {
+        BudgetPeriod::PayPeriod => format!("{}", hours),
+        _ => format!("{}h/{}", hours, per.name()),
+    }
+}
+
+// how seriously the lint subcommand takes the violation of a rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+// the rules of the lint subcommand, with their default settings; the threshold of long-event is
+// in hours, that of short-description in characters, and that of gap in minutes
+pub const LINT_RULES: [(&str, &str); 5] = [
+    ("untagged", "warning"),
+    ("long-event", "warning 10"),
+    ("short-description", "warning 3"),
+    ("gap", "off 60"),
+    ("missing-done", "warning"),
+];
+
+// the setting of a lint rule: a severity and, for those rules that have one, a threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LintRule {
+    pub severity: Severity,
+    pub threshold: Option<f32>,
+}
+
+impl LintRule {
+    // parses a setting such as 'error 8' for the named rule; a rule with a threshold keeps its
+    // default threshold if none is given
+    pub fn parse(rule: &str, v: &str) -> Result<LintRule, String> {
+        let default = LINT_RULES
+            .iter()
+            .find(|(r, _)| *r == rule)
+            .map(|(_, d)| *d)
+            .ok_or_else(|| {
+                format!(
+                    "unknown lint rule '{}'; the rules are {}",
+                    rule,
+                    LINT_RULES
+                        .iter()
+                        .map(|(r, _)| *r)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        let default_threshold = default.split_whitespace().nth(1);
+        let mut parts = v.split_whitespace();
+        let severity = match parts.next() {
+            Some("off") => Severity::Off,
+            Some("warning") => Severity::Warning,
+            Some("error") => Severity::Error,
+            _ => {
+                return Err(format!(
+                    "the setting of lint rule {} must begin with off, warning, or error",
+                    rule
+                ))
+            }
+        };
+        let threshold = match (parts.next(), default_threshold) {
+            (Some(_), None) => return Err(format!("lint rule {} takes no threshold", rule)),
+            (t, Some(d)) => match t.unwrap_or(d).parse::<f32>() {
+                Ok(t) if t > 0.0 => Some(t),
+                _ => {
+                    return Err(format!(
+                        "the threshold of lint rule {} must be a positive number",
+                        rule
+                    ))
+                }
+            },
+            (None, None) => None,
+        };
+        if parts.next().is_some() {
+            return Err(format!(
+                "too many parts in the setting of lint rule {}",
+                rule
+            ));
+        }
+        Ok(LintRule {
+            severity,
+            threshold,
+        })
+    }
+}
+
+impl std::fmt::Display for LintRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.threshold {
+            Some(t) => write!(f, "{} {}", self.severity.name(), t),
+            None => write!(f, "{}", self.severity.name()),
+        }
+    }
+}
+
+// the rules deciding which of two overlapping vacation records takes precedence, in their default
+// order
+pub const VACATION_PRECEDENCE: [&str; 4] = ["kind", "repeating", "earlier", "narrower"];
+
+// parses a list of vacation precedence rules such as 'narrower, -kind'
+pub fn parse_vacation_precedence(v: &str) -> Result<Vec<String>, String> {
+    let mut rules: Vec<String> = vec![];
+    for rule in v.split(|c: char| c == ',' || c.is_whitespace()) {
+        if rule.is_empty() {
+            continue;
+        }
+        let name = rule.strip_prefix('-').unwrap_or(rule);
+        if !VACATION_PRECEDENCE.contains(&name) {
+            return Err(format!(
+                "unknown vacation precedence rule '{}'; the rules are {}",
+                name,
+                VACATION_PRECEDENCE.join(", ")
+            ));
+        }
+        if rules
+            .iter()
+            .any(|r| r.strip_prefix('-').unwrap_or(r) == name)
+        {
+            return Err(format!("vacation precedence rule {} is given twice", name));
+        }
+        rules.push(rule.to_owned());
+    }
+    if rules.is_empty() {
+        return Err(String::from("no vacation precedence rule is given"));
+    }
+    Ok(rules)
+}
+
 #[derive(Clone)]
 pub struct Configuration {
     pub day_length: f32,
+    pub day_lengths: [Option<f32>; 7], // per-weekday overrides of day_length, indexed from Sunday
     pub editor: Option<Vec<String>>,
+    pub pager: bool, // whether long output goes through a pager
     pub length_pay_period: u32,
     pub precision: Precision,
     pub truncation: Truncation,
+    pub raw: bool, // whether durations are shown as exact seconds; set by --raw, never saved
     pub start_pay_period: Option<NaiveDate>,
+    pub pay_period: PayPeriod, // how pay periods are reckoned
+    pub fiscal_year_start: Option<(u32, u32)>, // month and day
     pub sunday_begins_week: bool,
     pub beginning_work_day: (usize, usize),
+    pub day_boundary: (usize, usize), // when one day ends and the next begins
+    pub auto_close: Option<AutoClose>, // when events left open overnight are deemed to end
     color: Option<bool>,
     pub workdays: u8, // bit flags
     pub max_width: Option<usize>,
     dir: String,
     pub h12: bool,
+    pub time_format: Option<String>, // strftime patterns overriding the default display formats
+    pub date_format: Option<String>,
+    pub locale: Option<String>, // the language of day and month names
+    pub week_numbers: bool,
+    pub utc_offsets: bool,
+    pub backups: usize,              // the number of backups to keep
+    pub perl_compatible: bool,       // whether to avoid writing what the Perl client can't read
+    pub warn_new_tags: bool,         // whether add and note warn of tags not yet in the log
+    pub fold_tag_case: bool,         // whether new tags are put in lower case
+    pub require_tags: bool,          // whether add refuses events without tags
+    pub user_name: Option<String>,   // the author recorded in new lines of a shared log
+    pub sync_remote: Option<String>, // the git repository the sync subcommand pulls from and pushes to
+    pub caldav_url: Option<String>,  // the calendar the push-calendar subcommand uploads to
+    pub caldav_user: Option<String>,
+    pub calendar_tag: Option<String>, // the tag of events the import-calendar subcommand creates
+    pub slack_emoji: String,
+    pub stale_slack_token: bool, // whether config.ini holds a Slack token, which is no longer read
+    pub jira_url: Option<String>, // the server the export subcommand posts worklogs to
+    pub jira_user: Option<String>,
+    pub vacation_precedence: Vec<String>, // the vacation precedence rules put first, e.g. "-kind"
+    pub webhooks: BTreeMap<String, String>, // URLs notified of changes to the log, by name
+    pub tag_aliases: BTreeMap<String, String>, // tags replaced by others when written
+    pub issue_patterns: BTreeMap<String, (String, String)>, // issue references and URL templates, by name
+    pub lint_rules: BTreeMap<String, LintRule>, // the lint rules whose settings are not the defaults
     pub style_map: BTreeMap<String, String>,
-    pub budgets: Option<Vec<(String, f32)>>,
+    pub budgets: Option<Vec<(String, f32, BudgetPeriod)>>, // tag, hours, and period
+    pub schedule_history: Vec<Schedule>, // earlier schedules, sorted by the date they ended
+    pub default_profile: Option<String>, // only meaningful in the base configuration
+    pub profile_directories: BTreeMap<String, String>, // likewise
 }
 
 fn default_style(identifier: &str) -> &'static str {
@@ -1078,9 +3083,14 @@ fn default_style(identifier: &str) -> &'static str {
 }
 
 impl Configuration {
+    #[cfg(feature = "cli")]
     fn max_term_size() -> usize {
         term_size::dimensions().unwrap_or((80, 0)).0 // if term_size fails us, use a default of 80
     }
+    #[cfg(not(feature = "cli"))]
+    fn max_term_size() -> usize {
+        80
+    }
     // the minimum of the current terminal width or the configured width, if any
     pub fn width(&self) -> usize {
         let t = Configuration::max_term_size();
@@ -1145,6 +3155,71 @@ impl Configuration {
             } else {
                 BEGINNING_WORK_DAY.clone()
             };
+            // a bad hand-edited value is ignored rather than breaking every command
+            let day_boundary = ini
+                .get_from(Some("time"), "day-boundary")
+                .and_then(|s| parse_day_boundary(s).ok())
+                .unwrap_or(DAY_BOUNDARY);
+            let auto_close = if let Some(s) = ini.get_from(Some("time"), "auto-close-at") {
+                AutoClose::parse_at(s)
+            } else if let Some(s) = ini.get_from(Some("time"), "auto-close-after") {
+                AutoClose::parse_after(s)
+            } else {
+                None
+            };
+            let mut day_lengths = [None; 7];
+            if let Some(section) = ini.section(Some("schedule")) {
+                for (key, value) in section.iter() {
+                    if let Some(i) = Configuration::day_index(key) {
+                        day_lengths[i] = Some(value.parse::<f32>().unwrap());
+                    }
+                }
+            }
+            let mut schedule_history = vec![];
+            for (name, section) in ini.iter() {
+                if let Some(date) = name.and_then(|n| n.strip_prefix("schedule before ")) {
+                    let parts = date.split("-").collect::<Vec<&str>>();
+                    let until = NaiveDate::from_ymd(
+                        parts[0].parse().unwrap(),
+                        parts[1].parse().unwrap(),
+                        parts[2].parse().unwrap(),
+                    );
+                    let mut day_lengths = [None; 7];
+                    for (key, value) in section.iter() {
+                        if let Some(i) = Configuration::day_index(key) {
+                            day_lengths[i] = Some(value.parse::<f32>().unwrap());
+                        }
+                    }
+                    let bwd: Vec<usize> = section
+                        .get("beginning-work-day")
+                        .unwrap_or("9:0")
+                        .split(":")
+                        .map(|s| s.parse::<usize>().unwrap())
+                        .collect();
+                    schedule_history.push(Schedule {
+                        until,
+                        workdays: Configuration::parse_workdays(
+                            section.get("workdays").unwrap_or(WORKDAYS),
+                        ),
+                        day_length: section
+                            .get("day-length")
+                            .unwrap_or(DAY_LENGTH)
+                            .parse()
+                            .unwrap(),
+                        day_lengths,
+                        beginning_work_day: (bwd[0], bwd[1]),
+                    });
+                }
+            }
+            schedule_history.sort_by_key(|s| s.until);
+            let mut profile_directories = BTreeMap::new();
+            if let Some(section) = ini.section(Some("profiles")) {
+                for (key, value) in section.iter() {
+                    if key != "default" {
+                        profile_directories.insert(key.to_owned(), value.to_owned());
+                    }
+                }
+            }
             let mut map = BTreeMap::new();
             for style in STYLES {
                 map.insert(
@@ -1155,11 +3230,15 @@ impl Configuration {
             }
             Configuration {
                 beginning_work_day,
+                day_boundary,
+                auto_close,
                 day_length: ini
                     .get_from_or(Some("time"), "day-length", DAY_LENGTH)
                     .parse()
                     .unwrap(),
+                day_lengths,
                 editor: editor,
+                pager: ini.get_from_or(Some("external"), "pager", PAGER) == "true",
                 length_pay_period: ini
                     .get_from_or(Some("time"), "pay-period-length", LENGTH_PAY_PERIOD)
                     .parse()
@@ -1174,13 +3253,97 @@ impl Configuration {
                     "truncation",
                     TRUNCATION,
                 )),
+                raw: false,
                 start_pay_period: start_pay_period,
+                pay_period: PayPeriod::from_s(ini.get_from_or(
+                    Some("time"),
+                    "pay-period",
+                    PAY_PERIOD,
+                )),
+                fiscal_year_start: ini
+                    .get_from(Some("time"), "fiscal-year-start")
+                    .and_then(Configuration::parse_month_day),
                 sunday_begins_week: ini.get_from_or(
                     Some("time"),
                     "sunday-begins-week",
                     SUNDAY_BEGINS_WEEK,
                 ) == "true",
                 h12: ini.get_from_or(Some("summary"), "clock", CLOCK) == "12",
+                time_format: ini
+                    .get_from(Some("summary"), "time-format")
+                    .map(|s| s.to_owned()),
+                date_format: ini
+                    .get_from(Some("summary"), "date-format")
+                    .map(|s| s.to_owned()),
+                locale: ini
+                    .get_from(Some("summary"), "locale")
+                    .map(|s| s.to_owned()),
+                week_numbers: ini.get_from_or(Some("summary"), "week-numbers", WEEK_NUMBERS)
+                    == "true",
+                utc_offsets: ini.get_from_or(Some("time"), "utc-offsets", UTC_OFFSETS) == "true",
+                backups: ini
+                    .get_from_or(Some("log"), "backups", BACKUPS)
+                    .parse()
+                    .unwrap(),
+                perl_compatible: ini.get_from_or(Some("log"), "perl-compatible", PERL_COMPATIBLE)
+                    == "true",
+                warn_new_tags: ini.get_from_or(Some("log"), "warn-new-tags", WARN_NEW_TAGS)
+                    == "true",
+                fold_tag_case: ini.get_from_or(Some("log"), "fold-tag-case", FOLD_TAG_CASE)
+                    == "true",
+                require_tags: ini.get_from_or(Some("log"), "require-tags", REQUIRE_TAGS) == "true",
+                user_name: ini.get_from(Some("log"), "user-name").map(|s| s.to_owned()),
+                sync_remote: ini.get_from(Some("sync"), "remote").map(|s| s.to_owned()),
+                caldav_url: ini.get_from(Some("caldav"), "url").map(|s| s.to_owned()),
+                caldav_user: ini.get_from(Some("caldav"), "user").map(|s| s.to_owned()),
+                calendar_tag: ini.get_from(Some("calendar"), "tag").map(|s| s.to_owned()),
+                slack_emoji: ini
+                    .get_from_or(Some("slack"), "emoji", SLACK_EMOJI)
+                    .to_owned(),
+                stale_slack_token: ini.get_from(Some("slack"), "token").is_some(),
+                jira_url: ini.get_from(Some("jira"), "url").map(|s| s.to_owned()),
+                jira_user: ini.get_from(Some("jira"), "user").map(|s| s.to_owned()),
+                vacation_precedence: ini
+                    .get_from(Some("vacation"), "precedence")
+                    .and_then(|v| parse_vacation_precedence(v).ok())
+                    .unwrap_or_default(),
+                webhooks: ini
+                    .section(Some("webhooks"))
+                    .map(|p| {
+                        p.iter()
+                            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                tag_aliases: ini
+                    .section(Some("tag-aliases"))
+                    .map(|p| {
+                        p.iter()
+                            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                lint_rules: ini
+                    .section(Some("lint"))
+                    .map(|p| {
+                        p.iter()
+                            .filter_map(|(k, v)| {
+                                LintRule::parse(k, v).ok().map(|r| (k.to_owned(), r))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                issue_patterns: ini
+                    .section(Some("issue-patterns"))
+                    .map(|p| {
+                        p.iter()
+                            .filter_map(|(k, v)| {
+                                ini.get_from(Some("issue-urls"), k)
+                                    .map(|url| (k.to_owned(), (v.to_owned(), url.to_owned())))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
                 color: color,
                 workdays: Configuration::parse_workdays(ini.get_from_or(
                     Some("time"),
@@ -1198,12 +3361,19 @@ impl Configuration {
                         Some(
                             p.iter()
                                 .map(|(key, value)| {
-                                    (String::from(key), value.parse::<f32>().unwrap())
+                                    let (hours, per) =
+                                        parse_budget(value).expect("could not parse budget");
+                                    (String::from(key), hours, per)
                                 })
                                 .collect(),
                         )
                     })
                     .or_else(|| None),
+                schedule_history,
+                default_profile: ini
+                    .get_from(Some("profiles"), "default")
+                    .map(|s| s.to_owned()),
+                profile_directories,
             }
         } else {
             Configuration::defaults(directory)
@@ -1217,28 +3387,74 @@ impl Configuration {
         }
         Configuration {
             day_length: DAY_LENGTH.parse().unwrap(),
+            day_lengths: [None; 7],
             editor: None,
+            pager: PAGER == "true",
             length_pay_period: LENGTH_PAY_PERIOD.parse().unwrap(),
             beginning_work_day: BEGINNING_WORK_DAY.clone(),
+            day_boundary: DAY_BOUNDARY,
+            auto_close: None,
             precision: Precision::from_s(PRECISION),
             truncation: Truncation::from_s(TRUNCATION),
+            raw: false,
             start_pay_period: None,
+            pay_period: PayPeriod::Fixed,
+            fiscal_year_start: None,
             color: None,
             sunday_begins_week: SUNDAY_BEGINS_WEEK == "true",
             workdays: Configuration::parse_workdays(WORKDAYS),
             max_width: None,
             dir: directory,
             h12: CLOCK == "12",
+            time_format: None,
+            date_format: None,
+            locale: None,
+            week_numbers: WEEK_NUMBERS == "true",
+            utc_offsets: UTC_OFFSETS == "true",
+            backups: BACKUPS.parse().unwrap(),
+            perl_compatible: PERL_COMPATIBLE == "true",
+            warn_new_tags: WARN_NEW_TAGS == "true",
+            fold_tag_case: FOLD_TAG_CASE == "true",
+            require_tags: REQUIRE_TAGS == "true",
+            user_name: None,
+            sync_remote: None,
+            caldav_url: None,
+            caldav_user: None,
+            calendar_tag: None,
+            slack_emoji: SLACK_EMOJI.to_owned(),
+            stale_slack_token: false,
+            jira_url: None,
+            jira_user: None,
+            vacation_precedence: vec![],
+            webhooks: BTreeMap::new(),
+            tag_aliases: BTreeMap::new(),
+            issue_patterns: BTreeMap::new(),
+            lint_rules: BTreeMap::new(),
             style_map: map,
             budgets: None,
+            schedule_history: vec![],
+            default_profile: None,
+            profile_directories: BTreeMap::new(),
         }
     }
     pub fn write(&self) {
+        self.ini()
+            .write_to_file(Configuration::config_file(Some(&self.dir)))
+            .expect("could not write config.ini");
+    }
+    // the non-default parameters as they are stored in config.ini
+    fn ini(&self) -> Ini {
         let mut ini = Ini::new();
         if self.day_length != DAY_LENGTH.parse::<f32>().unwrap() {
             ini.with_section(Some("time"))
                 .set("day-length", format!("{}", self.day_length));
         }
+        for (i, c) in "SMTWHFA".chars().enumerate() {
+            if let Some(l) = self.day_lengths[i] {
+                ini.with_section(Some("schedule"))
+                    .set(c.to_string(), format!("{}", l));
+            }
+        }
         if self.beginning_work_day != BEGINNING_WORK_DAY {
             ini.with_section(Some("time")).set(
                 "beginning-work-day",
@@ -1248,6 +3464,20 @@ impl Configuration {
                 ),
             );
         }
+        if self.day_boundary != DAY_BOUNDARY {
+            ini.with_section(Some("time")).set(
+                "day-boundary",
+                format!("{}:{:02}", self.day_boundary.0, self.day_boundary.1),
+            );
+        }
+        if let Some(a) = self.auto_close {
+            if let Some(at) = a.at() {
+                ini.with_section(Some("time")).set("auto-close-at", at);
+            } else if let Some(after) = a.after() {
+                ini.with_section(Some("time"))
+                    .set("auto-close-after", after);
+            }
+        }
         if let Some(s) = self.editor.as_ref() {
             let s = s.join(" ");
             ini.with_section(Some("external")).set("editor", s);
@@ -1256,6 +3486,10 @@ impl Configuration {
             ini.with_section(Some("time"))
                 .set("pay-period-length", format!("{}", self.length_pay_period));
         }
+        if self.pay_period != PayPeriod::from_s(PAY_PERIOD) {
+            ini.with_section(Some("time"))
+                .set("pay-period", self.pay_period.to_s());
+        }
         if self.precision != Precision::from_s(PRECISION) {
             ini.with_section(Some("summary"))
                 .set("precision", format!("{}", self.precision.to_s()));
@@ -1271,6 +3505,10 @@ impl Configuration {
                 format!("{} {} {}", spp.year(), spp.month(), spp.day()),
             );
         }
+        if let Some((month, day)) = self.fiscal_year_start {
+            ini.with_section(Some("time"))
+                .set("fiscal-year-start", format!("{}-{}", month, day));
+        }
         if self.sunday_begins_week != SUNDAY_BEGINS_WEEK.parse::<bool>().unwrap() {
             ini.with_section(Some("time"))
                 .set("sunday-begins-week", format!("{}", self.sunday_begins_week));
@@ -1279,6 +3517,27 @@ impl Configuration {
             ini.with_section(Some("summary"))
                 .set("clock", format!("{}", if self.h12 { "12" } else { "24" }));
         }
+        if let Some(f) = &self.time_format {
+            ini.with_section(Some("summary")).set("time-format", f);
+        }
+        if let Some(f) = &self.date_format {
+            ini.with_section(Some("summary")).set("date-format", f);
+        }
+        if let Some(l) = &self.locale {
+            ini.with_section(Some("summary")).set("locale", l);
+        }
+        if self.week_numbers != (WEEK_NUMBERS == "true") {
+            ini.with_section(Some("summary"))
+                .set("week-numbers", format!("{}", self.week_numbers));
+        }
+        if self.utc_offsets != (UTC_OFFSETS == "true") {
+            ini.with_section(Some("time"))
+                .set("utc-offsets", format!("{}", self.utc_offsets));
+        }
+        if self.pager != (PAGER == "true") {
+            ini.with_section(Some("external"))
+                .set("pager", format!("{}", self.pager));
+        }
         if let Some(c) = self.color {
             ini.with_section(Some("color"))
                 .set("color", format!("{}", c));
@@ -1291,6 +3550,69 @@ impl Configuration {
             ini.with_section(Some("summary"))
                 .set("max-width", format!("{}", self.max_width.unwrap()));
         }
+        if self.backups != BACKUPS.parse::<usize>().unwrap() {
+            ini.with_section(Some("log"))
+                .set("backups", format!("{}", self.backups));
+        }
+        if self.perl_compatible != (PERL_COMPATIBLE == "true") {
+            ini.with_section(Some("log"))
+                .set("perl-compatible", format!("{}", self.perl_compatible));
+        }
+        if self.warn_new_tags != (WARN_NEW_TAGS == "true") {
+            ini.with_section(Some("log"))
+                .set("warn-new-tags", format!("{}", self.warn_new_tags));
+        }
+        if self.fold_tag_case != (FOLD_TAG_CASE == "true") {
+            ini.with_section(Some("log"))
+                .set("fold-tag-case", format!("{}", self.fold_tag_case));
+        }
+        if self.require_tags != (REQUIRE_TAGS == "true") {
+            ini.with_section(Some("log"))
+                .set("require-tags", format!("{}", self.require_tags));
+        }
+        if let Some(name) = &self.user_name {
+            ini.with_section(Some("log")).set("user-name", name);
+        }
+        if let Some(remote) = &self.sync_remote {
+            ini.with_section(Some("sync")).set("remote", remote);
+        }
+        if let Some(url) = &self.caldav_url {
+            ini.with_section(Some("caldav")).set("url", url);
+        }
+        if let Some(user) = &self.caldav_user {
+            ini.with_section(Some("caldav")).set("user", user);
+        }
+        if let Some(tag) = &self.calendar_tag {
+            ini.with_section(Some("calendar")).set("tag", tag);
+        }
+        if self.slack_emoji != SLACK_EMOJI {
+            ini.with_section(Some("slack"))
+                .set("emoji", &self.slack_emoji);
+        }
+        if let Some(url) = &self.jira_url {
+            ini.with_section(Some("jira")).set("url", url);
+        }
+        if let Some(user) = &self.jira_user {
+            ini.with_section(Some("jira")).set("user", user);
+        }
+        if !self.vacation_precedence.is_empty() {
+            ini.with_section(Some("vacation"))
+                .set("precedence", self.vacation_precedence.join(", "));
+        }
+        for (name, url) in &self.webhooks {
+            ini.with_section(Some("webhooks")).set(name, url);
+        }
+        for (alias, tag) in &self.tag_aliases {
+            ini.with_section(Some("tag-aliases")).set(alias, tag);
+        }
+        for (rule, setting) in &self.lint_rules {
+            ini.with_section(Some("lint"))
+                .set(rule, setting.to_string());
+        }
+        for (name, (pattern, url)) in &self.issue_patterns {
+            ini.with_section(Some("issue-patterns")).set(name, pattern);
+            ini.with_section(Some("issue-urls")).set(name, url);
+        }
         for style in &self.style_map {
             if style.1 != default_style(&style.0) {
                 ini.with_section(Some("style")).set(style.0, style.1);
@@ -1299,11 +3621,91 @@ impl Configuration {
         if let Some(budgets) = &self.budgets {
             for pair in budgets {
                 ini.with_section(Some("budget"))
-                    .set(pair.0.clone(), format!("{}", pair.1));
+                    .set(pair.0.clone(), budget_string(pair.1, pair.2));
             }
         }
-        ini.write_to_file(Configuration::config_file(Some(&self.dir)))
-            .expect("could not write config.ini");
+        for schedule in &self.schedule_history {
+            let name = schedule.section_name();
+            ini.with_section(Some(name.clone()))
+                .set(
+                    "workdays",
+                    Configuration::serialize_workday_flags(schedule.workdays),
+                )
+                .set("day-length", format!("{}", schedule.day_length))
+                .set(
+                    "beginning-work-day",
+                    format!(
+                        "{}:{}",
+                        schedule.beginning_work_day.0, schedule.beginning_work_day.1
+                    ),
+                );
+            for (i, c) in "SMTWHFA".chars().enumerate() {
+                if let Some(l) = schedule.day_lengths[i] {
+                    ini.with_section(Some(name.clone()))
+                        .set(c.to_string(), format!("{}", l));
+                }
+            }
+        }
+        if let Some(name) = &self.default_profile {
+            ini.with_section(Some("profiles")).set("default", name);
+        }
+        for (name, dir) in &self.profile_directories {
+            ini.with_section(Some("profiles")).set(name, dir);
+        }
+        ini
+    }
+    // the setting of a lint rule, configured or default
+    // all the vacation precedence rules in the order they are applied, each with whether it is
+    // reversed
+    pub fn vacation_precedence_rules(&self) -> Vec<(&str, bool)> {
+        let mut rules = self
+            .vacation_precedence
+            .iter()
+            .map(|r| match r.strip_prefix('-') {
+                Some(r) => (r, true),
+                None => (r.as_str(), false),
+            })
+            .collect::<Vec<_>>();
+        for rule in VACATION_PRECEDENCE.iter() {
+            if !rules.iter().any(|(r, _)| r == rule) {
+                rules.push((rule, false));
+            }
+        }
+        rules
+    }
+    pub fn lint_rule(&self, rule: &str) -> LintRule {
+        self.lint_rules.get(rule).copied().unwrap_or_else(|| {
+            let (_, default) = LINT_RULES.iter().find(|(r, _)| *r == rule).unwrap();
+            LintRule::parse(rule, default).unwrap()
+        })
+    }
+    // a tag as the normalization rules have it: in lower case, if tag case is folded, and
+    // replaced by the tag it is an alias of, if it is one
+    pub fn normalize_tag(&self, tag: &str) -> String {
+        if self.fold_tag_case {
+            let tag = tag.to_lowercase();
+            self.tag_aliases
+                .iter()
+                .find(|(alias, _)| alias.to_lowercase() == tag)
+                .map(|(_, t)| t.to_lowercase())
+                .unwrap_or(tag)
+        } else {
+            self.tag_aliases
+                .get(tag)
+                .cloned()
+                .unwrap_or_else(|| tag.to_owned())
+        }
+    }
+    // the tags normalized, in order, without duplicates
+    pub fn normalize_tags(&self, tags: &[String]) -> Vec<String> {
+        let mut normalized: Vec<String> = vec![];
+        for tag in tags {
+            let tag = self.normalize_tag(tag);
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        normalized
     }
     pub fn directory(&self) -> Option<&str> {
         Some(&self.dir)
@@ -1312,6 +3714,84 @@ impl Configuration {
     pub fn workdays(&mut self, workdays: &str) {
         self.workdays = Configuration::parse_workdays(workdays);
     }
+    // the time after midnight at which one day ends and the next begins
+    pub fn day_boundary_offset(&self) -> Duration {
+        Duration::hours(self.day_boundary.0 as i64) + Duration::minutes(self.day_boundary.1 as i64)
+    }
+    // the day to which a moment belongs given the day boundary
+    pub fn logical_date(&self, time: &NaiveDateTime) -> NaiveDate {
+        (*time - self.day_boundary_offset()).date()
+    }
+    // when an event begun at the given time and left open past the day boundary is deemed to
+    // have ended, if there is an auto-close policy
+    pub fn auto_close_time(&self, start: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let date = self.logical_date(start);
+        let next_day = self.start_of_day(&(date + Duration::days(1)));
+        let time = match self.auto_close? {
+            AutoClose::At(h, m) => {
+                let mut time = date.and_hms(h as u32, m as u32, 0);
+                if time < self.start_of_day(&date) {
+                    // the time falls after midnight but before the day boundary
+                    time += Duration::days(1);
+                }
+                if time <= *start {
+                    next_day
+                } else {
+                    time
+                }
+            }
+            AutoClose::After(hours) => {
+                let hours = hours.unwrap_or_else(|| match self.day_length_on(&date) {
+                    l if l > 0.0 => l,
+                    _ => self.day_length,
+                });
+                *start + Duration::seconds((hours * 3600.0) as i64)
+            }
+        };
+        Some(time.min(next_day))
+    }
+    // the moment the given day begins given the day boundary
+    pub fn start_of_day(&self, date: &NaiveDate) -> NaiveDateTime {
+        date.and_hms(0, 0, 0) + self.day_boundary_offset()
+    }
+    // shifts the ends of a range falling on midnight to the day boundary
+    pub fn shift_to_day_boundary(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> (NaiveDateTime, NaiveDateTime) {
+        let midnight = NaiveTime::from_hms(0, 0, 0);
+        let shift = |t: NaiveDateTime| {
+            if t.time() == midnight {
+                t + self.day_boundary_offset()
+            } else {
+                t
+            }
+        };
+        (shift(start), shift(end))
+    }
+    // parses a month-day pair such as 7-1 or 07-01
+    fn parse_month_day(s: &str) -> Option<(u32, u32)> {
+        lazy_static! {
+            static ref MONTH_DAY: Regex = Regex::new(r"\A\s*(\d{1,2})-(\d{1,2})\s*\z").unwrap();
+        }
+        let captures = MONTH_DAY.captures(s)?;
+        let month = captures[1].parse::<u32>().unwrap();
+        let day = captures[2].parse::<u32>().unwrap();
+        // use a year without a February 29th so every fiscal year has a start
+        NaiveDate::from_ymd_opt(2001, month, day).map(|_| (month, day))
+    }
+    // the first day of the fiscal year containing the given date, if a fiscal year is configured
+    pub fn fiscal_year_containing(&self, date: &NaiveDate) -> Option<NaiveDate> {
+        let (month, day) = self.fiscal_year_start?;
+        let start = NaiveDate::from_ymd(date.year(), month, day);
+        if &start > date {
+            Some(NaiveDate::from_ymd(date.year() - 1, month, day))
+        } else {
+            Some(start)
+        }
+    }
+    #[cfg(feature = "cli")]
     fn editor(&mut self, editor: &str) {
         self.editor = Some(editor.split_whitespace().map(|s| s.to_owned()).collect());
     }
@@ -1350,6 +3830,165 @@ impl Configuration {
             }
         }
     }
+    // the effective configuration as a JSON object
+    pub fn to_json(&self) -> String {
+        let optional = |s: &Option<String>| s.clone().map(Value::from).unwrap_or(Value::Null);
+        let mut sources = Map::new();
+        let editor = match self.effective_editor() {
+            Some((editor, source)) => {
+                if let Some(source) = source {
+                    sources.insert("editor".to_owned(), Value::from(source));
+                }
+                Value::from(editor.join(" "))
+            }
+            None => Value::Null,
+        };
+        let (color, source) = self.effective_color();
+        if let Some(source) = source {
+            sources.insert("color".to_owned(), Value::from(source));
+        }
+        let mut day_lengths = Map::new();
+        for (i, c) in "SMTWHFA".chars().enumerate() {
+            if let Some(l) = self.day_lengths[i] {
+                day_lengths.insert(c.to_string(), json!(l));
+            }
+        }
+        let schedule_history: Vec<Value> = self
+            .schedule_history
+            .iter()
+            .map(|s| {
+                json!({
+                    "until": s.until.format("%F").to_string(),
+                    "workdays": Configuration::serialize_workday_flags(s.workdays),
+                    "day-length": s.day_length,
+                    "beginning-work-day": format!("{}:{:02}", s.beginning_work_day.0, s.beginning_work_day.1),
+                })
+            })
+            .collect();
+        let mut budgets = Map::new();
+        let mut budget_periods = Map::new();
+        if let Some(bs) = &self.budgets {
+            for (tag, hours, per) in bs {
+                budgets.insert(tag.clone(), json!(hours));
+                budget_periods.insert(tag.clone(), json!(per.name()));
+            }
+        }
+        json!({
+            "precision": self.precision.to_s(),
+            "truncation": self.truncation.to_s(),
+            "max-width": self.max_width,
+            "backups": self.backups,
+            "perl-compatible": self.perl_compatible,
+            "warn-new-tags": self.warn_new_tags,
+            "fold-tag-case": self.fold_tag_case,
+            "require-tags": self.require_tags,
+            "tag-aliases": self.tag_aliases,
+            "user-name": optional(&self.user_name),
+            "sync-remote": optional(&self.sync_remote),
+            "caldav-url": optional(&self.caldav_url),
+            "caldav-user": optional(&self.caldav_user),
+            "calendar-tag": optional(&self.calendar_tag),
+            "slack-emoji": self.slack_emoji,
+            "jira-url": optional(&self.jira_url),
+            "jira-user": optional(&self.jira_user),
+            "vacation-precedence": self
+                .vacation_precedence_rules()
+                .iter()
+                .map(|(rule, reversed)| format!("{}{}", if *reversed { "-" } else { "" }, rule))
+                .collect::<Vec<_>>(),
+            "webhooks": self.webhooks,
+            "issue-patterns": self
+                .issue_patterns
+                .iter()
+                .map(|(name, (pattern, url))| (name.clone(), json!({"pattern": pattern, "url": url})))
+                .collect::<Map<String, Value>>(),
+            "lint": LINT_RULES
+                .iter()
+                .map(|(rule, _)| (rule.to_string(), json!(self.lint_rule(rule).to_string())))
+                .collect::<Map<String, Value>>(),
+            "length-pay-period": self.length_pay_period,
+            "pay-period": self.pay_period.to_s(),
+            "start-pay-period": self.start_pay_period.map(|d| d.format("%F").to_string()),
+            "fiscal-year-start": self.fiscal_year_start.map(|(m, d)| format!("{}-{}", m, d)),
+            "sunday-begins-week": self.sunday_begins_week,
+            "clock": if self.h12 { 12 } else { 24 },
+            "time-format": optional(&self.time_format),
+            "date-format": optional(&self.date_format),
+            "locale": optional(&self.locale),
+            "week-numbers": self.week_numbers,
+            "utc-offsets": self.utc_offsets,
+            "workdays": self.serialize_workdays(),
+            "beginning-work-day": format!("{}:{:02}", self.beginning_work_day.0, self.beginning_work_day.1),
+            "day-length": self.day_length,
+            "day-lengths": day_lengths,
+            "day-boundary": format!("{}:{:02}", self.day_boundary.0, self.day_boundary.1),
+            "auto-close-at": self.auto_close.and_then(|a| a.at()),
+            "auto-close-after": self.auto_close.and_then(|a| a.after()),
+            "editor": editor,
+            "color": color,
+            "pager": self.pager,
+            "schedule-history": schedule_history,
+            "styles": self.style_map,
+            "budgets": budgets,
+            "budget-periods": budget_periods,
+            "sources": sources,
+        })
+        .to_string()
+    }
+    // the configuration in the base directory, where profile settings are kept
+    pub fn base() -> Configuration {
+        let dir = base_dir(None);
+        if !dir.as_path().exists() {
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                panic!(
+                    "could not create base directory {}: {}",
+                    dir.to_str().unwrap(),
+                    e
+                );
+            }
+        }
+        Configuration::read(None, None)
+    }
+    // whether this is the configuration in the base directory
+    pub fn is_base(&self) -> bool {
+        base_dir(None)
+            .canonicalize()
+            .map(|d| d.to_str() == Some(&self.dir))
+            .unwrap_or(false)
+    }
+    // the directory holding the log of the named profile
+    pub fn profile_directory(&self, name: &str) -> PathBuf {
+        if let Some(dir) = self.profile_directories.get(name) {
+            PathBuf::from(dir)
+        } else {
+            let mut dir = base_dir(None);
+            dir.push("profiles");
+            dir.push(name);
+            dir
+        }
+    }
+    // all known profiles, sorted by name
+    pub fn profiles(&self) -> Vec<(String, PathBuf)> {
+        let mut names: BTreeSet<String> = self.profile_directories.keys().cloned().collect();
+        let mut dir = base_dir(None);
+        dir.push("profiles");
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                if entry.path().is_dir() {
+                    if let Some(name) = entry.file_name().to_str() {
+                        names.insert(name.to_owned());
+                    }
+                }
+            }
+        }
+        names
+            .into_iter()
+            .map(|n| {
+                let dir = self.profile_directory(&n);
+                (n, dir)
+            })
+            .collect()
+    }
     pub fn config_file(directory: Option<&str>) -> PathBuf {
         let mut path = base_dir(directory);
         path.push("config.ini");
@@ -1365,58 +4004,158 @@ impl Configuration {
         workdays
     }
     fn serialize_workdays(&self) -> String {
+        Configuration::serialize_workday_flags(self.workdays)
+    }
+    fn serialize_workday_flags(workdays: u8) -> String {
         let mut s = String::new();
         for (i, c) in "SMTWHFA".chars().enumerate() {
-            if (1 << i) & self.workdays > 0 {
+            if (1 << i) & workdays > 0 {
                 s.push(c);
             }
         }
         s
     }
+    // the index in SMTWHFA of a day of the week
+    fn day_index(day: &str) -> Option<usize> {
+        if day.len() == 1 {
+            "SMTWHFA".find(day)
+        } else {
+            None
+        }
+    }
+    // the earlier schedule in force on the given date, if any
+    fn schedule_on(&self, date: &NaiveDate) -> Option<&Schedule> {
+        self.schedule_history.iter().find(|s| date < &s.until)
+    }
+    // preserve the current schedule as the one in force before the given date
+    pub fn record_schedule_change(&mut self, date: NaiveDate) -> Result<(), String> {
+        if let Some(last) = self.schedule_history.last() {
+            if last.until >= date {
+                return Err(format!(
+                    "a schedule change has already been recorded for {}; schedule changes must be recorded in chronological order",
+                    last.until
+                ));
+            }
+        }
+        self.schedule_history.push(Schedule {
+            until: date,
+            workdays: self.workdays,
+            day_length: self.day_length,
+            day_lengths: self.day_lengths,
+            beginning_work_day: self.beginning_work_day,
+        });
+        Ok(())
+    }
     pub fn is_workday(&self, date: &NaiveDate) -> bool {
         let i = (date.weekday().number_from_sunday() - 1) as u8;
-        self.workdays & (1 << i) > 0
+        let (workdays, day_lengths) = if let Some(s) = self.schedule_on(date) {
+            (s.workdays, &s.day_lengths)
+        } else {
+            (self.workdays, &self.day_lengths)
+        };
+        workdays & (1 << i) > 0 && day_lengths[i as usize] != Some(0.0)
     }
-    // find the first pay period start date *after* the given date
-    pub fn next_start_pay_period(&self, date: &NaiveDate) -> Option<NaiveDate> {
-        if let Some(known_pay_period_start_date) = self.start_pay_period {
-            let delta = date
-                .signed_duration_since(known_pay_period_start_date)
-                .num_days();
-            let l = self.length_pay_period as i64;
-            let remainder = delta % l;
-            if remainder < 0 {
-                Some(date.clone() - Duration::days(remainder))
+    // the number of hours one is expected to work on the given date
+    pub fn day_length_on(&self, date: &NaiveDate) -> f32 {
+        if self.is_workday(date) {
+            let i = (date.weekday().number_from_sunday() - 1) as usize;
+            if let Some(s) = self.schedule_on(date) {
+                s.day_lengths[i].unwrap_or(s.day_length)
             } else {
-                Some(date.clone() + Duration::days(l - remainder))
+                self.day_lengths[i].unwrap_or(self.day_length)
             }
         } else {
-            None
+            0.0
         }
     }
-    pub fn hours_in_pay_period(&self) -> Option<f32> {
-        if let Some(d) = self.start_pay_period {
-            let mut acc: f32 = 0.0;
-            let mut d = d.clone();
-            for _ in 0..self.length_pay_period {
-                if self.is_workday(&d) {
-                    acc += self.day_length;
+    // when the work day began on the given date
+    pub fn beginning_work_day_on(&self, date: &NaiveDate) -> (usize, usize) {
+        if let Some(s) = self.schedule_on(date) {
+            s.beginning_work_day
+        } else {
+            self.beginning_work_day
+        }
+    }
+    // whether pay periods can be found
+    pub fn has_pay_period(&self) -> bool {
+        self.pay_period != PayPeriod::Fixed || self.start_pay_period.is_some()
+    }
+    // find the first pay period start date *after* the given date
+    pub fn next_start_pay_period(&self, date: &NaiveDate) -> Option<NaiveDate> {
+        match self.pay_period {
+            PayPeriod::Fixed => {
+                let known_pay_period_start_date = self.start_pay_period?;
+                let delta = date
+                    .signed_duration_since(known_pay_period_start_date)
+                    .num_days();
+                let l = self.length_pay_period as i64;
+                let remainder = delta % l;
+                if remainder < 0 {
+                    Some(date.clone() - Duration::days(remainder))
+                } else {
+                    Some(date.clone() + Duration::days(l - remainder))
                 }
-                d += Duration::days(1)
             }
-            Some(acc)
+            _ => self.pay_period_containing(date).map(|(_, end)| end),
+        }
+    }
+    // the first day of the pay period containing the given date and the first day of the next
+    pub fn pay_period_containing(&self, date: &NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let first = NaiveDate::from_ymd(date.year(), date.month(), 1);
+        let next_month = if date.month() == 12 {
+            NaiveDate::from_ymd(date.year() + 1, 1, 1)
         } else {
-            None
+            NaiveDate::from_ymd(date.year(), date.month() + 1, 1)
+        };
+        let middle = NaiveDate::from_ymd(date.year(), date.month(), 16);
+        match self.pay_period {
+            PayPeriod::Fixed => {
+                let end = self.next_start_pay_period(date)?;
+                Some((end - Duration::days(self.length_pay_period as i64), end))
+            }
+            PayPeriod::Monthly => Some((first, next_month)),
+            PayPeriod::Semimonthly if date < &middle => Some((first, middle)),
+            PayPeriod::Semimonthly => Some((middle, next_month)),
         }
     }
+    // the hours one is expected to work in the current pay period
+    pub fn hours_in_pay_period(&self) -> Option<f32> {
+        let (start, end) = match self.pay_period {
+            PayPeriod::Fixed => {
+                let start = self.start_pay_period?;
+                (start, start + Duration::days(self.length_pay_period as i64))
+            }
+            _ => self.pay_period_containing(&self.logical_date(&current_time()))?,
+        };
+        let mut acc: f32 = 0.0;
+        let mut d = start;
+        while d < end {
+            acc += self.day_length_on(&d);
+            d += Duration::days(1)
+        }
+        Some(acc)
+    }
     pub fn two_timer_config(&self) -> Option<Config> {
         Some(
             Config::new()
+                .now(current_time())
                 .monday_starts_week(!self.sunday_begins_week)
                 .pay_period_start(self.start_pay_period)
                 .pay_period_length(self.length_pay_period),
         )
     }
+    // like two_timer_config, but between midnight and the day boundary 'today' is still the day
+    // before
+    pub fn logical_two_timer_config(&self) -> Option<Config> {
+        let now = current_time();
+        let today = self.logical_date(&now);
+        if today == now.date() {
+            self.two_timer_config()
+        } else {
+            self.two_timer_config()
+                .map(|c| c.now(today.and_hms(23, 59, 59)))
+        }
+    }
     pub fn set_precision(&mut self, identifier: &str) {
         self.precision = Precision::from_s(identifier);
     }
@@ -1541,6 +4280,101 @@ mod tests {
         assert_eq!(0.002, trunctation.prepare(0.0011, &precision));
     }
 
+    #[test]
+    fn per_weekday_day_length() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        c.day_lengths[Configuration::day_index("M").unwrap()] = Some(10.0);
+        c.day_lengths[Configuration::day_index("F").unwrap()] = Some(0.0);
+        let monday = NaiveDate::from_ymd(2022, 5, 16);
+        assert_eq!(10.0, c.day_length_on(&monday));
+        assert_eq!(8.0, c.day_length_on(&(monday + Duration::days(1))));
+        let friday = monday + Duration::days(4);
+        assert!(!c.is_workday(&friday), "a zero-length day is not a workday");
+        assert_eq!(0.0, c.day_length_on(&friday));
+        assert_eq!(0.0, c.day_length_on(&(friday + Duration::days(1))));
+        c.start_pay_period = Some(NaiveDate::from_ymd(2022, 5, 15));
+        c.length_pay_period = 7;
+        assert_eq!(Some(34.0), c.hours_in_pay_period());
+    }
+
+    #[test]
+    fn schedule_history() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        let change = NaiveDate::from_ymd(2024, 3, 1); // a Friday
+        c.record_schedule_change(change).unwrap();
+        c.workdays("MTWH");
+        c.day_length = 10.0;
+        c.beginning_work_day = (7, 30);
+        let thursday_before = change - Duration::days(1);
+        let thursday_after = change + Duration::days(6);
+        assert_eq!(8.0, c.day_length_on(&thursday_before));
+        assert_eq!(10.0, c.day_length_on(&thursday_after));
+        assert!(
+            c.is_workday(&(change - Duration::days(7))),
+            "Fridays were workdays"
+        );
+        assert!(!c.is_workday(&change), "but no longer");
+        assert_eq!((9, 0), c.beginning_work_day_on(&thursday_before));
+        assert_eq!((7, 30), c.beginning_work_day_on(&thursday_after));
+        assert!(
+            c.record_schedule_change(change).is_err(),
+            "changes must be chronological"
+        );
+    }
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!(Some((12.5, BudgetPeriod::PayPeriod)), parse_budget("12.5"));
+        assert_eq!(Some((5.0, BudgetPeriod::Week)), parse_budget("5h/week"));
+        assert_eq!(
+            Some((5.0, BudgetPeriod::Week)),
+            parse_budget("5 hours / wk")
+        );
+        assert_eq!(Some((1.5, BudgetPeriod::Day)), parse_budget("1.5/day"));
+        assert_eq!(Some((40.0, BudgetPeriod::Month)), parse_budget("40h/Month"));
+        assert_eq!(
+            Some((20.0, BudgetPeriod::PayPeriod)),
+            parse_budget("20h/pay period")
+        );
+        assert_eq!(None, parse_budget("5h/fortnight"));
+        assert_eq!(None, parse_budget("lots"));
+        for (hours, per) in [(12.5, BudgetPeriod::PayPeriod), (5.0, BudgetPeriod::Week)] {
+            assert_eq!(Some((hours, per)), parse_budget(&budget_string(hours, per)));
+        }
+    }
+
+    #[test]
+    fn test_vacation_precedence() {
+        assert_eq!(
+            vec!["narrower", "-kind"],
+            parse_vacation_precedence("narrower, -kind").unwrap()
+        );
+        assert!(parse_vacation_precedence("kind,shorter").is_err());
+        assert!(parse_vacation_precedence("kind -kind").is_err(), "twice");
+        assert!(parse_vacation_precedence(" , ").is_err(), "empty");
+        let mut c = Configuration::defaults("foo".to_owned());
+        assert_eq!(
+            vec![
+                ("kind", false),
+                ("repeating", false),
+                ("earlier", false),
+                ("narrower", false)
+            ],
+            c.vacation_precedence_rules()
+        );
+        c.vacation_precedence = vec!["narrower".to_owned(), "-kind".to_owned()];
+        assert_eq!(
+            vec![
+                ("narrower", false),
+                ("kind", true),
+                ("repeating", false),
+                ("earlier", false)
+            ],
+            c.vacation_precedence_rules(),
+            "the rules left out follow in the default order"
+        );
+    }
+
     #[test]
     fn next_start_pay_period_same() {
         let mut c = Configuration::defaults("foo".to_owned());
@@ -1605,4 +4439,181 @@ mod tests {
             c.next_start_pay_period(&date).unwrap()
         )
     }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn strftime_validation() {
+        assert!(valid_strftime(String::from("%Y-%m-%d %H:%M")).is_ok());
+        assert!(valid_strftime(String::from("%A, %e %B")).is_ok());
+        assert!(valid_strftime(String::from("%Q")).is_err());
+        assert!(valid_date_format(String::from("%A, %e %B")).is_ok());
+        assert!(valid_date_format(String::from("%H:%M")).is_err());
+        assert!(valid_time_format(String::from("%H:%M")).is_ok());
+        assert!(valid_time_format(String::from("%F %T")).is_ok());
+    }
+
+    #[test]
+    fn profile_names() {
+        assert!(valid_profile_name(String::from("work")).is_ok());
+        assert!(valid_profile_name(String::from("job_2-b")).is_ok());
+        assert!(valid_profile_name(String::from("default")).is_err());
+        assert!(valid_profile_name(String::from("../work")).is_err());
+        assert!(valid_profile_name(String::from("")).is_err());
+    }
+
+    #[test]
+    fn fiscal_year() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        let date = NaiveDate::from_ymd(2020, 3, 4);
+        assert_eq!(None, c.fiscal_year_containing(&date));
+        assert_eq!(Some((10, 1)), Configuration::parse_month_day("10-1"));
+        assert_eq!(Some((7, 1)), Configuration::parse_month_day("07-01"));
+        assert_eq!(None, Configuration::parse_month_day("2-29"));
+        assert_eq!(None, Configuration::parse_month_day("13-1"));
+        c.fiscal_year_start = Some((10, 1));
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2019, 10, 1)),
+            c.fiscal_year_containing(&date)
+        );
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2020, 10, 1)),
+            c.fiscal_year_containing(&NaiveDate::from_ymd(2020, 10, 1))
+        );
+    }
+
+    #[test]
+    fn pay_periods() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        let date = NaiveDate::from_ymd(2020, 12, 16);
+        assert!(!c.has_pay_period());
+        assert_eq!(None, c.pay_period_containing(&date));
+        c.start_pay_period = Some(NaiveDate::from_ymd(2020, 1, 6));
+        assert_eq!(
+            Some((
+                NaiveDate::from_ymd(2020, 12, 7),
+                NaiveDate::from_ymd(2020, 12, 21)
+            )),
+            c.pay_period_containing(&date)
+        );
+        c.pay_period = PayPeriod::Monthly;
+        assert_eq!(
+            Some((
+                NaiveDate::from_ymd(2020, 12, 1),
+                NaiveDate::from_ymd(2021, 1, 1)
+            )),
+            c.pay_period_containing(&date)
+        );
+        c.pay_period = PayPeriod::Semimonthly;
+        c.start_pay_period = None;
+        assert!(c.has_pay_period());
+        assert_eq!(
+            Some((
+                NaiveDate::from_ymd(2020, 12, 16),
+                NaiveDate::from_ymd(2021, 1, 1)
+            )),
+            c.pay_period_containing(&date)
+        );
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2020, 12, 16)),
+            c.next_start_pay_period(&NaiveDate::from_ymd(2020, 12, 15))
+        );
+    }
+
+    #[test]
+    fn tag_normalization() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        c.tag_aliases.insert("mtg".to_owned(), "meeting".to_owned());
+        let tags = |ts: &[&str]| ts.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!("meeting", c.normalize_tag("mtg"));
+        assert_eq!(
+            "MTG",
+            c.normalize_tag("MTG"),
+            "without folding aliases match exactly"
+        );
+        assert_eq!(
+            tags(&["meeting", "Meeting"]),
+            c.normalize_tags(&tags(&["mtg", "Meeting", "meeting"]))
+        );
+        c.fold_tag_case = true;
+        assert_eq!("meeting", c.normalize_tag("MTG"));
+        assert_eq!(
+            tags(&["meeting", "plugh"]),
+            c.normalize_tags(&tags(&["mtg", "Meeting", "PLUGH", "plugh"]))
+        );
+    }
+
+    #[test]
+    fn lint_rules() {
+        let mut c = Configuration::defaults("foo".to_owned());
+        for (rule, default) in LINT_RULES.iter() {
+            assert_eq!(*default, c.lint_rule(rule).to_string());
+        }
+        let rule = LintRule::parse("gap", "error").unwrap();
+        assert_eq!(Severity::Error, rule.severity);
+        assert_eq!(Some(60.0), rule.threshold, "the default threshold is kept");
+        assert_eq!(
+            "error 90",
+            LintRule::parse("gap", "error 90").unwrap().to_string()
+        );
+        assert!(LintRule::parse("gap", "loud").is_err());
+        assert!(LintRule::parse("gap", "error -1").is_err());
+        assert!(LintRule::parse("untagged", "error 2").is_err());
+        assert!(LintRule::parse("nonsense", "error").is_err());
+        c.lint_rules.insert(
+            "untagged".to_owned(),
+            LintRule::parse("untagged", "off").unwrap(),
+        );
+        assert_eq!(Severity::Off, c.lint_rule("untagged").severity);
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn ini_validation() {
+        let c = Configuration::defaults("foo".to_owned());
+        assert!(check_ini(&c.ini()).is_ok(), "the defaults are valid");
+        for (section, key, value) in [
+            ("time", "day-length", "abc"),
+            ("time", "start-pay-period", "2020 2 30"),
+            ("time", "beginning-work-day", "9"),
+            ("time", "day-boundary", "13"),
+            ("summary", "max-width", "-1"),
+            ("summary", "precision", "tenth"),
+            ("schedule", "M", "lots"),
+            ("budget", "foo", "hours"),
+            ("schedule before 2020-1", "M", "3"),
+        ]
+        .iter()
+        {
+            let mut ini = c.ini();
+            ini.with_section(Some(*section)).set(*key, *value);
+            assert!(check_ini(&ini).is_err(), "{} {} = {}", section, key, value);
+        }
+    }
+
+    #[test]
+    fn test_parse_day_boundary() {
+        assert_eq!(Ok((4, 0)), parse_day_boundary("4"));
+        assert_eq!(Ok((4, 30)), parse_day_boundary("4:30"));
+        assert!(parse_day_boundary("13").is_err());
+        assert!(parse_day_boundary("4:").is_err());
+        let dir = crate::util::TestDirectory::new("test_parse_day_boundary");
+        for (value, boundary) in [("4", (4, 0)), ("4:30", (4, 30)), ("late", DAY_BOUNDARY)].iter() {
+            std::fs::write(
+                dir.path.join("config.ini"),
+                format!("[time]\nday-boundary = {}\n", value),
+            )
+            .unwrap();
+            assert_eq!(*boundary, dir.configuration().day_boundary, "{}", value);
+        }
+        for boundary in [(4, 0), (4, 5), (11, 30)].iter() {
+            let mut conf = dir.configuration();
+            conf.day_boundary = *boundary;
+            conf.write();
+            assert_eq!(
+                *boundary,
+                dir.configuration().day_boundary,
+                "written and read"
+            );
+        }
+    }
 }
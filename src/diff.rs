@@ -0,0 +1,293 @@
+// a minimal line diff for showing what an edit changed
+
+// the lines of context shown around each change
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Same,
+    Remove,
+    Add,
+}
+
+// beyond this many edits the diff is summarized rather than shown; the trace kept to recover the
+// edits grows with the square of their number
+const MAX_EDITS: usize = 1000;
+
+// the number of lines the two lists have in common at the beginning and at the end
+fn common_ends(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, suffix)
+}
+
+// the shortest edit script turning one list of lines into another, by Myers' algorithm, or None
+// if it would require more than MAX_EDITS edits
+fn script(old: &[&str], new: &[&str]) -> Option<Vec<Op>> {
+    // edits are usually few and close together, so trim what is the same at either end
+    let (prefix, suffix) = common_ends(old, new);
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // for each d, the furthest x reached on diagonals -d - 1 through d + 1 before it
+    let mut trace = vec![];
+    'outer: for d in 0..=max as isize {
+        if d as usize > MAX_EDITS {
+            return None;
+        }
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        let mut k = -d;
+        while k <= d {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'outer;
+            }
+            k += 2;
+        }
+    }
+    // walk back through the trace to recover the edits
+    let mut ops = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let i = (k + d + 1) as usize;
+        let prev_k = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + d + 1) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Same);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Add } else { Op::Remove });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    let mut script = vec![Op::Same; prefix];
+    script.extend(ops.into_iter().rev());
+    script.extend(vec![Op::Same; suffix]);
+    Some(script)
+}
+
+// a unified diff of two texts, or None if they are the same; each line is paired with the
+// name of the style used to display it, if any
+pub fn unified(
+    old_name: &str,
+    old: &str,
+    new_name: &str,
+    new: &str,
+) -> Option<Vec<(Option<&'static str>, String)>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let ops = match script(&old, &new) {
+        Some(ops) => ops,
+        None => {
+            // too many changes to show line by line, so just say how many there are
+            let (prefix, suffix) = common_ends(&old, &new);
+            let (removed, added) = (old.len() - prefix - suffix, new.len() - prefix - suffix);
+            return Some(vec![
+                (Some("removed"), format!("--- {}", old_name)),
+                (Some("added"), format!("+++ {}", new_name)),
+                (
+                    Some("hunk"),
+                    format!(
+                        "@@ -{},{} +{},{} @@",
+                        prefix + 1,
+                        removed,
+                        prefix + 1,
+                        added
+                    ),
+                ),
+                (
+                    None,
+                    format!(
+                        " too many changes to show: {} lines removed, {} added",
+                        removed, added
+                    ),
+                ),
+            ]);
+        }
+    };
+    let changes = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, &op)| op != Op::Same)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if changes.is_empty() {
+        return None;
+    }
+    // group the changes into hunks whose context would otherwise overlap
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut lines = vec![
+        (Some("removed"), format!("--- {}", old_name)),
+        (Some("added"), format!("+++ {}", new_name)),
+    ];
+    // the positions in each text where each op begins
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut o, mut n) = (0, 0);
+    for op in &ops {
+        positions.push((o, n));
+        match op {
+            Op::Same => {
+                o += 1;
+                n += 1;
+            }
+            Op::Remove => o += 1,
+            Op::Add => n += 1,
+        }
+    }
+    for (start, end) in hunks {
+        let slice = &ops[start..end];
+        let old_length = slice.iter().filter(|&&op| op != Op::Add).count();
+        let new_length = slice.iter().filter(|&&op| op != Op::Remove).count();
+        let (o, n) = positions[start];
+        // by convention an empty range is numbered from the line before it
+        let number = |p: usize, l: usize| if l == 0 { p } else { p + 1 };
+        lines.push((
+            Some("hunk"),
+            format!(
+                "@@ -{},{} +{},{} @@",
+                number(o, old_length),
+                old_length,
+                number(n, new_length),
+                new_length
+            ),
+        ));
+        for (i, op) in slice.iter().enumerate() {
+            let (o, n) = positions[start + i];
+            lines.push(match op {
+                Op::Same => (None, format!(" {}", old[o])),
+                Op::Remove => (Some("removed"), format!("-{}", old[o])),
+                Op::Add => (Some("added"), format!("+{}", new[n])),
+            });
+        }
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> Vec<String> {
+        unified("a", old, "b", new)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, l)| l)
+            .collect()
+    }
+
+    #[test]
+    fn test_no_change() {
+        assert!(unified("a", "1\n2\n", "b", "1\n2\n").is_none());
+    }
+
+    #[test]
+    fn test_change_in_middle() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        assert_eq!(
+            vec![
+                "--- a",
+                "+++ b",
+                "@@ -2,7 +2,7 @@",
+                " 2",
+                " 3",
+                " 4",
+                "-5",
+                "+five",
+                " 6",
+                " 7",
+                " 8"
+            ],
+            diff(old, new)
+        );
+    }
+
+    #[test]
+    fn test_separate_hunks() {
+        let old = (1..=20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (1..=20)
+            .filter(|&i| i != 19)
+            .map(|i| {
+                if i == 2 {
+                    String::from("two\n")
+                } else {
+                    format!("{}\n", i)
+                }
+            })
+            .collect::<String>();
+        let lines = diff(&old, &new);
+        let hunks = lines
+            .iter()
+            .filter(|l| l.starts_with("@@"))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["@@ -1,5 +1,5 @@", "@@ -16,5 +16,4 @@"], hunks);
+    }
+
+    #[test]
+    fn test_too_many_changes() {
+        let old = (0..3000).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (0..3000)
+            .map(|i| {
+                if i == 0 {
+                    String::from("0\n")
+                } else {
+                    format!("new {}\n", i)
+                }
+            })
+            .collect::<String>();
+        assert_eq!(
+            vec![
+                "--- a",
+                "+++ b",
+                "@@ -2,2999 +2,2999 @@",
+                " too many changes to show: 2999 lines removed, 2999 added"
+            ],
+            diff(&old, &new)
+        );
+    }
+
+    #[test]
+    fn test_addition_to_empty() {
+        assert_eq!(
+            vec!["--- a", "+++ b", "@@ -0,0 +1,2 @@", "+1", "+2"],
+            diff("", "1\n2\n")
+        );
+    }
+}
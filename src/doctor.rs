@@ -0,0 +1,217 @@
+extern crate chrono;
+extern crate clap;
+extern crate serde_json;
+
+use crate::backup;
+use crate::configure::Configuration;
+use crate::log::{Event, Item, LogController};
+use crate::util::{current_time, fatal, info, insert_dones, success, warn};
+use chrono::{Duration, NaiveDateTime};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::json;
+
+fn after_help() -> &'static str {
+    "\
+The doctor subcommand looks for problems in the log: lines that cannot be parsed and events \
+left open past the day boundary, most likely because you forgot to type `job done` before \
+going home.
+
+  > job doctor
+  warning: 'fixing the parser', begun 2020-03-05 15:02:11, runs until 2020-03-06 08:55:40; it would be ended at 2020-03-05 18:00:00
+
+Summaries already treat such events as ending when the auto-close policy says they ended -- \
+see `job configure --auto-close-at` and `job configure --auto-close-after`. With --fix the \
+doctor adds the corresponding DONE lines to the log, first backing it up as the backup \
+subcommand would. Without an auto-close policy the doctor can report these events but not \
+fix them. Lines that cannot be parsed you must fix yourself; see `job edit --validate`.
+
+All prefixes of 'doctor' after 'd' -- 'do' is already an alias of done, so 'doc', 'doct', \
+and 'docto' -- are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("doctor")
+            .aliases(&["doc", "doct", "docto"])
+            .about("Looks for problems in the log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("fix")
+                    .long("fix")
+                    .help("Ends events left open overnight per the auto-close policy")
+                    .long_help("Adds a DONE line to the log for each event left open past the day boundary, \
+                    at the time the auto-close policy says it ended."),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let reader = LogController::new(None, &conf).expect("could not read log");
+    let mut problems = 0;
+    for item in reader.items() {
+        if let Item::Error(msg, offset) = item {
+            problems += 1;
+            if matches.is_present("json") {
+                println!(
+                    "{}",
+                    json!({"type": "Malformed", "line": offset + 1, "error": msg})
+                );
+            } else {
+                warn(format!("line {} is malformed: {}", offset + 1, msg), &conf);
+            }
+        }
+    }
+    let now = current_time();
+    let overnight = overnight_events(reader.events_from_the_beginning(), &now, &conf);
+    for (event, close) in overnight.iter() {
+        problems += 1;
+        if matches.is_present("json") {
+            println!(
+                "{}",
+                json!({
+                    "type": "Overnight",
+                    "event": serde_json::from_str::<serde_json::Value>(&event.to_json(&now, &conf)).unwrap(),
+                    "close": close.map(|t| format!("{}", t)),
+                })
+            );
+        } else {
+            warn(
+                format!(
+                    "'{}', begun {}, runs until {}{}",
+                    event.description,
+                    event.start.format("%F %T"),
+                    event
+                        .end
+                        .map(|t| t.format("%F %T").to_string())
+                        .unwrap_or_else(|| String::from("now")),
+                    close
+                        .map(|t| format!("; it would be ended at {}", t.format("%F %T")))
+                        .unwrap_or_default()
+                ),
+                &conf,
+            );
+        }
+    }
+    if problems == 0 {
+        success("found no problems", &conf);
+        return;
+    }
+    if !matches.is_present("fix") {
+        info(
+            format!(
+                "found {} problem{}",
+                problems,
+                if problems == 1 { "" } else { "s" }
+            ),
+            &conf,
+        );
+        return;
+    }
+    if overnight.is_empty() {
+        warn("there is nothing the doctor can fix", &conf);
+        return;
+    }
+    if conf.auto_close.is_none() {
+        fatal(
+            "there is no auto-close policy; set one with `job configure --auto-close-at` or `job configure --auto-close-after`",
+            &conf,
+        );
+    }
+    let times = overnight
+        .iter()
+        .filter_map(|(_, close)| *close)
+        .collect::<Vec<_>>();
+    if let Some(backup) = backup::create(&conf) {
+        info(
+            format!("backed up the log to {}", backup.to_str().unwrap()),
+            &conf,
+        );
+    }
+    insert_dones(&times, &conf);
+    success(
+        format!(
+            "added {} DONE line{}",
+            times.len(),
+            if times.len() == 1 { "" } else { "s" }
+        ),
+        &conf,
+    );
+}
+
+// the events running past the day boundary, with the time at which the auto-close policy, if
+// any, ends them
+pub(crate) fn overnight_events<I: Iterator<Item = Event>>(
+    events: I,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> Vec<(Event, Option<NaiveDateTime>)> {
+    events
+        .filter(|e| {
+            let next_day = conf.start_of_day(&(conf.logical_date(&e.start) + Duration::days(1)));
+            e.end.unwrap_or(*now) > next_day
+        })
+        .map(|e| {
+            let mut closed = e.clone();
+            let close = if closed.auto_close(now, conf) {
+                closed.end
+            } else {
+                None
+            };
+            (e, close)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configure::AutoClose;
+    use crate::util::TestDirectory;
+    use chrono::NaiveDate;
+
+    fn event(start: NaiveDateTime, end: Option<NaiveDateTime>) -> Event {
+        let mut e = Event::coin(String::from("foo"), vec![]);
+        e.start = start;
+        e.end = end;
+        e
+    }
+
+    #[test]
+    fn test_overnight_events() {
+        let dir = TestDirectory::new("test_overnight_events");
+        let mut conf = dir.configuration();
+        let day = NaiveDate::from_ymd(2020, 1, 2);
+        let now = day.and_hms(12, 0, 0) + Duration::days(2);
+        let events = vec![
+            event(day.and_hms(9, 0, 0), Some(day.and_hms(17, 0, 0))),
+            event(
+                day.and_hms(17, 0, 0),
+                Some(day.and_hms(9, 0, 0) + Duration::days(1)),
+            ),
+            event(day.and_hms(20, 0, 0) + Duration::days(1), None),
+        ];
+        let overnight = overnight_events(events.clone().into_iter(), &now, &conf);
+        assert_eq!(2, overnight.len());
+        assert!(
+            overnight.iter().all(|(_, close)| close.is_none()),
+            "no policy"
+        );
+        conf.auto_close = Some(AutoClose::At(18, 0));
+        let overnight = overnight_events(events.clone().into_iter(), &now, &conf);
+        assert_eq!(Some(day.and_hms(18, 0, 0)), overnight[0].1);
+        assert_eq!(
+            Some(day.and_hms(0, 0, 0) + Duration::days(2)),
+            overnight[1].1,
+            "begun after the auto-close time, so ended at the day boundary"
+        );
+        conf.auto_close = Some(AutoClose::After(Some(2.0)));
+        let overnight = overnight_events(events.into_iter(), &now, &conf);
+        assert_eq!(Some(day.and_hms(19, 0, 0)), overnight[0].1);
+        assert_eq!(
+            Some(day.and_hms(22, 0, 0) + Duration::days(1)),
+            overnight[1].1
+        );
+    }
+}
@@ -1,11 +1,12 @@
 extern crate chrono;
 extern crate clap;
+extern crate serde_json;
 
 use crate::configure::Configuration;
-use crate::log::{Event, Item, LogController};
-use crate::util::{check_for_ongoing_event, describe, display_events, warn};
-use chrono::Local;
-use clap::{App, SubCommand};
+use crate::hook::notify;
+use crate::log::{Done, Event, Item, LogController};
+use crate::util::{check_for_ongoing_event, current_time, describe, display_events, warn};
+use clap::{App, ArgMatches, SubCommand};
 
 fn after_help() -> &'static str {
     "\
@@ -30,24 +31,35 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
     )
 }
 
-pub fn run(directory: Option<&str>) {
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
     let mut reader = LogController::new(None, &conf).expect("could not read log");
     if let Some(event) = reader.last_event() {
         check_for_ongoing_event(&mut reader, &conf);
         if event.ongoing() {
             let (done, offset) = reader.close_event();
-            describe(
-                "ending",
-                Some(&event.description),
-                Item::Done(done, offset),
+            let json = done_json(&done, &event, &conf);
+            notify("done", &json, &conf, matches);
+            if matches.is_present("json") {
+                println!("{}", json);
+            } else {
+                describe(
+                    "ending",
+                    Some(&event.description),
+                    Item::Done(done, offset),
+                    &conf,
+                );
+            }
+        } else if matches.is_present("json") {
+            warn(
+                "the most recent event is not ongoing; no change to log",
                 &conf,
             );
         } else {
             warn("the most recent event is not ongoing", &conf);
-            let now = Local::now().naive_local();
+            let now = current_time();
             let start = &event.start.clone();
-            let event = Event::gather_by_day(vec![event], &now);
+            let event = Event::gather_by_day(vec![event], &now, &conf);
             println!();
             display_events(event, start, &now, &conf);
             println!();
@@ -57,3 +69,14 @@ pub fn run(directory: Option<&str>) {
         warn("there is currently no event in the log", &conf)
     }
 }
+
+// the JSON representation of a DONE line along with the event it ends
+pub fn done_json(done: &Done, event: &Event, conf: &Configuration) -> String {
+    let mut ended = event.clone();
+    ended.end = Some(done.0);
+    format!(
+        r#"{{"type":"Done","time":{},"event":{}}}"#,
+        serde_json::to_string(&format!("{}", done.0)).unwrap(),
+        ended.to_json(&done.0, conf)
+    )
+}
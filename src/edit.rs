@@ -1,16 +1,24 @@
 extern crate chrono;
 extern crate clap;
+extern crate two_timer;
 
+use crate::backup::{discard, prune, snapshot};
 use crate::configure::Configuration;
-use crate::log::{parse_line, timestamp, Item, LogController};
-use crate::util::{base_dir, fatal, log_path, success, warn};
-use chrono::{Local, NaiveDateTime};
+use crate::diff::unified;
+use crate::log::{parse_line, timestamp, Item, ItemsAfter, LogController};
+use crate::storage::Storage;
+use crate::util::{
+    base_dir, current_time, expand_period, fatal, info, interactive, log_path, quiet, success,
+    warn, yes_or_no, Style,
+};
+use chrono::NaiveDateTime;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use std::fs::{copy, File};
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str::FromStr;
+use two_timer::parse;
 
 const BUFFER_SIZE: usize = 16 * 1024;
 
@@ -24,7 +32,17 @@ comment them out, provide a preceding explanation of the error, and notify you o
 of errors it found and the line number of the first error. It also creates a backup of the log \
 file before it opens the editor, so if need be you can destroy the botched log file and restore \
 the backup. You will have to do this manually. If it finds no errors it will destroy the backup \
-and restore any pre-existing backup it may have found."
+and restore any pre-existing backup it may have found. In addition, before the editor opens edit \
+makes a lasting backup of the log, which `job backup --restore` can restore.
+
+If your editor is vim, emacs, nano, or another which understands +<line>, the log is opened \
+at the first error comment left by an earlier validation or, if there is none, at the end \
+of the log, where the most recent events are. If validation finds errors, you are asked whether \
+to reopen the editor at the first of them, and so on until the log is clean. With --retry the editor \
+is reopened without asking.
+
+Before validating your changes edit shows them as a unified diff against the backup, colored \
+with the added, removed, and hunk styles. See `job configure --help`."
 }
 
 pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
@@ -53,6 +71,29 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 immediately fixes these errors, removing the error markers. --error-comments checks whether any remain.")
                 .conflicts_with("validate")
             )
+            .arg(
+                Arg::with_name("period")
+                .long("period")
+                .short("p")
+                .help("Edits only the part of the log in this period")
+                .long_help("Extracts the lines of the log in the given period into a temporary file, opens \
+                the editor on that, and once you are done splices your changes back into the log and validates them. \
+                The extract begins with the last timestamped line before the period, so an event ongoing when \
+                the period begins can be edited too. E.g., --period today, --period 'last friday'.")
+                .value_name("period")
+                .conflicts_with_all(&["validate", "error-comments"])
+            )
+            .arg(
+                Arg::with_name("retry")
+                .long("retry")
+                .short("r")
+                .help("Reopens the editor until no errors remain")
+                .long_help("If validation finds errors after you edit the log, reopens the editor at the \
+                first error comment without asking, and does so again until none remain or you close the editor \
+                without changing anything. Without --retry you are asked whether to reopen the editor if \
+                job is running in a terminal.")
+                .conflicts_with_all(&["validate", "error-comments"])
+            )
     )
 }
 
@@ -61,22 +102,10 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     if matches.is_present("validate") {
         validation_messages(0, 0, &conf, None, None, None);
     } else if matches.is_present("error-comments") {
-        let mut log = LogController::new(None, &conf).expect("could not open log for validation");
-        let mut error_lines: Vec<String> = vec![];
-        for item in log.items() {
-            match item {
-                Item::Comment(line_offset) => {
-                    let line = log
-                        .larry
-                        .get(line_offset)
-                        .expect(&format!("failed to read line {}", line_offset + 1));
-                    if line.starts_with("# ERROR") {
-                        error_lines.push((line_offset + 1).to_string());
-                    }
-                }
-                _ => (),
-            }
-        }
+        let error_lines: Vec<String> = error_comments(&conf)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
         if error_lines.is_empty() {
             success("no error comments found", &conf);
         } else {
@@ -94,12 +123,15 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         }
     } else {
-        if let Some((mut args, _)) = conf.effective_editor() {
-            let editor = args.remove(0);
-            let mut command = Command::new(&editor);
-            while !args.is_empty() {
-                command.arg(args.remove(0));
+        if let Some((args, _)) = conf.effective_editor() {
+            if let Some(phrase) = matches.value_of("period") {
+                if edit_period(phrase, args.clone(), &conf) {
+                    retry(&args, matches, &conf);
+                }
+                return;
             }
+            let mut command = editor_command(args.clone(), starting_line(&conf));
+            let snapshot = snapshot(&conf);
             let backed_up_backup = backup_backup(conf.directory());
             copy(log_path(conf.directory()), backup(None, conf.directory()))
                 .expect("could not make backup log");
@@ -115,10 +147,20 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 if let Some((offset, line_number)) =
                     find_change_offset(None, None, conf.directory())
                 {
+                    prune(&conf);
+                    show_diff(
+                        &read_file(backup(None, conf.directory())),
+                        &read_log(&conf),
+                        &conf,
+                    );
                     validation_messages(offset, line_number, &conf, None, None, None);
+                    retry(&args, matches, &conf);
                 } else {
                     success("no change found in log file; deleting backup...", &conf);
                     restore_backup(backed_up_backup, conf.directory());
+                    if let Some(snapshot) = snapshot {
+                        discard(snapshot, &conf);
+                    }
                 }
             } else {
                 fatal(
@@ -139,6 +181,248 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     }
 }
 
+// reopens the editor at the first error comment, with --retry or if the user agrees, until
+// no error comments remain or the user leaves the log unchanged
+fn retry(args: &[String], matches: &ArgMatches, conf: &Configuration) {
+    while let Some(&line) = error_comments(conf).first() {
+        if !(matches.is_present("retry")
+            || interactive() && yes_or_no(format!("reopen the editor at line {}?", line)))
+        {
+            break;
+        }
+        let before = read_log(conf);
+        let status = editor_command(args.to_vec(), line)
+            .arg(log_path(conf.directory()).to_str().unwrap())
+            .status()
+            .expect("failed to start editor process");
+        if !status.success() {
+            warn(
+                "the editor closed with an error; leaving the log as it is",
+                conf,
+            );
+            break;
+        }
+        let after = read_log(conf);
+        if after == before {
+            warn(
+                format!(
+                    "the log is unchanged; error comments remain at line {}",
+                    line
+                ),
+                conf,
+            );
+            break;
+        }
+        show_diff(&before, &after, conf);
+        let (offset, line_number) =
+            find_change_offset(None, None, conf.directory()).unwrap_or((0, 0));
+        validation_messages(offset, line_number, conf, None, None, None);
+    }
+}
+
+fn read_log(conf: &Configuration) -> Vec<u8> {
+    read_file(log_path(conf.directory()))
+}
+
+fn read_file(path: PathBuf) -> Vec<u8> {
+    let mut text = vec![];
+    File::open(&path)
+        .and_then(|mut f| f.read_to_end(&mut text))
+        .unwrap_or_else(|e| panic!("could not read {}: {}", path.to_str().unwrap(), e));
+    text
+}
+
+// shows what changed so the user can confirm it was what they meant to do
+fn show_diff(before: &[u8], after: &[u8], conf: &Configuration) {
+    if quiet() {
+        return;
+    }
+    if let Some(lines) = unified(
+        "log.bak",
+        &String::from_utf8_lossy(before),
+        "log",
+        &String::from_utf8_lossy(after),
+    ) {
+        let style = Style::new(conf);
+        for (s, line) in lines {
+            match s {
+                Some(s) => println!("{}", style.paint(s, line)),
+                None => println!("{}", line),
+            }
+        }
+    }
+}
+
+// edits the lines of the log in the given period in isolation, then splices them back,
+// returning whether anything changed
+fn edit_period(phrase: &str, args: Vec<String>, conf: &Configuration) -> bool {
+    let expanded = expand_period(phrase, false, conf);
+    let (start, end) = match parse(&expanded, conf.two_timer_config()) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(_) => {
+            fatal(
+                format!("could not parse '{}' as a time expression", phrase),
+                conf,
+            );
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        conf,
+    );
+    let mut log = LogController::new(None, conf).expect("could not open log");
+    let length = log.storage.len();
+    let first = log.find_line(&start).map_or(length, |i| i.offset());
+    let last = ItemsAfter::new(first, log_path(conf.directory()).to_str().unwrap())
+        .find(|i| matches!(i.time(), Some((t, _)) if t >= &end))
+        .map_or(length, |i| i.offset());
+    let byte_offset = |line: usize| {
+        if line < length {
+            log.storage
+                .byte_offset(line)
+                .expect("could not find line in log") as usize
+        } else {
+            std::fs::metadata(log_path(conf.directory()))
+                .expect("could not read log metadata")
+                .len() as usize
+        }
+    };
+    let (from, to) = (byte_offset(first), byte_offset(last));
+    info(
+        format!(
+            "editing lines {} through {} of the log",
+            first + 1,
+            last.max(first + 1)
+        ),
+        conf,
+    );
+    let text = read_log(conf);
+    let snapshot = snapshot(conf);
+    let extract = period_file(conf.directory());
+    File::create(&extract)
+        .and_then(|mut f| f.write_all(&text[from..to]))
+        .expect("could not write the period to a temporary file");
+    let status = editor_command(args, (last - first).max(1))
+        .arg(extract.to_str().unwrap())
+        .status()
+        .expect("failed to start editor process");
+    let mut edited = vec![];
+    File::open(&extract)
+        .and_then(|mut f| f.read_to_end(&mut edited))
+        .expect("could not read the edited period");
+    std::fs::remove_file(&extract).expect("could not remove the temporary file");
+    if !status.success() {
+        fatal(
+            "the editor closed with an error; the log is unchanged",
+            conf,
+        );
+    }
+    if edited == text[from..to] {
+        success("no change found in the period", conf);
+        if let Some(snapshot) = snapshot {
+            discard(snapshot, conf);
+        }
+        return false;
+    }
+    if !(edited.is_empty() || edited.ends_with(b"\n")) {
+        edited.push(b'\n');
+    }
+    prune(conf);
+    let mut spliced = text[..from].to_vec();
+    spliced.extend(edited);
+    spliced.extend(&text[to..]);
+    show_diff(&text, &spliced, conf);
+    backup_backup(conf.directory());
+    copy(log_path(conf.directory()), backup(None, conf.directory()))
+        .expect("could not make backup log");
+    File::create(log_path(conf.directory()))
+        .and_then(|mut f| f.write_all(&spliced))
+        .expect("could not splice the edited period into the log");
+    validation_messages(from, first, conf, None, None, None);
+    true
+}
+
+// the temporary file holding the part of the log being edited
+fn period_file(directory: Option<&str>) -> PathBuf {
+    let mut path = base_dir(directory);
+    path.push("edit.tmp");
+    path
+}
+
+// the command to start the editor with the cursor on the given line, if it can be put there
+fn editor_command(mut args: Vec<String>, line: usize) -> Command {
+    let editor = args.remove(0);
+    let mut command = Command::new(&editor);
+    command.args(args);
+    if let Some(arg) = goto_line(&editor, line) {
+        command.arg(arg);
+    }
+    command
+}
+
+// editors known to accept +<line> to open a file with the cursor on that line
+const LINE_EDITORS: &[&str] = &[
+    "vi",
+    "vim",
+    "nvim",
+    "gvim",
+    "mvim",
+    "view",
+    "emacs",
+    "emacsclient",
+    "nano",
+    "pico",
+    "micro",
+    "kak",
+    "joe",
+    "jed",
+    "mg",
+    "ne",
+];
+
+// the argument which puts the editor's cursor on the given line, if the editor understands it
+fn goto_line(editor: &str, line: usize) -> Option<String> {
+    let name = Path::new(editor).file_name()?.to_str()?;
+    if LINE_EDITORS.contains(&name) {
+        Some(format!("+{}", line))
+    } else {
+        None
+    }
+}
+
+// the line to open the log at: the first error comment left by an earlier validation, if
+// any, otherwise the last line, where the most recent events are
+fn starting_line(conf: &Configuration) -> usize {
+    if let Some(&line) = error_comments(conf).first() {
+        line
+    } else {
+        let log = LogController::new(None, conf).expect("could not open log");
+        log.storage.len().max(1)
+    }
+}
+
+// the line numbers of the comments marking errors found by validation
+fn error_comments(conf: &Configuration) -> Vec<usize> {
+    let mut log = LogController::new(None, conf).expect("could not open log for validation");
+    let mut error_lines = vec![];
+    for item in log.items() {
+        match item {
+            Item::Comment(line_offset) => {
+                let line = log
+                    .storage
+                    .get(line_offset)
+                    .expect(&format!("failed to read line {}", line_offset + 1));
+                if line.starts_with("# ERROR") {
+                    error_lines.push(line_offset + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    error_lines
+}
+
 fn restore_backup(backed_up_backup: bool, directory: Option<&str>) {
     std::fs::remove_file(backup(None, directory)).expect("failed to remove log.bak");
     if backed_up_backup {
@@ -323,7 +607,7 @@ fn validate(
     let mut first_error = 0;
     let mut error_count = 0;
     let mut open_task = false;
-    let now = now.unwrap_or(Local::now().naive_local());
+    let now = now.unwrap_or_else(current_time);
     let mut log = LogController::new(Some(log_file(log, conf.directory())), conf)
         .expect("could not open edited log file");
     let mut last_timestamp = log
@@ -518,6 +802,14 @@ mod tests {
         ret
     }
 
+    #[test]
+    fn test_goto_line() {
+        assert_eq!(Some(String::from("+12")), goto_line("/usr/bin/vim", 12));
+        assert_eq!(Some(String::from("+1")), goto_line("emacsclient", 1));
+        assert_eq!(None, goto_line("/usr/bin/open", 12));
+        assert_eq!(None, goto_line("code", 12));
+    }
+
     #[test]
     fn test_find_change_offset_when_no_change() {
         let disambiguator1 = "test_find_change_offset_when_no_change1";
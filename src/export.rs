@@ -0,0 +1,713 @@
+extern crate chrono;
+extern crate clap;
+extern crate regex;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::calendar::upload;
+use crate::configure::Configuration;
+use crate::log::{Event, Filter, LogController, Note};
+use crate::util::{
+    base_dir, common_search_or_filter_arguments, current_time, duration_string, expand_period,
+    fatal, info, remainder, success, warn, warn_of_unknown_tags,
+};
+use crate::vacation::VacationController;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::{stdout, Write};
+use two_timer::parse;
+
+// the events read from the log at a time when streaming
+const PAGE_SIZE: usize = 1000;
+
+lazy_static! {
+    static ref ISSUE_KEY: Regex = Regex::new(r"\b[A-Z][A-Z0-9_]*-[1-9][0-9]*\b").unwrap();
+}
+
+fn after_help() -> &'static str {
+    "\
+The export subcommand turns the events in a period into records for other programs. With \
+--jira it produces JIRA worklog entries, one for each issue on each day:
+
+  > job export --jira yesterday
+  {\"comment\":\"fixing the parser\",\"issue\":\"PROJ-123\",\"started\":\"2020-03-04T09:00:00.000-0500\",\"timeSpentSeconds\":9000}
+  {\"comment\":\"stand-up; planning\",\"issue\":\"ADMIN-7\",\"started\":\"2020-03-04T11:30:00.000-0500\",\"timeSpentSeconds\":3600}
+
+The issue of an event is the first issue key -- PROJ-123, say -- in its description. Events \
+without a key may be assigned one by a mapping file, given with --map or, by default, the file \
+jira.map in the job log directory. Each line of the mapping file assigns a key to the events \
+with some tag, or to the events whose description matches a regular expression written between \
+slashes:
+
+  # jira.map
+  meetings = ADMIN-7
+  /code review/ = PROJ-100
+
+The first line that applies wins. Events which still have no issue are left out, and you are \
+told how much time they account for. Ongoing events are left out as well. The filtering \
+options of the summary subcommand -- --tag, --rx, and so forth -- select which events are \
+exported.
+
+With --jsonl every event in the period is printed as a JSON object on a line of its own, in \
+the form produced by `job summary --json`:
+
+  > job export --jsonl 2010 through 2020 > decade.jsonl
+
+Events spanning the day boundary are split into one piece for each day, as in a summary. The \
+events are written as they are read from the log, so exports of any length need little memory.
+
+With --unified the events, the notes, and the vacation time in the period are printed together \
+in order of time, divided, merged, and filled in with vacation exactly as the summary \
+subcommand does it, so totals computed from the export agree with those it shows. Add --csv \
+for a spreadsheet rather than lines of JSON:
+
+  > job export --unified --csv 'last month' > march.csv
+  type,start,end,duration,vacation,author,tags,description
+  Event,2020-03-02 09:00:00,2020-03-02 11:30:00,2.50,,,\"bugs, parser\",fixing the parser
+  Note,2020-03-02 10:15:00,,,,,,the tests are slow
+
+Ongoing events end at the present moment, as in a summary.
+
+With --post the entries are added to the issues on the JIRA server set with \
+`job configure --jira-url` rather than printed. The API token is taken from the \
+JOBLOG_JIRA_TOKEN environment variable or, if that is unset, from your ~/.netrc file. Posting \
+uses curl, which must be installed. JIRA cannot tell a worklog entry posted twice from two \
+entries, so take care not to post the same period twice.
+
+All prefixes of 'export' after 'e' -- 'ex', 'exp', 'expo', and 'expor' -- are aliases of the \
+subcommand. The 'e' prefix is reserved for the edit subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(common_search_or_filter_arguments(
+        SubCommand::with_name("export")
+            .aliases(&["ex", "exp", "expo", "expor"])
+            .about("Exports events for other programs")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("jira")
+                    .long("jira")
+                    .help("Produces JIRA worklog entries")
+                    .long_help(
+                        "Produces a worklog entry for each issue on each day, the issue being \
+                        found in the event's description or assigned by the mapping file.",
+                    )
+                    .display_order(1),
+            )
+            .arg(
+                Arg::with_name("map")
+                    .long("map")
+                    .help("Assigns issues to events lacking them")
+                    .long_help(
+                        "A file each of whose lines assigns an issue key to the events with a tag or \
+                        whose description matches a /regular expression/, e.g., 'meetings = ADMIN-7'. \
+                        If no file is given, jira.map in the job log directory is used if it exists.",
+                    )
+                    .value_name("file")
+                    .requires("jira")
+                    .display_order(2),
+            )
+            .arg(
+                Arg::with_name("post")
+                    .long("post")
+                    .help("Posts the entries to the JIRA server")
+                    .long_help(
+                        "Adds the worklog entries to the issues on the server set with \
+                        `job configure --jira-url` rather than printing them.",
+                    )
+                    .requires("jira")
+                    .display_order(3),
+            )
+            .arg(
+                Arg::with_name("jsonl")
+                    .long("jsonl")
+                    .help("Prints each event as a line of JSON")
+                    .long_help(
+                        "Prints each event in the period as a JSON object on a line of its own, \
+                        writing them as they are read from the log.",
+                    )
+                    .conflicts_with("jira")
+                    .display_order(4),
+            )
+            .arg(
+                Arg::with_name("unified")
+                    .long("unified")
+                    .help("Prints events, notes, and vacation in one stream")
+                    .long_help(
+                        "Prints the events, notes, and vacation time in the period in order of time, \
+                        computed as the summary subcommand computes them.",
+                    )
+                    .conflicts_with_all(&["jira", "jsonl"])
+                    .display_order(5),
+            )
+            .arg(
+                Arg::with_name("csv")
+                    .long("csv")
+                    .help("Prints the unified stream as CSV")
+                    .long_help(
+                        "Prints the unified stream as comma-separated values with a header line \
+                        rather than as lines of JSON.",
+                    )
+                    .requires("unified")
+                    .display_order(6),
+            )
+            .arg(
+                Arg::with_name("period")
+                    .help("time expression")
+                    .long_help(
+                        "All the <period> arguments are concatenated to produce a time expression.",
+                    )
+                    .value_name("period")
+                    .default_value("today")
+                    .multiple(true),
+            )
+            .display_order(display_order),
+        Some(true),
+    ))
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    if !(matches.is_present("jira") || matches.is_present("jsonl") || matches.is_present("unified"))
+    {
+        fatal(
+            "choose a format for the export: --jira, --jsonl, or --unified",
+            &conf,
+        );
+    }
+    let url = if matches.is_present("post") {
+        match &conf.jira_url {
+            Some(url) => Some(url.trim_end_matches('/').to_owned()),
+            None => {
+                fatal(
+                    "no JIRA server is configured; see `job configure --jira-url`",
+                    &conf,
+                );
+                unreachable!()
+            }
+        }
+    } else {
+        None
+    };
+    let map = mapping(matches.value_of("map"), &conf);
+    let phrase = remainder("period", matches);
+    let (start, end) = match parse(
+        &expand_period(&phrase, false, &conf),
+        conf.logical_two_timer_config(),
+    ) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), &conf);
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        &conf,
+    );
+    let now = current_time();
+    let filter = Filter::new(matches);
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    if matches.is_present("jsonl") {
+        stream(&mut reader, &start, &end, &filter, &now, &conf);
+        return;
+    }
+    if matches.is_present("unified") {
+        unified(
+            &mut reader,
+            &start,
+            &end,
+            &filter,
+            &now,
+            matches.is_present("csv"),
+            &conf,
+        );
+        return;
+    }
+    let events = reader
+        .events_in_range(&start, &end)
+        .into_iter()
+        .filter(|e| !e.ongoing() && filter.matches(e))
+        .collect();
+    let events = Event::gather_by_day(events, &end, &conf)
+        .into_iter()
+        .filter(|e| e.start >= start && e.start < end)
+        .collect::<Vec<_>>();
+    let (worklogs, unassigned) = worklogs(&events, &map, &now, &conf);
+    if unassigned > 0.0 {
+        warn(
+            format!(
+                "{} hours belong to events with no issue",
+                duration_string(unassigned, &conf)
+            ),
+            &conf,
+        );
+    }
+    if worklogs.is_empty() {
+        warn("no worklog entry found", &conf);
+        return;
+    }
+    let url = match url {
+        Some(url) => url,
+        None => {
+            for w in worklogs.iter() {
+                println!("{}", w.to_json());
+            }
+            return;
+        }
+    };
+    let mut posted = 0;
+    for w in worklogs.iter() {
+        match upload(
+            "POST",
+            &format!("{}/rest/api/2/issue/{}/worklog", url, w.issue),
+            "application/json",
+            &w.body().to_string(),
+            &conf.jira_user,
+            "JOBLOG_JIRA_TOKEN",
+            &conf,
+        ) {
+            Ok(()) => posted += 1,
+            Err(err) => warn(
+                format!(
+                    "could not post the worklog for {} on {}: {}",
+                    w.issue, w.date, err
+                ),
+                &conf,
+            ),
+        }
+    }
+    success(
+        format!(
+            "posted {} worklog entr{} to {}",
+            posted,
+            if posted == 1 { "y" } else { "ies" },
+            url
+        ),
+        &conf,
+    );
+}
+
+// prints the events a page at a time so the whole period is never in memory at once
+fn stream(
+    reader: &mut LogController,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    filter: &Filter,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) {
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    let mut found = false;
+    for page in reader.events_in_range_paged(start, end, PAGE_SIZE) {
+        for e in Event::gather_by_day(page, end, conf)
+            .into_iter()
+            .filter(|e| &e.start >= start && &e.start < end && filter.matches(e))
+        {
+            found = true;
+            // the reader has gone away, perhaps because the output is piped to head
+            if writeln!(out, "{}", e.to_json(now, conf)).is_err() {
+                return;
+            }
+        }
+    }
+    if !found {
+        warn("no event found", conf);
+        warn_of_unknown_tags(&filter.tags(), conf);
+    }
+}
+
+// prints the events, notes, and vacation time in the period in order of time, the events treated
+// as the summary subcommand treats them
+fn unified(
+    reader: &mut LogController,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    filter: &Filter,
+    now: &NaiveDateTime,
+    csv: bool,
+    conf: &Configuration,
+) {
+    let first = match reader.first_timestamp() {
+        Some(t) => t,
+        None => {
+            warn("the log is empty", conf);
+            return;
+        }
+    };
+    // as in a summary, vacation is only added between the beginning of the log and today
+    let start = if first > *start {
+        conf.start_of_day(&conf.logical_date(&first))
+    } else {
+        *start
+    };
+    let tomorrow = conf.start_of_day(&(conf.logical_date(now) + Duration::days(1)));
+    let end = if *end > tomorrow { tomorrow } else { *end };
+    let mut notes = reader
+        .notes_in_range(&start, &end)
+        .into_iter()
+        .filter(|n| filter.matches(n))
+        .collect::<Vec<_>>();
+    notes.sort_by_key(|n| n.time);
+    let mut notes = notes.into_iter().peekable();
+    let events = reader
+        .events_in_range_streaming(&start, &end)
+        .filter(|e| filter.matches(e));
+    let events =
+        Event::merge_contiguous_streaming(Event::gather_by_day_streaming(events, &end, conf), conf);
+    let vacations = VacationController::read(None, conf.directory());
+    let events = vacations.add_vacation_times_streaming(&start, &end, events, conf, None, filter);
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    let note = |n: &Note| {
+        if csv {
+            csv_note(n)
+        } else {
+            n.to_json(now, conf)
+        }
+    };
+    if csv && writeln!(out, "{}", CSV_HEADER).is_err() {
+        return;
+    }
+    let mut found = false;
+    for e in events {
+        found = true;
+        while let Some(n) = notes.next_if(|n| n.time < e.start) {
+            if writeln!(out, "{}", note(&n)).is_err() {
+                return;
+            }
+        }
+        let line = if csv {
+            csv_event(&e, now, conf)
+        } else {
+            e.to_json(now, conf)
+        };
+        // the reader has gone away, perhaps because the output is piped to head
+        if writeln!(out, "{}", line).is_err() {
+            return;
+        }
+    }
+    for n in notes {
+        found = true;
+        if writeln!(out, "{}", note(&n)).is_err() {
+            return;
+        }
+    }
+    if !found {
+        warn("nothing found", conf);
+        warn_of_unknown_tags(&filter.tags(), conf);
+    }
+}
+
+const CSV_HEADER: &str = "type,start,end,duration,vacation,author,tags,description";
+
+// an event as a line of the unified CSV stream
+fn csv_event(event: &Event, now: &NaiveDateTime, conf: &Configuration) -> String {
+    csv_row(&[
+        "Event",
+        &event.start.to_string(),
+        &event.end.map(|t| t.to_string()).unwrap_or_default(),
+        &duration_string(event.duration(now), conf),
+        match &event.vacation_type {
+            Some(t) if t.is_empty() => "ordinary",
+            Some(t) => t,
+            None => "",
+        },
+        event.author.as_deref().unwrap_or_default(),
+        &event.tags.join(", "),
+        &event.description,
+    ])
+}
+
+// a note as a line of the unified CSV stream
+fn csv_note(note: &Note) -> String {
+    csv_row(&[
+        "Note",
+        &note.time.to_string(),
+        "",
+        "",
+        "",
+        note.author.as_deref().unwrap_or_default(),
+        &note.tags.join(", "),
+        &note.description,
+    ])
+}
+
+// the fields joined by commas, those containing commas, quotes, or line breaks quoted
+fn csv_row(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// what assigns an issue to an event lacking one
+#[derive(Debug)]
+enum Matcher {
+    Tag(String),
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, event: &Event) -> bool {
+        match self {
+            Matcher::Tag(tag) => event.tags.contains(tag),
+            Matcher::Pattern(rx) => rx.is_match(&event.description),
+        }
+    }
+}
+
+// the mapping in the given file or, failing that, jira.map in the job log directory, if any
+fn mapping(file: Option<&str>, conf: &Configuration) -> Vec<(Matcher, String)> {
+    let path = match file {
+        Some(f) => f.to_owned(),
+        None => {
+            let mut path = base_dir(conf.directory());
+            path.push("jira.map");
+            if !path.as_path().exists() {
+                return vec![];
+            }
+            path.to_str().unwrap().to_owned()
+        }
+    };
+    let text = match read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            fatal(format!("could not read {}: {}", path, e), conf);
+            unreachable!()
+        }
+    };
+    match parse_mapping(&text) {
+        Ok(map) => map,
+        Err(e) => {
+            fatal(format!("{} in {}", e, path), conf);
+            unreachable!()
+        }
+    }
+}
+
+// lines of the form "<tag> = KEY" or "/<regex>/ = KEY"; blank lines and those beginning with #
+// are ignored
+fn parse_mapping(text: &str) -> Result<Vec<(Matcher, String)>, String> {
+    let mut map = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (left, key) = match line.rfind('=') {
+            Some(offset) => (line[..offset].trim(), line[offset + 1..].trim()),
+            None => return Err(format!("line {} lacks an =", i + 1)),
+        };
+        if ISSUE_KEY.find(key).is_none_or(|m| m.as_str() != key) {
+            return Err(format!("line {}: '{}' is not an issue key", i + 1, key));
+        }
+        let matcher = if left.len() > 1 && left.starts_with('/') && left.ends_with('/') {
+            match Regex::new(&left[1..left.len() - 1]) {
+                Ok(rx) => Matcher::Pattern(rx),
+                Err(e) => return Err(format!("line {}: {}", i + 1, e)),
+            }
+        } else if left.is_empty() {
+            return Err(format!("line {} lacks a tag or pattern", i + 1));
+        } else {
+            Matcher::Tag(left.to_owned())
+        };
+        map.push((matcher, key.to_owned()));
+    }
+    Ok(map)
+}
+
+// the issue an event's time is logged against
+fn issue(event: &Event, map: &[(Matcher, String)]) -> Option<String> {
+    ISSUE_KEY
+        .find(&event.description)
+        .map(|m| m.as_str().to_owned())
+        .or_else(|| {
+            map.iter()
+                .find(|(m, _)| m.matches(event))
+                .map(|(_, key)| key.clone())
+        })
+}
+
+// the time spent on an issue on a day
+#[derive(Debug)]
+struct Worklog {
+    issue: String,
+    date: NaiveDate,
+    started: NaiveDateTime,
+    seconds: i64,
+    comments: Vec<String>,
+}
+
+impl Worklog {
+    // the JSON JIRA expects
+    fn body(&self) -> serde_json::Value {
+        let started = Local
+            .from_local_datetime(&self.started)
+            .earliest()
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3f%z").to_string())
+            .unwrap_or_else(|| {
+                self.started
+                    .format("%Y-%m-%dT%H:%M:%S%.3f+0000")
+                    .to_string()
+            });
+        json!({
+            "started": started,
+            "timeSpentSeconds": self.seconds,
+            "comment": self.comments.join("; "),
+        })
+    }
+    // the body plus the issue
+    fn to_json(&self) -> String {
+        let mut json = self.body();
+        json["issue"] = json!(self.issue);
+        json.to_string()
+    }
+}
+
+// the worklog entries for the events, ordered by day and start, and the seconds spent on events
+// with no issue
+fn worklogs(
+    events: &[Event],
+    map: &[(Matcher, String)],
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> (Vec<Worklog>, f32) {
+    let mut unassigned = 0.0;
+    let mut by_day: BTreeMap<(NaiveDate, String), (NaiveDateTime, f32, Vec<String>)> =
+        BTreeMap::new();
+    for e in events {
+        match issue(e, map) {
+            Some(key) => {
+                let entry = by_day.entry((conf.logical_date(&e.start), key)).or_insert((
+                    e.start,
+                    0.0,
+                    vec![],
+                ));
+                entry.1 += e.duration(now);
+                if !entry.2.contains(&e.description) {
+                    entry.2.push(e.description.clone());
+                }
+            }
+            None => unassigned += e.duration(now),
+        }
+    }
+    let mut worklogs = by_day
+        .into_iter()
+        // JIRA counts time in whole minutes
+        .map(|((date, issue), (started, seconds, comments))| Worklog {
+            issue,
+            date,
+            started,
+            seconds: (seconds / 60.0).round() as i64 * 60,
+            comments,
+        })
+        .filter(|w| w.seconds > 0)
+        .collect::<Vec<_>>();
+    worklogs.sort_by_key(|w| w.started);
+    (worklogs, unassigned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    fn event(description: &str, tags: &[&str], start: NaiveDateTime, hours: i64) -> Event {
+        Event::builder()
+            .start(start)
+            .end(start + chrono::Duration::hours(hours))
+            .description(description)
+            .tags(tags)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_worklogs() {
+        let dir = TestDirectory::new("test_export_worklogs");
+        let conf = dir.configuration();
+        let map = parse_mapping("# comment\nmeetings = ADMIN-7\n/review/ = PROJ-100\n")
+            .expect("could not parse mapping");
+        assert!(parse_mapping("meetings = admin").is_err());
+        assert!(parse_mapping("meetings ADMIN-7").is_err());
+        let day = NaiveDate::from_ymd(2020, 3, 2);
+        let events = vec![
+            event("PROJ-123 fixing the parser", &[], day.and_hms(9, 0, 0), 1),
+            event("stand-up", &["meetings"], day.and_hms(10, 0, 0), 1),
+            event("more PROJ-123", &["meetings"], day.and_hms(11, 0, 0), 2),
+            event("code review", &[], day.and_hms(13, 0, 0), 1),
+            event("lunch", &[], day.and_hms(14, 0, 0), 1),
+            event(
+                "PROJ-123 again",
+                &[],
+                (day + chrono::Duration::days(1)).and_hms(9, 0, 0),
+                1,
+            ),
+        ];
+        let now = day.and_hms(12, 0, 0) + chrono::Duration::days(2);
+        let (worklogs, unassigned) = worklogs(&events, &map, &now, &conf);
+        assert_eq!(60.0 * 60.0, unassigned, "lunch");
+        let summary = worklogs
+            .iter()
+            .map(|w| (w.issue.as_str(), w.date, w.seconds / 3600))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("PROJ-123", day, 3),
+                ("ADMIN-7", day, 1),
+                ("PROJ-100", day, 1),
+                ("PROJ-123", day + chrono::Duration::days(1), 1),
+            ],
+            summary,
+            "a key in the description beats the mapping"
+        );
+        assert_eq!(
+            vec!["PROJ-123 fixing the parser", "more PROJ-123"],
+            worklogs[0].comments
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            "a,\"b, c\",\"say \"\"hi\"\"\",,\"x\ny\"",
+            csv_row(&["a", "b, c", "say \"hi\"", "", "x\ny"])
+        );
+        let dir = TestDirectory::new("test_export_csv");
+        let conf = dir.configuration();
+        let day = NaiveDate::from_ymd(2020, 3, 2);
+        let now = day.and_hms(18, 0, 0);
+        let mut e = event(
+            "fixing the parser",
+            &["parser", "bugs"],
+            day.and_hms(9, 0, 0),
+            2,
+        );
+        assert_eq!(
+            "Event,2020-03-02 09:00:00,2020-03-02 11:00:00,2.00,,,\"bugs, parser\",fixing the parser",
+            csv_event(&e, &now, &conf)
+        );
+        e.end = None;
+        e.vacation_type = Some(String::new());
+        e.author = Some("bob".to_owned());
+        assert_eq!(
+            "Event,2020-03-02 09:00:00,,9.00,ordinary,bob,\"bugs, parser\",fixing the parser",
+            csv_event(&e, &now, &conf),
+            "an ongoing event lasts until now"
+        );
+    }
+}
@@ -3,9 +3,12 @@ extern crate clap;
 extern crate regex;
 
 use crate::configure::Configuration;
-use crate::log::{Event, Filter, LogController, Note};
-use crate::util::{common_search_or_filter_arguments, display_events, display_notes, warn};
-use chrono::Local;
+use crate::log::{Event, Filter, Note};
+use crate::util::{
+    common_search_or_filter_arguments, current_time, display_events, display_notes,
+    include_archives_argument, limit_and_offset, limit_arguments, log_reader, warn,
+    warn_of_unknown_tags,
+};
 use clap::{App, ArgMatches, SubCommand};
 
 fn after_help() -> &'static str {
@@ -22,6 +25,17 @@ subcommand is the way to go.
   TOTAL HOURS 1.25
   g           1.25
 
+To see more than one, use --limit, and to skip some, --offset:
+
+  > job first --tag g --offset 1 --limit 3
+
+shows the second through fourth events tagged g.
+
+If you have truncated the log, --include-archives searches the heads cut from it as well, so \
+you can find when you really first worked on something:
+
+  > job first --include-archives --tag g
+
 All prefixes of 'first' are aliases of the subcommand.
 "
 }
@@ -32,6 +46,8 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
             .aliases(&["f", "fi", "fir", "firs"])
             .about("Shows the first task recorded")
             .after_help(after_help())
+            .args(&limit_arguments())
+            .arg(include_archives_argument())
             .display_order(display_order),
         None,
     ))
@@ -40,33 +56,60 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let filter = Filter::new(matches);
     let conf = Configuration::read(None, directory);
-    let reader = LogController::new(None, &conf).expect("could not read log");
+    let reader = log_reader(None, matches, &conf).expect("could not read log");
+    let (limit, offset) = limit_and_offset(matches);
+    // by default just the one
+    let limit = limit.unwrap_or(1);
     if matches.is_present("notes") {
-        let note: Vec<Note> = reader
+        let notes: Vec<Note> = reader
             .notes_from_the_beginning()
             .filter(|n| filter.matches(n))
-            .take(1)
+            .skip(offset)
+            .take(limit)
             .collect();
-        if note.is_empty() {
-            warn("no note found", &conf)
+        if notes.is_empty() {
+            warn("no note found", &conf);
+            warn_of_unknown_tags(&filter.tags(), &conf);
         } else {
-            let start = &note[0].time.clone();
-            let now = Local::now().naive_local();
-            display_notes(note, start, &now, &conf);
+            let start = &notes[0].time.clone();
+            let now = current_time();
+            if matches.is_present("json") {
+                for n in notes.iter() {
+                    println!("{}", n.to_json(&now, &conf));
+                }
+            } else if matches.is_present("tsv") {
+                for n in notes.iter() {
+                    println!("{}", n.to_tsv(&now, &conf));
+                }
+            } else {
+                display_notes(notes, start, &now, &conf);
+            }
         }
     } else {
-        let event: Vec<Event> = reader
+        let events: Vec<Event> = reader
             .events_from_the_beginning()
             .filter(|n| filter.matches(n))
-            .take(1)
+            .skip(offset)
+            .take(limit)
             .collect();
-        if event.is_empty() {
-            warn("no event found", &conf)
+        if events.is_empty() {
+            warn("no event found", &conf);
+            warn_of_unknown_tags(&filter.tags(), &conf);
         } else {
-            let start = &event[0].start.clone();
-            let now = Local::now().naive_local();
-            let event = Event::gather_by_day(event, &now);
-            display_events(event, start, &now, &conf);
+            let start = &events[0].start.clone();
+            let now = current_time();
+            if matches.is_present("json") {
+                for e in events.iter() {
+                    println!("{}", e.to_json(&now, &conf));
+                }
+            } else if matches.is_present("tsv") {
+                for e in events.iter() {
+                    println!("{}", e.to_tsv(&now, &conf));
+                }
+            } else {
+                let events = Event::gather_by_day(events, &now, &conf);
+                display_events(events, start, &now, &conf);
+            }
         }
     }
 }
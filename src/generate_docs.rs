@@ -0,0 +1,315 @@
+extern crate clap;
+
+use crate::configure::Configuration;
+use crate::util::{fatal, success};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+
+fn after_help() -> &'static str {
+    "\
+The generate-docs subcommand writes a man page for job and one for each of its subcommands \
+into the given directory, creating the directory if need be:
+
+  > job generate-docs /usr/local/share/man/man1
+  > man job-summary
+
+The pages are made from the same descriptions as the --help text, so they are as complete as \
+it is. Each is named after its command -- job.1, job-add.1, job-summary.1 and so forth -- and \
+belongs in section 1 of the manual.
+
+All prefixes of 'generate-docs' -- 'ge', 'gen', and so forth -- are aliases of the \
+subcommand. 'g' is an alias of git-hook."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("generate-docs")
+            .aliases(&[
+                "ge",
+                "gen",
+                "gene",
+                "gener",
+                "genera",
+                "generat",
+                "generate",
+                "generate-",
+                "generate-d",
+                "generate-do",
+                "generate-doc",
+            ])
+            .about("Writes man pages for job and its subcommands")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("dir")
+                    .help("The directory to write the man pages into")
+                    .value_name("dir")
+                    .required(true),
+            )
+            .display_order(display_order),
+    )
+}
+
+// the application is passed in because only the executable knows all the subcommands
+pub fn run(directory: Option<&str>, matches: &ArgMatches, app: fn() -> App<'static, 'static>) {
+    let conf = Configuration::read(None, directory);
+    let dir = Path::new(matches.value_of("dir").unwrap());
+    if let Err(e) = create_dir_all(dir) {
+        fatal(format!("could not create {}: {}", dir.display(), e), &conf);
+    }
+    let main = help(app, None);
+    let names = subcommands(&main);
+    let pages = names
+        .iter()
+        .map(|name| format!("job-{}", name))
+        .collect::<Vec<_>>();
+    let mut written = vec![(
+        String::from("job"),
+        man_page(
+            "job",
+            &main,
+            &pages.iter().map(|p| p.as_str()).collect::<Vec<_>>(),
+        ),
+    )];
+    for (name, page) in names.iter().zip(pages.iter()) {
+        written.push((
+            page.clone(),
+            man_page(page, &help(app, Some(name)), &["job"]),
+        ));
+    }
+    for (name, page) in written.iter() {
+        let path = dir.join(format!("{}.1", name));
+        if let Err(e) = write(&path, page) {
+            fatal(format!("could not write {}: {}", path.display(), e), &conf);
+        }
+    }
+    success(
+        format!("wrote {} man pages to {}", written.len(), dir.display()),
+        &conf,
+    );
+}
+
+// the long help of the command or one of its subcommands, unwrapped and uncolored
+fn help(app: fn() -> App<'static, 'static>, subcommand: Option<&str>) -> String {
+    let mut args = vec!["job"];
+    if let Some(name) = subcommand {
+        args.push(name);
+    }
+    args.push("--help");
+    match app()
+        .global_setting(AppSettings::ColorNever)
+        .set_term_width(0)
+        .get_matches_from_safe(args)
+    {
+        Err(e) => e.message,
+        Ok(_) => unreachable!(),
+    }
+}
+
+// the names of the subcommands listed in the help, excepting help itself
+fn subcommands(help: &str) -> Vec<String> {
+    sections(help)
+        .into_iter()
+        .filter(|(title, _)| title == "SUBCOMMANDS")
+        .flat_map(|(_, lines)| lines)
+        .filter_map(|l| l.split_whitespace().next().map(|s| s.to_owned()))
+        .filter(|s| s != "help")
+        .collect()
+}
+
+// the help text divided into its sections: the header, with an empty title, USAGE, FLAGS, and
+// so forth, and the text after them, titled DESCRIPTION
+fn sections(help: &str) -> Vec<(String, Vec<String>)> {
+    let mut sections = vec![(String::new(), vec![])];
+    let mut described = false;
+    for line in help.lines() {
+        let line = line.trim_end();
+        if !described && !line.is_empty() && !line.starts_with(' ') {
+            if line.ends_with(':') && line.chars().all(|c| c.is_ascii_uppercase() || c == ':') {
+                sections.push((line.trim_end_matches(':').to_owned(), vec![]));
+                continue;
+            } else if sections.len() > 1 {
+                described = true;
+                sections.push((String::from("DESCRIPTION"), vec![]));
+            }
+        }
+        sections.last_mut().unwrap().1.push(line.to_owned());
+    }
+    sections
+}
+
+// a man page in roff made from a help text
+fn man_page(name: &str, help: &str, see_also: &[&str]) -> String {
+    let sections = sections(help);
+    // the header is the name and version, the authors, if any, and the description
+    let about = sections[0]
+        .1
+        .iter()
+        .rfind(|l| !l.is_empty())
+        .cloned()
+        .unwrap_or_default();
+    let mut page = format!(
+        ".TH {} 1 \"\" \"job {}\" \"Job Log Manual\"\n.SH NAME\n{} \\- {}\n",
+        escape(&name.to_uppercase()),
+        env!("CARGO_PKG_VERSION"),
+        escape(name),
+        escape(&about)
+    );
+    for (title, lines) in sections.iter().skip(1) {
+        match title.as_str() {
+            "USAGE" => {
+                page += ".SH SYNOPSIS\n";
+                for l in lines.iter().filter(|l| !l.is_empty()) {
+                    page += &format!("{}\n.br\n", escape(l.trim()));
+                }
+            }
+            "DESCRIPTION" => page += &format!(".SH DESCRIPTION\n{}", paragraphs(lines)),
+            _ => page += &format!(".SH {}\n{}", title, entries(lines)),
+        }
+    }
+    if !see_also.is_empty() {
+        page += ".SH SEE ALSO\n";
+        page += &see_also
+            .iter()
+            .map(|n| format!("\\fB{}\\fR(1)", escape(n)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        page.push('\n');
+    }
+    page
+}
+
+// the items of a list of options, arguments, or subcommands: each begins on a line indented
+// four spaces, or eight for an option with no short form, with its help either after it or on
+// the lines following
+fn entries(lines: &[String]) -> String {
+    let mut roff = String::new();
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        let indentation = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if indentation <= 8 {
+            let (tag, help) = match line.find("  ") {
+                Some(i) => (&line[..i], line[i..].trim()),
+                None => (line, ""),
+            };
+            roff += &format!(".TP\n\\fB{}\\fR\n", escape(tag));
+            if !help.is_empty() {
+                roff += &format!("{}\n", escape(help));
+            }
+        } else {
+            roff += &format!("{}\n", escape(line));
+        }
+    }
+    roff
+}
+
+// prose, with indented lines, such as examples, kept as they are
+fn paragraphs(lines: &[String]) -> String {
+    let mut roff = String::new();
+    let mut verbatim = false;
+    let mut fresh = true;
+    for line in lines {
+        if line.is_empty() {
+            fresh = true;
+            continue;
+        }
+        let indented = line.starts_with(' ');
+        if indented != verbatim || fresh {
+            if verbatim {
+                roff += ".fi\n";
+            }
+            roff += if indented { ".PP\n.nf\n" } else { ".PP\n" };
+            verbatim = indented;
+        }
+        fresh = false;
+        roff += &format!("{}\n", escape(line));
+    }
+    if verbatim {
+        roff += ".fi\n";
+    }
+    roff
+}
+
+// protects text from interpretation as roff
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_man_page() {
+        let help = "\
+job-frob
+Frobs the log
+
+USAGE:
+    job frob [FLAGS] <what>
+
+FLAGS:
+    -f, --force    Frobs regardless
+    -h, --help
+            Prints help information
+        --now <time>
+            Treats this time as the present
+
+ARGS:
+    <what>    What to frob
+
+The frob subcommand frobs. For example:
+
+  > job frob .log
+
+That's all.";
+        assert_eq!(
+            ".TH JOB\\-FROB 1 \"\" \"job ".to_owned()
+                + env!("CARGO_PKG_VERSION")
+                + "\" \"Job Log Manual\"
+.SH NAME
+job\\-frob \\- Frobs the log
+.SH SYNOPSIS
+job frob [FLAGS] <what>
+.br
+.SH FLAGS
+.TP
+\\fB\\-f, \\-\\-force\\fR
+Frobs regardless
+.TP
+\\fB\\-h, \\-\\-help\\fR
+Prints help information
+.TP
+\\fB\\-\\-now <time>\\fR
+Treats this time as the present
+.SH ARGS
+.TP
+\\fB<what>\\fR
+What to frob
+.SH DESCRIPTION
+.PP
+The frob subcommand frobs. For example:
+.PP
+.nf
+  > job frob .log
+.fi
+.PP
+That's all.
+.SH SEE ALSO
+\\fBjob\\fR(1)
+",
+            man_page("job-frob", help, &["job"])
+        );
+        assert_eq!(
+            vec!["add", "summary"],
+            subcommands(
+                "job 1.0\nLogs jobs\n\nUSAGE:\n    job <SUBCOMMAND>\n\nSUBCOMMANDS:\n    add        Adds\n    help       Helps\n    summary    Sums\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,227 @@
+extern crate clap;
+
+use crate::cancel::ongoing_event;
+use crate::configure::Configuration;
+use crate::hook::notify;
+use crate::log::{log_line, Item, LogController};
+use crate::merge::tmp_path;
+use crate::util::{describe, log_path, some_nws, success, warn};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::fs::{canonicalize, read_to_string, rename, write};
+use std::process::Command;
+
+fn after_help() -> &'static str {
+    "\
+The git-hook subcommand ties your commits to the time you have tracked. Run from a git \
+post-commit hook, it adds a note to the log giving the current branch and the subject of \
+the commit just made:
+
+  > job git-hook --print --tag git > .git/hooks/post-commit
+  > chmod +x .git/hooks/post-commit
+  > git commit -m 'fix the parser'
+  > job summary --with-notes
+  Wednesday,  4 March
+     9:00 - ongoing  1.25  plugh  fixing the parser
+       10:14  note: parser-fix: fix the parser (3f2a9c1) (git)
+
+With --amend the commit is instead appended to the description of the ongoing event, if there \
+is one. With --print the subcommand does nothing but print a hook script which invokes it \
+with the other options given. Without an ongoing event, or outside a git repository, there is \
+nothing to amend, and a note is added or nothing is done, respectively.
+
+git, which must be installed, is run in the current directory, which during a hook is the top \
+of the repository.
+
+All prefixes of 'git-hook' -- 'g', 'gi', 'git', and so forth -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("git-hook")
+            .aliases(&["g", "gi", "git", "git-", "git-h", "git-ho", "git-hoo"])
+            .about("Records the latest git commit in the log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("tag")
+                .short("t")
+                .long("tag")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Adds this tag to the note")
+                .long_help("A tag to give the note recording the commit. Tags are ignored with --amend.")
+                .value_name("tag")
+                .validator(|v| if some_nws(&v) {Ok(())} else {Err(format!("tag {:?} needs some non-whitespace character", v))})
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("amend")
+                .long("amend")
+                .help("Appends the commit to the ongoing event's description")
+                .long_help("Rather than adding a note, appends the branch and subject of the commit to the description of \
+                the ongoing event. If no event is ongoing, a note is added as usual.")
+                .display_order(2)
+            )
+            .arg(
+                Arg::with_name("print")
+                .long("print")
+                .help("Prints a post-commit hook script")
+                .long_help("Prints a shell script suitable for .git/hooks/post-commit which invokes this subcommand \
+                with the other options given.")
+                .display_order(3)
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let tags: Vec<String> = matches
+        .values_of("tag")
+        .map(|values| values.map(|s| s.to_owned()).collect())
+        .unwrap_or_default();
+    if matches.is_present("print") {
+        print!("{}", script(&tags, matches.is_present("amend"), directory));
+        return;
+    }
+    let subject = match git(&["log", "-1", "--format=%s"]) {
+        Some(subject) => subject,
+        None => {
+            warn("found no git commit", &conf);
+            return;
+        }
+    };
+    let hash = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_default();
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD");
+    let commit = commit_description(branch.as_deref(), &subject, &hash);
+    if matches.is_present("amend") {
+        let path = log_path(conf.directory());
+        let text = read_to_string(&path).expect("could not read log");
+        let mut lines = text.lines().map(|l| l.to_owned()).collect::<Vec<_>>();
+        let found = ongoing_event(&lines.iter().map(|l| l.as_str()).collect::<Vec<_>>());
+        if let Some((mut event, offset)) = found {
+            match amended(&event.description, &commit) {
+                Some(description) => {
+                    event.description = description;
+                    lines[offset] = log_line(&event, &conf);
+                    let mut text = lines.join("\n");
+                    text.push('\n');
+                    let tmp = tmp_path(&conf);
+                    write(&tmp, text).expect("could not write temporary log");
+                    rename(&tmp, &path).expect("failed to move the new log into place");
+                    success(format!("amended {}", event.description), &conf);
+                }
+                None => warn("the ongoing event already records the commit", &conf),
+            }
+            return;
+        }
+    }
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let (note, offset) = reader.append_note(commit, tags);
+    let json = note.to_json(&note.time, &conf);
+    notify("note", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else {
+        describe("noted", None, Item::Note(note, offset), &conf);
+    }
+}
+
+// the trimmed output of a git command, if it succeeds and says something
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if output.is_empty() {
+        None
+    } else {
+        Some(output)
+    }
+}
+
+// e.g., "parser-fix: fix the parser (3f2a9c1)"
+fn commit_description(branch: Option<&str>, subject: &str, hash: &str) -> String {
+    let mut description = match branch {
+        Some(branch) => format!("{}: {}", branch, subject),
+        None => subject.to_owned(),
+    };
+    if !hash.is_empty() {
+        description += &format!(" ({})", hash);
+    }
+    description
+}
+
+// the description with the commit appended, unless it is there already
+fn amended(description: &str, commit: &str) -> Option<String> {
+    if description.contains(commit) {
+        None
+    } else if description.trim().is_empty() {
+        Some(commit.to_owned())
+    } else {
+        Some(format!("{}; {}", description, commit))
+    }
+}
+
+// a post-commit hook invoking this subcommand; a hook must not fail the commit, so any
+// failure is ignored
+fn script(tags: &[String], amend: bool, directory: Option<&str>) -> String {
+    let mut command = String::from("job");
+    if let Some(directory) = directory {
+        // the hook runs in the repository, so a relative directory won't do
+        let directory = canonicalize(directory)
+            .ok()
+            .and_then(|d| d.to_str().map(|d| d.to_owned()))
+            .unwrap_or_else(|| directory.to_owned());
+        command += &format!(" --directory {}", shell_quote(&directory));
+    }
+    command += " git-hook";
+    if amend {
+        command += " --amend";
+    }
+    for t in tags {
+        command += &format!(" --tag {}", shell_quote(t));
+    }
+    format!(
+        "#!/bin/sh\n# records each commit in the job log; see `job git-hook --help`\n{} >/dev/null 2>&1 || true\n",
+        command
+    )
+}
+
+fn shell_quote(s: &str) -> String {
+    if s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+    {
+        s.to_owned()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_description() {
+        assert_eq!(
+            "parser-fix: fix the parser (3f2a9c1)",
+            commit_description(Some("parser-fix"), "fix the parser", "3f2a9c1")
+        );
+        assert_eq!(
+            "fix the parser (3f2a9c1)",
+            commit_description(None, "fix the parser", "3f2a9c1"),
+            "detached HEAD"
+        );
+        assert_eq!(
+            Some(String::from("parsing; main: fix (abc)")),
+            amended("parsing", "main: fix (abc)")
+        );
+        assert_eq!(None, amended("parsing; main: fix (abc)", "main: fix (abc)"));
+        assert_eq!(
+            "#!/bin/sh\n# records each commit in the job log; see `job git-hook --help`\njob git-hook --amend --tag git --tag 'my tag' >/dev/null 2>&1 || true\n",
+            script(&[String::from("git"), String::from("my tag")], true, None)
+        );
+    }
+}
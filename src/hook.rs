@@ -0,0 +1,73 @@
+extern crate clap;
+extern crate serde_json;
+
+use crate::configure::Configuration;
+use crate::slack::update_status;
+use crate::util::warn;
+use clap::ArgMatches;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// the number of times delivery to a webhook is retried after a transient failure
+const RETRIES: usize = 3;
+// the seconds allowed for each attempt
+const TIMEOUT: usize = 10;
+
+// posts a JSON payload describing a change to the log to every configured webhook and updates
+// the Slack status, if it is kept
+// the payload is an object with an "action" property -- add, append, cancel, done, note, resume,
+// or switch -- and an "item" property holding the JSON representation of the event, note, DONE
+// line, or switch
+pub fn notify(action: &str, item: &str, conf: &Configuration, matches: &ArgMatches) {
+    if matches.is_present("no-hooks") {
+        return;
+    }
+    update_status(action, item, conf);
+    if conf.webhooks.is_empty() {
+        return;
+    }
+    let payload = format!(
+        r#"{{"action":{},"item":{}}}"#,
+        serde_json::to_string(action).unwrap(),
+        item
+    );
+    for (name, url) in conf.webhooks.iter() {
+        if let Err(e) = post(url, &payload) {
+            warn(format!("could not notify webhook {}: {}", name, e), conf);
+        }
+    }
+}
+
+// curl does the retrying and spares us a dependency on an HTTP client; it runs in the background
+// so an unreachable webhook does not hold up the command, and whatever it has to say is discarded
+fn post(url: &str, payload: &str) -> Result<(), String> {
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--retry",
+            &RETRIES.to_string(),
+            "--retry-connrefused",
+            "--max-time",
+            &TIMEOUT.to_string(),
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(payload.as_bytes())
+        .map_err(|e| format!("could not send payload to curl: {}", e))?;
+    // the thread is never joined; it only reaps curl so the server does not accumulate zombies
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,364 @@
+// stuff for writing and reading iCalendar (RFC 5545) data
+extern crate chrono;
+
+use crate::log::Event;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+// the unique identifier of the calendar event corresponding to an event; as no two events in the
+// log begin at the same moment, the start time suffices
+pub fn uid(event: &Event) -> String {
+    format!("{}@jobrog", event.start.format("%Y%m%dT%H%M%S"))
+}
+
+// a VCALENDAR holding a single VEVENT representing the event, which must have ended
+pub fn calendar(event: &Event, stamp: &NaiveDateTime) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//dfhoughton//jobrog//EN"),
+        String::from("BEGIN:VEVENT"),
+        format!("UID:{}", uid(event)),
+        format!("DTSTAMP:{}", utc(stamp)),
+        format!("DTSTART:{}", utc(&event.start)),
+        format!(
+            "DTEND:{}",
+            utc(&event.end.expect("the event has not ended"))
+        ),
+        format!("SUMMARY:{}", escape(&event.description)),
+    ];
+    if !event.tags.is_empty() {
+        lines.push(format!(
+            "CATEGORIES:{}",
+            event
+                .tags
+                .iter()
+                .map(|t| escape(t))
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+    }
+    lines.push(String::from("END:VEVENT"));
+    lines.push(String::from("END:VCALENDAR"));
+    let mut ics = lines
+        .iter()
+        .map(|l| fold(l))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    ics += "\r\n";
+    ics
+}
+
+// a local time as a UTC date-time
+fn utc(time: &NaiveDateTime) -> String {
+    let time = Local
+        .from_local_datetime(time)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc).naive_utc())
+        .unwrap_or(*time);
+    format!("{}Z", time.format("%Y%m%dT%H%M%S"))
+}
+
+// escapes the characters special in text values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// breaks a content line into lines of no more than 75 octets, the continuations beginning
+// with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+// a timed event read from a calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meeting {
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+// the timed events in iCalendar data, in order of their start; all-day and cancelled events are
+// ignored, as are recurrence rules, so only the first occurrence of a recurring event is found
+pub fn meetings(text: &str) -> Vec<Meeting> {
+    let mut meetings = vec![];
+    let mut depth = 0; // how deeply nested we are within a VEVENT
+    let mut summary = String::new();
+    let (mut start, mut end, mut duration) = (None, None, None);
+    let mut skip = false;
+    for line in unfold(text) {
+        let (name, params, value) = match property(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+        match (name.as_str(), value.to_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") if depth == 0 => {
+                depth = 1;
+                summary.clear();
+                start = None;
+                end = None;
+                duration = None;
+                skip = false;
+                continue;
+            }
+            ("BEGIN", _) if depth > 0 => depth += 1,
+            ("END", "VEVENT") if depth == 1 => {
+                depth = 0;
+                let end = end.or_else(|| start.and_then(|s| duration.map(|d| s + d)));
+                if let (false, Some(start), Some(end)) = (skip, start, end) {
+                    if end > start {
+                        meetings.push(Meeting {
+                            summary: summary.clone(),
+                            start,
+                            end,
+                        });
+                    }
+                }
+            }
+            ("END", _) if depth > 1 => depth -= 1,
+            _ => (),
+        }
+        if depth != 1 {
+            continue;
+        }
+        let all_day = params.iter().any(|p| p.eq_ignore_ascii_case("VALUE=DATE"));
+        match name.as_str() {
+            "SUMMARY" => summary = unescape(&value),
+            "STATUS" => skip |= value.eq_ignore_ascii_case("CANCELLED"),
+            "DTSTART" => {
+                start = date_time(&value);
+                skip |= all_day || start.is_none();
+            }
+            "DTEND" => end = date_time(&value),
+            "DURATION" => duration = parse_duration(&value),
+            _ => (),
+        }
+    }
+    meetings.sort_by_key(|m| m.start);
+    meetings
+}
+
+// the content lines with their continuations rejoined
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+        lines.push(line.to_owned());
+    }
+    lines
+}
+
+// a content line split into its upper-cased name, its parameters, and its value
+fn property(line: &str) -> Option<(String, Vec<String>, String)> {
+    // the value begins at the first colon not within a quoted parameter value
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?;
+    let mut parts = line[..colon.0].split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts.map(|p| p.trim().to_owned()).collect();
+    Some((name, params, line[colon.0 + 1..].to_owned()))
+}
+
+// a date-time as a local time; a time in UTC is converted, others are taken to be local already
+fn date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z').or_else(|| value.strip_suffix('z')) {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        Some(
+            Utc.from_utc_datetime(&time)
+                .with_timezone(&Local)
+                .naive_local(),
+        )
+    } else if value.len() == 8 {
+        // a bare date, as found in all-day events
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(|d| d.and_hms(0, 0, 0))
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+    }
+}
+
+// parses a duration such as PT1H30M or P1D
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim().trim_start_matches('+');
+    if value.starts_with('-') {
+        return None;
+    }
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut time = false;
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match (c, time) {
+                    ('W', false) => 7 * 24 * 60 * 60,
+                    ('D', false) => 24 * 60 * 60,
+                    ('H', true) => 60 * 60,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if number.is_empty() {
+        Some(Duration::seconds(seconds))
+    } else {
+        None
+    }
+}
+
+// reverses escape, putting a space in place of any line break
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut escaped = false;
+    for c in text.chars() {
+        if escaped {
+            match c {
+                'n' | 'N' => unescaped.push(' '),
+                _ => unescaped.push(c),
+            }
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_calendar() {
+        let day = NaiveDate::from_ymd(2020, 3, 2);
+        let event = Event::builder()
+            .start(day.and_hms(9, 0, 0))
+            .end(day.and_hms(10, 30, 0))
+            .description("planning, again; sigh")
+            .tag("meetings")
+            .build()
+            .unwrap();
+        let ics = calendar(&event, &day.and_hms(12, 0, 0));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nUID:20200302T090000@jobrog\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:planning\\, again\\; sigh\r\n"));
+        assert!(ics.contains("\r\nCATEGORIES:meetings\r\n"));
+        let long = "x".repeat(200);
+        let folded = fold(&long);
+        assert!(folded.split("\r\n").all(|l| l.len() <= 75));
+        assert_eq!(long, folded.replace("\r\n ", ""));
+    }
+
+    #[test]
+    fn test_meetings() {
+        let day = NaiveDate::from_ymd(2020, 3, 2);
+        let text = "\
+BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+DTSTART;TZID=\"America/New_York\":20200302T130000\r
+DURATION:PT1H30M\r
+SUMMARY:retro\\, with\r
+  cake\r
+BEGIN:VALARM\r
+SUMMARY:reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20200302T090000\r
+DTEND:20200302T093000\r
+SUMMARY:stand-up\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20200302\r
+DTEND;VALUE=DATE:20200303\r
+SUMMARY:offsite\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20200302T150000\r
+DTEND:20200302T160000\r
+STATUS:CANCELLED\r
+SUMMARY:planning\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20200302T170000Z\r
+DTEND:20200302T173000Z\r
+SUMMARY:sync\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let meetings = meetings(text);
+        let utc = |h, m| {
+            Utc.from_utc_datetime(&day.and_hms(h, m, 0))
+                .with_timezone(&Local)
+                .naive_local()
+        };
+        let mut expected = vec![
+            Meeting {
+                summary: String::from("stand-up"),
+                start: day.and_hms(9, 0, 0),
+                end: day.and_hms(9, 30, 0),
+            },
+            Meeting {
+                summary: String::from("retro, with cake"),
+                start: day.and_hms(13, 0, 0),
+                end: day.and_hms(14, 30, 0),
+            },
+            Meeting {
+                summary: String::from("sync"),
+                start: utc(17, 0),
+                end: utc(17, 30),
+            },
+        ];
+        expected.sort_by_key(|m| m.start);
+        assert_eq!(
+            expected, meetings,
+            "all-day and cancelled events are ignored"
+        );
+        assert_eq!(Some(Duration::days(8)), parse_duration("P1W1D"));
+        assert_eq!(None, parse_duration("PT1H30"));
+    }
+}
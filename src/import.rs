@@ -0,0 +1,264 @@
+extern crate clap;
+
+use crate::configure::Configuration;
+use crate::log::log_header;
+use crate::merge::{
+    conflicts, count, drop_orphaned_dones, merge, read_blocks, replace_log, Block, LogState, Source,
+};
+use crate::migrate::to_version_1;
+use crate::util::{base_dir, fatal, info, log_path, success, warn};
+use crate::vacation;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::fs::{canonicalize, remove_file, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+fn after_help() -> &'static str {
+    "\
+The import subcommand brings records kept by other programs into the log. With --perl it \
+imports a log kept by the Perl client, App::JobLog:
+
+  > job import --perl ~/old-joblog/log
+
+The Perl log is first converted as the migrate subcommand would convert it and is then merged \
+into your log as the merge subcommand would merge it: lines the two logs share are kept once, \
+and if an event in one log begins while an event in the other is still open, the import is \
+refused unless you give it --force. If there is a vacation file beside the Perl log, any \
+vacation records in it that are not in your vacation file are added to yours.
+
+Before the imported log replaces your own, your log is copied to log.pre-import.bak in the job \
+log directory.
+
+If you expect to go on using the Perl client, set `job configure --perl-compatible true`. \
+Thereafter the log and vacation file will hold nothing the Perl client cannot read.
+
+All prefixes of 'import' after 'i' -- 'im', 'imp', 'impo', and 'impor' -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("import")
+            .aliases(&["im", "imp", "impo", "impor"])
+            .about("Imports records kept by other programs")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("perl")
+                .long("perl")
+                .help("Imports a log kept by the Perl client")
+                .long_help("Merges into your log the log, and any vacation file beside it, kept by the Perl App::JobLog.")
+                .value_name("log")
+                .required(true)
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("force")
+                .long("force")
+                .short("f")
+                .help("Imports the log despite conflicts")
+                .long_help("Writes the merged log even if events in one log overlap open events in the other.")
+                .conflicts_with("dry-run")
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Reports what the import would do without doing it")
+                .display_order(2)
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let perl = matches.value_of("perl").unwrap();
+    let path = log_path(conf.directory());
+    if !Path::new(perl).exists() {
+        fatal(format!("there is no log at {}", perl), &conf);
+    }
+    if path.as_path().exists() && canonicalize(perl).ok() == canonicalize(&path).ok() {
+        fatal(
+            format!(
+                "{} is already your log; use `job migrate` to bring it up to date",
+                perl
+            ),
+            &conf,
+        );
+    }
+    let theirs = match perl_blocks(perl, &conf) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            fatal(format!("{}; fix this before importing", e), &conf);
+            unreachable!()
+        }
+    };
+    let mut read = LogState::of(&conf);
+    let ours = if path.as_path().exists() {
+        match read_blocks(path.to_str().unwrap(), Source::Ours) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                fatal(
+                    format!(
+                        "{}; fix this with `job edit --validate` before importing",
+                        e
+                    ),
+                    &conf,
+                );
+                unreachable!()
+            }
+        }
+    } else {
+        vec![]
+    };
+    let (ours_count, theirs_count) = (count(&ours), count(&theirs));
+    let merged = merge(ours, theirs);
+    let added = merged
+        .iter()
+        .filter(|b| b.line.is_some() && b.source == Source::Theirs)
+        .count();
+    let conflicts = conflicts(&merged, "the Perl log");
+    for c in conflicts.iter() {
+        warn(c, &conf);
+    }
+    let vacation = perl_vacation(perl);
+    if matches.is_present("dry-run") {
+        success(
+            format!(
+                "importing would add {} of the {} timestamped lines in {} to the {} in your log{}",
+                added,
+                theirs_count,
+                perl,
+                ours_count,
+                if vacation.is_some() {
+                    " and merge the vacation file beside it into yours"
+                } else {
+                    ""
+                }
+            ),
+            &conf,
+        );
+        return;
+    }
+    if !conflicts.is_empty() && !matches.is_present("force") {
+        fatal(
+            format!(
+                "found {} conflict{}; fix {} or import with --force",
+                conflicts.len(),
+                if conflicts.len() == 1 { "" } else { "s" },
+                if conflicts.len() == 1 { "it" } else { "them" },
+            ),
+            &conf,
+        );
+    }
+    if added == 0 {
+        success(format!("{} adds nothing to your log", perl), &conf);
+    } else {
+        if !path.as_path().exists() {
+            // replace_log backs up the log, so there must be one
+            let mut log = File::create(&path).expect("could not create log");
+            writeln!(log, "{}", log_header()).expect("could not write to log");
+            read = LogState::of(&conf);
+        }
+        let backup = replace_log(
+            &drop_orphaned_dones(merged),
+            "log.pre-import.bak",
+            &read,
+            &conf,
+        );
+        success(
+            format!(
+                "added {} timestamped lines from {}; your original log is in {}",
+                added,
+                perl,
+                backup.to_str().unwrap()
+            ),
+            &conf,
+        );
+    }
+    if let Some(vacation) = vacation {
+        info(
+            format!(
+                "importing vacation records from {}",
+                vacation.to_str().unwrap()
+            ),
+            &conf,
+        );
+        let added = vacation::import(vacation, &conf);
+        success(
+            format!(
+                "added {} vacation record{}",
+                added,
+                if added == 1 { "" } else { "s" }
+            ),
+            &conf,
+        );
+    }
+}
+
+// reads the Perl log into blocks, first converting it to the current log format
+fn perl_blocks(perl: &str, conf: &Configuration) -> Result<Vec<Block>, String> {
+    let file = File::open(perl).map_err(|e| format!("could not open {}: {}", perl, e))?;
+    let mut tmp = base_dir(conf.directory());
+    tmp.push("import.tmp");
+    {
+        let mut writer =
+            BufWriter::new(File::create(&tmp).expect("could not create temporary file"));
+        for (offset, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| format!("could not read {}: {}", perl, e))?;
+            let line = to_version_1(&line, offset).unwrap_or(line);
+            writeln!(writer, "{}", line).expect("could not write to temporary file");
+        }
+    }
+    let blocks = read_blocks(tmp.to_str().unwrap(), Source::Theirs)
+        .map_err(|e| e.replace(tmp.to_str().unwrap(), perl));
+    remove_file(&tmp).expect("could not remove temporary file");
+    blocks
+}
+
+// the Perl client keeps its vacation file beside its log
+fn perl_vacation(perl: &str) -> Option<PathBuf> {
+    let vacation = Path::new(perl).with_file_name("vacation");
+    if vacation.as_path().is_file() {
+        Some(vacation)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+    use std::fs::{read_to_string, write};
+
+    #[test]
+    fn test_perl_blocks() {
+        let dir = TestDirectory::new("test_perl_blocks");
+        let conf = dir.configuration();
+        let perl = dir.path.join("perl");
+        write(
+            &perl,
+            "# 2020/1/2\r\n2020 01 02 09 00 00:a:foo\r\n2020 01 02 10 30 00:DONE\r\n",
+        )
+        .expect("could not write Perl log");
+        let blocks = perl_blocks(perl.to_str().unwrap(), &conf).unwrap();
+        assert_eq!(2, count(&blocks));
+        assert_eq!(
+            Some("2020  1  2  9  0  0:a:foo"),
+            blocks[0].line.as_deref(),
+            "line converted"
+        );
+        assert!(blocks.iter().all(|b| b.source == Source::Theirs));
+        let tmp = dir.path.join("import.tmp");
+        assert!(!tmp.as_path().exists(), "temporary file removed");
+        assert_eq!(None, perl_vacation(perl.to_str().unwrap()));
+        let vacation = dir.path.join("vacation");
+        write(&vacation, "").expect("could not write vacation file");
+        assert_eq!(Some(vacation), perl_vacation(perl.to_str().unwrap()));
+        assert!(
+            read_to_string(&perl).unwrap().contains('\r'),
+            "Perl log untouched"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+extern crate chrono;
+extern crate clap;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::ics::{meetings, Meeting};
+use crate::log::{log_line, parse_line, Done, Event, Item, LogController, LogLine};
+use crate::merge::{drop_orphaned_dones, merge, read_blocks, replace_log, Block, LogState, Source};
+use crate::util::{
+    current_time, expand_period, fatal, info, log_path, remainder, some_nws, success, warn,
+};
+use chrono::NaiveDateTime;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::read_to_string;
+use std::process::Command;
+use two_timer::parse;
+
+// the seconds allowed for fetching a calendar
+const TIMEOUT: usize = 30;
+
+fn after_help() -> &'static str {
+    "\
+The import-calendar subcommand reads the meetings in an iCalendar file or feed and adds the \
+meetings in a period to the log as events, so you needn't type in a day of back-to-back \
+meetings by hand:
+
+  > job import-calendar ~/Downloads/work.ics yesterday
+  added 4 meetings; your original log is in /home/me/.joblog/log.pre-import-calendar.bak
+
+The first argument is either the path of a .ics file or an http or https URL, which is fetched \
+with curl. The remaining arguments are concatenated to produce the period, which defaults to \
+today. Each meeting becomes an event beginning and ending when the meeting does, its title the \
+event's description. The events are given the tags passed with --tag or, if there are none, the \
+tag set with `job configure --calendar-tag`.
+
+Meetings already logged are skipped: any meeting overlapping an event in the log, or an earlier \
+meeting, is left out, as are meetings that have not yet ended. All-day and cancelled events are \
+ignored, as are recurrence rules -- only the first occurrence of a recurring meeting is found. \
+Times given in UTC are converted to local time; other times are taken to be local times \
+already.
+
+As with the batch subcommand, the new events are interleaved with the lines already in the log \
+by time, and the log is first copied to log.pre-import-calendar.bak in the job log directory. \
+With --dry-run the subcommand lists the meetings it would add without adding them.
+
+All prefixes of 'import-calendar' after 'import' are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("import-calendar")
+            .aliases(&[
+                "import-",
+                "import-c",
+                "import-ca",
+                "import-cal",
+                "import-cale",
+                "import-calen",
+                "import-calend",
+                "import-calenda",
+            ])
+            .about("Adds the meetings in an iCalendar file or feed to the log")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("tag")
+                .short("t")
+                .long("tag")
+                .multiple(true)
+                .number_of_values(1)
+                .help("add this tag to the events")
+                .long_help("A tag to give every event created. If no tag is given, the tag set with `job configure --calendar-tag`, if any, is used.")
+                .value_name("tag")
+                .validator(|v| if some_nws(&v) {Ok(())} else {Err(format!("{:?} is not a suitable tag: it has no non-whitespace character", v))} )
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Lists the meetings that would be added without adding them")
+                .display_order(2)
+            )
+            .arg(
+                Arg::with_name("calendar")
+                    .help("an iCalendar file or URL")
+                    .value_name("ics-or-url")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("period")
+                    .help("time expression")
+                    .long_help(
+                        "All the <period> arguments are concatenated to produce a time expression.",
+                    )
+                    .value_name("period")
+                    .default_value("today")
+                    .multiple(true),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let source = matches.value_of("calendar").unwrap();
+    let phrase = remainder("period", matches);
+    let (start, end) = match parse(
+        &expand_period(&phrase, false, &conf),
+        conf.logical_two_timer_config(),
+    ) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), &conf);
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        &conf,
+    );
+    let text = match fetch(source) {
+        Ok(text) => text,
+        Err(e) => {
+            fatal(e, &conf);
+            unreachable!()
+        }
+    };
+    let now = current_time();
+    let found = meetings(&text)
+        .into_iter()
+        .filter(|m| m.start >= start && m.start < end)
+        .collect::<Vec<_>>();
+    if found.is_empty() {
+        warn("no meeting found", &conf);
+        return;
+    }
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    // the last meeting may run past the end of the period
+    let last = found.iter().map(|m| m.end).max().unwrap().max(end);
+    let logged = reader.events_in_range(&start, &last);
+    let new = unlogged(found, &logged, &now);
+    if new.is_empty() {
+        success(
+            "every meeting is already logged; the log is unchanged",
+            &conf,
+        );
+        return;
+    }
+    let tags = match matches.values_of("tag") {
+        Some(values) => values.map(|v| v.to_owned()).collect(),
+        None => conf.calendar_tag.iter().cloned().collect::<Vec<_>>(),
+    };
+    if matches.is_present("dry-run") {
+        for m in new.iter() {
+            println!(
+                "{} - {}  {}",
+                m.start.format("%F %H:%M"),
+                m.end.format("%H:%M"),
+                m.summary
+            );
+        }
+        success(format!("would add {}", describe(new.len())), &conf);
+        return;
+    }
+    let read = LogState::of(&conf);
+    let ours = match read_blocks(log_path(conf.directory()).to_str().unwrap(), Source::Ours) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            fatal(
+                format!("{}; fix this with `job edit --validate` first", e),
+                &conf,
+            );
+            unreachable!()
+        }
+    };
+    let starts = ours
+        .iter()
+        .filter(|b| matches!(b.item, Some(Item::Event(_, _))))
+        .filter_map(|b| b.time)
+        .chain(new.iter().map(|m| m.start))
+        .collect::<Vec<_>>();
+    let mut theirs = vec![];
+    for m in new.iter() {
+        let event = match Event::builder()
+            .start(m.start)
+            .description(&m.summary)
+            .tags(&tags)
+            .author(conf.user_name.clone())
+            .build()
+        {
+            Ok(event) => event,
+            Err(e) => {
+                warn(format!("skipping {:?}: {}", m.summary, e), &conf);
+                continue;
+            }
+        };
+        theirs.push(block(&event, &conf));
+        // an event beginning as the meeting ends will end it; a DONE there would end that event
+        // instead
+        if !starts.contains(&m.end) {
+            theirs.push(block(&Done(m.end), &conf));
+        }
+    }
+    // as the meetings overlap no logged event, the merge cannot produce conflicts
+    let backup = replace_log(
+        &drop_orphaned_dones(merge(ours, theirs)),
+        "log.pre-import-calendar.bak",
+        &read,
+        &conf,
+    );
+    success(
+        format!(
+            "added {}; your original log is in {}",
+            describe(new.len()),
+            backup.to_str().unwrap()
+        ),
+        &conf,
+    );
+}
+
+// reads the calendar from a file or, via curl, a URL
+fn fetch(source: &str) -> Result<String, String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let output = Command::new("curl")
+            .args([
+                "--silent",
+                "--show-error",
+                "--fail",
+                "--location",
+                "--max-time",
+                &TIMEOUT.to_string(),
+                source,
+            ])
+            .output()
+            .map_err(|e| format!("could not run curl: {}", e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(format!(
+                "could not fetch {}: {}",
+                source,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    } else {
+        read_to_string(source).map_err(|e| format!("could not read {}: {}", source, e))
+    }
+}
+
+// the meetings that have ended and overlap neither a logged event nor an earlier meeting
+fn unlogged(meetings: Vec<Meeting>, logged: &[Event], now: &NaiveDateTime) -> Vec<Meeting> {
+    let mut new: Vec<Meeting> = vec![];
+    for m in meetings {
+        if m.end > *now || m.summary.is_empty() {
+            continue;
+        }
+        let overlaps_log = logged
+            .iter()
+            .any(|e| e.start < m.end && e.end.unwrap_or(*now) > m.start);
+        let overlaps_new = new.iter().any(|n| n.start < m.end && n.end > m.start);
+        if !(overlaps_log || overlaps_new) {
+            new.push(m);
+        }
+    }
+    new
+}
+
+// the log line of an event or DONE
+fn block<T: LogLine>(item: &T, conf: &Configuration) -> Block {
+    let line = log_line(item, conf);
+    Block {
+        time: Some(*item.time()),
+        item: Some(parse_line(&line, 0)),
+        line: Some(line),
+        comments: vec![],
+        source: Source::Theirs,
+    }
+}
+
+fn describe(n: usize) -> String {
+    format!("{} meeting{}", n, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn meeting(summary: &str, start: NaiveDateTime, end: NaiveDateTime) -> Meeting {
+        Meeting {
+            summary: summary.to_owned(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_unlogged() {
+        let day = NaiveDate::from_ymd(2020, 3, 2);
+        let mut logged = Event::coin(String::from("coding"), vec![]);
+        logged.start = day.and_hms(10, 0, 0);
+        logged.end = Some(day.and_hms(11, 0, 0));
+        let now = day.and_hms(16, 0, 0);
+        let meetings = vec![
+            meeting("stand-up", day.and_hms(9, 0, 0), day.and_hms(9, 30, 0)),
+            meeting(
+                "already logged",
+                day.and_hms(10, 30, 0),
+                day.and_hms(11, 30, 0),
+            ),
+            meeting("planning", day.and_hms(11, 0, 0), day.and_hms(12, 0, 0)),
+            meeting(
+                "double-booked",
+                day.and_hms(11, 30, 0),
+                day.and_hms(12, 30, 0),
+            ),
+            meeting("later", day.and_hms(15, 30, 0), day.and_hms(16, 30, 0)),
+        ];
+        let summaries = unlogged(meetings, &[logged], &now)
+            .into_iter()
+            .map(|m| m.summary)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["stand-up", "planning"], summaries);
+    }
+}
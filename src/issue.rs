@@ -0,0 +1,121 @@
+// stuff for recognizing references to issues in descriptions
+extern crate regex;
+
+use crate::configure::Configuration;
+use regex::Regex;
+
+// the configured issue patterns, compiled
+pub struct Issues {
+    patterns: Vec<(Regex, String)>, // each pattern with its URL template
+}
+
+impl Issues {
+    pub fn new(conf: &Configuration) -> Issues {
+        Issues {
+            patterns: conf
+                .issue_patterns
+                .values()
+                .filter_map(|(pattern, url)| Regex::new(pattern).ok().map(|rx| (rx, url.clone())))
+                .collect(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+    // the references in the text, each with the URL of the issue, in the order they occur; where
+    // references overlap, the earlier wins
+    pub fn find(&self, text: &str) -> Vec<(usize, usize, String)> {
+        let mut found = vec![];
+        for (rx, template) in self.patterns.iter() {
+            for captures in rx.captures_iter(text) {
+                let whole = captures.get(0).unwrap();
+                if whole.as_str().is_empty() {
+                    continue;
+                }
+                let mut url = String::new();
+                captures.expand(template, &mut url);
+                found.push((whole.start(), whole.end(), url));
+            }
+        }
+        found.sort_by_key(|(start, end, _)| (*start, usize::MAX - end));
+        let mut last = 0;
+        found
+            .into_iter()
+            .filter(|(start, end, _)| {
+                let keep = *start >= last;
+                if keep {
+                    last = *end;
+                }
+                keep
+            })
+            .collect()
+    }
+    // the distinct references in the text
+    pub fn references(&self, text: &str) -> Vec<String> {
+        let mut references: Vec<String> = vec![];
+        for (start, end, _) in self.find(text) {
+            let reference = text[start..end].to_owned();
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+        references
+    }
+    // the text with each reference made a terminal hyperlink to its issue
+    pub fn link(&self, text: &str) -> String {
+        let mut linked = String::new();
+        let mut last = 0;
+        for (start, end, url) in self.find(text) {
+            linked += &text[last..start];
+            linked += &hyperlink(&text[start..end], &url);
+            last = end;
+        }
+        linked += &text[last..];
+        linked
+    }
+}
+
+// an OSC 8 hyperlink, which terminals that don't understand it display as the bare text
+fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_issues() {
+        let dir = TestDirectory::new("test_issues");
+        let mut conf = dir.configuration();
+        conf.issue_patterns.insert(
+            String::from("github"),
+            (
+                String::from(r"#(\d+)"),
+                String::from("https://github.com/me/repo/issues/$1"),
+            ),
+        );
+        conf.issue_patterns.insert(
+            String::from("jira"),
+            (
+                String::from(r"\b[A-Z]+-\d+\b"),
+                String::from("https://example.atlassian.net/browse/$0"),
+            ),
+        );
+        let issues = Issues::new(&conf);
+        let text = "fixing #12 and PROJ-3, again #12";
+        assert_eq!(
+            vec!["#12", "PROJ-3"],
+            issues.references(text),
+            "distinct references in order"
+        );
+        assert_eq!(
+            "fixing \x1b]8;;https://github.com/me/repo/issues/12\x1b\\#12\x1b]8;;\x1b\\ and \
+            \x1b]8;;https://example.atlassian.net/browse/PROJ-3\x1b\\PROJ-3\x1b]8;;\x1b\\, again \
+            \x1b]8;;https://github.com/me/repo/issues/12\x1b\\#12\x1b]8;;\x1b\\",
+            issues.link(text)
+        );
+        assert_eq!("no issue", issues.link("no issue"));
+    }
+}
@@ -1,12 +1,22 @@
 extern crate chrono;
 extern crate clap;
+extern crate colonnade;
 extern crate regex;
 
 use crate::configure::Configuration;
 use crate::log::{Event, Filter, LogController, Note};
-use crate::util::{common_search_or_filter_arguments, display_events, display_notes, warn};
-use chrono::Local;
-use clap::{App, ArgMatches, SubCommand};
+use crate::util::{
+    common_search_or_filter_arguments, current_time, display_events, display_notes, fatal,
+    fuzzy_pick, interactive, limit_and_offset, limit_arguments, warn, warn_of_unknown_tags, Style,
+};
+use chrono::NaiveDateTime;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+
+// the most hits --pick lists at once
+const PICK: usize = 10;
+// how many hits --pick searches by default
+const FUZZY_PICK: usize = 1000;
 
 fn after_help() -> &'static str {
     "\
@@ -22,6 +32,21 @@ or the last task or note you worked on of a particular type. This command satisf
   mr          4.00
   sb          4.00
 
+To see more than one, use --limit, and to skip some of the most recent, --offset:
+
+  > job last --limit 20 --tag meetings
+
+shows the last 20 meetings, earliest first.
+
+To find a particular one among many hits, use --pick. This asks for letters from its description or \
+tags, lists the hits they match best, and asks again until you choose one by its number:
+
+  > job last --tag meetings --pick
+  type to search, or give a hit's number: flb rv
+   1  2020-01-17 13:10  42 mr sb  Multi-Floob Review Part 1
+   2  2020-01-10 09:00  42 mr     Floob review prep
+  type to search, or give a hit's number: 1
+
 All prefixes of 'last' are aliases of the subcommand."
 }
 
@@ -31,6 +56,16 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
             .aliases(&["l", "la", "las"])
             .about("Shows the last task recorded")
             .after_help(after_help())
+            .args(&limit_arguments())
+            .arg(
+                Arg::with_name("pick")
+                    .long("pick")
+                    .help("Chooses among the hits by fuzzy search")
+                    .long_help("Asks for letters from the description or tags of the hit wanted, lists the hits \
+                    they match, in order but not necessarily together, and asks again until you choose one. \
+                    Only the last 1000 hits are searched unless you give a --limit.")
+                    .display_order(1),
+            )
             .display_order(display_order),
         None,
     ))
@@ -40,32 +75,135 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let filter = Filter::new(matches);
     let conf = Configuration::read(None, directory);
     let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let (limit, offset) = limit_and_offset(matches);
+    let pick = matches.is_present("pick");
+    if pick && !interactive() {
+        fatal(
+            "cannot ask which hit you want; use --limit and --offset instead",
+            &conf,
+        );
+    }
+    // by default just the one, or as many as are worth searching
+    let limit = limit.unwrap_or(if pick { FUZZY_PICK } else { 1 });
     if matches.is_present("notes") {
-        let note: Vec<Note> = reader
+        let mut notes: Vec<Note> = reader
             .notes_from_the_end()
             .filter(|n| filter.matches(n))
-            .take(1)
+            .skip(offset)
+            .take(limit)
             .collect();
-        if note.is_empty() {
-            warn("no note found", &conf)
+        if pick && !notes.is_empty() {
+            let row = |n: &Note| (n.time, n.tags.join(" "), n.description.clone());
+            match pick_one(&notes, row, &conf) {
+                Some(n) => notes = vec![n],
+                None => return,
+            }
+        }
+        // the most recent are found first
+        notes.reverse();
+        if notes.is_empty() {
+            warn("no note found", &conf);
+            warn_of_unknown_tags(&filter.tags(), &conf);
         } else {
-            let start = &note[0].time.clone();
-            let now = Local::now().naive_local();
-            display_notes(note, start, &now, &conf);
+            let start = &notes[0].time.clone();
+            let now = current_time();
+            if matches.is_present("json") {
+                for n in notes.iter() {
+                    println!("{}", n.to_json(&now, &conf));
+                }
+            } else if matches.is_present("tsv") {
+                for n in notes.iter() {
+                    println!("{}", n.to_tsv(&now, &conf));
+                }
+            } else {
+                display_notes(notes, start, &now, &conf);
+            }
         }
     } else {
-        let event: Vec<Event> = reader
+        let mut events: Vec<Event> = reader
             .events_from_the_end()
             .filter(|n| filter.matches(n))
-            .take(1)
+            .skip(offset)
+            .take(limit)
             .collect();
-        if event.is_empty() {
-            warn("no event found", &conf)
+        if pick && !events.is_empty() {
+            let row = |e: &Event| (e.start, e.tags.join(" "), e.description.clone());
+            match pick_one(&events, row, &conf) {
+                Some(e) => events = vec![e],
+                None => return,
+            }
+        }
+        events.reverse();
+        if events.is_empty() {
+            warn("no event found", &conf);
+            warn_of_unknown_tags(&filter.tags(), &conf);
         } else {
-            let start = &event[0].start.clone();
-            let now = Local::now().naive_local();
-            let event = Event::gather_by_day(event, &now);
-            display_events(event, start, &now, &conf);
+            let start = &events[0].start.clone();
+            let now = current_time();
+            if matches.is_present("json") {
+                for e in events.iter() {
+                    println!("{}", e.to_json(&now, &conf));
+                }
+            } else if matches.is_present("tsv") {
+                for e in events.iter() {
+                    println!("{}", e.to_tsv(&now, &conf));
+                }
+            } else {
+                let events = Event::gather_by_day(events, &now, &conf);
+                display_events(events, start, &now, &conf);
+            }
+        }
+    }
+}
+
+// asks which of the hits, most recent first, is wanted; a hit is represented by its time, tags,
+// and description
+fn pick_one<T, F>(hits: &[T], row: F, conf: &Configuration) -> Option<T>
+where
+    T: Clone,
+    F: Fn(&T) -> (NaiveDateTime, String, String),
+{
+    fuzzy_pick(
+        hits,
+        |h| {
+            let (_, tags, description) = row(h);
+            format!("{} {}", description, tags)
+        },
+        |found| display_hits(&found.iter().map(&row).collect::<Vec<_>>(), conf),
+        PICK,
+        "hit",
+        conf,
+    )
+}
+
+// a numbered list of hits to choose among
+fn display_hits(hits: &[(NaiveDateTime, String, String)], conf: &Configuration) {
+    let style = Style::new(conf);
+    let data = hits
+        .iter()
+        .enumerate()
+        .map(|(i, (time, tags, description))| {
+            vec![
+                (i + 1).to_string(),
+                time.format("%F %H:%M").to_string(),
+                tags.clone(),
+                description.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut table = Colonnade::new(4, conf.width()).expect("insufficient space for hit list");
+    table.columns[0].alignment(Alignment::Right);
+    table.columns[3].priority(1);
+    for row in table.macerate(&data).expect("failed to macerate data") {
+        for line in row {
+            for (cell_num, (margin, cell)) in line.iter().enumerate() {
+                let cell = match cell_num {
+                    2 => style.paint("tags", cell),
+                    _ => cell.to_owned(),
+                };
+                print!("{}{}", margin, cell);
+            }
+            println!();
         }
     }
 }
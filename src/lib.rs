@@ -1,20 +1,91 @@
+// the configuration serializes itself with one long json! invocation
+#![recursion_limit = "256"]
+
+#[cfg(feature = "cli")]
 pub mod add;
+#[cfg(feature = "cli")]
+pub mod append;
+#[cfg(feature = "cli")]
+pub mod backup;
+#[cfg(feature = "cli")]
+pub mod batch;
+#[cfg(feature = "cli")]
+pub mod budget;
+#[cfg(feature = "cli")]
+pub mod calendar;
+#[cfg(feature = "cli")]
+pub mod cancel;
 pub mod configure;
+pub mod diff;
+#[cfg(feature = "cli")]
+pub mod doctor;
+#[cfg(feature = "cli")]
 pub mod done;
+#[cfg(feature = "cli")]
 pub mod edit;
+#[cfg(feature = "cli")]
+pub mod export;
+#[cfg(feature = "cli")]
 pub mod first;
+#[cfg(feature = "cli")]
+pub mod generate_docs;
+#[cfg(feature = "cli")]
+pub mod git_hook;
+#[cfg(feature = "cli")]
+pub mod hook;
+pub mod ics;
+#[cfg(feature = "cli")]
+pub mod import;
+#[cfg(feature = "cli")]
+pub mod import_calendar;
+pub mod issue;
+#[cfg(feature = "cli")]
 pub mod last;
+#[cfg(feature = "cli")]
+pub mod lint;
 pub mod log;
+#[cfg(feature = "cli")]
+pub mod merge;
+#[cfg(feature = "cli")]
+pub mod migrate;
+#[cfg(feature = "cli")]
 pub mod note;
+#[cfg(feature = "cli")]
+pub mod pager;
+#[cfg(feature = "cli")]
 pub mod parse;
+#[cfg(feature = "cli")]
+pub mod period;
+#[cfg(feature = "cli")]
+pub mod periods;
+#[cfg(feature = "cli")]
 pub mod resume;
+#[cfg(feature = "cli")]
+pub mod serve;
+#[cfg(feature = "cli")]
+pub mod slack;
+#[cfg(feature = "cli")]
 pub mod statistics;
+#[cfg(feature = "cli")]
+pub mod status;
+pub mod storage;
+#[cfg(feature = "cli")]
 pub mod summary;
+#[cfg(feature = "cli")]
+pub mod switch;
+#[cfg(feature = "cli")]
+pub mod sync;
+#[cfg(feature = "cli")]
 pub mod tag;
+#[cfg(feature = "cli")]
 pub mod truncate;
 pub mod util;
 pub mod vacation;
+#[cfg(feature = "cli")]
 pub mod when;
+#[cfg(feature = "cli")]
+pub mod zone;
+#[cfg(feature = "cli")]
 extern crate clap;
 #[macro_use]
 extern crate pidgin;
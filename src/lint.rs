@@ -0,0 +1,349 @@
+extern crate chrono;
+extern crate clap;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::backup;
+use crate::configure::{Configuration, Severity, LINT_RULES};
+use crate::doctor::overnight_events;
+use crate::log::{Event, LogController};
+use crate::util::{
+    current_time, duration_string, expand_period, fatal, info, insert_dones, remainder, success,
+    warn, Style,
+};
+use chrono::{Duration, NaiveDateTime};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::json;
+use two_timer::parse;
+
+fn after_help() -> &'static str {
+    "\
+The lint subcommand checks the events in the log against a set of rules, reporting each \
+event that breaks one:
+
+  > job lint 'last week'
+  warning: untagged: 'reading email', begun 2020-03-02 09:01:14, has no tags
+  warning: long-event: 'fixing the parser', begun 2020-03-04 13:10:00, lasts 19.75 hours
+  found 2 problems
+
+The rules are
+
+  untagged           events without tags
+  long-event         events lasting longer than some number of hours
+  short-description  descriptions shorter than some number of characters
+  gap                untracked time during work hours longer than some number of minutes
+  missing-done       events left open past the day boundary
+
+Each rule has a severity -- off, warning, or error -- and each but untagged and missing-done \
+a threshold. Set them with `job configure --lint`:
+
+  > job configure --lint long-event 'error 12' --lint gap 'warning 30'
+
+If no period is given, the whole log is checked. The subcommand exits with a non-zero status \
+if any violation of a rule whose severity is error is found, or, with --strict, any violation \
+at all, so you can run it in a script or a CI job.
+
+With --fix the subcommand fixes what it can fix safely: it adds a DONE line for each event left \
+open past the day boundary at the time the auto-close policy says it ended, first backing up \
+the log as the backup subcommand would. See `job configure --auto-close-at` and `job configure \
+--auto-close-after`. The other problems you must fix yourself.
+
+'li' and 'lin' are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("lint")
+            .aliases(&["li", "lin"])
+            .about("Checks the log against configurable rules")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("period")
+                    .help("The period to check")
+                    .long_help(
+                        "Words describing the period to check. E.g., 'last week' or '2016-10-2'. \
+                        If no period is given, the whole log is checked.",
+                    )
+                    .value_name("word")
+                    .multiple(true),
+            )
+            .arg(
+                Arg::with_name("fix")
+                    .long("fix")
+                    .help("Ends events left open overnight per the auto-close policy")
+                    .long_help("Adds a DONE line to the log for each event left open past the day boundary, \
+                    at the time the auto-close policy says it ended. This is the only fix the lint subcommand \
+                    considers safe."),
+            )
+            .arg(
+                Arg::with_name("strict")
+                    .long("strict")
+                    .help("Exits with a non-zero status on warnings as well as errors"),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let now = current_time();
+    let first = match reader.first_timestamp() {
+        Some(t) => t,
+        None => {
+            warn("the log is empty", &conf);
+            return;
+        }
+    };
+    let (start, end) = if matches.is_present("period") {
+        let phrase = remainder("period", matches);
+        let expression = expand_period(&phrase, false, &conf);
+        match parse(&expression, conf.logical_two_timer_config()) {
+            Ok((start, end, _)) => {
+                let (start, end) = conf.shift_to_day_boundary(start, end);
+                info(
+                    format!("'{}' is interpreted as {} to {}", phrase, start, end),
+                    &conf,
+                );
+                (start, end)
+            }
+            Err(e) => {
+                fatal(e.msg(), &conf);
+                unreachable!()
+            }
+        }
+    } else {
+        (first, now + Duration::seconds(1))
+    };
+    let events = reader.events_in_range(&start, &end);
+    let findings = findings(&events, &now, &conf);
+    let style = Style::new(&conf);
+    let mut failed = false;
+    for finding in findings.iter() {
+        if finding.severity == Severity::Error || matches.is_present("strict") {
+            failed = true;
+        }
+        if matches.is_present("json") {
+            println!(
+                "{}",
+                json!({
+                    "rule": finding.rule,
+                    "severity": finding.severity.name(),
+                    "start": format!("{}", finding.time),
+                    "message": finding.message,
+                })
+            );
+        } else if finding.severity == Severity::Error {
+            eprintln!(
+                "{} {}: {}",
+                style.paint("error", "error:"),
+                finding.rule,
+                finding.message
+            );
+        } else {
+            warn(format!("{}: {}", finding.rule, finding.message), &conf);
+        }
+    }
+    if findings.is_empty() {
+        success("found no problems", &conf);
+    } else {
+        info(
+            format!(
+                "found {} problem{}",
+                findings.len(),
+                if findings.len() == 1 { "" } else { "s" }
+            ),
+            &conf,
+        );
+    }
+    if matches.is_present("fix") {
+        let times = findings.iter().filter_map(|f| f.close).collect::<Vec<_>>();
+        if times.is_empty() {
+            warn("there is nothing the lint subcommand can fix", &conf);
+        } else {
+            if let Some(backup) = backup::create(&conf) {
+                info(
+                    format!("backed up the log to {}", backup.to_str().unwrap()),
+                    &conf,
+                );
+            }
+            insert_dones(&times, &conf);
+            success(
+                format!(
+                    "added {} DONE line{}",
+                    times.len(),
+                    if times.len() == 1 { "" } else { "s" }
+                ),
+                &conf,
+            );
+            // what has been fixed no longer counts against the log
+            failed = findings.iter().any(|f| {
+                f.close.is_none() && (f.severity == Severity::Error || matches.is_present("strict"))
+            });
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+// a violation of a lint rule
+#[derive(Debug)]
+struct Finding {
+    rule: &'static str,
+    severity: Severity,
+    time: NaiveDateTime, // when the offending event, or gap, began
+    message: String,
+    close: Option<NaiveDateTime>, // for a missing DONE, when the auto-close policy ends the event
+}
+
+// the violations of the lint rules among the events, in order of time
+fn findings(events: &[Event], now: &NaiveDateTime, conf: &Configuration) -> Vec<Finding> {
+    let mut findings = vec![];
+    let describe = |e: &Event| format!("'{}', begun {}", e.description, e.start.format("%F %T"));
+    for (rule, _) in LINT_RULES.iter() {
+        let setting = conf.lint_rule(rule);
+        if setting.severity == Severity::Off {
+            continue;
+        }
+        let threshold = setting.threshold.unwrap_or_default();
+        let mut finding = |time: NaiveDateTime, message: String, close: Option<NaiveDateTime>| {
+            findings.push(Finding {
+                rule,
+                severity: setting.severity,
+                time,
+                message,
+                close,
+            })
+        };
+        match *rule {
+            "untagged" => {
+                for e in events.iter().filter(|e| e.tags.is_empty()) {
+                    finding(e.start, format!("{}, has no tags", describe(e)), None);
+                }
+            }
+            "long-event" => {
+                for e in events.iter() {
+                    let hours = e.duration(now) / (60.0 * 60.0);
+                    if hours > threshold {
+                        finding(
+                            e.start,
+                            format!(
+                                "{}, lasts {} hours",
+                                describe(e),
+                                duration_string(e.duration(now), conf)
+                            ),
+                            None,
+                        );
+                    }
+                }
+            }
+            "short-description" => {
+                for e in events.iter() {
+                    if (e.description.trim().chars().count() as f32) < threshold {
+                        finding(
+                            e.start,
+                            format!(
+                                "{}, has a description shorter than {} characters",
+                                describe(e),
+                                threshold
+                            ),
+                            None,
+                        );
+                    }
+                }
+            }
+            "gap" => {
+                for (_, from, to) in crate::util::gaps(events, conf) {
+                    let minutes = (to - from).num_seconds() as f32 / 60.0;
+                    if minutes > threshold {
+                        finding(
+                            from,
+                            format!(
+                                "nothing is logged from {} to {}",
+                                from.format("%F %T"),
+                                to.format("%T")
+                            ),
+                            None,
+                        );
+                    }
+                }
+            }
+            "missing-done" => {
+                for (e, close) in overnight_events(events.iter().cloned(), now, conf) {
+                    finding(e.start, format!("{}, was never ended", describe(&e)), close);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    findings.sort_by_key(|f| f.time);
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configure::{AutoClose, LintRule};
+    use crate::util::TestDirectory;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_findings() {
+        let dir = TestDirectory::new("test_findings");
+        let mut conf = dir.configuration();
+        let day = NaiveDate::from_ymd(2020, 1, 6);
+        let now = day.and_hms(12, 0, 0) + Duration::days(2);
+        let event = |description: &str, tags: &[&str], start, end| {
+            Event::builder()
+                .description(description)
+                .tags(tags)
+                .start(start)
+                .end(end)
+                .build()
+                .unwrap()
+        };
+        let events = vec![
+            event(
+                "standup",
+                &["meeting"],
+                day.and_hms(9, 0, 0),
+                day.and_hms(9, 15, 0),
+            ),
+            event("foo", &[], day.and_hms(11, 0, 0), day.and_hms(12, 0, 0)),
+            event(
+                "fixing the parser",
+                &["plugh"],
+                day.and_hms(12, 0, 0),
+                day.and_hms(8, 0, 0) + Duration::days(1),
+            ),
+        ];
+        let rules = |findings: &[Finding]| findings.iter().map(|f| f.rule).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["untagged", "long-event", "missing-done"],
+            rules(&findings(&events, &now, &conf))
+        );
+        conf.lint_rules.insert(
+            "short-description".to_owned(),
+            LintRule::parse("short-description", "error 4").unwrap(),
+        );
+        conf.lint_rules.insert(
+            "gap".to_owned(),
+            LintRule::parse("gap", "warning 90").unwrap(),
+        );
+        conf.lint_rules.insert(
+            "untagged".to_owned(),
+            LintRule::parse("untagged", "off").unwrap(),
+        );
+        let found = findings(&events, &now, &conf);
+        assert_eq!(
+            vec!["gap", "short-description", "long-event", "missing-done"],
+            rules(&found)
+        );
+        assert_eq!(Severity::Error, found[1].severity);
+        assert_eq!(None, found[3].close, "no auto-close policy");
+        conf.auto_close = Some(AutoClose::At(18, 0));
+        let found = findings(&events, &now, &conf);
+        assert_eq!(Some(day.and_hms(18, 0, 0)), found[3].close);
+    }
+}
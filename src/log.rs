@@ -1,20 +1,27 @@
 // stuff for
 extern crate chrono;
+#[cfg(feature = "cli")]
 extern crate clap;
-extern crate larry;
 extern crate pidgin;
 extern crate regex;
 extern crate serde_json;
+extern crate strsim;
 use crate::configure::Configuration;
-use crate::util::{duration_string, log_path};
-use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use crate::storage::{FileStorage, Storage};
+use crate::util::{
+    current_time, duration_string, hours_string, info, log_path, parse_duration, tsv_row,
+};
+use chrono::{
+    Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike,
+};
+#[cfg(feature = "cli")]
 use clap::ArgMatches;
-use larry::Larry;
 use pidgin::{Grammar, Matcher};
 use regex::{Regex, RegexSet};
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Lines, Write};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 lazy_static! {
     // making this public is useful for testing, but best to keep it hidden to
@@ -27,8 +34,10 @@ lazy_static! {
         log_item         -> <timestamped_item> | <blank> | <comment>
         blank            -> r(r"\s*")
         comment          -> r(r"\s*#.*")
-        timestamped_item -> <timestamp> <ti_continuation>
-        timestamp        -> r(r"\s*[1-9]\d{3}(?:\s+\d{1,2}){5}\s*")
+        timestamped_item -> <timestamp> <estimate> <author> <ti_continuation> | <timestamp> <estimate> <ti_continuation> | <timestamp> <author> <ti_continuation> | <timestamp> <ti_continuation>
+        timestamp        -> r(r"\s*[1-9]\d{3}(?:\s+\d{1,2}){5}(?:\s+[+-]\d{4})?\s*") // the UTC offset is optional
+        estimate         -> r(r"~(?:\d+[hms])+\s*") // so is the estimate, e.g. ~1h30m; it comes before the author, which could swallow it
+        author           -> r(r"@(?:\\.|[^:<\\])*") // the author is also optional; its colons, <, and \ are escaped as in tags
         ti_continuation  -> <taggable> | <done>
         taggable         -> <tag_separator> <tags> (":") <description>
         tag_separator    -> <event> | <note>
@@ -41,6 +50,28 @@ lazy_static! {
     pub static ref MATCHER: Matcher = LOG_LINES.matcher().unwrap();
 }
 
+// the version of the log format this version of job writes; logs predating the version
+// comment are version 0
+pub const LOG_VERSION: usize = 1;
+
+// the first line of a new log
+pub fn log_header() -> String {
+    format!("# job log version {}", LOG_VERSION)
+}
+
+// the version of the format of the log at the given path, as recorded in its first line
+pub fn log_version(path: &str) -> usize {
+    lazy_static! {
+        static ref VERSION: Regex = Regex::new(r"\A\s*#\s*job log version (\d+)\s*\z").unwrap();
+    }
+    File::open(path)
+        .ok()
+        .and_then(|f| BufReader::new(f).lines().next())
+        .and_then(|l| l.ok())
+        .and_then(|l| VERSION.captures(&l).map(|c| c[1].parse().unwrap_or(0)))
+        .unwrap_or(0)
+}
+
 // parses a log line into an appropriate data structure preserving the line offset
 pub fn parse_line(line: &str, offset: usize) -> Item {
     if let Some(ast) = MATCHER.parse(line) {
@@ -53,6 +84,10 @@ pub fn parse_line(line: &str, offset: usize) -> Item {
                     } else {
                         let tags = parse_tags(ast.name("tags").unwrap().as_str());
                         let description = ast.name("description").unwrap().as_str();
+                        let author = ast.name("author").map(|a| parse_author(a.as_str()));
+                        let estimate = ast
+                            .name("estimate")
+                            .and_then(|e| parse_duration(&e.as_str()[1..]));
                         if ast.has("event") {
                             Item::Event(
                                 Event {
@@ -64,6 +99,9 @@ pub fn parse_line(line: &str, offset: usize) -> Item {
                                     tags: tags,
                                     vacation: false,
                                     vacation_type: None,
+                                    holiday: false,
+                                    author,
+                                    estimate,
                                 },
                                 offset,
                             )
@@ -73,6 +111,7 @@ pub fn parse_line(line: &str, offset: usize) -> Item {
                                     time: timestamp,
                                     description: description.to_owned(),
                                     tags: tags,
+                                    author,
                                 },
                                 offset,
                             )
@@ -90,9 +129,47 @@ pub fn parse_line(line: &str, offset: usize) -> Item {
     }
 }
 
-pub struct LogController {
-    pub larry: Larry,
-    pub path: String,
+// how many parsed lines a log controller remembers
+const PARSE_CACHE_SIZE: usize = 256;
+
+// the most recently parsed lines of a log, by offset, so that moving back and forth over the
+// same lines does not parse them again
+#[derive(Default)]
+struct ParseCache {
+    items: HashMap<usize, Item>,
+    recency: VecDeque<usize>, // the offsets cached, the most recently used last
+}
+
+impl ParseCache {
+    // the item at the offset, parsed only if it isn't already cached
+    fn parse(&mut self, storage: &mut dyn Storage, offset: usize) -> Item {
+        if let Some(item) = self.items.get(&offset) {
+            let item = item.clone();
+            if let Some(i) = self.recency.iter().rposition(|o| *o == offset) {
+                self.recency.remove(i);
+            }
+            self.recency.push_back(offset);
+            return item;
+        }
+        let item = parse_line(storage.get(offset).unwrap(), offset);
+        if self.items.len() >= PARSE_CACHE_SIZE {
+            if let Some(o) = self.recency.pop_front() {
+                self.items.remove(&o);
+            }
+        }
+        self.items.insert(offset, item.clone());
+        self.recency.push_back(offset);
+        item
+    }
+}
+
+// the storage defaults to the log file
+pub struct LogController<S: Storage = FileStorage> {
+    pub storage: S,
+    cache: ParseCache,      // the lines most recently parsed
+    utc_offsets: bool,      // whether to record UTC offsets in appended lines
+    day_boundary: Duration, // when one day ends and the next begins
+    author: Option<String>, // the user-name to record in appended events and notes
 }
 
 impl LogController {
@@ -101,20 +178,32 @@ impl LogController {
         conf: &Configuration,
     ) -> Result<LogController, std::io::Error> {
         let log = log.unwrap_or(log_path(conf.directory()));
-        let path = log.as_path().to_str();
-        Larry::new(log.as_path()).and_then(|log| {
-            Ok(LogController {
-                larry: log,
-                path: path.unwrap().to_owned(),
-            })
-        })
+        info(format!("reading log {}", log.display()), conf);
+        FileStorage::new(log.as_path()).map(|storage| LogController::with_storage(storage, conf))
+    }
+}
+
+impl<S: Storage> LogController<S> {
+    pub fn with_storage(storage: S, conf: &Configuration) -> LogController<S> {
+        LogController {
+            storage,
+            cache: ParseCache::default(),
+            // the Perl client can read neither UTC offsets nor authors
+            utc_offsets: conf.utc_offsets && !conf.perl_compatible,
+            day_boundary: conf.day_boundary_offset(),
+            author: if conf.perl_compatible {
+                None
+            } else {
+                conf.user_name.clone()
+            },
+        }
     }
     // find best line offset for a timestamp in a log file
     // best is the earliest instance of the line with the timestamp or, barring that, the earliest
     // timestamped line immediately before the timestamp
     pub fn find_line(&mut self, time: &NaiveDateTime) -> Option<Item> {
         if let Some(start) = self.get_after(0) {
-            let end = self.get_before(self.larry.len() - 1);
+            let end = self.get_before(self.storage.len() - 1);
             let time = start.advance(time);
             Some(self.narrow_in(&time, start, end))
         } else {
@@ -122,11 +211,12 @@ impl LogController {
         }
     }
     pub fn first_timestamp(&self) -> Option<NaiveDateTime> {
-        let item = ItemsAfter::new(0, &self.path).find(|i| i.has_time());
+        let item = self.items().find(|i| i.has_time());
         item.and_then(|i| Some(i.time().unwrap().0.clone()))
     }
     pub fn last_timestamp(&mut self) -> Option<NaiveDateTime> {
-        let item = ItemsBefore::new(self.larry.len(), self).find(|i| i.has_time());
+        let item = ItemsBefore::new(self.storage.len(), &mut self.storage, &mut self.cache)
+            .find(|i| i.has_time());
         item.and_then(|i| Some(i.time().unwrap().0.clone()))
     }
     fn narrow_in(&mut self, time: &NaiveDateTime, start: Item, end: Item) -> Item {
@@ -211,8 +301,8 @@ impl LogController {
     // get an index-item pair at or before the given time starting at the given index
     // this moves forward from earlier lines to later
     fn get_after(&mut self, i: usize) -> Option<Item> {
-        for i in i..self.larry.len() {
-            let item = parse_line(self.larry.get(i).unwrap(), i);
+        for i in i..self.storage.len() {
+            let item = self.cache.parse(&mut self.storage, i);
             let t = item.time();
             if let Some((_, _)) = t {
                 return Some(item);
@@ -222,17 +312,17 @@ impl LogController {
     }
     // just returns iterator from a given offset forward -- needed for validation
     pub fn items_before(&mut self, offset: usize) -> ItemsBefore {
-        ItemsBefore::new(offset, self)
+        ItemsBefore::new(offset, &mut self.storage, &mut self.cache)
     }
     // get the first index-item pair at
     // this moves in reverse from later lines to earlier
     fn get_before(&mut self, i: usize) -> Item {
         let mut i = i;
-        if i >= self.larry.len() {
-            i = self.larry.len() - 1;
+        if i >= self.storage.len() {
+            i = self.storage.len() - 1;
         }
         loop {
-            let item = parse_line(self.larry.get(i).unwrap(), i);
+            let item = self.cache.parse(&mut self.storage, i);
             match item {
                 Item::Done(_, _) | Item::Note(_, _) | Item::Event(_, _) => return item,
                 _ => (),
@@ -251,7 +341,7 @@ impl LogController {
         let mut ptr = item.clone();
         while i > 0 {
             i -= 1;
-            let next = parse_line(self.larry.get(i).unwrap(), i);
+            let next = self.cache.parse(&mut self.storage, i);
             let next_time = next.time();
             if let Some((next_time, _)) = next_time {
                 if time == next_time {
@@ -264,21 +354,23 @@ impl LogController {
         ptr
     }
     pub fn events_from_the_end(&mut self) -> EventsBefore {
-        EventsBefore::new(self.larry.len(), self)
+        let offset = self.storage.len();
+        EventsBefore::new(offset, &mut self.storage, &mut self.cache)
     }
     pub fn notes_from_the_end(&mut self) -> NotesBefore {
-        NotesBefore::new(self.larry.len(), self)
+        let offset = self.storage.len();
+        NotesBefore::new(offset, &mut self.storage, &mut self.cache)
     }
     pub fn events_from_the_beginning(self) -> EventsAfter {
-        EventsAfter::new(0, &self)
+        EventsAfter::new(0, &self.storage)
     }
     pub fn notes_from_the_beginning(self) -> NotesAfter {
-        NotesAfter::new(0, &self)
+        NotesAfter::new(0, &self.storage)
     }
     pub fn events_in_range(&mut self, start: &NaiveDateTime, end: &NaiveDateTime) -> Vec<Event> {
         let mut ret = vec![];
         if let Some(item) = self.find_line(start) {
-            for e in EventsAfter::new(item.offset(), self) {
+            for e in EventsAfter::new(item.offset(), &self.storage) {
                 if &e.start < end {
                     ret.push(e);
                 } else {
@@ -295,7 +387,7 @@ impl LogController {
     ) -> Vec<Item> {
         let mut ret = vec![];
         if let Some(item) = self.find_line(start) {
-            for i in ItemsAfter::new(item.offset(), &self.path) {
+            for i in ItemsAfter::from_storage(item.offset(), &self.storage) {
                 match &i {
                     Item::Note(n, _) => {
                         if &n.time > end {
@@ -321,7 +413,7 @@ impl LogController {
         let mut ret = vec![];
         if let Some(item) = self.find_line(start) {
             let mut at_first = true;
-            for n in NotesAfter::new(item.offset(), self) {
+            for n in NotesAfter::new(item.offset(), &self.storage) {
                 if at_first && &n.time < start {
                     at_first = false;
                     continue;
@@ -337,15 +429,70 @@ impl LogController {
         }
         ret
     }
+    // the events of events_in_range, earliest first, in pages of at most page_size events read
+    // from the log as they are needed
+    pub fn events_in_range_paged<'a>(
+        &'a mut self,
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+        page_size: usize,
+    ) -> Pages<'a, Event> {
+        Pages::new(self.events_in_range_streaming(start, end), page_size)
+    }
+    // the events of events_in_range, read from the log as they are needed
+    pub fn events_in_range_streaming<'a>(
+        &'a mut self,
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+    ) -> Box<dyn Iterator<Item = Event> + 'a> {
+        let end = *end;
+        match self.find_line(start) {
+            Some(item) => Box::new(
+                EventsAfter::new(item.offset(), &self.storage).take_while(move |e| e.start < end),
+            ),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+    // the events of events_in_range, latest first, in pages of at most page_size events
+    pub fn events_in_range_paged_reverse<'a>(
+        &'a mut self,
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+        page_size: usize,
+    ) -> Pages<'a, Event> {
+        let end = *end;
+        // the earliest event is the first after the line for the start of the range
+        let first = self
+            .find_line(start)
+            .and_then(|item| EventsAfter::new(item.offset(), &self.storage).next())
+            .map(|e| e.start)
+            .filter(|t| t < &end);
+        let events: Box<dyn Iterator<Item = Event> + 'a> = match first {
+            Some(first) => {
+                // begin with the first line at or after the end of the range
+                let offset = self.find_line(&end).unwrap().offset();
+                let offset = ItemsAfter::from_storage(offset, &self.storage)
+                    .find(|i| i.time().is_some_and(|(t, _)| t >= &end))
+                    .map(|i| i.offset())
+                    .unwrap_or_else(|| self.storage.len());
+                Box::new(
+                    EventsBefore::new(offset, &mut self.storage, &mut self.cache)
+                        .take_while(move |e| e.start >= first),
+                )
+            }
+            None => Box::new(std::iter::empty()),
+        };
+        Pages::new(events, page_size)
+    }
     pub fn last_event(&mut self) -> Option<Event> {
-        // because Larry caches the line, re-acquiring the last event is cheap
+        // because the lines are cached once parsed, re-acquiring the last event is cheap
         self.events_from_the_end().find(|_| true)
     }
     pub fn forgot_to_end_last_event(&mut self) -> bool {
         if let Some(event) = self.last_event() {
             if event.ongoing() {
-                let now = Local::now().naive_local();
-                event.start.date() != now.date()
+                let now = current_time();
+                (event.start - self.day_boundary).date() != (now - self.day_boundary).date()
             } else {
                 false
             }
@@ -354,10 +501,11 @@ impl LogController {
         }
     }
     fn needs_newline(&mut self) -> bool {
-        if self.larry.len() > 0 {
+        if self.storage.len() > 0 {
+            let offset = self.storage.len() - 1;
             let last_line = self
-                .larry
-                .get(self.larry.len() - 1)
+                .storage
+                .get(offset)
                 .expect("could not obtain last line of log");
             let last_char = last_line.bytes().last().unwrap();
             !(last_char == 0x0D || last_char == 0x0A)
@@ -365,58 +513,242 @@ impl LogController {
             false
         }
     }
-    // this method devours the reader because it invalidates the information cached in larry
+    // this method devours the reader because it invalidates the information cached in storage
     pub fn append_event(&mut self, description: String, tags: Vec<String>) -> (Event, usize) {
-        let event = Event::coin(description, tags);
+        self.append_estimated_event(description, tags, None)
+    }
+    // like append_event, but recording the seconds the event is expected to take
+    pub fn append_estimated_event(
+        &mut self,
+        description: String,
+        tags: Vec<String>,
+        estimate: Option<i64>,
+    ) -> (Event, usize) {
+        let mut event = Event::coin(description, tags);
+        event.author = self.author.clone();
+        event.estimate = estimate;
         self.append_to_log(event, "could not append event to log")
     }
-    // this method devours the reader because it invalidates the information cached in larry
+    // this method devours the reader because it invalidates the information cached in storage
     pub fn append_note(&mut self, description: String, tags: Vec<String>) -> (Note, usize) {
-        let note = Note::coin(description, tags);
+        let mut note = Note::coin(description, tags);
+        note.author = self.author.clone();
         self.append_to_log(note, "could not append note to log")
     }
     pub fn close_event(&mut self) -> (Done, usize) {
-        let done = Done(Local::now().naive_local());
+        let done = Done::coin();
         self.append_to_log(done, "could not append DONE line to log")
     }
     pub fn append_to_log<T: LogLine>(&mut self, item: T, error_message: &str) -> (T, usize) {
-        let mut log = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open(&self.path)
-            .unwrap();
+        let offset = self.storage.len();
+        let mut text = String::new();
         if self.needs_newline() {
-            writeln!(log, "").expect("could not append to log file");
+            text.push('\n');
         }
-        let now = Local::today().naive_local();
-        if let Some(ts) = self.last_timestamp() {
-            if ts.date() != now {
-                writeln!(log, "# {}/{}/{}", now.year(), now.month(), now.day())
-                    .expect("could not append date comment to log");
-            }
-        } else {
-            writeln!(log, "# {}/{}/{}", now.year(), now.month(), now.day())
-                .expect("could not append date comment to log");
+        let now = current_time().date();
+        if self.last_timestamp().is_none_or(|ts| ts.date() != now) {
+            text += &format!("# {}/{}/{}\n", now.year(), now.month(), now.day());
         }
-        writeln!(log, "{}", &item.to_line()).expect(error_message);
-        (item, self.larry.len())
+        let line = if self.utc_offsets {
+            item.to_zoned_line()
+        } else {
+            item.to_line()
+        };
+        text += &format!("{}\n", line);
+        self.storage.append(&text).expect(error_message);
+        (item, offset)
     }
     // iterator over all items, first to last
     pub fn items(&self) -> ItemsAfter {
-        ItemsAfter::new(0, &self.path)
+        ItemsAfter::from_storage(0, &self.storage)
+    }
+    // every tag used in the log
+    pub fn tag_inventory(&self) -> BTreeSet<String> {
+        let mut tags = BTreeSet::new();
+        for item in self.items() {
+            match item {
+                Item::Event(e, _) => tags.extend(e.tags),
+                Item::Note(n, _) => tags.extend(n.tags),
+                _ => (),
+            }
+        }
+        tags
+    }
+    // the totals carried forward in the comments heading a truncated log, if any
+    pub fn carried_forward(&mut self) -> Option<CarryForward> {
+        let mut carried: Option<CarryForward> = None;
+        for i in 0..self.storage.len() {
+            let line = self.storage.get(i).ok()?;
+            if let Some(c) = CarryForward::from_line(line) {
+                carried = Some(match carried {
+                    Some(mut carried) => {
+                        carried.add(c);
+                        carried
+                    }
+                    None => c,
+                });
+            } else if parse_line(line.trim_end(), i).has_time() {
+                break;
+            }
+        }
+        carried
+    }
+    // the totals of the lines before the offset, together with those they carry forward; an
+    // event begun before the offset counts in full, wherever it ends
+    pub fn carry_forward(&mut self, offset: usize, now: &NaiveDateTime) -> CarryForward {
+        let mut carried = self.carried_forward().unwrap_or_default();
+        let mut open: Option<Event> = None;
+        let tally = |carried: &mut CarryForward, mut e: Event, end: &NaiveDateTime| {
+            e.end = Some(*end);
+            let hours = e.duration(now) as f64 / (60.0 * 60.0);
+            carried.hours += hours;
+            for t in e.tags {
+                *carried.tags.entry(t).or_insert(0.0) += hours;
+            }
+        };
+        for item in self.items() {
+            if let Some((t, _)) = item.time() {
+                if let Item::Event(_, _) | Item::Done(_, _) = item {
+                    if let Some(e) = open.take() {
+                        tally(&mut carried, e, t);
+                    }
+                }
+                if item.offset() >= offset {
+                    if open.is_none() {
+                        break;
+                    }
+                    continue;
+                }
+                carried.first = Some(carried.first.map_or(*t, |f| f.min(*t)));
+            } else if item.offset() >= offset {
+                continue;
+            }
+            match item {
+                Item::Event(e, _) => {
+                    carried.events += 1;
+                    open = Some(e);
+                }
+                Item::Note(n, _) => {
+                    carried.notes += 1;
+                    carried.note_tags.extend(n.tags);
+                }
+                _ => (),
+            }
+        }
+        if let Some(e) = open {
+            tally(&mut carried, e, now);
+        }
+        carried
+    }
+}
+
+// the totals of the events and notes truncate removes from the head of the log, kept in a
+// comment at the top of what remains so statistics about the whole log still come out right
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CarryForward {
+    pub first: Option<NaiveDateTime>, // the earliest timestamp removed
+    pub hours: f64,                   // the hours logged
+    pub events: usize,
+    pub notes: usize,
+    pub tags: BTreeMap<String, f64>, // the hours logged by event tag
+    pub note_tags: BTreeSet<String>,
+}
+
+const CARRY_FORWARD: &str = "# carried forward: ";
+
+impl CarryForward {
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}{}",
+            CARRY_FORWARD,
+            serde_json::json!({
+                "first": self.first.map(|t| format!("{}", t.format("%F %T"))),
+                "hours": self.hours,
+                "events": self.events,
+                "notes": self.notes,
+                "tags": self.tags,
+                "note-tags": self.note_tags,
+            })
+        )
+    }
+    pub fn from_line(line: &str) -> Option<CarryForward> {
+        let json: serde_json::Value =
+            serde_json::from_str(line.trim().strip_prefix(CARRY_FORWARD.trim_end())?).ok()?;
+        Some(CarryForward {
+            first: json["first"]
+                .as_str()
+                .and_then(|t| NaiveDateTime::parse_from_str(t, "%F %T").ok()),
+            hours: json["hours"].as_f64()?,
+            events: json["events"].as_u64()? as usize,
+            notes: json["notes"].as_u64()? as usize,
+            tags: json["tags"]
+                .as_object()?
+                .iter()
+                .filter_map(|(t, h)| h.as_f64().map(|h| (t.clone(), h)))
+                .collect(),
+            note_tags: json["note-tags"]
+                .as_array()?
+                .iter()
+                .filter_map(|t| t.as_str().map(|t| t.to_owned()))
+                .collect(),
+        })
+    }
+    pub fn add(&mut self, other: CarryForward) {
+        self.first = match (self.first, other.first) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.hours += other.hours;
+        self.events += other.events;
+        self.notes += other.notes;
+        for (t, h) in other.tags {
+            *self.tags.entry(t).or_insert(0.0) += h;
+        }
+        self.note_tags.extend(other.note_tags);
+    }
+}
+
+// the items of an iterator gathered into pages
+pub struct Pages<'a, T> {
+    items: Box<dyn Iterator<Item = T> + 'a>,
+    size: usize,
+}
+
+impl<'a, T> Pages<'a, T> {
+    fn new(items: Box<dyn Iterator<Item = T> + 'a>, size: usize) -> Pages<'a, T> {
+        assert!(size > 0, "pages must have room for something");
+        Pages { items, size }
+    }
+}
+
+impl<'a, T> Iterator for Pages<'a, T> {
+    type Item = Vec<T>;
+    fn next(&mut self) -> Option<Vec<T>> {
+        let page = self.items.by_ref().take(self.size).collect::<Vec<_>>();
+        if page.is_empty() {
+            None
+        } else {
+            Some(page)
+        }
     }
 }
 
 pub struct ItemsBefore<'a> {
     offset: Option<usize>,
-    larry: &'a mut Larry,
+    storage: &'a mut dyn Storage,
+    cache: &'a mut ParseCache,
 }
 
 impl<'a> ItemsBefore<'a> {
-    fn new(offset: usize, reader: &mut LogController) -> ItemsBefore {
+    fn new(
+        offset: usize,
+        storage: &'a mut dyn Storage,
+        cache: &'a mut ParseCache,
+    ) -> ItemsBefore<'a> {
         ItemsBefore {
             offset: if offset == 0 { None } else { Some(offset) },
-            larry: &mut reader.larry,
+            storage,
+            cache,
         }
     }
 }
@@ -426,8 +758,7 @@ impl<'a> Iterator for ItemsBefore<'a> {
     fn next(&mut self) -> Option<Item> {
         if let Some(o) = self.offset {
             let o2 = o - 1;
-            let line = self.larry.get(o2).unwrap();
-            let item = parse_line(line, o);
+            let item = self.cache.parse(self.storage, o2);
             self.offset = if o2 > 0 { Some(o2) } else { None };
             Some(item)
         } else {
@@ -438,24 +769,27 @@ impl<'a> Iterator for ItemsBefore<'a> {
 
 pub struct ItemsAfter {
     offset: usize,
-    bufreader: Lines<BufReader<File>>,
+    lines: Box<dyn Iterator<Item = std::io::Result<String>>>,
 }
 
 impl ItemsAfter {
+    // the items in the log file at the path
     pub fn new(offset: usize, path: &str) -> ItemsAfter {
-        let mut bufreader =
-            BufReader::new(File::open(path).expect("could not open log file")).lines();
-        for _ in 0..offset {
-            bufreader.next();
+        let storage = FileStorage::new(Path::new(path)).expect("could not open log file");
+        ItemsAfter::from_storage(offset, &storage)
+    }
+    pub fn from_storage(offset: usize, storage: &dyn Storage) -> ItemsAfter {
+        ItemsAfter {
+            offset,
+            lines: storage.lines_from(offset).expect("could not open log"),
         }
-        ItemsAfter { offset, bufreader }
     }
 }
 
 impl Iterator for ItemsAfter {
     type Item = Item;
     fn next(&mut self) -> Option<Item> {
-        if let Some(res) = self.bufreader.next() {
+        if let Some(res) = self.lines.next() {
             let line = res.expect("could not read log line");
             let item = parse_line(&line, self.offset);
             self.offset += 1;
@@ -471,9 +805,13 @@ pub struct NotesBefore<'a> {
 }
 
 impl<'a> NotesBefore<'a> {
-    fn new(offset: usize, reader: &mut LogController) -> NotesBefore {
+    fn new(
+        offset: usize,
+        storage: &'a mut dyn Storage,
+        cache: &'a mut ParseCache,
+    ) -> NotesBefore<'a> {
         NotesBefore {
-            item_iterator: ItemsBefore::new(offset, reader),
+            item_iterator: ItemsBefore::new(offset, storage, cache),
         }
     }
 }
@@ -500,9 +838,9 @@ pub struct NotesAfter {
 }
 
 impl NotesAfter {
-    fn new(offset: usize, reader: &LogController) -> NotesAfter {
+    fn new(offset: usize, storage: &dyn Storage) -> NotesAfter {
         NotesAfter {
-            item_iterator: ItemsAfter::new(offset, &reader.path),
+            item_iterator: ItemsAfter::from_storage(offset, storage),
         }
     }
 }
@@ -530,23 +868,22 @@ pub struct EventsBefore<'a> {
 }
 
 impl<'a> EventsBefore<'a> {
-    fn new(offset: usize, reader: &mut LogController) -> EventsBefore {
+    fn new(
+        offset: usize,
+        storage: &'a mut dyn Storage,
+        cache: &'a mut ParseCache,
+    ) -> EventsBefore<'a> {
         // the last event may be underway at the offset, so find out when it ends
-        let items_after = ItemsAfter::new(offset, &reader.path);
-        let timed_item = items_after
-            .filter(|i| match i {
+        let last_time = (offset..storage.len())
+            .map(|i| cache.parse(storage, i))
+            .find(|i| match i {
                 Item::Event(_, _) | Item::Done(_, _) => true,
                 _ => false,
             })
-            .find(|i| i.time().is_some());
-        let last_time = if let Some(i) = timed_item {
-            Some(i.time().unwrap().0.to_owned())
-        } else {
-            None
-        };
+            .map(|i| i.time().unwrap().0.to_owned());
         EventsBefore {
             last_time,
-            item_iterator: ItemsBefore::new(offset, reader),
+            item_iterator: ItemsBefore::new(offset, storage, cache),
         }
     }
 }
@@ -587,10 +924,10 @@ pub struct EventsAfter {
 }
 
 impl EventsAfter {
-    fn new(offset: usize, reader: &LogController) -> EventsAfter {
+    fn new(offset: usize, storage: &dyn Storage) -> EventsAfter {
         EventsAfter {
             next_item: None,
-            item_iterator: ItemsAfter::new(offset, &reader.path),
+            item_iterator: ItemsAfter::from_storage(offset, storage),
         }
     }
     fn get_end_time(&mut self) -> Option<NaiveDateTime> {
@@ -635,11 +972,12 @@ impl Iterator for EventsAfter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::MemoryStorage;
     use chrono::Duration;
     use rand::seq::SliceRandom;
     use rand::{thread_rng, Rng};
     use std::fs::File;
-    use std::io::LineWriter;
+    use std::io::{LineWriter, Write};
     use std::ops::AddAssign;
     use std::str::FromStr;
 
@@ -716,6 +1054,7 @@ mod tests {
                         time: time.clone(),
                         description: random_text(),
                         tags: random_tags(),
+                        author: None,
                     },
                     offset,
                 )
@@ -735,6 +1074,9 @@ mod tests {
                             description: random_text(),
                             vacation: false,
                             vacation_type: None,
+                            holiday: false,
+                            author: None,
+                            estimate: None,
                         },
                         offset,
                     )
@@ -870,6 +1212,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_memory_storage() {
+        let (conf_path, conf) = test_configuration("test_memory_storage");
+        let text = "# job log version 1\n\
+            2020  3  4  8  0  0:foo:parsing\n\
+            2020  3  4  9  0  0:DONE\n\
+            2020  3  4 10  0  0::writing";
+        let mut log = LogController::with_storage(MemoryStorage::new(text), &conf);
+        let start = NaiveDate::from_ymd(2020, 3, 4).and_hms(0, 0, 0);
+        let end = NaiveDate::from_ymd(2020, 3, 5).and_hms(0, 0, 0);
+        let events = log.events_in_range(&start, &end);
+        assert_eq!(2, events.len());
+        assert_eq!(vec!["foo"], events[0].tags);
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2020, 3, 4).and_hms(9, 0, 0)),
+            events[0].end
+        );
+        assert!(log.last_event().unwrap().ongoing());
+        log.close_event();
+        assert!(
+            log.storage.text().starts_with(text) && log.storage.text().ends_with(":DONE\n"),
+            "the DONE line is appended on a line of its own"
+        );
+        let mut reread = LogController::with_storage(MemoryStorage::new(log.storage.text()), &conf);
+        assert!(!reread.last_event().unwrap().ongoing());
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let mut storage = MemoryStorage::new(
+            &(0..PARSE_CACHE_SIZE + 1)
+                .map(|i| format!("2020  3  4  8 {:2} {:2}::event {}\n", i / 60, i % 60, i))
+                .collect::<String>(),
+        );
+        let mut cache = ParseCache::default();
+        for i in 0..PARSE_CACHE_SIZE {
+            assert_eq!(i, cache.parse(&mut storage, i).offset());
+        }
+        // using the first line again makes the second the least recently used
+        cache.parse(&mut storage, 0);
+        cache.parse(&mut storage, PARSE_CACHE_SIZE);
+        assert_eq!(PARSE_CACHE_SIZE, cache.items.len());
+        assert!(cache.items.contains_key(&0));
+        assert!(!cache.items.contains_key(&1));
+        match cache.parse(&mut storage, PARSE_CACHE_SIZE) {
+            Item::Event(e, _) => assert_eq!(format!("event {}", PARSE_CACHE_SIZE), e.description),
+            _ => panic!("expected an event"),
+        }
+    }
+
+    #[test]
+    fn test_carry_forward() {
+        let (conf_path, conf) = test_configuration("test_carry_forward");
+        let text = "# job log version 1\n\
+            2020  3  4  8  0  0:foo:parsing\n\
+            2020  3  4  8 30  0<NOTE>bar:a note\n\
+            2020  3  4  9  0  0:foo bar:writing\n\
+            2020  3  4 10  0  0:DONE\n\
+            2020  3  5  8  0  0::reading";
+        let mut log = LogController::with_storage(MemoryStorage::new(text), &conf);
+        assert_eq!(None, log.carried_forward());
+        let now = NaiveDate::from_ymd(2020, 3, 5).and_hms(9, 0, 0);
+        let carried = log.carry_forward(4, &now);
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2020, 3, 4).and_hms(8, 0, 0)),
+            carried.first
+        );
+        assert_eq!((2.0, 2, 1), (carried.hours, carried.events, carried.notes));
+        assert_eq!(Some(&2.0), carried.tags.get("foo"));
+        assert_eq!(Some(&1.0), carried.tags.get("bar"));
+        assert!(carried.note_tags.contains("bar"));
+        assert_eq!(
+            Some(carried.clone()),
+            CarryForward::from_line(&carried.to_line())
+        );
+        // truncating the truncated log carries the earlier totals forward too
+        let text = format!(
+            "# job log version 1\n{}\n2020  3  4  9  0  0:foo bar:writing\n\
+            2020  3  4 10  0  0:DONE\n2020  3  5  8  0  0::reading",
+            log.carry_forward(2, &now).to_line()
+        );
+        let mut log = LogController::with_storage(MemoryStorage::new(&text), &conf);
+        assert_eq!(
+            (1.0, 1),
+            log.carried_forward().map(|c| (c.hours, c.events)).unwrap(),
+            "the event begun before the offset counts in full"
+        );
+        let again = log.carry_forward(4, &now);
+        assert_eq!((2.0, 2, 0), (again.hours, again.events, again.notes));
+        assert_eq!(Some(&2.0), again.tags.get("foo"));
+        cleanup(&[&conf_path]);
+    }
+
     #[test]
     fn test_notes_in_range() {
         let (items, path) = random_log(100, vec![Need::N, Need::N], "test_notes_in_range");
@@ -929,6 +1365,45 @@ mod tests {
         cleanup(&[&path, &conf_path]);
     }
 
+    #[test]
+    fn test_events_in_range_paged() {
+        let (items, path) = random_log(40, vec![Need::E, Need::E], "test_events_in_range_paged");
+        let events = closed_events(items);
+        let (conf_path, conf) = test_configuration("test_events_in_range_paged");
+        let mut log_reader =
+            LogController::new(Some(PathBuf::from_str(&path).unwrap()), &conf).unwrap();
+        let key = |e: &Event| (e.start, e.end, e.description.clone());
+        let minute = Duration::minutes(1);
+        for i in 0..events.len() {
+            for j in i..events.len() {
+                for (start, end) in [
+                    (events[i].start, events[j].start),
+                    (events[i].start + minute, events[j].start + minute),
+                ] {
+                    let expected = log_reader
+                        .events_in_range(&start, &end)
+                        .iter()
+                        .map(key)
+                        .collect::<Vec<_>>();
+                    let pages = log_reader
+                        .events_in_range_paged(&start, &end, 3)
+                        .collect::<Vec<_>>();
+                    assert!(pages.iter().all(|p| !p.is_empty() && p.len() <= 3));
+                    let found = pages.iter().flatten().map(key).collect::<Vec<_>>();
+                    assert_eq!(expected, found, "same events");
+                    let mut found = log_reader
+                        .events_in_range_paged_reverse(&start, &end, 2)
+                        .flatten()
+                        .map(|e| key(&e))
+                        .collect::<Vec<_>>();
+                    found.reverse();
+                    assert_eq!(expected, found, "same events in reverse");
+                }
+            }
+        }
+        cleanup(&[&path, &conf_path]);
+    }
+
     #[test]
     fn test_notes_from_end() {
         let (items, path) = random_log(100, vec![Need::N], "test_notes_from_end");
@@ -1218,6 +1693,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1241,6 +1717,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1267,6 +1744,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1290,6 +1768,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1313,6 +1792,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1337,6 +1817,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1364,6 +1845,7 @@ mod tests {
                     time,
                     tags,
                     description,
+                    ..
                 },
                 _,
             ) => {
@@ -1387,120 +1869,647 @@ mod tests {
     }
 
     #[test]
-    fn test_done() {
-        match parse_line("2019 12 1 16 3 30:DONE", 0) {
-            Item::Done(Done(time), _) => {
-                assert_eq!(2019, time.year());
-                assert_eq!(12, time.month());
-                assert_eq!(1, time.day());
-                assert_eq!(16, time.hour());
-                assert_eq!(3, time.minute());
-                assert_eq!(30, time.second());
-            }
-            _ => assert!(false, "failed to parse a DONE line"),
-        };
-        match parse_line(" 2019  12   1  16  3  30 :DONE", 0) {
-            Item::Done(Done(time), _) => {
-                assert_eq!(2019, time.year(), "space doesn't matter");
-                assert_eq!(12, time.month(), "space doesn't matter");
-                assert_eq!(1, time.day(), "space doesn't matter");
-                assert_eq!(16, time.hour(), "space doesn't matter");
-                assert_eq!(3, time.minute(), "space doesn't matter");
-                assert_eq!(30, time.second(), "space doesn't matter");
-            }
-            _ => assert!(false, "failed to parse a DONE line"),
-        };
+    fn test_utc_offset() {
+        let local = NaiveDate::from_ymd(2019, 12, 1).and_hms(16, 3, 30);
+        let mut event = Event::coin("an event".to_owned(), vec!["foo".to_owned()]);
+        event.start = local;
+        let line = event.to_zoned_line();
+        assert!(
+            Regex::new(r"\A2019 12  1 16  3 30 [+-]\d{4}:foo:an event\z")
+                .unwrap()
+                .is_match(&line),
+            "offset follows timestamp"
+        );
+        match parse_line(&line, 0) {
+            Item::Event(e, _) => assert_eq!(local, e.start, "round trip preserves time"),
+            _ => assert!(false, "failed to parse a zoned event line"),
+        }
+        // the same moment recorded in two different zones
+        let t1 = parse_timestamp("2019 12  1 16  3 30 +0100").unwrap();
+        let t2 = parse_timestamp("2019 12  1 10  3 30 -0500").unwrap();
+        assert_eq!(t1, t2, "offsets are taken into account");
+        match parse_line("2019 12  1 16  3 30 -0000:DONE", 0) {
+            Item::Done(_, _) => (),
+            _ => assert!(false, "failed to parse a zoned DONE line"),
+        }
+        match parse_line("2019 12  1 16  3 30 +2500:DONE", 0) {
+            Item::Error(_, _) => (),
+            _ => assert!(false, "accepted a bad offset"),
+        }
     }
 
     #[test]
-    fn test_tag_whitespace_handling() {
-        let e = Event::coin(
-            String::from("foo"),
-            vec![String::from("foo bar"), String::from("baz   plugh")],
+    fn test_without_periods() {
+        let day = NaiveDate::from_ymd(2020, 1, 2);
+        let mut event = Event::coin("an event".to_owned(), vec![]);
+        event.start = day.and_hms(9, 0, 0);
+        event.end = Some(day.and_hms(17, 0, 0));
+        let times = |events: Vec<Event>| {
+            events
+                .iter()
+                .map(|e| (e.start.hour(), e.end.unwrap().hour()))
+                .collect::<Vec<_>>()
+        };
+        let now = day.and_hms(18, 0, 0);
+        let periods = [(day.and_hms(12, 0, 0), day.and_hms(13, 0, 0))];
+        assert_eq!(
+            vec![(9, 12), (13, 17)],
+            times(Event::without_periods(vec![event.clone()], &periods, &now)),
+            "divided"
         );
-        match parse_line(e.to_line().as_str(), 0) {
-            Item::Event(Event { tags, .. }, _) => {
-                assert_eq!(2, tags.len());
-                assert!(tags.contains(&String::from("foo bar")));
-                assert!(tags.contains(&String::from("baz plugh")));
-            }
-            _ => assert!(false, "failed to parse line as an event"),
-        }
+        let periods = [
+            (day.and_hms(8, 0, 0), day.and_hms(10, 0, 0)),
+            (day.and_hms(16, 0, 0), day.and_hms(18, 0, 0)),
+        ];
+        assert_eq!(
+            vec![(10, 16)],
+            times(Event::without_periods(vec![event.clone()], &periods, &now)),
+            "trimmed"
+        );
+        let periods = [(day.and_hms(0, 0, 0), day.and_hms(23, 0, 0))];
+        assert!(Event::without_periods(vec![event], &periods, &now).is_empty());
     }
 
     #[test]
-    fn test_zero_padding() {
-        match parse_line("2019 12 01 16 03 30:DONE", 0) {
-            Item::Done(Done(time), _) => {
-                assert_eq!(1, time.day());
-                assert_eq!(3, time.minute());
-            }
-            _ => assert!(false, "failed to parse a DONE line"),
-        };
+    fn test_filter_builder() {
+        let start = NaiveDate::from_ymd(2020, 3, 4).and_hms(8, 0, 0);
+        let event = Event::builder()
+            .start(start)
+            .end(start + Duration::minutes(15))
+            .description("Daily standup")
+            .tag("acme")
+            .build()
+            .unwrap();
+        let passes = |builder: FilterBuilder| builder.build().unwrap().matches(&event);
+        assert!(passes(Filter::builder()));
+        assert!(passes(Filter::builder().tags(&["acme"])));
+        assert!(!passes(Filter::builder().excluded_tags(&["acme"])));
+        assert!(passes(Filter::builder().some_tags(&["acme", "bigco"])));
+        assert!(!passes(Filter::builder().untagged(true)));
+        assert!(!passes(Filter::builder().patterns(&["daily"])));
+        assert!(passes(
+            Filter::builder()
+                .patterns(&["daily"])
+                .case_insensitive(true)
+        ));
+        assert!(!passes(
+            Filter::builder().patterns(&["stand"]).whole_words(true)
+        ));
+        assert!(!passes(Filter::builder().excluded_patterns(&["standup"])));
+        assert!(passes(Filter::builder().shorter_than(60 * 60)));
+        assert!(!passes(Filter::builder().longer_than(60 * 60)));
+        assert!(
+            passes(Filter::builder().tags(&[])),
+            "an empty list is no test"
+        );
+        assert!(Filter::builder().patterns(&["("]).build().is_err());
     }
 
     #[test]
-    fn test_comment() {
-        let success = match parse_line("#foo", 0) {
-            Item::Comment(_) => true,
-            _ => false,
-        };
-        assert!(success, "recognized '#foo' as a comment line");
-        let success = match parse_line("   #foo", 0) {
-            Item::Comment(_) => true,
-            _ => false,
-        };
-        assert!(success, "comments can have leading space");
+    fn test_tag_suggestions() {
+        let known = ["meeting", "meetings", "code-review", "a"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            vec!["meeting", "meetings"],
+            tag_suggestions("meetign", &known)
+        );
+        assert_eq!(vec!["code-review"], tag_suggestions("Code-Reveiw", &known));
+        assert_eq!(vec!["a"], tag_suggestions("b", &known));
+        assert!(tag_suggestions("lunch", &known).is_empty());
+        let filter = Filter::builder()
+            .tags(&["b"])
+            .excluded_tags(&["a"])
+            .expression(FilterExpression::parse("tag:c OR NOT (tag:a AND rx:d)").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(vec!["a", "b", "c"], filter.tags());
     }
 
     #[test]
-    fn test_error() {
-        let success = match parse_line("foo", 0) {
-            Item::Error(_, _) => true,
-            _ => false,
+    fn test_filter_expression() {
+        let mut event = Event::coin("daily standup".to_owned(), vec!["acme".to_owned()]);
+        let matches = |expression: &str, event: &Event| {
+            FilterExpression::parse(expression).unwrap().matches(event)
         };
-        assert!(success, "recognized 'foo' as a malformed log line");
+        assert!(matches("tag:acme", &event));
+        assert!(matches("(tag:acme OR tag:bigco) AND NOT rx:review", &event));
+        assert!(!matches(
+            "(tag:acme OR tag:bigco) AND NOT rx:standup",
+            &event
+        ));
+        assert!(
+            !matches("tag:acme not rx:stand(up)?", &event),
+            "implicit AND"
+        );
+        assert!(matches("tag:bigco or rx:\"daily standup\"", &event));
+        assert!(matches("NOT tag:bigco AND NOT untagged", &event));
+        assert!(
+            matches("tag:bigco AND tag:acme OR tag:acme", &event),
+            "AND binds more tightly than OR"
+        );
+        event.tags.clear();
+        assert!(matches("untagged", &event));
+        for bad in &[
+            "",
+            "tag:",
+            "(tag:acme",
+            "tag:acme)",
+            "foo",
+            "bar:baz",
+            "rx:(",
+            "tag:a OR",
+        ] {
+            assert!(FilterExpression::parse(bad).is_err(), "{:?} is bad", bad);
+        }
     }
 
     #[test]
-    fn test_blank() {
-        let success = match parse_line("", 0) {
-            Item::Blank(_) => true,
-            _ => false,
-        };
-        assert!(success, "recognized an empty line as a blank");
-        let success = match parse_line("     ", 0) {
-            Item::Blank(_) => true,
-            _ => false,
-        };
-        assert!(success, "recognized a whitespace line as a blank");
+    fn test_similarity() {
+        assert_eq!(1.0, similarity("fixing the parser", "fixing the parser"));
+        assert!(
+            similarity("parser fixing", "fixing the parser") > 0.99,
+            "word order"
+        );
+        assert!(
+            similarity("fixnig parsre", "fixing the parser") >= FUZZY_THRESHOLD,
+            "typos"
+        );
+        assert!(similarity("email", "fixing the parser") < FUZZY_THRESHOLD);
+        assert_eq!(0.0, similarity("", "fixing the parser"));
     }
 
     #[test]
-    fn stack_overflow_regression() {
-        let (items, path) = random_log(23, vec![Need::E, Need::E], "stack_overflow_regression");
-        let events = closed_events(items);
-        assert!(events.len() > 1, "found more than one event");
-        let (conf_path, conf) = test_configuration("stack_overflow_regression");
-        let mut log_reader =
-            LogController::new(Some(PathBuf::from_str(&path).unwrap()), &conf).unwrap();
-        let e = events.first().unwrap();
-        let false_start = e.start - Duration::days(1);
-        let found_events = log_reader.events_in_range(&false_start, e.end.as_ref().unwrap());
-        assert_eq!(1, found_events.len(), "found one event");
-        assert_eq!(e.start, found_events[0].start, "same start");
-        assert_eq!(e.end, found_events[0].end, "same end");
-        assert_eq!(e.tags, found_events[0].tags, "same tags");
+    fn test_log_line() {
+        let dir = crate::util::TestDirectory::new("test_log_line");
+        let mut conf = dir.configuration();
+        let mut event = Event::coin("an event".to_owned(), vec!["foo".to_owned()]);
+        event.start = NaiveDate::from_ymd(2019, 12, 1).and_hms(16, 3, 30);
+        event.author = Some("bob".to_owned());
+        assert_eq!(event.to_line(), log_line(&event, &conf));
+        conf.utc_offsets = true;
+        assert_eq!(event.to_zoned_line(), log_line(&event, &conf));
+        conf.perl_compatible = true;
         assert_eq!(
-            e.description, found_events[0].description,
-            "same description"
+            "2019 12  1 16  3 30:foo:an event",
+            log_line(&event, &conf),
+            "the Perl client gets neither offset nor author"
         );
-        cleanup(&[&path, &conf_path]);
+        let note = Note::builder()
+            .time(event.start)
+            .description("a note".to_owned())
+            .author(Some("bob".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!("2019 12  1 16  3 30<NOTE>:a note", log_line(&note, &conf));
     }
-}
 
-// everything you could find in a stream of lines from a log
-#[derive(Debug, Clone)]
+    #[test]
+    fn test_author() {
+        let mut event = Event::coin("an event".to_owned(), vec!["foo".to_owned()]);
+        event.start = NaiveDate::from_ymd(2019, 12, 1).and_hms(16, 3, 30);
+        event.author = Some("Jane: <Doe>".to_owned());
+        let line = event.to_line();
+        assert_eq!(
+            "2019 12  1 16  3 30 @Jane\\: \\<Doe>:foo:an event", line,
+            "author follows timestamp"
+        );
+        match parse_line(&line, 0) {
+            Item::Event(e, _) => {
+                assert_eq!(
+                    Some("Jane: <Doe>"),
+                    e.author.as_deref(),
+                    "author round trips"
+                );
+                assert_eq!(vec!["foo"], e.tags, "tags survive");
+                assert_eq!("an event", e.description, "description survives");
+            }
+            _ => assert!(false, "failed to parse an event with an author"),
+        }
+        match parse_line("2019 12  1 16  3 30 +0100 @bob<NOTE>bar:a note", 0) {
+            Item::Note(n, _) => assert_eq!(Some("bob"), n.author.as_deref()),
+            _ => assert!(false, "failed to parse a zoned note with an author"),
+        }
+        match parse_line("2019 12  1 16  3 30::no author", 0) {
+            Item::Event(e, _) => assert_eq!(None, e.author),
+            _ => assert!(false, "failed to parse an event without an author"),
+        }
+    }
+
+    #[test]
+    fn test_estimate() {
+        let mut event = Event::coin("an event".to_owned(), vec!["foo".to_owned()]);
+        event.start = NaiveDate::from_ymd(2019, 12, 1).and_hms(16, 3, 30);
+        event.estimate = Some(90 * 60);
+        assert_eq!(
+            "2019 12  1 16  3 30 ~1h30m:foo:an event",
+            event.to_line(),
+            "estimate follows timestamp"
+        );
+        event.author = Some("bob".to_owned());
+        let line = event.to_line();
+        assert_eq!(
+            "2019 12  1 16  3 30 ~1h30m @bob:foo:an event", line,
+            "estimate precedes author"
+        );
+        match parse_line(&line, 0) {
+            Item::Event(e, _) => {
+                assert_eq!(Some(90 * 60), e.estimate, "estimate round trips");
+                assert_eq!(Some("bob"), e.author.as_deref(), "author survives");
+                assert_eq!(vec!["foo"], e.tags, "tags survive");
+            }
+            _ => assert!(false, "failed to parse an event with an estimate"),
+        }
+        match parse_line("2019 12  1 16  3 30 +0100 ~45s::zoned", 0) {
+            Item::Event(e, _) => assert_eq!(Some(45), e.estimate),
+            _ => assert!(false, "failed to parse a zoned event with an estimate"),
+        }
+        match parse_line("2019 12  1 16  3 30:~2h:a tag", 0) {
+            Item::Event(e, _) => {
+                assert_eq!(None, e.estimate, "tags may begin with ~");
+                assert_eq!(vec!["~2h"], e.tags);
+            }
+            _ => assert!(false, "failed to parse an event without an estimate"),
+        }
+    }
+
+    #[test]
+    fn test_raw_json() {
+        let (conf_path, mut conf) = test_configuration("test_raw_json");
+        let start = NaiveDate::from_ymd(2020, 3, 4).and_hms(8, 0, 0);
+        let event = Event::builder()
+            .description("parsing")
+            .start(start)
+            .end(start + Duration::seconds(20 * 60 + 7))
+            .build()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&event.to_json(&start, &conf)).unwrap();
+        assert_eq!(0.34, json["duration"].as_f64().unwrap());
+        assert_eq!(1207, json["seconds"].as_i64().unwrap());
+        conf.raw = true;
+        let json: serde_json::Value = serde_json::from_str(&event.to_json(&start, &conf)).unwrap();
+        assert_eq!(
+            0.34,
+            json["duration"].as_f64().unwrap(),
+            "--raw leaves the JSON as it is"
+        );
+        assert_eq!("1207", crate::util::duration_string(1207.0, &conf));
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_tsv() {
+        let (conf_path, conf) = test_configuration("test_tsv");
+        let start = NaiveDate::from_ymd(2020, 3, 4).and_hms(8, 0, 0);
+        let event = Event::builder()
+            .description("parsing\tlexing")
+            .tags(&["foo", "bar"])
+            .start(start)
+            .end(start + Duration::minutes(90))
+            .build()
+            .unwrap();
+        assert_eq!(
+            "Event\t2020-03-04 08:00:00\t2020-03-04 09:30:00\t1.50\t\t\tbar, foo\tparsing\\tlexing",
+            event.to_tsv(&start, &conf)
+        );
+        let note = Note::builder()
+            .description("a note")
+            .time(start)
+            .author(Some("bob".to_owned()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            "Note\t2020-03-04 08:00:00\t\t\t\tbob\t\ta note",
+            note.to_tsv(&start, &conf)
+        );
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_day_boundary() {
+        let (conf_path, mut conf) = test_configuration("test_day_boundary");
+        conf.day_boundary = (4, 0);
+        let start = NaiveDate::from_ymd(2021, 5, 3).and_hms(20, 0, 0);
+        let end = NaiveDate::from_ymd(2021, 5, 4).and_hms(6, 0, 0);
+        let mut event = Event::coin("night shift".to_owned(), vec![]);
+        event.start = start;
+        event.end = Some(end);
+        let events = Event::gather_by_day(vec![event.clone()], &end, &conf);
+        assert_eq!(2, events.len(), "split at the day boundary");
+        assert_eq!(
+            NaiveDate::from_ymd(2021, 5, 4).and_hms(4, 0, 0),
+            events[0].end.unwrap()
+        );
+        assert!(!events[0].overlaps_start());
+        assert!(events[0].overlaps_end());
+        assert!(events[1].overlaps_start());
+        assert!(!events[1].overlaps_end());
+        assert_eq!(
+            NaiveDate::from_ymd(2021, 5, 3),
+            conf.logical_date(&NaiveDate::from_ymd(2021, 5, 4).and_hms(2, 0, 0)),
+            "work after midnight belongs to the previous day"
+        );
+        event.end = Some(NaiveDate::from_ymd(2021, 5, 4).and_hms(3, 0, 0));
+        let events = Event::gather_by_day(vec![event], &end, &conf);
+        assert_eq!(1, events.len(), "no split before the day boundary");
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_merge_contiguous() {
+        let (conf_path, conf) = test_configuration("test_merge_contiguous");
+        let start = NaiveDate::from_ymd(2021, 5, 3).and_hms(20, 0, 0);
+        let mut e1 = Event::coin("night".to_owned(), vec!["a".to_owned()]);
+        e1.start = start;
+        e1.end = Some(start + Duration::hours(10));
+        let mut e2 = Event::coin("more".to_owned(), vec!["a".to_owned()]);
+        e2.start = e1.end.unwrap();
+        e2.end = Some(e2.start + Duration::hours(1));
+        let mut e3 = Event::coin("still more".to_owned(), vec!["a".to_owned()]);
+        e3.start = e2.end.unwrap();
+        e3.end = Some(e3.start + Duration::hours(1));
+        let events = Event::merge_contiguous(vec![e1, e2, e3], &conf);
+        assert_eq!(2, events.len(), "no merging across days");
+        assert_eq!(10.0 * 60.0 * 60.0, events[0].duration(&start));
+        assert_eq!("more; still more", events[1].description);
+        cleanup(&[&conf_path]);
+    }
+
+    // US Eastern time in 2021: clocks sprang forward at 2 am on March 14 and fell back at 2 am
+    // on November 7
+    #[derive(Debug, Clone)]
+    struct Eastern;
+
+    impl Eastern {
+        fn edt() -> FixedOffset {
+            FixedOffset::west(4 * 60 * 60)
+        }
+        fn est() -> FixedOffset {
+            FixedOffset::west(5 * 60 * 60)
+        }
+    }
+
+    impl TimeZone for Eastern {
+        type Offset = FixedOffset;
+        fn from_offset(_: &FixedOffset) -> Self {
+            Eastern
+        }
+        fn offset_from_local_date(&self, local: &NaiveDate) -> chrono::LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(12, 0, 0))
+        }
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> chrono::LocalResult<FixedOffset> {
+            let spring = NaiveDate::from_ymd(2021, 3, 14).and_hms(2, 0, 0);
+            let fall = NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 0, 0);
+            if local < &spring {
+                chrono::LocalResult::Single(Eastern::est())
+            } else if local < &(spring + Duration::hours(1)) {
+                chrono::LocalResult::None
+            } else if local < &fall {
+                chrono::LocalResult::Single(Eastern::edt())
+            } else if local < &(fall + Duration::hours(1)) {
+                chrono::LocalResult::Ambiguous(Eastern::edt(), Eastern::est())
+            } else {
+                chrono::LocalResult::Single(Eastern::est())
+            }
+        }
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms(12, 0, 0))
+        }
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let spring = NaiveDate::from_ymd(2021, 3, 14).and_hms(7, 0, 0);
+            let fall = NaiveDate::from_ymd(2021, 11, 7).and_hms(6, 0, 0);
+            if utc >= &spring && utc < &fall {
+                Eastern::edt()
+            } else {
+                Eastern::est()
+            }
+        }
+    }
+
+    #[test]
+    fn test_spring_forward() {
+        let (conf_path, conf) = test_configuration("test_spring_forward");
+        let start = NaiveDate::from_ymd(2021, 3, 13).and_hms(22, 0, 0);
+        let end = NaiveDate::from_ymd(2021, 3, 14).and_hms(4, 0, 0);
+        assert_eq!(
+            5 * 60 * 60,
+            elapsed_seconds_in(&Eastern, &start, &end),
+            "an hour is lost"
+        );
+        let ordinary = NaiveDate::from_ymd(2021, 3, 20).and_hms(22, 0, 0);
+        assert_eq!(
+            6 * 60 * 60,
+            elapsed_seconds_in(&Eastern, &ordinary, &(ordinary + Duration::hours(6))),
+            "ordinary days are unaffected"
+        );
+        let mut event = Event::coin("overnight".to_owned(), vec![]);
+        event.start = start;
+        event.end = Some(end);
+        assert_eq!(5.0 * 60.0 * 60.0, event.duration_in(&Eastern, &end));
+        let events = Event::gather_by_day(vec![event], &end, &conf);
+        assert_eq!(2, events.len(), "split at midnight");
+        assert_eq!(2.0 * 60.0 * 60.0, events[0].duration_in(&Eastern, &end));
+        assert_eq!(3.0 * 60.0 * 60.0, events[1].duration_in(&Eastern, &end));
+        // a time skipped by the transition is treated as if the clocks had not yet changed
+        let skipped = NaiveDate::from_ymd(2021, 3, 14).and_hms(2, 30, 0);
+        assert_eq!(
+            60 * 60,
+            elapsed_seconds_in(&Eastern, &start.date().succ().and_hms(1, 30, 0), &skipped)
+        );
+        // projection
+        let now = NaiveDate::from_ymd(2021, 3, 14).and_hms(1, 0, 0);
+        assert_eq!(
+            NaiveDate::from_ymd(2021, 3, 14).and_hms(4, 0, 0),
+            advance_by_in(&Eastern, &now, 2 * 60 * 60),
+            "two hours after 1 am is 4 am"
+        );
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_fall_back() {
+        let (conf_path, conf) = test_configuration("test_fall_back");
+        let start = NaiveDate::from_ymd(2021, 11, 6).and_hms(22, 0, 0);
+        let end = NaiveDate::from_ymd(2021, 11, 7).and_hms(4, 0, 0);
+        assert_eq!(
+            7 * 60 * 60,
+            elapsed_seconds_in(&Eastern, &start, &end),
+            "an hour is gained"
+        );
+        let mut event = Event::coin("overnight".to_owned(), vec![]);
+        event.start = start;
+        event.end = Some(end);
+        assert_eq!(7.0 * 60.0 * 60.0, event.duration_in(&Eastern, &end));
+        let events = Event::gather_by_day(vec![event], &end, &conf);
+        assert_eq!(2, events.len(), "split at midnight");
+        assert_eq!(2.0 * 60.0 * 60.0, events[0].duration_in(&Eastern, &end));
+        assert_eq!(5.0 * 60.0 * 60.0, events[1].duration_in(&Eastern, &end));
+        // an event ending in the repeated hour
+        let first = NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 30, 0);
+        let second = NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 15, 0);
+        assert_eq!(
+            45 * 60,
+            elapsed_seconds_in(&Eastern, &first, &second),
+            "the end falls after the clocks changed"
+        );
+        // projection
+        let now = NaiveDate::from_ymd(2021, 11, 7).and_hms(0, 30, 0);
+        assert_eq!(
+            NaiveDate::from_ymd(2021, 11, 7).and_hms(1, 30, 0),
+            advance_by_in(&Eastern, &now, 2 * 60 * 60),
+            "two hours after 12:30 am is 1:30 am"
+        );
+        cleanup(&[&conf_path]);
+    }
+
+    #[test]
+    fn test_done() {
+        match parse_line("2019 12 1 16 3 30:DONE", 0) {
+            Item::Done(Done(time), _) => {
+                assert_eq!(2019, time.year());
+                assert_eq!(12, time.month());
+                assert_eq!(1, time.day());
+                assert_eq!(16, time.hour());
+                assert_eq!(3, time.minute());
+                assert_eq!(30, time.second());
+            }
+            _ => assert!(false, "failed to parse a DONE line"),
+        };
+        match parse_line(" 2019  12   1  16  3  30 :DONE", 0) {
+            Item::Done(Done(time), _) => {
+                assert_eq!(2019, time.year(), "space doesn't matter");
+                assert_eq!(12, time.month(), "space doesn't matter");
+                assert_eq!(1, time.day(), "space doesn't matter");
+                assert_eq!(16, time.hour(), "space doesn't matter");
+                assert_eq!(3, time.minute(), "space doesn't matter");
+                assert_eq!(30, time.second(), "space doesn't matter");
+            }
+            _ => assert!(false, "failed to parse a DONE line"),
+        };
+    }
+
+    #[test]
+    fn test_tag_whitespace_handling() {
+        let e = Event::coin(
+            String::from("foo"),
+            vec![String::from("foo bar"), String::from("baz   plugh")],
+        );
+        match parse_line(e.to_line().as_str(), 0) {
+            Item::Event(Event { tags, .. }, _) => {
+                assert_eq!(2, tags.len());
+                assert!(tags.contains(&String::from("foo bar")));
+                assert!(tags.contains(&String::from("baz plugh")));
+            }
+            _ => assert!(false, "failed to parse line as an event"),
+        }
+    }
+
+    #[test]
+    fn test_zero_padding() {
+        match parse_line("2019 12 01 16 03 30:DONE", 0) {
+            Item::Done(Done(time), _) => {
+                assert_eq!(1, time.day());
+                assert_eq!(3, time.minute());
+            }
+            _ => assert!(false, "failed to parse a DONE line"),
+        };
+    }
+
+    #[test]
+    fn test_comment() {
+        let success = match parse_line("#foo", 0) {
+            Item::Comment(_) => true,
+            _ => false,
+        };
+        assert!(success, "recognized '#foo' as a comment line");
+        let success = match parse_line("   #foo", 0) {
+            Item::Comment(_) => true,
+            _ => false,
+        };
+        assert!(success, "comments can have leading space");
+    }
+
+    #[test]
+    fn test_error() {
+        let success = match parse_line("foo", 0) {
+            Item::Error(_, _) => true,
+            _ => false,
+        };
+        assert!(success, "recognized 'foo' as a malformed log line");
+    }
+
+    #[test]
+    fn test_blank() {
+        let success = match parse_line("", 0) {
+            Item::Blank(_) => true,
+            _ => false,
+        };
+        assert!(success, "recognized an empty line as a blank");
+        let success = match parse_line("     ", 0) {
+            Item::Blank(_) => true,
+            _ => false,
+        };
+        assert!(success, "recognized a whitespace line as a blank");
+    }
+
+    #[test]
+    fn test_builders() {
+        let start = NaiveDate::from_ymd(2020, 3, 4).and_hms(8, 0, 0);
+        let event = Event::builder()
+            .start(start)
+            .end(start + Duration::hours(1))
+            .description("fixing the parser")
+            .tags(&["xyzzy", "plugh", "xyzzy"])
+            .build()
+            .unwrap();
+        assert_eq!(vec!["plugh", "xyzzy"], event.tags);
+        assert_eq!(Some(start + Duration::hours(1)), event.end);
+        assert!(Event::builder()
+            .start(start)
+            .end(start - Duration::hours(1))
+            .build()
+            .is_err());
+        assert!(Event::builder().description("foo\nbar").build().is_err());
+        assert!(Note::builder().tag(" ").build().is_err());
+        let note = Note::builder()
+            .time(start)
+            .description("remember the milk")
+            .author(Some(String::from("Bob")))
+            .build()
+            .unwrap();
+        assert_eq!(start, note.time);
+        assert_eq!(Some(String::from("Bob")), note.author);
+    }
+
+    #[test]
+    fn stack_overflow_regression() {
+        let (items, path) = random_log(23, vec![Need::E, Need::E], "stack_overflow_regression");
+        let events = closed_events(items);
+        assert!(events.len() > 1, "found more than one event");
+        let (conf_path, conf) = test_configuration("stack_overflow_regression");
+        let mut log_reader =
+            LogController::new(Some(PathBuf::from_str(&path).unwrap()), &conf).unwrap();
+        let e = events.first().unwrap();
+        let false_start = e.start - Duration::days(1);
+        let found_events = log_reader.events_in_range(&false_start, e.end.as_ref().unwrap());
+        assert_eq!(1, found_events.len(), "found one event");
+        assert_eq!(e.start, found_events[0].start, "same start");
+        assert_eq!(e.end, found_events[0].end, "same end");
+        assert_eq!(e.tags, found_events[0].tags, "same tags");
+        assert_eq!(
+            e.description, found_events[0].description,
+            "same description"
+        );
+        cleanup(&[&path, &conf_path]);
+    }
+}
+
+// everything you could find in a stream of lines from a log
+#[derive(Debug, Clone)]
 pub enum Item {
     Event(Event, usize),
     Note(Note, usize),
@@ -1576,9 +2585,29 @@ impl PartialOrd for Item {
     }
 }
 
+// times bearing a UTC offset are converted into local time
 pub fn parse_timestamp(timestamp: &str) -> Result<NaiveDateTime, String> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"\d+").unwrap();
+        static ref OFFSET: Regex = Regex::new(r"([+-])(\d{2})(\d{2})\s*\z").unwrap();
+    }
+    if let Some(captures) = OFFSET.captures(timestamp) {
+        let hours = captures[2].parse::<i32>().unwrap();
+        let minutes = captures[3].parse::<i32>().unwrap();
+        if hours > 23 || minutes > 59 {
+            return Err(format!("bad UTC offset: {}", &captures[0].trim()));
+        }
+        let seconds = (hours * 60 + minutes) * 60;
+        let offset = if &captures[1] == "-" {
+            FixedOffset::west(seconds)
+        } else {
+            FixedOffset::east(seconds)
+        };
+        let time = parse_timestamp(&timestamp[0..captures.get(0).unwrap().start()])?;
+        return match offset.from_local_datetime(&time).single() {
+            Some(t) => Ok(t.with_timezone(&Local).naive_local()),
+            None => Err(String::from("impossible time")),
+        };
     }
     let numbers: Vec<_> = RE.find_iter(timestamp).map(|m| m.as_str()).collect();
     // at this point the log lines grammar ensures all the parsing will be fine
@@ -1627,6 +2656,75 @@ pub fn timestamp(ts: &NaiveDateTime) -> String {
     )
 }
 
+// the local UTC offset at the given local time in the form +hhmm
+pub fn utc_offset(ts: &NaiveDateTime) -> String {
+    let seconds = match Local.from_local_datetime(ts).earliest() {
+        Some(t) => t.offset().fix().local_minus_utc(),
+        // a time skipped by a daylight saving transition; use the offset of the hour before
+        None => Local
+            .from_local_datetime(&(*ts - Duration::hours(1)))
+            .earliest()
+            .map(|t| t.offset().fix().local_minus_utc())
+            .unwrap_or(0),
+    };
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+// the seconds that actually elapse between two naive local times, taking daylight saving
+// transitions into account
+pub fn elapsed_seconds(start: &NaiveDateTime, end: &NaiveDateTime) -> i64 {
+    elapsed_seconds_in(&Local, start, end)
+}
+
+// elapsed_seconds in an arbitrary time zone; this facilitates testing
+pub fn elapsed_seconds_in<Tz: TimeZone>(
+    tz: &Tz,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+) -> i64 {
+    match (to_instant(tz, start), to_instant(tz, end)) {
+        (Some(s), Some(e)) => {
+            let delta = e.timestamp() - s.timestamp();
+            if delta < 0 {
+                // the clocks fell back and the end is in the repeated hour
+                if let Some(e) = tz.from_local_datetime(end).latest() {
+                    return e.timestamp() - s.timestamp();
+                }
+            }
+            delta
+        }
+        _ => end.timestamp() - start.timestamp(),
+    }
+}
+
+// the naive local time the given number of seconds after the given naive local time
+pub fn advance_by(time: &NaiveDateTime, seconds: i64) -> NaiveDateTime {
+    advance_by_in(&Local, time, seconds)
+}
+
+// advance_by in an arbitrary time zone
+pub fn advance_by_in<Tz: TimeZone>(tz: &Tz, time: &NaiveDateTime, seconds: i64) -> NaiveDateTime {
+    match to_instant(tz, time) {
+        Some(t) => (t + Duration::seconds(seconds)).naive_local(),
+        None => *time + Duration::seconds(seconds),
+    }
+}
+
+// fix a naive local time to a moment; when clocks fall back and a time occurs twice we take the
+// first occurrence; times skipped when clocks spring forward are treated as if the clocks had
+// not yet changed
+fn to_instant<Tz: TimeZone>(tz: &Tz, time: &NaiveDateTime) -> Option<chrono::DateTime<Tz>> {
+    match tz.from_local_datetime(time).earliest() {
+        Some(t) => Some(t),
+        None => tz
+            .from_local_datetime(&(*time - Duration::hours(1)))
+            .earliest()
+            .map(|t| t + Duration::hours(1)),
+    }
+}
+
 // converts a tag string in the log into a deduped, unescaped set of tags
 pub fn parse_tags(tags: &str) -> Vec<String> {
     let mut parsed = vec![];
@@ -1661,6 +2759,71 @@ pub fn parse_tags(tags: &str) -> Vec<String> {
     parsed
 }
 
+// converts the author portion of a log line, including the initial @, into an unescaped name
+fn parse_author(author: &str) -> String {
+    let mut parsed = String::with_capacity(author.len());
+    let mut escaped = false;
+    for c in author[1..].trim_end().chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else {
+            escaped = false;
+            parsed.push(c);
+        }
+    }
+    parsed
+}
+
+// convert an author, if any, back into a part of a log string
+fn author(author: &Option<String>) -> String {
+    let mut s = String::new();
+    if let Some(author) = author {
+        s += " @";
+        for c in author.chars() {
+            match c {
+                ':' | '\\' | '<' => s.push('\\'),
+                _ => (),
+            }
+            s.push(c);
+        }
+    }
+    s
+}
+
+// convert an estimate, if any, into a part of a log string, in hours, minutes, and seconds
+fn estimate(estimate: &Option<i64>) -> String {
+    let mut s = String::new();
+    if let Some(seconds) = estimate {
+        s += " ~";
+        let (h, m, sec) = (seconds / (60 * 60), seconds % (60 * 60) / 60, seconds % 60);
+        for (n, unit) in [(h, 'h'), (m, 'm'), (sec, 's')].iter() {
+            if *n > 0 {
+                s += &format!("{}{}", n, unit);
+            }
+        }
+        if *seconds == 0 {
+            s += "0m";
+        }
+    }
+    s
+}
+
+fn estimate_json(estimate: &Option<i64>) -> String {
+    if let Some(seconds) = estimate {
+        format!(r#""estimate":{},"#, *seconds as f32 / (60.0 * 60.0))
+    } else {
+        String::new()
+    }
+}
+
+fn author_json(author: &Option<String>) -> String {
+    if let Some(author) = author {
+        format!(r#""author":{},"#, serde_json::to_string(author).unwrap())
+    } else {
+        String::new()
+    }
+}
+
 // convert tags back into a part of a log string
 pub fn tags(tags: &Vec<String>) -> String {
     let mut v = tags.clone();
@@ -1702,14 +2865,20 @@ pub struct Event {
     pub tags: Vec<String>,
     pub vacation: bool,
     pub vacation_type: Option<String>,
+    pub holiday: bool, // whether the vacation time is from a repeating vacation record
+    pub author: Option<String>,
+    pub estimate: Option<i64>, // the seconds the event was expected to take
 }
 
 impl Event {
+    pub fn builder() -> EventBuilder {
+        EventBuilder::default()
+    }
     pub fn coin(description: String, mut tags: Vec<String>) -> Event {
         tags.sort_unstable();
         tags.dedup();
         Event {
-            start: Local::now().naive_local(),
+            start: now(),
             start_overlap: false,
             end: None,
             end_overlap: false,
@@ -1717,6 +2886,9 @@ impl Event {
             tags: tags,
             vacation: false,
             vacation_type: None,
+            holiday: false,
+            author: None,
+            estimate: None,
         }
     }
     fn bounded_time(self, end: Option<NaiveDateTime>) -> Self {
@@ -1729,6 +2901,9 @@ impl Event {
             tags: self.tags,
             vacation: self.vacation,
             vacation_type: self.vacation_type,
+            holiday: self.holiday,
+            author: self.author,
+            estimate: self.estimate,
         }
     }
     pub fn ongoing(&self) -> bool {
@@ -1737,8 +2912,12 @@ impl Event {
     // the duration of the task in seconds
     // the second parameter is necessary for ongoing tasks
     pub fn duration(&self, now: &NaiveDateTime) -> f32 {
+        self.duration_in(&Local, now)
+    }
+    // the duration in an arbitrary time zone
+    pub fn duration_in<Tz: TimeZone>(&self, tz: &Tz, now: &NaiveDateTime) -> f32 {
         let end = self.end.as_ref().unwrap_or(now);
-        (end.timestamp() - self.start.timestamp()) as f32
+        elapsed_seconds_in(tz, &self.start, end) as f32
     }
     // split an event into two at a time boundary
     fn split(self, time: NaiveDateTime) -> (Self, Self) {
@@ -1749,77 +2928,123 @@ impl Event {
         start.end_overlap = true;
         start.end = Some(time.clone());
         end.start = time;
-        end.end_overlap = true;
+        end.start_overlap = true;
         (start, end)
     }
     // take a vector of events and convert them into sets not overlapping by day
-    pub fn gather_by_day(events: Vec<Event>, end_date: &NaiveDateTime) -> Vec<Event> {
+    // days are split at the configured day boundary; even where a daylight saving transition
+    // skips or repeats this time the pieces' durations, computed via elapsed_seconds, sum to the whole
+    pub fn gather_by_day(
+        events: Vec<Event>,
+        end_date: &NaiveDateTime,
+        conf: &Configuration,
+    ) -> Vec<Event> {
+        Self::gather_by_day_streaming(events, end_date, conf).collect()
+    }
+    // like gather_by_day, but dividing the events as they are needed
+    pub fn gather_by_day_streaming<'a, I>(
+        events: I,
+        end_date: &NaiveDateTime,
+        conf: &'a Configuration,
+    ) -> impl Iterator<Item = Event> + 'a
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        let now = current_time(); // we assume there are no future events in the log
+        let end_date = if now < *end_date { now } else { *end_date };
+        events
+            .into_iter()
+            .take_while(move |e| e.start < end_date)
+            .flat_map(move |mut e| {
+                let mut pieces = vec![];
+                e.auto_close(&end_date, conf);
+                loop {
+                    let split_date =
+                        conf.start_of_day(&(conf.logical_date(&e.start) + Duration::days(1)));
+                    if e.end.unwrap_or(end_date) <= split_date {
+                        pieces.push(e);
+                        break;
+                    }
+                    let (e1, e2) = e.split(split_date);
+                    e = e2;
+                    pieces.push(e1);
+                }
+                pieces
+            })
+    }
+    // removes the given periods from the events, trimming or dividing those that overlap them
+    pub fn without_periods(
+        events: Vec<Event>,
+        periods: &[(NaiveDateTime, NaiveDateTime)],
+        now: &NaiveDateTime,
+    ) -> Vec<Event> {
         let mut ret = vec![];
-        let mut end_date = end_date;
-        let now = Local::now().naive_local(); // we assume there are no future events in the log
-        if &now < &end_date {
-            end_date = &now;
-        }
-        for mut e in events {
-            if &e.start >= end_date {
-                break;
-            }
-            loop {
-                match e.end.as_ref() {
-                    Some(&time) => {
-                        if time.date() == e.start.date() {
-                            ret.push(e);
-                            break;
-                        }
-                        let split_date = e.start.date().and_hms(0, 0, 0) + Duration::days(1);
-                        let (e1, e2) = e.split(split_date);
-                        e = e2;
-                        ret.push(e1);
+        for e in events {
+            let mut pieces = vec![e];
+            for (start, end) in periods {
+                let mut remaining = vec![];
+                for p in pieces {
+                    let p_end = p.end.unwrap_or(*now);
+                    if p_end <= *start || p.start >= *end {
+                        remaining.push(p);
+                        continue;
                     }
-                    None => {
-                        if e.start.date() == end_date.date() {
-                            ret.push(e);
-                            break;
-                        } else {
-                            let split_date = e.start.date().and_hms(0, 0, 0) + Duration::days(1);
-                            let (e1, e2) = e.split(split_date);
-                            e = e2;
-                            ret.push(e1);
-                        }
+                    if p.start < *start {
+                        let mut before = p.clone();
+                        before.end = Some(*start);
+                        remaining.push(before);
+                    }
+                    if p_end > *end {
+                        let mut after = p;
+                        after.start = *end;
+                        remaining.push(after);
                     }
                 }
+                pieces = remaining;
             }
+            ret.extend(pieces);
         }
         ret
     }
-    fn mergeable(&self, other: &Self) -> bool {
-        if self.end_overlap {
+    // if the event runs past the day boundary and there is an auto-close policy, end it when
+    // the policy says it ended
+    pub fn auto_close(&mut self, now: &NaiveDateTime, conf: &Configuration) -> bool {
+        let next_day = conf.start_of_day(&(conf.logical_date(&self.start) + Duration::days(1)));
+        let end = self.end.unwrap_or(*now);
+        if end <= next_day {
+            return false;
+        }
+        match conf.auto_close_time(&self.start) {
+            Some(time) if time < end => {
+                self.end = Some(time);
+                true
+            }
+            _ => false,
+        }
+    }
+    fn mergeable(&self, other: &Self, conf: &Configuration) -> bool {
+        if self.start_overlap || self.end_overlap {
             // keep overlapped events separate to facilitate display
             return false;
         }
         if let Some(t) = self.end {
-            t.day() == self.start.day() && // other isn't in a different day -- don't merge across day boundaries
-            t == other.start  && self.tags == other.tags
+            conf.logical_date(&t) == conf.logical_date(&self.start) && // other isn't in a different day -- don't merge across day boundaries
+            t == other.start  && self.tags == other.tags && self.author == other.author &&
+            other.estimate.is_none() // an estimate belongs to the event begun with it
         } else {
             false
         }
     }
     // this event was split off a larger one that overlapped a day boundary
-    // it is the second part
+    // it is not the first part
     pub fn overlaps_start(&self) -> bool {
-        self.end_overlap && self.start.hour() == 0
+        self.start_overlap
     }
     // this event was split off a larger one that overlapped a day boundary
-    // it is the first part
+    // it is not the last part
     pub fn overlaps_end(&self) -> bool {
-        if !self.end_overlap {
-            return false;
-        }
-        if let Some(t) = self.end {
-            t.day() != self.start.day()
-        } else {
-            false
-        }
+        self.end_overlap
     }
     fn merge(&mut self, other: Self) {
         self.description = self.description.clone() + "; " + &other.description;
@@ -1827,22 +3052,40 @@ impl Event {
         self.end_overlap = other.end_overlap;
     }
     // like gather_by_day, but it also merges similar events -- similar events must have the same date and tags
-    pub fn gather_by_day_and_merge(events: Vec<Event>, end_date: &NaiveDateTime) -> Vec<Event> {
-        let mut events = Self::gather_by_day(events, end_date);
-        if events.is_empty() {
-            return events;
-        }
-        let mut ret = vec![];
-        ret.push(events.remove(0));
-        for e in events {
-            let i = ret.len() - 1;
-            if ret[i].mergeable(&e) {
-                ret[i].merge(e);
-            } else {
-                ret.push(e);
+    pub fn gather_by_day_and_merge(
+        events: Vec<Event>,
+        end_date: &NaiveDateTime,
+        conf: &Configuration,
+    ) -> Vec<Event> {
+        Self::merge_contiguous(Self::gather_by_day(events, end_date, conf), conf)
+    }
+    // merges contiguous events with the same date and tags
+    pub fn merge_contiguous(events: Vec<Event>, conf: &Configuration) -> Vec<Event> {
+        Self::merge_contiguous_streaming(events, conf).collect()
+    }
+    // like merge_contiguous, but merging the events as they are needed
+    pub fn merge_contiguous_streaming<'a, I>(
+        events: I,
+        conf: &'a Configuration,
+    ) -> impl Iterator<Item = Event> + 'a
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        let mut events = events.into_iter();
+        let mut next = events.next();
+        std::iter::from_fn(move || {
+            let mut merged = next.take()?;
+            for e in events.by_ref() {
+                if merged.mergeable(&e, conf) {
+                    merged.merge(e);
+                } else {
+                    next = Some(e);
+                    break;
+                }
             }
-        }
-        ret
+            Some(merged)
+        })
     }
     pub fn to_json(&self, now: &NaiveDateTime, conf: &Configuration) -> String {
         let end = if let Some(time) = self.end {
@@ -1851,28 +3094,57 @@ impl Event {
             "null".to_owned()
         };
         format!(
-            r#"{{"type":"Event","start":{},"end":{},"duration":{},{}"tags":{},"description":{}}}"#,
+            r#"{{"type":"Event","start":{},"end":{},"duration":{},"seconds":{},{}{}{}"tags":{},"description":{}}}"#,
             serde_json::to_string(&format!("{}", self.start)).unwrap(),
             end,
-            duration_string(self.duration(now), conf),
+            hours_string(self.duration(now), conf),
+            self.duration(now).round() as i64,
             if let Some(t) = &self.vacation_type {
                 format!("\"vacation\":\"{}\",", if t == "" { "ordinary" } else { t })
             } else {
                 "".to_owned()
             },
+            author_json(&self.author),
+            estimate_json(&self.estimate),
             serde_json::to_string(&self.tags).unwrap(),
             serde_json::to_string(&self.description).unwrap()
         )
     }
+    // the event as a line of tab-separated values with the columns of export --unified --csv
+    pub fn to_tsv(&self, now: &NaiveDateTime, conf: &Configuration) -> String {
+        tsv_row(&[
+            "Event",
+            &self.start.to_string(),
+            &self.end.map(|t| t.to_string()).unwrap_or_default(),
+            &duration_string(self.duration(now), conf),
+            match &self.vacation_type {
+                Some(t) if t.is_empty() => "ordinary",
+                Some(t) => t,
+                None => "",
+            },
+            self.author.as_deref().unwrap_or_default(),
+            &self.tags.join(", "),
+            &self.description,
+        ])
+    }
 }
 
 impl Searchable for Event {
     fn text(&self) -> &str {
         &self.description
     }
+    fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
     fn tags(&self) -> Vec<&str> {
         self.tags.iter().map(|s| s.as_str()).collect()
     }
+    fn seconds(&self) -> Option<i64> {
+        Some(elapsed_seconds(
+            &self.start,
+            &self.end.unwrap_or_else(current_time),
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1880,43 +3152,181 @@ pub struct Note {
     pub time: NaiveDateTime,
     pub description: String,
     pub tags: Vec<String>,
+    pub author: Option<String>,
 }
 
 impl Note {
+    pub fn builder() -> NoteBuilder {
+        NoteBuilder::default()
+    }
     pub fn coin(description: String, mut tags: Vec<String>) -> Note {
         tags.sort_unstable();
         tags.dedup();
         Note {
-            time: Local::now().naive_local(),
+            time: now(),
             description: description,
             tags: tags,
+            author: None,
         }
     }
     pub fn to_json(&self, _now: &NaiveDateTime, _conf: &Configuration) -> String {
         format!(
-            r#"{{"type":"Note","time":{},"tags":{},"description":{}}}"#,
+            r#"{{"type":"Note","time":{},{}"tags":{},"description":{}}}"#,
             serde_json::to_string(&format!("{}", self.time)).unwrap(),
+            author_json(&self.author),
             serde_json::to_string(&self.tags).unwrap(),
             serde_json::to_string(&self.description).unwrap()
         )
     }
+    pub fn to_tsv(&self, _now: &NaiveDateTime, _conf: &Configuration) -> String {
+        tsv_row(&[
+            "Note",
+            &self.time.to_string(),
+            "",
+            "",
+            "",
+            self.author.as_deref().unwrap_or_default(),
+            &self.tags.join(", "),
+            &self.description,
+        ])
+    }
 }
 
 impl Searchable for Note {
     fn text(&self) -> &str {
         &self.description
     }
+    fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
     fn tags(&self) -> Vec<&str> {
         self.tags.iter().map(|s| s.as_str()).collect()
     }
 }
 
+// the present, as fixed by --now if it is, to the second, which is all the log records
+fn now() -> NaiveDateTime {
+    current_time().with_nanosecond(0).unwrap()
+}
+
+// the problem, if any, with what is to go into a log line
+pub(crate) fn check_line_parts(description: &str, tags: &[String]) -> Result<(), String> {
+    if description.contains(['\n', '\r']) {
+        return Err(String::from("a description cannot contain a line break"));
+    }
+    for tag in tags {
+        if tag.trim().is_empty() {
+            return Err(format!("tag {:?} needs some non-whitespace character", tag));
+        } else if tag.contains(['\n', '\r']) {
+            return Err(format!("tag {:?} cannot contain a line break", tag));
+        }
+    }
+    Ok(())
+}
+
+// builds an event, by default one beginning now, and makes sure it can go in the log:
+//
+//   let event = Event::builder().start(start).tags(&["plugh"]).description("fixing the parser").build()?;
+#[derive(Default)]
+pub struct EventBuilder {
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    description: String,
+    tags: Vec<String>,
+    author: Option<String>,
+    estimate: Option<i64>,
+}
+
+impl EventBuilder {
+    pub fn start(mut self, start: NaiveDateTime) -> Self {
+        self.start = Some(start);
+        self
+    }
+    pub fn end(mut self, end: NaiveDateTime) -> Self {
+        self.end = Some(end);
+        self
+    }
+    pub fn description<T: ToString>(mut self, description: T) -> Self {
+        self.description = description.to_string();
+        self
+    }
+    pub fn tags<T: ToString>(mut self, tags: &[T]) -> Self {
+        self.tags.extend(tags.iter().map(|t| t.to_string()));
+        self
+    }
+    pub fn tag<T: ToString>(mut self, tag: T) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+    pub fn author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+    // the seconds the event is expected to take
+    pub fn estimate(mut self, estimate: Option<i64>) -> Self {
+        self.estimate = estimate;
+        self
+    }
+    pub fn build(self) -> Result<Event, String> {
+        check_line_parts(&self.description, &self.tags)?;
+        let start = self.start.unwrap_or_else(now);
+        if self.end.is_some_and(|end| end < start) {
+            return Err(String::from("an event cannot end before it begins"));
+        }
+        let mut event = Event::coin(self.description, self.tags);
+        event.start = start;
+        event.end = self.end;
+        event.author = self.author;
+        event.estimate = self.estimate;
+        Ok(event)
+    }
+}
+
+// builds a note, by default one taken now, and makes sure it can go in the log
+#[derive(Default)]
+pub struct NoteBuilder {
+    time: Option<NaiveDateTime>,
+    description: String,
+    tags: Vec<String>,
+    author: Option<String>,
+}
+
+impl NoteBuilder {
+    pub fn time(mut self, time: NaiveDateTime) -> Self {
+        self.time = Some(time);
+        self
+    }
+    pub fn description<T: ToString>(mut self, description: T) -> Self {
+        self.description = description.to_string();
+        self
+    }
+    pub fn tags<T: ToString>(mut self, tags: &[T]) -> Self {
+        self.tags.extend(tags.iter().map(|t| t.to_string()));
+        self
+    }
+    pub fn tag<T: ToString>(mut self, tag: T) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+    pub fn author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+    pub fn build(self) -> Result<Note, String> {
+        check_line_parts(&self.description, &self.tags)?;
+        let mut note = Note::coin(self.description, self.tags);
+        note.time = self.time.unwrap_or_else(now);
+        note.author = self.author;
+        Ok(note)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Done(pub NaiveDateTime);
 
 impl Done {
     pub fn coin() -> Done {
-        Done(Local::now().naive_local())
+        Done(now())
     }
 }
 
@@ -1927,9 +3337,35 @@ pub enum Direction {
 
 pub trait LogLine {
     fn to_line(&self) -> String;
+    fn time(&self) -> &NaiveDateTime;
+    // the log line with the local UTC offset following the timestamp
+    fn to_zoned_line(&self) -> String {
+        let ts = timestamp(self.time());
+        let line = self.to_line();
+        format!("{} {}{}", ts, utc_offset(self.time()), &line[ts.len()..])
+    }
+    // the log line without the author, if any
+    fn to_anonymous_line(&self) -> String {
+        self.to_line()
+    }
+}
+
+// the line to write to the log for an item; the Perl client can read neither UTC offsets nor
+// authors, so in Perl-compatible mode it gets neither
+pub fn log_line<T: LogLine>(item: &T, conf: &Configuration) -> String {
+    if conf.perl_compatible {
+        item.to_anonymous_line()
+    } else if conf.utc_offsets {
+        item.to_zoned_line()
+    } else {
+        item.to_line()
+    }
 }
 
 impl LogLine for Done {
+    fn time(&self) -> &NaiveDateTime {
+        &self.0
+    }
     fn to_line(&self) -> String {
         let mut ts = timestamp(&self.0);
         ts += ":DONE";
@@ -1938,8 +3374,12 @@ impl LogLine for Done {
 }
 
 impl LogLine for Note {
+    fn time(&self) -> &NaiveDateTime {
+        &self.time
+    }
     fn to_line(&self) -> String {
         let mut ts = timestamp(&self.time);
+        ts += &author(&self.author);
         ts += "<NOTE>";
         let tags = tags(&self.tags);
         ts += &tags;
@@ -1947,11 +3387,23 @@ impl LogLine for Note {
         ts += &self.description;
         ts
     }
+    fn to_anonymous_line(&self) -> String {
+        Note {
+            author: None,
+            ..self.clone()
+        }
+        .to_line()
+    }
 }
 
 impl LogLine for Event {
+    fn time(&self) -> &NaiveDateTime {
+        &self.start
+    }
     fn to_line(&self) -> String {
         let mut ts = timestamp(&self.start);
+        ts += &estimate(&self.estimate);
+        ts += &author(&self.author);
         ts.push(':');
         let tags = tags(&self.tags);
         ts += &tags;
@@ -1959,11 +3411,25 @@ impl LogLine for Event {
         ts += &self.description;
         ts
     }
+    fn to_anonymous_line(&self) -> String {
+        Event {
+            author: None,
+            ..self.clone()
+        }
+        .to_line()
+    }
 }
 
 pub trait Searchable {
     fn tags(&self) -> Vec<&str>;
     fn text(&self) -> &str;
+    fn author(&self) -> Option<&str> {
+        None
+    }
+    // how long the thing lasted, if it has a duration
+    fn seconds(&self) -> Option<i64> {
+        None
+    }
 }
 
 pub struct Filter<'a> {
@@ -1973,46 +3439,97 @@ pub struct Filter<'a> {
     some_patterns: Option<RegexSet>,
     no_patterns: Option<RegexSet>,
     empty: bool,
+    authors: Option<Vec<&'a str>>,
+    fuzzy: Option<&'a str>,
+    longer_than: Option<i64>, // in seconds
+    shorter_than: Option<i64>,
+    expression: Option<FilterExpression>,
 }
 
 impl<'a> Filter<'a> {
+    pub fn builder() -> FilterBuilder<'a> {
+        FilterBuilder::default()
+    }
     pub fn dummy() -> Filter<'a> {
-        Filter {
-            all_tags: None,
-            no_tags: None,
-            some_tags: None,
-            some_patterns: None,
-            no_patterns: None,
-            empty: false,
-        }
+        Filter::builder().build().unwrap()
     }
+    #[cfg(feature = "cli")]
     pub fn new(matches: &'a ArgMatches) -> Filter<'a> {
-        let all_tags = matches
-            .values_of("tag")
-            .and_then(|values| Some(values.collect()));
-        let no_tags = matches
-            .values_of("tag-none")
-            .and_then(|values| Some(values.collect()));
-        let some_tags = matches
-            .values_of("tag-some")
-            .and_then(|values| Some(values.collect()));
-        let some_patterns = matches
-            .values_of("rx")
-            .and_then(|values| Some(RegexSet::new(values).unwrap()));
-        let no_patterns = matches
-            .values_of("rx-not")
-            .and_then(|values| Some(RegexSet::new(values).unwrap()));
-        let empty = matches.is_present("no-tags");
-        Filter {
-            all_tags,
-            no_tags,
-            some_tags,
-            some_patterns,
-            no_patterns,
-            empty,
+        let mut builder = Filter::builder()
+            .untagged(matches.is_present("no-tags"))
+            .case_insensitive(matches.is_present("rx-i"))
+            .whole_words(matches.is_present("rx-word"));
+        if let Some(values) = matches.values_of("tag") {
+            builder = builder.tags(&values.collect::<Vec<_>>());
         }
+        if let Some(values) = matches.values_of("tag-none") {
+            builder = builder.excluded_tags(&values.collect::<Vec<_>>());
+        }
+        if let Some(values) = matches.values_of("tag-some") {
+            builder = builder.some_tags(&values.collect::<Vec<_>>());
+        }
+        if let Some(values) = matches.values_of("rx") {
+            builder = builder.patterns(&values.collect::<Vec<_>>());
+        }
+        if let Some(values) = matches.values_of("rx-not") {
+            builder = builder.excluded_patterns(&values.collect::<Vec<_>>());
+        }
+        if let Some(values) = matches.values_of("author") {
+            builder = builder.authors(&values.collect::<Vec<_>>());
+        }
+        if let Some(fuzzy) = matches.value_of("fuzzy") {
+            builder = builder.fuzzy(fuzzy);
+        }
+        if let Some(seconds) = matches.value_of("longer-than").and_then(parse_duration) {
+            builder = builder.longer_than(seconds);
+        }
+        if let Some(seconds) = matches.value_of("shorter-than").and_then(parse_duration) {
+            builder = builder.shorter_than(seconds);
+        }
+        // the validators have already confirmed the patterns and expression are parsable
+        if let Some(expression) = matches.value_of("filter") {
+            builder = builder.expression(FilterExpression::parse(expression).unwrap());
+        }
+        builder.build().unwrap()
+    }
+    // the tags the filter tests for
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags = vec![];
+        let lists = [&self.all_tags, &self.some_tags, &self.no_tags];
+        for list in lists.iter().filter_map(|l| l.as_ref()) {
+            tags.extend(list.iter().copied());
+        }
+        if let Some(expression) = &self.expression {
+            tags.extend(expression.tags());
+        }
+        tags.sort_unstable();
+        tags.dedup();
+        tags
     }
     pub fn matches<T: Searchable>(&self, filterable: &T) -> bool {
+        if let Some(expression) = self.expression.as_ref() {
+            if !expression.matches(filterable) {
+                return false;
+            }
+        }
+        if let Some(authors) = self.authors.as_ref() {
+            match filterable.author() {
+                Some(author) if authors.contains(&author) => (),
+                _ => return false,
+            }
+        }
+        if let Some(fuzzy) = self.fuzzy {
+            if similarity(fuzzy, filterable.text()) < FUZZY_THRESHOLD {
+                return false;
+            }
+        }
+        if let Some(seconds) = filterable.seconds() {
+            if self.longer_than.is_some_and(|t| seconds <= t)
+                || self.shorter_than.is_some_and(|t| seconds >= t)
+            {
+                return false;
+            }
+        }
         let tags = filterable.tags();
         let text = filterable.text();
         if tags.is_empty() {
@@ -2078,3 +3595,355 @@ impl<'a> Filter<'a> {
         true
     }
 }
+
+// builds a filter without a command line; everything passes a filter with no tests:
+//
+//   let filter = Filter::builder().tags(&["acme"]).excluded_patterns(&["standup"]).build()?;
+#[derive(Default)]
+pub struct FilterBuilder<'a> {
+    all_tags: Option<Vec<&'a str>>,
+    no_tags: Option<Vec<&'a str>>,
+    some_tags: Option<Vec<&'a str>>,
+    some_patterns: Option<Vec<&'a str>>,
+    no_patterns: Option<Vec<&'a str>>,
+    case_insensitive: bool,
+    whole_words: bool,
+    empty: bool,
+    authors: Option<Vec<&'a str>>,
+    fuzzy: Option<&'a str>,
+    longer_than: Option<i64>,
+    shorter_than: Option<i64>,
+    expression: Option<FilterExpression>,
+}
+
+impl<'a> FilterBuilder<'a> {
+    // tags all of which must be present
+    pub fn tags(mut self, tags: &[&'a str]) -> Self {
+        self.all_tags.get_or_insert_with(Vec::new).extend(tags);
+        self
+    }
+    // tags none of which may be present
+    pub fn excluded_tags(mut self, tags: &[&'a str]) -> Self {
+        self.no_tags.get_or_insert_with(Vec::new).extend(tags);
+        self
+    }
+    // tags at least one of which must be present
+    pub fn some_tags(mut self, tags: &[&'a str]) -> Self {
+        self.some_tags.get_or_insert_with(Vec::new).extend(tags);
+        self
+    }
+    // regular expressions at least one of which must match the description
+    pub fn patterns(mut self, patterns: &[&'a str]) -> Self {
+        self.some_patterns
+            .get_or_insert_with(Vec::new)
+            .extend(patterns);
+        self
+    }
+    // regular expressions none of which may match the description
+    pub fn excluded_patterns(mut self, patterns: &[&'a str]) -> Self {
+        self.no_patterns
+            .get_or_insert_with(Vec::new)
+            .extend(patterns);
+        self
+    }
+    pub fn case_insensitive(mut self, insensitive: bool) -> Self {
+        self.case_insensitive = insensitive;
+        self
+    }
+    // the patterns must match whole words
+    pub fn whole_words(mut self, word: bool) -> Self {
+        self.whole_words = word;
+        self
+    }
+    // only things without tags pass
+    pub fn untagged(mut self, empty: bool) -> Self {
+        self.empty = empty;
+        self
+    }
+    pub fn authors(mut self, authors: &[&'a str]) -> Self {
+        self.authors.get_or_insert_with(Vec::new).extend(authors);
+        self
+    }
+    // text the description must resemble
+    pub fn fuzzy(mut self, text: &'a str) -> Self {
+        self.fuzzy = Some(text);
+        self
+    }
+    // the least duration, in seconds, an event may have
+    pub fn longer_than(mut self, seconds: i64) -> Self {
+        self.longer_than = Some(seconds);
+        self
+    }
+    // the greatest duration, in seconds, an event may have
+    pub fn shorter_than(mut self, seconds: i64) -> Self {
+        self.shorter_than = Some(seconds);
+        self
+    }
+    pub fn expression(mut self, expression: FilterExpression) -> Self {
+        self.expression = Some(expression);
+        self
+    }
+    pub fn build(self) -> Result<Filter<'a>, String> {
+        let (insensitive, word) = (self.case_insensitive, self.whole_words);
+        let set = |patterns: Option<Vec<&str>>| -> Result<Option<RegexSet>, String> {
+            match patterns {
+                None => Ok(None),
+                Some(patterns) => RegexSet::new(patterns.iter().map(|rx| {
+                    let rx = if word {
+                        format!(r"\b(?:{})\b", rx)
+                    } else {
+                        rx.to_string()
+                    };
+                    if insensitive {
+                        format!("(?i){}", rx)
+                    } else {
+                        rx
+                    }
+                }))
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            }
+        };
+        // an empty list is no test at all
+        let some = |list: Option<Vec<&'a str>>| list.filter(|l| !l.is_empty());
+        Ok(Filter {
+            all_tags: some(self.all_tags),
+            no_tags: some(self.no_tags),
+            some_tags: some(self.some_tags),
+            some_patterns: set(some(self.some_patterns))?,
+            no_patterns: set(some(self.no_patterns))?,
+            empty: self.empty,
+            authors: some(self.authors),
+            fuzzy: self.fuzzy,
+            longer_than: self.longer_than,
+            shorter_than: self.shorter_than,
+            expression: self.expression,
+        })
+    }
+}
+
+// a boolean combination of tests such as `(tag:acme OR tag:bigco) AND NOT rx:standup`
+//
+// NOT binds more tightly than AND, which binds more tightly than OR, and terms placed side by
+// side without an operator are ANDed; the operators are case-insensitive
+#[derive(Debug, Clone)]
+pub enum FilterExpression {
+    Tag(String),
+    Pattern(Regex),
+    Author(String),
+    Untagged,
+    Not(Box<FilterExpression>),
+    And(Vec<FilterExpression>),
+    Or(Vec<FilterExpression>),
+}
+
+impl FilterExpression {
+    pub fn parse(expression: &str) -> Result<FilterExpression, String> {
+        let tokens = FilterExpression::tokenize(expression)?;
+        if tokens.is_empty() {
+            return Err(String::from("the filter expression is empty"));
+        }
+        let mut tokens = tokens.into_iter().peekable();
+        let parsed = FilterExpression::parse_or(&mut tokens)?;
+        match tokens.next() {
+            None => Ok(parsed),
+            Some(t) => Err(format!("unexpected '{}' in filter expression", t)),
+        }
+    }
+    pub fn matches<T: Searchable>(&self, filterable: &T) -> bool {
+        match self {
+            FilterExpression::Tag(tag) => filterable.tags().contains(&tag.as_str()),
+            FilterExpression::Pattern(rx) => rx.is_match(filterable.text()),
+            FilterExpression::Author(author) => filterable.author() == Some(author.as_str()),
+            FilterExpression::Untagged => filterable.tags().is_empty(),
+            FilterExpression::Not(e) => !e.matches(filterable),
+            FilterExpression::And(es) => es.iter().all(|e| e.matches(filterable)),
+            FilterExpression::Or(es) => es.iter().any(|e| e.matches(filterable)),
+        }
+    }
+    // the tags the expression tests for
+    fn tags(&self) -> Vec<&str> {
+        match self {
+            FilterExpression::Tag(tag) => vec![tag.as_str()],
+            FilterExpression::Not(e) => e.tags(),
+            FilterExpression::And(es) | FilterExpression::Or(es) => {
+                es.iter().flat_map(|e| e.tags()).collect()
+            }
+            _ => vec![],
+        }
+    }
+    // splits the expression into parentheses, operators, and terms; a term's value may be
+    // quoted, and an unquoted value ends at whitespace or at a closing parenthesis it did not
+    // open, so `(tag:a OR rx:b(c))` works as expected
+    fn tokenize(expression: &str) -> Result<Vec<String>, String> {
+        let mut tokens = vec![];
+        let mut chars = expression.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '(' || c == ')' {
+                tokens.push(c.to_string());
+                chars.next();
+            } else {
+                let mut token = String::new();
+                let mut depth = 0;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || (c == ')' && depth == 0) {
+                        break;
+                    }
+                    chars.next();
+                    if (c == '"' || c == '\'') && token.ends_with(':') {
+                        // a quoted value runs to the matching quote
+                        loop {
+                            match chars.next() {
+                                Some(q) if q == c => break,
+                                Some(ch) => token.push(ch),
+                                None => {
+                                    return Err(format!(
+                                        "unterminated quotation in filter expression: {}{}",
+                                        c, token
+                                    ))
+                                }
+                            }
+                        }
+                        break;
+                    }
+                    if c == '(' {
+                        depth += 1;
+                    } else if c == ')' {
+                        depth -= 1;
+                    }
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+        Ok(tokens)
+    }
+    fn parse_or<I: Iterator<Item = String>>(
+        tokens: &mut std::iter::Peekable<I>,
+    ) -> Result<FilterExpression, String> {
+        let mut terms = vec![FilterExpression::parse_and(tokens)?];
+        while tokens.next_if(|t| t.eq_ignore_ascii_case("or")).is_some() {
+            terms.push(FilterExpression::parse_and(tokens)?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            FilterExpression::Or(terms)
+        })
+    }
+    fn parse_and<I: Iterator<Item = String>>(
+        tokens: &mut std::iter::Peekable<I>,
+    ) -> Result<FilterExpression, String> {
+        let mut terms = vec![FilterExpression::parse_not(tokens)?];
+        // AND is optional between terms
+        while tokens.next_if(|t| t.eq_ignore_ascii_case("and")).is_some()
+            || tokens
+                .peek()
+                .is_some_and(|t| t != ")" && !t.eq_ignore_ascii_case("or"))
+        {
+            terms.push(FilterExpression::parse_not(tokens)?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            FilterExpression::And(terms)
+        })
+    }
+    fn parse_not<I: Iterator<Item = String>>(
+        tokens: &mut std::iter::Peekable<I>,
+    ) -> Result<FilterExpression, String> {
+        if tokens.next_if(|t| t.eq_ignore_ascii_case("not")).is_some() {
+            return Ok(FilterExpression::Not(Box::new(
+                FilterExpression::parse_not(tokens)?,
+            )));
+        }
+        match tokens.next() {
+            None => Err(String::from("the filter expression ends unexpectedly")),
+            Some(t) if t == "(" => {
+                let e = FilterExpression::parse_or(tokens)?;
+                match tokens.next() {
+                    Some(t) if t == ")" => Ok(e),
+                    _ => Err(String::from("unmatched '(' in filter expression")),
+                }
+            }
+            Some(t) => FilterExpression::term(&t),
+        }
+    }
+    fn term(token: &str) -> Result<FilterExpression, String> {
+        if token.eq_ignore_ascii_case("untagged") {
+            return Ok(FilterExpression::Untagged);
+        }
+        let (kind, value) = match token.find(':') {
+            Some(i) => (&token[..i], &token[i + 1..]),
+            None => {
+                return Err(format!(
+                    "'{}' is not a filter term; terms look like tag:x, rx:x, author:x, or untagged",
+                    token
+                ))
+            }
+        };
+        if value.is_empty() {
+            return Err(format!("the filter term '{}' has no value", token));
+        }
+        match kind.to_ascii_lowercase().as_str() {
+            "tag" => Ok(FilterExpression::Tag(value.to_owned())),
+            "author" => Ok(FilterExpression::Author(value.to_owned())),
+            "rx" => Regex::new(value)
+                .map(FilterExpression::Pattern)
+                .map_err(|_| format!("'{}' cannot be parsed as a regular expression", value)),
+            _ => Err(format!(
+                "unknown filter term '{}'; terms look like tag:x, rx:x, author:x, or untagged",
+                kind
+            )),
+        }
+    }
+}
+
+// the known tags a tag not among them is probably a misspelling of, nearest first
+pub fn tag_suggestions<'a>(tag: &str, known: &'a BTreeSet<String>) -> Vec<&'a str> {
+    let lowercase = tag.to_lowercase();
+    let tolerance = (tag.chars().count() / 3).max(1);
+    let mut suggestions = known
+        .iter()
+        .map(|k| {
+            (
+                strsim::levenshtein(&lowercase, &k.to_lowercase()),
+                k.as_str(),
+            )
+        })
+        .filter(|(distance, _)| *distance <= tolerance)
+        .collect::<Vec<_>>();
+    suggestions.sort();
+    suggestions.into_iter().take(3).map(|(_, k)| k).collect()
+}
+
+// the similarity below which a text does not fuzzily match a query
+pub const FUZZY_THRESHOLD: f64 = 0.8;
+
+// how well a text matches a query, from 0 to 1, forgiving typos and differences of word order:
+// each word of the query is paired with the word of the text most like it, and the
+// similarities of these pairs are averaged
+pub fn similarity(query: &str, text: &str) -> f64 {
+    let words = |s: &str| {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+    };
+    let query = words(query);
+    let text = words(text);
+    if query.is_empty() || text.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = query
+        .iter()
+        .map(|q| {
+            text.iter()
+                .map(|t| strsim::jaro_winkler(q, t))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / query.len() as f64
+}
@@ -4,16 +4,20 @@ extern crate jobrog;
 
 use clap::{App, Arg};
 use jobrog::{
-    add, configure, done, edit, first, last, note, parse, resume, statistics, summary, tag,
-    truncate, util, vacation, when,
+    add, append, backup, batch, budget, calendar, cancel, configure, doctor, done, edit, export,
+    first, generate_docs, git_hook, import, import_calendar, last, lint, merge, migrate, note,
+    parse, period, periods, resume, serve, statistics, status, summary, switch, sync, tag,
+    truncate, util, vacation, when, zone,
 };
+use std::env;
 
 fn after_help() -> &'static str {
     "The 'job' executable allows one to maintain and view a log of daily activity."
 }
 
-fn main() {
-    let mut cli = App::new("job")
+// the command line interface, which generate-docs also describes
+fn cli() -> App<'static, 'static> {
+    let mut app = App::new("job")
         .version(crate_version!())
         .author(crate_authors!())
         .after_help(after_help())
@@ -28,7 +32,115 @@ fn main() {
                     "If you need or want to use a directory other than .joblog \
             in your home directory to store job log's log, vacation file, configuration \
             file, and so forth, specify this alternative directory with --directory. \
-            As with .joblog, if it does not exist it will be created as needed.",
+            As with .joblog, if it does not exist it will be created as needed. \
+            If you always use the same alternative directory you may instead set the \
+            JOBLOG_DIR or JOBROG_DIRECTORY environment variable. --directory takes \
+            precedence over either.",
+                ),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .short("j")
+                .global(true)
+                .help("Writes output as JSON")
+                .long_help(
+                    "Subcommands which report on the log -- summary, first, last, statistics, status, \
+            when, period, periods, budget, lint, doctor, tag, parse-time, backup --list, vacation \
+            --list, and configure --list -- and those which change it -- add, append, cancel, done, \
+            note, resume, switch, and git-hook -- write their output as JSON rather than text. Lists of events, notes, \
+            and vacations are written as line-delimited JSON, one object per line. Warnings and \
+            other messages still go to the standard error. See the README for the schemas.",
+                ),
+        )
+        .arg(
+            Arg::with_name("tsv")
+                .long("tsv")
+                .global(true)
+                .conflicts_with("json")
+                .help("Writes output as tab-separated values")
+                .long_help(
+                    "The summary, first, last, tag, and statistics subcommands write their output \
+            as tab-separated values, one row per line, without a header or the padding that aligns \
+            tables, for awk, cut, and the like. Backslashes, tabs, and line breaks within fields are \
+            written as \\\\, \\t, \\n, and \\r. See the README for the columns.",
+                ),
+        )
+        .arg(
+            Arg::with_name("now")
+                .long("now")
+                .global(true)
+                .value_name("time")
+                .help("Treats this time as the present")
+                .long_help(
+                    "Interprets relative time expressions such as 'today' and measures ongoing events \
+            as if it were the beginning of the given time expression rather than the actual present. \
+            This is useful for reproducible reports and for producing a report as it would have looked \
+            at some earlier time. New lines in the log are given this time too, so an event added with \
+            --now begins at it. If JOBLOG_NOW is set, its value is used unless --now is given.",
+                ),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .global(true)
+                .conflicts_with("verbose")
+                .help("Reports only errors")
+                .long_help(
+                    "Suppresses confirmations, warnings, and other chatter, leaving only errors and \
+            the output you asked for. Useful in scripts.",
+                ),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .global(true)
+                .help("Reports the details of what job is doing")
+                .long_help(
+                    "Reports on the standard error the files job reads and writes, the lines \
+            it finds in the log, and how it interprets time expressions. Useful when job does \
+            something you don't expect.",
+                ),
+        )
+        .arg(
+            Arg::with_name("no-hooks")
+                .long("no-hooks")
+                .global(true)
+                .help("Does not notify webhooks of changes to the log")
+                .long_help(
+                    "If you have configured webhooks, the add, append, cancel, done, note, resume, \
+            switch, and git-hook subcommands, and serve when it adds events, notes, or DONE lines, post \
+            a JSON description of the change to each. --no-hooks suppresses this. See configure \
+            --webhook.",
+                ),
+        )
+        .arg(
+            Arg::with_name("no-pager")
+                .long("no-pager")
+                .global(true)
+                .help("Does not page long output")
+                .long_help(
+                    "When the standard output is a terminal, long summaries, statistics, and vacation \
+            listings are shown through the pager named by the PAGER environment variable, or less \
+            if it is unset. --no-pager writes them directly to the terminal. You may turn paging off \
+            altogether with configure --pager false.",
+                ),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("name")
+                .conflicts_with("directory")
+                .validator(configure::valid_profile_name)
+                .help("Uses the log of the named profile")
+                .long_help(
+                    "Profiles let you keep separate logs -- for work and personal projects, say, \
+            or for two different jobs -- without juggling --directory paths. Each profile has its \
+            own log, vacation file, and configuration, kept by default in a subdirectory of the \
+            profiles directory in the base directory. See configure --default-profile and \
+            configure --profile-directory.",
                 ),
         );
     // for determining the listing order
@@ -48,17 +160,57 @@ fn main() {
         parse::cli,
         truncate::cli,
         statistics::cli,
+        zone::cli,
+        sync::cli,
+        merge::cli,
+        serve::cli,
+        batch::cli,
+        backup::cli,
+        migrate::cli,
+        import::cli,
+        switch::cli,
+        cancel::cli,
+        append::cli,
+        doctor::cli,
+        period::cli,
+        periods::cli,
+        budget::cli,
+        calendar::cli,
+        import_calendar::cli,
+        export::cli,
+        git_hook::cli,
+        status::cli,
+        lint::cli,
+        generate_docs::cli,
     ];
     for (i, command) in order.iter().enumerate() {
-        cli = command(cli, i);
+        app = command(app, i);
     }
-    let matches = cli.get_matches();
-    let directory = matches.value_of("directory");
+    app
+}
+
+fn main() {
+    let matches = cli().get_matches();
+    // global options are also found among the subcommand's matches
+    let (_, sub) = matches.subcommand();
+    let present = |name| matches.is_present(name) || sub.map_or(false, |m| m.is_present(name));
+    util::set_verbosity(present("quiet"), present("verbose"));
+    let directory =
+        util::resolve_directory(matches.value_of("directory"), matches.value_of("profile"));
+    let directory = directory.as_deref();
     util::init(directory);
+    if let Some(now) = matches
+        .value_of("now")
+        .or_else(|| sub.and_then(|m| m.value_of("now")))
+        .map(|s| s.to_owned())
+        .or_else(|| env::var("JOBLOG_NOW").ok().filter(|s| !s.trim().is_empty()))
+    {
+        util::set_now(&now, directory);
+    }
     match matches.subcommand() {
         ("add", Some(m)) => add::run(directory, m),
         ("note", Some(m)) => note::run(directory, m),
-        ("done", _) => done::run(directory),
+        ("done", Some(m)) => done::run(directory, m),
         ("edit", Some(m)) => edit::run(directory, m),
         ("resume", Some(m)) => resume::run(directory, m),
         ("last", Some(m)) => last::run(directory, m),
@@ -71,6 +223,28 @@ fn main() {
         ("vacation", Some(m)) => vacation::run(directory, m),
         ("statistics", Some(m)) => statistics::run(directory, m),
         ("parse-time", Some(m)) => parse::run(directory, m),
+        ("zone", Some(m)) => zone::run(directory, m),
+        ("sync", Some(m)) => sync::run(directory, m),
+        ("merge", Some(m)) => merge::run(directory, m),
+        ("serve", Some(m)) => serve::run(directory, m),
+        ("batch", Some(m)) => batch::run(directory, m),
+        ("backup", Some(m)) => backup::run(directory, m),
+        ("migrate", Some(m)) => migrate::run(directory, m),
+        ("import", Some(m)) => import::run(directory, m),
+        ("switch", Some(m)) => switch::run(directory, m),
+        ("cancel", Some(m)) => cancel::run(directory, m),
+        ("append", Some(m)) => append::run(directory, m),
+        ("doctor", Some(m)) => doctor::run(directory, m),
+        ("period", Some(m)) => period::run(directory, m),
+        ("periods", Some(m)) => periods::run(directory, m),
+        ("budget", Some(m)) => budget::run(directory, m),
+        ("push-calendar", Some(m)) => calendar::run(directory, m),
+        ("import-calendar", Some(m)) => import_calendar::run(directory, m),
+        ("export", Some(m)) => export::run(directory, m),
+        ("git-hook", Some(m)) => git_hook::run(directory, m),
+        ("status", Some(m)) => status::run(directory, m),
+        ("lint", Some(m)) => lint::run(directory, m),
+        ("generate-docs", Some(m)) => generate_docs::run(directory, m, cli),
         _ => println!("{}", matches.usage()),
     }
 }
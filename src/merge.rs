@@ -0,0 +1,566 @@
+extern crate chrono;
+extern crate clap;
+extern crate regex;
+
+use crate::configure::Configuration;
+use crate::log::{parse_line, timestamp, Item};
+use crate::util::{base_dir, fatal, info, log_path, success, warn, yes_or_no};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use std::fs::{copy, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+fn after_help() -> &'static str {
+    "\
+If you have kept logs on two machines you may wish to combine them. The merge subcommand \
+interleaves the lines of another log with those of your own by timestamp:
+
+  > job merge ~/laptop/.joblog/log
+
+Lines the two logs share -- if, for instance, one began as a copy of the other -- are kept only \
+once. Comments travel with the timestamped line that follows them and date comments are \
+regenerated to suit the merged log.
+
+Because a new event implicitly ends the one before it, an event begun in one log while an event \
+in the other is still open would cut the latter short. The merge subcommand reports such \
+conflicts and, unless you give it --force, leaves your log unchanged so that you can fix them \
+first. In either case lines which cannot be parsed must be fixed before the logs can be merged.
+
+Before the merged log replaces your own, your log is copied to log.pre-merge.bak in the job log \
+directory.
+
+All prefixes of 'merge' after 'm' -- 'me', 'mer', 'merg' -- are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("merge")
+            .aliases(&["me", "mer", "merg"])
+            .about("Merges another log into this one")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("force")
+                .long("force")
+                .short("f")
+                .help("Merges the logs despite conflicts")
+                .long_help("Writes the merged log even if events in one log overlap open events in the other.")
+                .conflicts_with("dry-run")
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Reports what the merge would do without doing it")
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("other")
+                .help("the log to merge into this one")
+                .value_name("other-log")
+                .required(true)
+            )
+            .display_order(display_order),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Source {
+    Ours,
+    Theirs,
+    Both,
+}
+
+// a timestamped line with the comments preceding it
+#[derive(Debug)]
+pub(crate) struct Block {
+    pub(crate) time: Option<NaiveDateTime>, // None only for comments trailing the last timestamped line
+    pub(crate) line: Option<String>,
+    pub(crate) comments: Vec<String>,
+    pub(crate) source: Source,
+    pub(crate) item: Option<Item>,
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let other = matches.value_of("other").unwrap();
+    let read = LogState::of(&conf);
+    let ours = match read_blocks(log_path(conf.directory()).to_str().unwrap(), Source::Ours) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            fatal(
+                format!("{}; fix this with `job edit --validate` before merging", e),
+                &conf,
+            );
+            unreachable!()
+        }
+    };
+    let theirs = match read_blocks(other, Source::Theirs) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            fatal(format!("{}; fix this before merging", e), &conf);
+            unreachable!()
+        }
+    };
+    let (ours_count, theirs_count) = (count(&ours), count(&theirs));
+    let merged = merge(ours, theirs);
+    let added = merged
+        .iter()
+        .filter(|b| b.line.is_some() && b.source == Source::Theirs)
+        .count();
+    let conflicts = conflicts(&merged, "the other log");
+    for c in conflicts.iter() {
+        warn(c, &conf);
+    }
+    if matches.is_present("dry-run") {
+        success(
+            format!(
+                "merging would add {} of the {} timestamped lines in {} to the {} in your log",
+                added, theirs_count, other, ours_count
+            ),
+            &conf,
+        );
+        return;
+    }
+    if !conflicts.is_empty() && !matches.is_present("force") {
+        fatal(
+            format!(
+                "found {} conflict{}; fix {} or merge with --force",
+                conflicts.len(),
+                if conflicts.len() == 1 { "" } else { "s" },
+                if conflicts.len() == 1 { "it" } else { "them" },
+            ),
+            &conf,
+        );
+    }
+    if added == 0 {
+        success(format!("{} adds nothing to your log", other), &conf);
+        return;
+    }
+    let tmp = tmp_path(&conf);
+    if tmp.as_path().exists()
+        && !yes_or_no(format!(
+            "the temporary log file {} already exists; overwrite?",
+            tmp.to_str().unwrap()
+        ))
+    {
+        fatal("could not merge logs", &conf);
+    }
+    let backup = replace_log(
+        &drop_orphaned_dones(merged),
+        "log.pre-merge.bak",
+        &read,
+        &conf,
+    );
+    success(
+        format!(
+            "added {} timestamped lines from {}; your original log is in {}",
+            added,
+            other,
+            backup.to_str().unwrap()
+        ),
+        &conf,
+    );
+}
+
+// where the new log is written before it replaces the old
+pub(crate) fn tmp_path(conf: &Configuration) -> PathBuf {
+    let mut tmp = base_dir(conf.directory());
+    tmp.push("log.tmp");
+    tmp
+}
+
+// the length and modification time of the log, by which replace_log tells whether anything has
+// written to the log since it was read
+#[derive(Debug, PartialEq)]
+pub(crate) struct LogState(u64, Option<SystemTime>);
+
+impl LogState {
+    pub(crate) fn of(conf: &Configuration) -> LogState {
+        match std::fs::metadata(log_path(conf.directory())) {
+            Ok(m) => LogState(m.len(), m.modified().ok()),
+            Err(_) => LogState(0, None),
+        }
+    }
+}
+
+// writes the blocks to log.tmp and, if they make a valid log and the log is as it was when it was
+// read, replaces the log with it, first copying the log to the named backup file; returns the
+// path of the backup
+pub(crate) fn replace_log(
+    blocks: &[Block],
+    backup: &str,
+    read: &LogState,
+    conf: &Configuration,
+) -> PathBuf {
+    let tmp = tmp_path(conf);
+    info(
+        format!("writing the new log to {}", tmp.to_str().unwrap()),
+        conf,
+    );
+    write_blocks(blocks, tmp.to_str().unwrap());
+    // confirm the new log is well-formed before it replaces the original
+    if let Err(e) = read_blocks(tmp.to_str().unwrap(), Source::Ours) {
+        fatal(
+            format!("the new log in {} is invalid: {}", tmp.to_str().unwrap(), e),
+            conf,
+        );
+    }
+    // a line added meanwhile, by job add, say, would be lost
+    if LogState::of(conf) != *read {
+        let _ = std::fs::remove_file(&tmp);
+        fatal(
+            "the log changed while this was underway, so it has been left as it was; try again",
+            conf,
+        );
+    }
+    let mut backup_path = base_dir(conf.directory());
+    backup_path.push(backup);
+    info(
+        format!("copying the log to {}", backup_path.to_str().unwrap()),
+        conf,
+    );
+    copy(log_path(conf.directory()), &backup_path).expect("could not back up the log");
+    std::fs::rename(&tmp, log_path(conf.directory()))
+        .expect("failed to move the new log into place");
+    backup_path
+}
+
+pub(crate) fn count(blocks: &[Block]) -> usize {
+    blocks.iter().filter(|b| b.line.is_some()).count()
+}
+
+// reads a log into blocks, discarding blank lines and date comments
+pub(crate) fn read_blocks(path: &str, source: Source) -> Result<Vec<Block>, String> {
+    lazy_static! {
+        static ref DATE_COMMENT: Regex =
+            Regex::new(r"\A\s*#\s*\d{4}/\d{1,2}/\d{1,2}\s*\z").unwrap();
+    }
+    let file = File::open(path).map_err(|e| format!("could not open {}: {}", path, e))?;
+    let mut blocks = vec![];
+    let mut comments = vec![];
+    let mut last: Option<NaiveDateTime> = None;
+    for (offset, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("could not read {}: {}", path, e))?;
+        let item = parse_line(&line, offset);
+        match item {
+            Item::Blank(_) => (),
+            Item::Comment(_) => {
+                if !DATE_COMMENT.is_match(&line) {
+                    comments.push(line)
+                }
+            }
+            Item::Error(e, _) => {
+                return Err(format!("line {} of {} is invalid: {}", offset + 1, path, e))
+            }
+            _ => {
+                let time = *item.time().unwrap().0;
+                if let Some(l) = last {
+                    if l > time {
+                        return Err(format!(
+                            "line {} of {} is out of order with the earlier timestamp {}",
+                            offset + 1,
+                            path,
+                            timestamp(&l)
+                        ));
+                    }
+                }
+                last = Some(time);
+                blocks.push(Block {
+                    time: Some(time),
+                    line: Some(line),
+                    comments: std::mem::take(&mut comments),
+                    source,
+                    item: Some(item),
+                });
+            }
+        }
+    }
+    if !comments.is_empty() {
+        blocks.push(Block {
+            time: None,
+            line: None,
+            comments,
+            source,
+            item: None,
+        });
+    }
+    Ok(blocks)
+}
+
+// interleaves two sequences of blocks by time, keeping shared lines only once
+pub(crate) fn merge(mut ours: Vec<Block>, mut theirs: Vec<Block>) -> Vec<Block> {
+    // the comments at the head of either log stay at the head of the merged log
+    let mut head: Vec<String> = vec![];
+    for blocks in [&mut ours, &mut theirs].iter_mut() {
+        if let Some(first) = blocks.first_mut() {
+            for c in std::mem::take(&mut first.comments) {
+                if !head.contains(&c) {
+                    head.push(c);
+                }
+            }
+        }
+    }
+    let mut merged: Vec<Block> = Vec::with_capacity(ours.len() + theirs.len());
+    let mut trailing: Vec<Block> = vec![];
+    let mut ours = ours.into_iter().peekable();
+    let mut theirs = theirs.into_iter().peekable();
+    loop {
+        let take_ours = match (ours.peek(), theirs.peek()) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(a), Some(b)) => match (a.time, b.time) {
+                (None, _) => false,
+                (_, None) => true,
+                (Some(t1), Some(t2)) => t1 <= t2,
+            },
+        };
+        let block = if take_ours {
+            ours.next().unwrap()
+        } else {
+            theirs.next().unwrap()
+        };
+        if block.line.is_none() {
+            trailing.push(block);
+            continue;
+        }
+        // a line shared by both logs will have the same time and text
+        if let Some(twin) = merged
+            .iter_mut()
+            .rev()
+            .take_while(|b| b.time == block.time)
+            .find(|b| {
+                b.source != block.source
+                    && b.line.as_ref().map(|l| l.trim()) == block.line.as_ref().map(|l| l.trim())
+            })
+        {
+            twin.source = Source::Both;
+            for c in block.comments {
+                if !twin.comments.contains(&c) {
+                    twin.comments.push(c);
+                }
+            }
+            continue;
+        }
+        merged.push(block);
+    }
+    let mut comments: Vec<String> = vec![];
+    for block in trailing {
+        for c in block.comments {
+            if !comments.contains(&c) {
+                comments.push(c);
+            }
+        }
+    }
+    if !comments.is_empty() {
+        merged.push(Block {
+            time: None,
+            line: None,
+            comments,
+            source: Source::Both,
+            item: None,
+        });
+    }
+    if !head.is_empty() {
+        match merged.first_mut() {
+            Some(first) => {
+                head.append(&mut first.comments);
+                first.comments = head;
+            }
+            None => merged.push(Block {
+                time: None,
+                line: None,
+                comments: head,
+                source: Source::Both,
+                item: None,
+            }),
+        }
+    }
+    merged
+}
+
+// describes the places where an event in one log overlaps an ongoing event in the other
+// the second parameter names the source of their blocks
+pub(crate) fn conflicts(merged: &[Block], theirs: &str) -> Vec<String> {
+    let mut conflicts = vec![];
+    let mut open: Option<(Source, NaiveDateTime)> = None;
+    for block in merged {
+        let (is_event, is_done) = match &block.item {
+            Some(Item::Event(_, _)) => (true, false),
+            Some(Item::Done(_, _)) => (false, true),
+            _ => (false, false),
+        };
+        if !(is_event || is_done) {
+            continue;
+        }
+        let time = block.time.unwrap();
+        if let Some((source, start)) = open {
+            if source != block.source && source != Source::Both && block.source != Source::Both {
+                conflicts.push(format!(
+                    "{} at {} in {} falls within the event begun at {} in {}",
+                    if is_event { "an event" } else { "a DONE" },
+                    timestamp(&time),
+                    describe(block.source, theirs),
+                    timestamp(&start),
+                    describe(source, theirs),
+                ));
+            }
+        }
+        open = if is_event {
+            Some((block.source, time))
+        } else {
+            None
+        };
+    }
+    conflicts
+}
+
+// where one log's DONE closed an event its other's DONE would close again, the second is dropped
+pub(crate) fn drop_orphaned_dones(mut merged: Vec<Block>) -> Vec<Block> {
+    let mut open = false;
+    for block in merged.iter_mut() {
+        match &block.item {
+            Some(Item::Event(_, _)) => open = true,
+            Some(Item::Done(_, _)) => {
+                if !open {
+                    // keep the comments
+                    block.line = None;
+                    block.time = None;
+                    block.item = None;
+                }
+                open = false;
+            }
+            _ => (),
+        }
+    }
+    merged
+}
+
+fn describe(source: Source, theirs: &str) -> &str {
+    match source {
+        Source::Ours => "your log",
+        Source::Theirs => theirs,
+        Source::Both => "each log",
+    }
+}
+
+pub(crate) fn write_blocks(blocks: &[Block], path: &str) {
+    let mut writer = BufWriter::new(File::create(path).expect("could not create log.tmp"));
+    let mut last_date: Option<NaiveDate> = None;
+    for (i, block) in blocks.iter().enumerate() {
+        let new_date = block
+            .time
+            .map(|t| t.date())
+            .filter(|&d| last_date != Some(d));
+        // the comments at the head of the log precede the first date comment
+        if i == 0 {
+            for c in block.comments.iter() {
+                writeln!(writer, "{}", c).expect("could not write to log.tmp");
+            }
+        }
+        if let Some(date) = new_date {
+            writeln!(writer, "# {}/{}/{}", date.year(), date.month(), date.day())
+                .expect("could not write to log.tmp");
+            last_date = Some(date);
+        }
+        if i > 0 {
+            for c in block.comments.iter() {
+                writeln!(writer, "{}", c).expect("could not write to log.tmp");
+            }
+        }
+        if let Some(line) = &block.line {
+            writeln!(writer, "{}", line).expect("could not write to log.tmp");
+        }
+    }
+    writer.flush().expect("could not flush log.tmp");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_log(path: &str, lines: &[&str]) {
+        let mut file = File::create(path).expect(&format!("could not create {}", path));
+        for line in lines {
+            writeln!(file, "{}", line).expect("could not write test log");
+        }
+    }
+
+    fn cleanup(paths: &[&str]) {
+        for p in paths {
+            if std::path::Path::new(p).exists() {
+                std::fs::remove_file(p).expect(&format!("failed to remove {}", p))
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let (a, b, out) = ("test_merge_a", "test_merge_b", "test_merge_out");
+        write_log(
+            a,
+            &[
+                "# job log",
+                "# 2020/1/1",
+                "2020  1  1  9  0  0::shared",
+                "# mine",
+                "2020  1  1 11  0  0:a:mine",
+                "2020  1  1 12  0  0:DONE",
+            ],
+        );
+        write_log(
+            b,
+            &[
+                "# job log",
+                "2020  1  1  9  0  0::shared",
+                "2020  1  1 10  0  0:DONE",
+                "# 2020/1/2",
+                "2020  1  2 11  0  0:b:theirs",
+            ],
+        );
+        let merged = merge(
+            read_blocks(a, Source::Ours).unwrap(),
+            read_blocks(b, Source::Theirs).unwrap(),
+        );
+        assert_eq!(5, count(&merged), "shared line kept once");
+        assert!(
+            conflicts(&merged, "the other log").is_empty(),
+            "no conflicts"
+        );
+        write_blocks(&drop_orphaned_dones(merged), out);
+        let text = std::fs::read_to_string(out).unwrap();
+        assert_eq!(
+            "# job log\n# 2020/1/1\n2020  1  1  9  0  0::shared\n2020  1  1 10  0  0:DONE\n# mine\n2020  1  1 11  0  0:a:mine\n2020  1  1 12  0  0:DONE\n# 2020/1/2\n2020  1  2 11  0  0:b:theirs\n",
+            text
+        );
+        cleanup(&[a, b, out]);
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let (a, b) = ("test_merge_conflict_a", "test_merge_conflict_b");
+        write_log(
+            a,
+            &["2020  1  1 11  0  0:a:mine", "2020  1  1 12  0  0:DONE"],
+        );
+        write_log(
+            b,
+            &["2020  1  1 11 30  0:b:theirs", "2020  1  1 11 45  0:DONE"],
+        );
+        let merged = merge(
+            read_blocks(a, Source::Ours).unwrap(),
+            read_blocks(b, Source::Theirs).unwrap(),
+        );
+        assert_eq!(
+            1,
+            conflicts(&merged, "the other log").len(),
+            "found overlap"
+        );
+        let merged = drop_orphaned_dones(merged);
+        assert_eq!(3, count(&merged), "dropped redundant DONE");
+        cleanup(&[a, b]);
+    }
+}
@@ -0,0 +1,197 @@
+extern crate clap;
+extern crate regex;
+
+use crate::configure::Configuration;
+use crate::log::{
+    log_header, log_version, parse_line, parse_timestamp, timestamp, Item, LOG_VERSION,
+};
+use crate::merge::tmp_path;
+use crate::util::{base_dir, fatal, info, log_path, success, yes_or_no};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use std::fs::{copy, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+fn after_help() -> &'static str {
+    "\
+New logs begin with a comment giving the version of the log format they use:
+
+  # job log version 1
+
+Logs begun by earlier versions of Job Log, or by the Perl App::JobLog, lack this comment. The \
+migrate subcommand brings such a log up to date, making whatever changes are needed to \
+convert it from its version to the current one. Converting a log to version 1 \
+adds the version comment, removes any carriage returns from line endings, and rewrites \
+timestamps in the standard form, so that, for instance, '2020 01 02 03 04 05' becomes \
+'2020  1  2  3  4  5'.
+
+Before the migrated log replaces the original, the original is copied to log.pre-migrate.bak \
+in the job log directory. With --dry-run the subcommand reports what it would change without \
+changing anything.
+
+All prefixes of 'migrate' after 'm' -- 'mi', 'mig', 'migr', etc. -- are aliases of the \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("migrate")
+            .aliases(&["mi", "mig", "migr", "migra", "migrat"])
+            .about("Upgrades the log to the current format")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("dry-run")
+                    .long("dry-run")
+                    .help("Reports what migration would change without changing anything"),
+            )
+            .display_order(display_order),
+    )
+}
+
+// converts lines of a log at one version of the format to the next; the conversion at index
+// i takes a log from version i to version i + 1, and each converts one line, given its offset,
+// returning None if the line is unchanged
+const MIGRATIONS: [fn(&str, usize) -> Option<String>; LOG_VERSION] = [to_version_1];
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let path = log_path(conf.directory());
+    let version = log_version(path.to_str().unwrap());
+    if version > LOG_VERSION {
+        fatal(
+            format!(
+                "the log is in version {} of the log format but this version of job only understands versions up to {}; you need to upgrade job",
+                version, LOG_VERSION
+            ),
+            &conf,
+        );
+    }
+    if version == LOG_VERSION {
+        success(
+            format!(
+                "the log is already in version {} of the log format",
+                version
+            ),
+            &conf,
+        );
+        return;
+    }
+    info(
+        format!(
+            "migrating the log from version {} to version {}",
+            version, LOG_VERSION
+        ),
+        &conf,
+    );
+    let file = File::open(&path).expect("could not open log");
+    let mut lines = vec![];
+    let mut changed = 0;
+    for (offset, line) in BufReader::new(file).lines().enumerate() {
+        let mut line = line.expect("could not read line from log");
+        for migration in MIGRATIONS.iter().skip(version) {
+            if let Some(l) = migration(&line, offset) {
+                line = l;
+                changed += 1;
+            }
+        }
+        lines.push(line);
+    }
+    // the header must be the first line, replacing the old header if there was one
+    if lines.first().map(|l| l.trim()) == Some("# job log") {
+        lines[0] = log_header();
+    } else {
+        lines.insert(0, log_header());
+    }
+    if matches.is_present("dry-run") {
+        success(
+            format!(
+                "migrating the log to version {} would add a version comment and change {} line{}",
+                LOG_VERSION,
+                changed,
+                if changed == 1 { "" } else { "s" }
+            ),
+            &conf,
+        );
+        return;
+    }
+    let tmp = tmp_path(&conf);
+    if tmp.as_path().exists()
+        && !yes_or_no(format!(
+            "the temporary log file {} already exists; overwrite?",
+            tmp.to_str().unwrap()
+        ))
+    {
+        fatal("could not migrate the log", &conf);
+    }
+    {
+        let mut writer =
+            BufWriter::new(File::create(&tmp).expect("could not create temporary log"));
+        for line in &lines {
+            writeln!(writer, "{}", line).expect("could not write to temporary log");
+        }
+    }
+    let mut backup = base_dir(conf.directory());
+    backup.push("log.pre-migrate.bak");
+    info(
+        format!("copying the log to {}", backup.to_str().unwrap()),
+        &conf,
+    );
+    copy(&path, &backup).expect("could not back up the log");
+    std::fs::rename(&tmp, &path).expect("failed to move the migrated log into place");
+    success(
+        format!(
+            "migrated the log to version {}, changing {} line{} besides adding the version comment; the original is in {}",
+            LOG_VERSION,
+            changed,
+            if changed == 1 { "" } else { "s" },
+            backup.to_str().unwrap()
+        ),
+        &conf,
+    );
+}
+
+// version 0 logs may have carriage returns and timestamps in irregular forms
+pub(crate) fn to_version_1(line: &str, offset: usize) -> Option<String> {
+    lazy_static! {
+        static ref TIMESTAMP: Regex = Regex::new(r"\A\s*[1-9]\d{3}(?:\s+\d{1,2}){5}").unwrap();
+    }
+    let trimmed = line.trim_end_matches('\r');
+    let mut migrated = trimmed.to_owned();
+    match parse_line(trimmed, offset) {
+        Item::Event(_, _) | Item::Note(_, _) | Item::Done(_, _) => {
+            let m = TIMESTAMP.find(trimmed).unwrap();
+            // parse_line has confirmed this is a good timestamp
+            let time = parse_timestamp(m.as_str()).unwrap();
+            migrated = timestamp(&time) + &trimmed[m.end()..];
+        }
+        _ => (),
+    }
+    if migrated == line {
+        None
+    } else {
+        Some(migrated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_version_1() {
+        assert_eq!(None, to_version_1("2020  1  2  3  4  5::foo", 0));
+        assert_eq!(
+            Some(String::from("2020  1  2  3  4  5:a b:foo")),
+            to_version_1("2020 01 02 03 04 05:a b:foo", 0)
+        );
+        assert_eq!(
+            Some(String::from("2020  1  2 13  4  5 +0100:DONE")),
+            to_version_1("2020 1 2 13 4 5 +0100:DONE\r", 0)
+        );
+        assert_eq!(
+            Some(String::from("# a comment")),
+            to_version_1("# a comment\r", 0)
+        );
+        assert_eq!(None, to_version_1("not a log line", 0));
+    }
+}
@@ -1,8 +1,11 @@
 extern crate clap;
 
 use crate::configure::Configuration;
+use crate::hook::notify;
 use crate::log::{Item, LogController};
-use crate::util::{check_for_ongoing_event, describe, remainder, some_nws};
+use crate::util::{
+    check_for_ongoing_event, check_new_tags, describe, new_tag_arguments, remainder, some_nws,
+};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
 fn after_help() -> &'static str {
@@ -17,6 +20,9 @@ between the timestamp and the tags:
 
   2020  1 18 12 10 26<NOTE>birthday paula:install Job Log
 
+As with the add subcommand, the warn-new-tags configuration, --strict-tags, and --new-tag \
+catch misspelled tags.
+
 All prefixes of 'note' are aliases of the subcommand."
 }
 
@@ -58,6 +64,7 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                     .required(true)
                     .multiple(true)
             )
+            .args(&new_tag_arguments())
             .display_order(display_order)
     )
 }
@@ -65,20 +72,31 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
     let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let mut tags: Vec<String> = matches
+        .values_of("tag")
+        .into_iter()
+        .chain(matches.values_of("new-tag"))
+        .flatten()
+        .map(|s| s.to_owned())
+        .collect();
+    tags = conf.normalize_tags(&tags);
+    check_new_tags(&tags, &reader, matches, &conf);
     check_for_ongoing_event(&mut reader, &conf);
     let description = remainder("note", matches);
-    let mut tags: Vec<String> = if let Some(values) = matches.values_of("tag") {
-        values.map(|s| s.to_owned()).collect()
-    } else {
-        vec![]
-    };
     if matches.is_present("copy-tags") {
         if let Some(event) = reader.last_event() {
             for t in event.tags {
                 tags.push(t);
             }
+            tags = conf.normalize_tags(&tags);
         }
     }
     let (note, offset) = reader.append_note(description, tags);
-    describe("noted", None, Item::Note(note, offset), &conf);
+    let json = note.to_json(&note.time, &conf);
+    notify("note", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else {
+        describe("noted", None, Item::Note(note, offset), &conf);
+    }
 }
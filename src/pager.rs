@@ -0,0 +1,108 @@
+extern crate clap;
+#[cfg(unix)]
+extern crate libc;
+
+use crate::configure::Configuration;
+use crate::util::{info, warn};
+use clap::ArgMatches;
+use std::env;
+use std::io::{stdout, Write};
+use std::process::Child;
+#[cfg(unix)]
+use std::process::{Command, Stdio};
+
+// while a Pager lives the standard output goes to the pager process; when it is
+// dropped the standard output is restored and we wait for the reader to quit the pager
+pub struct Pager {
+    child: Child,
+    stdout: i32,
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        stdout().flush().ok();
+        restore(self.stdout);
+        self.child.wait().ok();
+    }
+}
+
+// sends the standard output through the pager if it is a terminal and paging isn't turned off
+//
+// Much as git does, we use PAGER, falling back to less, and, unless LESS is already set, give
+// less the options FRX so it quits at once if the output fits on one screen, passes ANSI styles
+// through, and leaves the output on the screen when it quits.
+pub fn page(conf: &Configuration, matches: &ArgMatches) -> Option<Pager> {
+    if !conf.pager || matches.is_present("no-pager") || !is_terminal() {
+        return None;
+    }
+    let command = env::var("PAGER").unwrap_or_else(|_| String::from("less"));
+    let command = command.trim();
+    if command.is_empty() || command == "cat" {
+        return None;
+    }
+    info(format!("paging output through {}", command), conf);
+    match spawn(command) {
+        Ok(pager) => Some(pager),
+        Err(e) => {
+            warn(
+                format!("could not start the pager {}: {}", command, e),
+                conf,
+            );
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+#[cfg(not(unix))]
+fn is_terminal() -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn spawn(command: &str) -> Result<Pager, String> {
+    use std::os::unix::io::AsRawFd;
+    let mut pager = Command::new("sh");
+    pager.arg("-c").arg(command).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        pager.env("LESS", "FRX");
+    }
+    let mut child = pager.spawn().map_err(|e| e.to_string())?;
+    stdout().flush().ok();
+    let pipe = child.stdin.take().unwrap();
+    let saved = unsafe {
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved < 0 || libc::dup2(pipe.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+            return Err(String::from("could not redirect the standard output"));
+        }
+        // if the reader quits the pager before seeing everything, die quietly rather than
+        // complaining about a broken pipe
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        saved
+    };
+    Ok(Pager {
+        child,
+        stdout: saved,
+    })
+}
+
+#[cfg(not(unix))]
+fn spawn(_command: &str) -> Result<Pager, String> {
+    Err(String::from("paging is only supported on unix"))
+}
+
+// closing our end of the pipe tells the pager it has all the output
+#[cfg(unix)]
+fn restore(saved: i32) {
+    unsafe {
+        libc::dup2(saved, libc::STDOUT_FILENO);
+        libc::close(saved);
+    }
+}
+
+#[cfg(not(unix))]
+fn restore(_saved: i32) {}
@@ -1,13 +1,20 @@
 extern crate chrono;
 extern crate clap;
 extern crate colonnade;
+extern crate serde_json;
 extern crate two_timer;
 
-use crate::configure::Configuration;
-use crate::util::{fatal, remainder, some_nws, Style};
+use crate::configure::{Configuration, PayPeriod};
+use crate::log::{timestamp, utc_offset};
+use crate::util::{
+    expand_period, fatal, format_time, names_pay_period, remainder, some_nws, Style,
+};
+use chrono::NaiveDateTime;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use colonnade::Colonnade;
-use two_timer::parse;
+use pidgin::Match;
+use serde_json::{json, Value};
+use two_timer::{parse, MATCHER, SMALL_MATCHER};
 
 fn after_help() -> &'static str {
     "\
@@ -41,9 +48,31 @@ which are available in the simple grammar:
   2016
   feb - mar
 
+Job Log itself also understands ISO week numbers -- 'week 37', 'week 37 2019' -- and, if you \
+have configured fiscal-year-start, 'this fiscal year', 'last fiscal year', and 'next fiscal year'.
+
 Both grammars are from https://github.com/dfhoughton/two-timer. You can find list of sample \
 expressions at this URL and, in the source code, the actual grammars.
 
+To use parse-time in scripts, give it --format. It then prints just the start and the end, \
+one per line, as seconds since the Unix epoch, as ISO 8601 timestamps with UTC offsets, or \
+as they would appear in the log:
+
+  > job parse-time --format unix last week
+  1600646400
+  1601251200
+
+With --json it prints a JSON object with the start, the end, and whether the end was \
+explicit. The times are in the given format or, without --format, in the form used by the \
+JSON output of other subcommands.
+
+If you don't understand why an expression means what it does, give parse-time --explain. \
+It will show how Job Log rewrote the expression before parsing it, if it did, which grammar \
+parsed it, the tree of grammar rules it matched, and how your configuration -- when weeks \
+begin and when pay periods begin and how long they last -- bears on its interpretation:
+
+  > job parse-time --explain last week
+
 All prefixes of 'parse-time' are aliases of the subcommand.
 "
 }
@@ -71,6 +100,27 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                     .value_name("word")
                     .multiple(true),
             )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .short("f")
+                    .help("Prints the times in this format")
+                    .long_help("Prints only the start and end, one per line, as seconds since the Unix epoch, \
+                    as ISO 8601 timestamps with UTC offsets, or as log timestamps. With --json the times in the JSON \
+                    object are in this format.")
+                    .possible_values(&["unix", "iso", "log"])
+                    .value_name("format"),
+            )
+            .arg(
+                Arg::with_name("explain")
+                    .long("explain")
+                    .short("e")
+                    .help("Explains how the expression was interpreted")
+                    .long_help("Shows the expression as rewritten by Job Log, the grammar that parsed it, the \
+                    grammar rules it matched, and the configuration parameters that affected its interpretation. \
+                    With --json the explanation is included in the JSON object.")
+                    .conflicts_with("format"),
+            )
             .display_order(display_order),
     )
 }
@@ -82,16 +132,64 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     }
     let phrase = remainder("period", matches);
     if some_nws(&phrase) {
-        match parse(&phrase.trim(), conf.two_timer_config()) {
+        let expanded = expand_period(phrase.trim(), false, &conf);
+        match parse(&expanded, conf.two_timer_config()) {
             Ok((start, end, range)) => {
+                let format = matches.value_of("format");
+                let explanation = if matches.is_present("explain") {
+                    Some(explain(phrase.trim(), &expanded, &conf))
+                } else {
+                    None
+                };
+                if matches.is_present("json") {
+                    let time = |t: &NaiveDateTime| match format {
+                        Some(f) => formatted(t, f),
+                        None => json!(format!("{}", t)),
+                    };
+                    let mut value =
+                        json!({"start": time(&start), "end": time(&end), "explicit": range});
+                    if let Some(explanation) = explanation {
+                        value["explanation"] = explanation.to_json();
+                    }
+                    println!("{}", value);
+                    return;
+                }
+                if let Some(f) = format {
+                    for t in &[start, end] {
+                        match formatted(t, f) {
+                            Value::String(s) => println!("{}", s),
+                            v => println!("{}", v),
+                        }
+                    }
+                    return;
+                }
                 let style = Style::new(&conf);
+                // times are shown plainly unless the configuration asks otherwise
+                let show = |t: &NaiveDateTime| {
+                    if conf.locale.is_none()
+                        && conf.time_format.is_none()
+                        && conf.date_format.is_none()
+                    {
+                        format!("{}", t)
+                    } else {
+                        let pattern = format!(
+                            "{} {} (%A)",
+                            conf.date_format.as_deref().unwrap_or("%F"),
+                            conf.time_format.as_deref().unwrap_or("%T")
+                        );
+                        format_time(t, &pattern, &conf)
+                    }
+                };
                 let data = [
-                    [String::from("start"), format!("{}", start)],
-                    [String::from("end"), format!("{}", end)],
+                    [String::from("start"), show(&start)],
+                    [String::from("end"), show(&end)],
                     [String::from("explicit end"), format!("{}", range)],
                 ];
                 let mut table = Colonnade::new(2, conf.width()).unwrap();
                 println!();
+                if let Some(explanation) = explanation {
+                    explanation.display(&style);
+                }
                 for row in table.macerate(&data).unwrap() {
                     for line in row {
                         for (cell_num, (margin, contents)) in line.iter().enumerate() {
@@ -112,3 +210,183 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
         fatal("no time expression provided", &conf);
     }
 }
+
+// how a time expression was understood
+struct Explanation {
+    expanded: Option<String>, // the expression as rewritten by expand_period, if it was
+    grammar: &'static str,
+    rules: Vec<(usize, String, String)>, // depth, rule, and text of each node in the parse tree
+    notes: Vec<String>,                  // how the configuration affected the interpretation
+}
+
+impl Explanation {
+    fn display(&self, style: &Style) {
+        if let Some(expanded) = &self.expanded {
+            println!("{} {}", style.paint("header", "rewritten as"), expanded);
+        }
+        println!("{} {}", style.paint("header", "grammar"), self.grammar);
+        println!("{}", style.paint("header", "rules"));
+        for (depth, rule, text) in &self.rules {
+            println!("  {}{} '{}'", "  ".repeat(*depth), rule, text);
+        }
+        for note in &self.notes {
+            println!("{} {}", style.paint("header", "note"), note);
+        }
+        println!();
+    }
+    fn to_json(&self) -> Value {
+        json!({
+            "rewritten": self.expanded,
+            "grammar": self.grammar,
+            "rules": self
+                .rules
+                .iter()
+                .map(|(depth, rule, text)| json!({"depth": depth, "rule": rule, "text": text}))
+                .collect::<Vec<_>>(),
+            "notes": self.notes,
+        })
+    }
+}
+
+// two_timer tries its simple grammar before its comprehensive one; we do likewise to discover
+// which one parsed the expression and how
+fn explain(phrase: &str, expanded: &str, conf: &Configuration) -> Explanation {
+    let (grammar, parse) = match SMALL_MATCHER.parse(expanded) {
+        Some(m) => ("simple", m),
+        None => ("comprehensive", MATCHER.parse(expanded).unwrap()),
+    };
+    let mut rules = vec![];
+    rule_tree(&parse, 0, &mut rules);
+    let mut notes = vec![];
+    let period = parse
+        .all_names("modifiable_period")
+        .iter()
+        .map(|m| m.as_str().to_lowercase())
+        .collect::<Vec<_>>();
+    if parse.has("a_day") || period.iter().any(|p| p.starts_with("week")) {
+        notes.push(format!(
+            "weeks begin on {}; see `job configure --sunday-begins-week`",
+            if conf.sunday_begins_week {
+                "Sunday"
+            } else {
+                "Monday"
+            }
+        ));
+    }
+    if period.iter().any(|p| p == "pp" || p.starts_with("pay")) || names_pay_period(phrase) {
+        notes.push(match conf.start_pay_period {
+            _ if conf.pay_period == PayPeriod::Monthly => String::from(
+                "pay periods are calendar months; see `job configure --pay-period`",
+            ),
+            _ if conf.pay_period == PayPeriod::Semimonthly => String::from(
+                "pay periods run from the 1st to the 15th and from the 16th to the end of the month; see `job configure --pay-period`",
+            ),
+            Some(d) => format!(
+                "pay periods are {} days long and one began on {}; see `job configure --start-pay-period` and `--length-pay-period`",
+                conf.length_pay_period,
+                d.format("%F")
+            ),
+            None => String::from(
+                "no pay period has been configured; see `job configure --start-pay-period`",
+            ),
+        });
+    }
+    Explanation {
+        expanded: if expanded == phrase {
+            None
+        } else {
+            Some(expanded.to_owned())
+        },
+        grammar,
+        rules,
+        notes,
+    }
+}
+
+// flattens the parse tree below the TOP rule, which is always the same
+fn rule_tree(m: &Match, depth: usize, rules: &mut Vec<(usize, String, String)>) {
+    let depth = if m.rule() == "TOP" {
+        depth
+    } else {
+        rules.push((depth, m.rule().to_owned(), m.as_str().to_owned()));
+        depth + 1
+    };
+    if let Some(children) = m.children() {
+        for child in children {
+            rule_tree(child, depth, rules);
+        }
+    }
+}
+
+// a time in one of the --format formats
+fn formatted(time: &NaiveDateTime, format: &str) -> Value {
+    let offset = utc_offset(time);
+    match format {
+        "unix" => {
+            let hours = offset[1..3].parse::<i64>().unwrap();
+            let minutes = offset[3..].parse::<i64>().unwrap();
+            let seconds = (hours * 60 + minutes) * 60;
+            let seconds = if offset.starts_with('-') {
+                -seconds
+            } else {
+                seconds
+            };
+            json!(time.timestamp() - seconds)
+        }
+        "iso" => json!(format!(
+            "{}{}:{}",
+            time.format("%FT%T"),
+            &offset[..3],
+            &offset[3..]
+        )),
+        _ => json!(timestamp(time)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+    use chrono::{Local, NaiveDate, TimeZone};
+
+    #[test]
+    fn test_explain() {
+        let dir = TestDirectory::new("test_explain");
+        let mut conf = dir.configuration();
+        conf.sunday_begins_week = true;
+        conf.start_pay_period = None;
+        let explanation = explain("last week", "last week", &conf);
+        assert_eq!(None, explanation.expanded);
+        assert_eq!("simple", explanation.grammar);
+        assert!(explanation
+            .rules
+            .iter()
+            .any(|(_, rule, text)| rule == "modifier" && text == "last"));
+        assert_eq!(
+            vec!["weeks begin on Sunday; see `job configure --sunday-begins-week`"],
+            explanation.notes
+        );
+        let explanation = explain("last pp", "last pp", &conf);
+        assert!(explanation.notes[0].starts_with("no pay period"));
+        let explanation = explain("since 9 am", "since 9 am", &conf);
+        assert_eq!("comprehensive", explanation.grammar);
+        assert!(explanation.notes.is_empty());
+        assert_eq!(0, explanation.rules[0].0, "tree begins at depth 0");
+    }
+
+    #[test]
+    fn test_formatted() {
+        let time = NaiveDate::from_ymd(2020, 1, 2).and_hms(3, 4, 5);
+        assert_eq!(json!("2020  1  2  3  4  5"), formatted(&time, "log"));
+        let unix = Local.from_local_datetime(&time).unwrap().timestamp();
+        assert_eq!(json!(unix), formatted(&time, "unix"));
+        let iso = formatted(&time, "iso");
+        let iso = iso.as_str().unwrap();
+        assert!(iso.starts_with("2020-01-02T03:04:05"), "local time first");
+        assert_eq!(
+            Local.from_local_datetime(&time).unwrap().to_rfc3339(),
+            iso,
+            "ISO 8601"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+extern crate chrono;
+extern crate clap;
+extern crate colonnade;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::log::{Event, Filter, LogController};
+use crate::util::{current_time, duration_string, expand_period, fatal, info, remainder, Style};
+use crate::vacation::VacationController;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+use serde_json::json;
+use std::collections::BTreeMap;
+use two_timer::parse;
+
+fn after_help() -> &'static str {
+    "\
+The when subcommand tells you when you will be done today. The period subcommand tells you \
+how you stand in the pay period as a whole: how many hours you are expected to work in it, \
+how many you have logged, how many remain, and how many you must work on each of the remaining \
+work days to finish on target.
+
+  > job period
+  pay period      2020-03-02 to 2020-03-15
+  expected                           72.00
+  logged                             41.25
+  remaining                          30.75
+  work days left                         4
+  daily pace                          7.69
+
+The hours expected are the hours of all the work days in the period, less any vacation. The \
+work days left include today. By default the period is the current pay period, but you may \
+give another time expression, such as 'last pay period'. To use pay periods you must have \
+configured them; see `job configure --start-pay-period` and `job configure --pay-period`.
+
+All prefixes of 'period' after 'p' -- 'pe', 'per', 'peri', and 'perio' -- are aliases of the \
+subcommand. The 'p' prefix is reserved for the parse-time subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("period")
+            .aliases(&["pe", "per", "peri", "perio"])
+            .about("Shows your progress through the pay period")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("period")
+                    .help("time expression")
+                    .long_help(
+                        "All the <period> arguments are concatenated to produce a time expression.",
+                    )
+                    .value_name("period")
+                    .default_value("pay period")
+                    .multiple(true),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    if !conf.has_pay_period() {
+        fatal(
+            "no pay period is configured; see `job configure --start-pay-period` and `--pay-period`",
+            &conf,
+        );
+    }
+    let phrase = remainder("period", matches);
+    let expression = expand_period(&phrase, false, &conf);
+    let (start, end) = match parse(&expression, conf.logical_two_timer_config()) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), &conf);
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        &conf,
+    );
+    let now = current_time();
+    let progress = Progress::new(&start, &end, &now, &conf);
+    let last_day = conf.logical_date(&end) - Duration::days(1);
+    if matches.is_present("json") {
+        println!(
+            "{}",
+            json!({
+                "period": phrase,
+                "start": format!("{}", start),
+                "end": format!("{}", end),
+                "hours-expected": progress.expected / (60.0 * 60.0),
+                "hours-logged": progress.logged / (60.0 * 60.0),
+                "hours-remaining": progress.remaining() / (60.0 * 60.0),
+                "workdays-left": progress.days_left,
+                "daily-pace": progress.pace().map(|p| p / (60.0 * 60.0)),
+            })
+        );
+        return;
+    }
+    let style = Style::new(&conf);
+    let mut colonnade = Colonnade::new(2, conf.width()).expect("could not build the period table");
+    colonnade.columns[1].alignment(Alignment::Right);
+    let data = [
+        [
+            String::from("pay period"),
+            format!(
+                "{} to {}",
+                conf.logical_date(&start).format("%F"),
+                last_day.format("%F")
+            ),
+        ],
+        [
+            String::from("expected"),
+            duration_string(progress.expected, &conf),
+        ],
+        [
+            String::from("logged"),
+            duration_string(progress.logged, &conf),
+        ],
+        [
+            String::from("remaining"),
+            duration_string(progress.remaining(), &conf),
+        ],
+        [
+            String::from("work days left"),
+            progress.days_left.to_string(),
+        ],
+        [
+            String::from("daily pace"),
+            progress
+                .pace()
+                .map(|p| duration_string(p, &conf))
+                .unwrap_or_default(),
+        ],
+    ];
+    for (i, line) in colonnade
+        .tabulate(&data)
+        .expect("could not tabulate data")
+        .iter()
+        .enumerate()
+    {
+        println!(
+            "{}",
+            if i % 2 == 0 {
+                style.paint("odd", line)
+            } else {
+                style.paint("even", line)
+            }
+        );
+    }
+    if progress.remaining() > 0.0 && progress.days_left == 0 {
+        info("there are no work days left in the period", &conf);
+    }
+}
+
+// where one stands in a period; times are in seconds
+struct Progress {
+    expected: f32,
+    logged: f32,
+    days_left: usize,
+}
+
+impl Progress {
+    fn new(
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+        now: &NaiveDateTime,
+        conf: &Configuration,
+    ) -> Progress {
+        let mut reader = LogController::new(None, conf).expect("could not read log");
+        let events = reader
+            .events_in_range(start, end)
+            .into_iter()
+            .map(|mut e| {
+                // an ongoing event has lasted only until now
+                if e.end.is_none() {
+                    e.end = Some(*now);
+                }
+                e
+            })
+            .collect::<Vec<_>>();
+        let events = Event::gather_by_day(events, end, conf);
+        let logged = events
+            .iter()
+            .filter(|e| e.start < *end && e.start >= *start)
+            .map(|e| e.duration(now))
+            .sum();
+        // vacation is counted to the end of the period so planned vacations reduce what is
+        // expected
+        let mut vacation: BTreeMap<NaiveDate, f32> = BTreeMap::new();
+        for e in VacationController::read(None, conf.directory())
+            .add_vacation_times(start, end, events, conf, Some(*end), &Filter::dummy())
+            .into_iter()
+            .filter(|e| e.vacation)
+        {
+            *vacation.entry(conf.logical_date(&e.start)).or_insert(0.0) += e.duration(end);
+        }
+        Progress::tally(start, end, now, logged, &vacation, conf)
+    }
+    fn tally(
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+        now: &NaiveDateTime,
+        logged: f32,
+        vacation: &BTreeMap<NaiveDate, f32>,
+        conf: &Configuration,
+    ) -> Progress {
+        let today = conf.logical_date(now);
+        let mut expected = 0.0;
+        let mut days_left = 0;
+        let mut date = conf.logical_date(start);
+        while conf.start_of_day(&date) < *end {
+            let length = conf.day_length_on(&date) * 60.0 * 60.0;
+            let off = vacation.get(&date).cloned().unwrap_or(0.0).min(length);
+            expected += length - off;
+            if date >= today && length > off {
+                days_left += 1;
+            }
+            date += Duration::days(1);
+        }
+        Progress {
+            expected,
+            logged,
+            days_left,
+        }
+    }
+    fn remaining(&self) -> f32 {
+        (self.expected - self.logged).max(0.0)
+    }
+    // the seconds one must work on each remaining work day to finish on target
+    fn pace(&self) -> Option<f32> {
+        if self.days_left == 0 {
+            None
+        } else {
+            Some(self.remaining() / self.days_left as f32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_tally() {
+        let dir = TestDirectory::new("test_period_tally");
+        let conf = dir.configuration();
+        // Monday 2 March to Sunday 15 March, 2020, with the default 8 hour weekdays
+        let monday = NaiveDate::from_ymd(2020, 3, 2);
+        let start = monday.and_hms(0, 0, 0);
+        let end = start + Duration::days(14);
+        let now = (monday + Duration::days(9)).and_hms(12, 0, 0);
+        let hour = 60.0 * 60.0;
+        let mut vacation = BTreeMap::new();
+        vacation.insert(monday + Duration::days(10), 8.0 * hour);
+        let progress = Progress::tally(&start, &end, &now, 40.0 * hour, &vacation, &conf);
+        assert_eq!(
+            72.0 * hour,
+            progress.expected,
+            "ten work days less one of vacation"
+        );
+        assert_eq!(32.0 * hour, progress.remaining());
+        assert_eq!(2, progress.days_left, "Wednesday and Friday");
+        assert_eq!(Some(16.0 * hour), progress.pace());
+        let progress = Progress::tally(&start, &end, &now, 80.0 * hour, &vacation, &conf);
+        assert_eq!(0.0, progress.remaining(), "ahead of schedule");
+    }
+}
@@ -0,0 +1,239 @@
+extern crate chrono;
+extern crate clap;
+extern crate colonnade;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::log::{Event, Filter, LogController};
+use crate::util::{current_time, duration_string, expand_period, fatal, info, remainder, Style};
+use crate::vacation::VacationController;
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+use serde_json::json;
+use two_timer::parse;
+
+fn after_help() -> &'static str {
+    "\
+The periods subcommand lists the pay periods overlapping a range of time, with the hours \
+logged in each, so you can check them against your pay stubs:
+
+  > job periods this month
+  start       end         logged  vacation
+  2020-02-24  2020-03-08   80.00      0.00
+  2020-03-09  2020-03-22   71.25      8.00
+  2020-03-23  2020-04-05   22.50      0.00
+
+By default the range is this year. The first period listed is the one under way when the \
+range begins and the last the one under way when it ends, so the first and last may extend \
+beyond the range. The hours logged do not include vacation, which has a column of its own. \
+To use pay periods you must have configured them; see `job configure --start-pay-period` and \
+`job configure --pay-period`.
+
+The subcommand has no aliases, as the prefixes of 'periods' are aliases of the period \
+subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("periods")
+            .about("Lists the pay periods in a range with the hours logged in each")
+            .after_help(after_help())
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("period")
+                    .help("time expression")
+                    .long_help(
+                        "All the <period> arguments are concatenated to produce a time expression.",
+                    )
+                    .value_name("period")
+                    .default_value("this year")
+                    .multiple(true),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    if !conf.has_pay_period() {
+        fatal(
+            "no pay period is configured; see `job configure --start-pay-period` and `--pay-period`",
+            &conf,
+        );
+    }
+    let phrase = remainder("period", matches);
+    let expression = expand_period(&phrase, false, &conf);
+    let (start, end) = match parse(&expression, conf.logical_two_timer_config()) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), &conf);
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        &conf,
+    );
+    let now = current_time();
+    let periods = tally(pay_periods(&start, &end, &conf), &now, &conf);
+    if matches.is_present("json") {
+        for p in periods.iter() {
+            println!(
+                "{}",
+                json!({
+                    "start": format!("{}", p.start),
+                    "end": format!("{}", p.end),
+                    "logged": p.logged / (60.0 * 60.0),
+                    "vacation": p.vacation / (60.0 * 60.0),
+                })
+            );
+        }
+        return;
+    }
+    let style = Style::new(&conf);
+    let mut data = vec![vec![
+        String::from("start"),
+        String::from("end"),
+        String::from("logged"),
+        String::from("vacation"),
+    ]];
+    for p in periods.iter() {
+        data.push(vec![
+            p.start.format("%F").to_string(),
+            p.end.pred().format("%F").to_string(),
+            duration_string(p.logged, &conf),
+            duration_string(p.vacation, &conf),
+        ]);
+    }
+    let mut table = Colonnade::new(4, conf.width()).expect("could not build the periods table");
+    for i in 1..4 {
+        table.columns[i].left_margin(2);
+    }
+    for i in 2..4 {
+        table.columns[i].alignment(Alignment::Right);
+    }
+    for (i, line) in table
+        .tabulate(&data)
+        .expect("could not tabulate data")
+        .iter()
+        .enumerate()
+    {
+        if i == 0 {
+            println!("{}", style.paint("header", line));
+        } else if i % 2 == 0 {
+            println!("{}", style.paint("even", line));
+        } else {
+            println!("{}", style.paint("odd", line));
+        }
+    }
+}
+
+// a pay period and the time logged in it, in seconds
+struct Period {
+    start: NaiveDate,
+    end: NaiveDate, // the first day of the next period
+    logged: f32,
+    vacation: f32,
+}
+
+// the first and next-to-first days of the pay periods overlapping the range
+fn pay_periods(
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    conf: &Configuration,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut periods = vec![];
+    let mut date = conf.logical_date(start);
+    while let Some(period) = conf.pay_period_containing(&date) {
+        if conf.start_of_day(&period.0) >= *end && !periods.is_empty() {
+            break;
+        }
+        periods.push(period);
+        date = period.1;
+    }
+    periods
+}
+
+// the time logged in each of the pay periods
+fn tally(
+    periods: Vec<(NaiveDate, NaiveDate)>,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> Vec<Period> {
+    let mut periods = periods
+        .into_iter()
+        .map(|(start, end)| Period {
+            start,
+            end,
+            logged: 0.0,
+            vacation: 0.0,
+        })
+        .collect::<Vec<_>>();
+    let (start, end) = match (periods.first(), periods.last()) {
+        (Some(first), Some(last)) => (
+            conf.start_of_day(&first.start),
+            conf.start_of_day(&last.end),
+        ),
+        _ => return periods,
+    };
+    let mut reader = LogController::new(None, conf).expect("could not read log");
+    let events = Event::gather_by_day(reader.events_in_range(&start, &end), &end, conf);
+    let events = VacationController::read(None, conf.directory()).add_vacation_times(
+        &start,
+        &end,
+        events,
+        conf,
+        None,
+        &Filter::dummy(),
+    );
+    for e in events.iter().filter(|e| e.start >= start) {
+        let date = conf.logical_date(&e.start);
+        if let Some(p) = periods.iter_mut().find(|p| p.start <= date && date < p.end) {
+            if e.vacation {
+                p.vacation += e.duration(now);
+            } else {
+                p.logged += e.duration(now);
+            }
+        }
+    }
+    periods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configure::PayPeriod;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_pay_periods() {
+        let dir = TestDirectory::new("test_pay_periods");
+        let mut conf = dir.configuration();
+        conf.pay_period = PayPeriod::Semimonthly;
+        let start = NaiveDate::from_ymd(2020, 2, 10).and_hms(0, 0, 0);
+        let end = NaiveDate::from_ymd(2020, 3, 16).and_hms(0, 0, 0);
+        let starts = |periods: Vec<(NaiveDate, NaiveDate)>| {
+            periods
+                .iter()
+                .map(|(s, _)| s.format("%F").to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["2020-02-01", "2020-02-16", "2020-03-01"],
+            starts(pay_periods(&start, &end, &conf))
+        );
+        assert_eq!(
+            vec!["2020-03-16"],
+            starts(pay_periods(&end, &end, &conf)),
+            "an empty range is in some period"
+        );
+        conf.pay_period = PayPeriod::Fixed;
+        conf.start_pay_period = Some(NaiveDate::from_ymd(2020, 1, 6));
+        assert_eq!(
+            vec!["2020-02-03", "2020-02-17", "2020-03-02"],
+            starts(pay_periods(&start, &end, &conf))
+        );
+    }
+}
@@ -1,10 +1,23 @@
 extern crate chrono;
 extern crate clap;
+extern crate colonnade;
 
 use crate::configure::Configuration;
-use crate::log::{Event, Filter, Item, LogController};
-use crate::util::{check_for_ongoing_event, common_search_or_filter_arguments, describe, warn};
-use clap::{App, ArgMatches, SubCommand};
+use crate::hook::notify;
+use crate::log::{similarity, Event, Filter, Item, LogController, FUZZY_THRESHOLD};
+use crate::util::{
+    check_vacation, close_at_argument, close_forgotten_event, common_search_or_filter_arguments,
+    describe, fatal, interactive, prompt, shorten_vacation_argument, warn, yes_or_no, Style,
+};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use colonnade::{Alignment, Colonnade};
+use std::collections::HashSet;
+
+// the number of tasks --pick lists by default
+const PICK: &str = "10";
+
+// the number of recent distinct tasks --fzf chooses among
+const FUZZY_PICK: usize = 1000;
 
 fn after_help() -> &'static str {
     "If you start the day by returning to what you were doing and the end of the previous \
@@ -17,9 +30,50 @@ type
 
   job resume --tag e
 
-To log the first task of the days as the email task. Any time you switch tasks back to \
-one you've done befoer you can resume the old task rather than type out its full description \
-and tags.
+to log the first task of the day as the email task. This resumes the most recent event \
+with the tag, whatever its description, so a tag can stand for a project you return to \
+although the particular tasks within it vary. If you give several tags, the most recent \
+event with all of them is resumed; with --tag-some, the most recent with any of them. Any \
+time you switch tasks back to one you've done before you can resume the old task rather \
+than type out its full description and tags.
+
+If several tasks have similar descriptions, it may be easier to choose among them from a \
+list. --pick lists the last 10 distinct tasks -- distinct descriptions and tags -- and asks \
+which you want to resume:
+
+  > job resume --pick
+   1  2020-03-06  e    email
+   2  2020-03-06  foo  fixing the parser
+   ...
+
+If the task is further back, --fzf lets you find it as a fuzzy finder would. Type some \
+letters of its description or tags, in order but not necessarily together, and the recent \
+tasks they match are listed, the best matches first. Type other letters to search again, the \
+number of a task to resume it, or nothing to resume the only task listed:
+
+  > job resume --fzf
+  type to search, or give a task's number: fxprs
+   1  2020-02-12  foo  fixing the parser
+   2  2020-01-30  bar  fixing the expression setter
+  type to search, or give a task's number: 1
+
+If you already know where the task falls in the --pick list, give its number instead:
+
+  > job resume 2
+
+Or give some words from its description. The task whose description most resembles them \
+is resumed, forgiving typos and differences of word order, once you have confirmed that it \
+is the task you want:
+
+  > job resume parsre fix
+  resume 'fixing the parser' (foo)? [Yn]
+
+When resume is not run in a terminal, so there is no one to confirm the choice, the words must \
+resemble only one task or be the whole description of one.
+
+As with the add subcommand, if an event begun on a previous day was never ended you are \
+asked when it ended, or you can say with --close-at, and if an ordinary vacation record covers \
+today you are warned, or, with --shorten-vacation, the record is made flex for today.
 
 All prefixes of 'resume' are aliases of the subcommand."
 }
@@ -30,6 +84,39 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
             .aliases(&["r", "re", "res", "resu", "resum"])
             .about("Resumes a stopped task")
             .after_help(after_help())
+            .arg(
+                Arg::with_name("pick")
+                    .long("pick")
+                    .short("p")
+                    .help("Lists recent tasks and asks which to resume")
+                    .long_help("Lists the given number of most recent distinct tasks -- 10 if no number is given -- \
+                    and asks which of them to resume. Only tasks passing the other filters are listed.")
+                    .value_name("n")
+                    .min_values(0)
+                    .max_values(1)
+                    .validator(positive)
+                    .display_order(0),
+            )
+            .arg(
+                Arg::with_name("fzf")
+                    .long("fzf")
+                    .help("Finds the task to resume by fuzzy search")
+                    .long_help("Asks for letters from the description or tags of the task to resume, lists the recent \
+                    distinct tasks they match, in order but not necessarily together, and asks again until you choose one. \
+                    Only tasks passing the other filters are listed.")
+                    .conflicts_with_all(&["pick", "task"])
+                    .display_order(1),
+            )
+            .arg(close_at_argument().display_order(2))
+            .arg(shorten_vacation_argument().display_order(3))
+            .arg(
+                Arg::with_name("task")
+                    .help("the number of the task to resume in the --pick list, or words from its description")
+                    .long_help("If this is a number, resumes the task with this number in the list --pick would show. \
+                    Otherwise, resumes the task whose description most resembles these words.")
+                    .value_name("task")
+                    .multiple(true),
+            )
             .display_order(display_order),
         Some(true),
     ))
@@ -39,19 +126,352 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let filter = Filter::new(matches);
     let conf = Configuration::read(None, directory);
     let mut reader = LogController::new(None, &conf).expect("could not read log");
-    check_for_ongoing_event(&mut reader, &conf);
-    let event: Vec<Event> = reader
-        .events_from_the_end()
-        .filter(|n| filter.matches(n))
-        .take(1)
-        .collect();
+    close_forgotten_event(&mut reader, matches, &conf);
+    let words = matches
+        .values_of("task")
+        .map(|v| v.collect::<Vec<_>>().join(" "));
+    let number = words.as_ref().and_then(|w| w.parse::<usize>().ok());
+    if number == Some(0) {
+        fatal("tasks are numbered from 1", &conf);
+    }
+    let event: Vec<Event> = if let (None, Some(words)) = (number, &words) {
+        match resembling(&mut reader, &filter, words, &conf) {
+            Some(event) => vec![event],
+            None => return,
+        }
+    } else if matches.is_present("fzf") {
+        match fuzzy_pick(&mut reader, &filter, &conf) {
+            Some(event) => vec![event],
+            None => return,
+        }
+    } else if matches.is_present("pick") || number.is_some() {
+        match pick(&mut reader, &filter, number, matches, &conf) {
+            Some(event) => vec![event],
+            None => return,
+        }
+    } else {
+        reader
+            .events_from_the_end()
+            .filter(|n| filter.matches(n))
+            .take(1)
+            .collect()
+    };
     if event.is_empty() {
         warn("no event found", &conf)
     } else if event[0].ongoing() {
         warn("event ongoing", &conf)
     } else {
-        let (event, offset) =
-            reader.append_event(event[0].description.clone(), event[0].tags.clone());
-        describe("resuming", None, Item::Event(event, offset), &conf);
+        let (event, offset) = reader.append_event(
+            event[0].description.clone(),
+            conf.normalize_tags(&event[0].tags),
+        );
+        check_vacation(matches, &conf);
+        let json = event.to_json(&event.start, &conf);
+        notify("resume", &json, &conf, matches);
+        if matches.is_present("json") {
+            println!("{}", json);
+        } else {
+            describe("resuming", None, Item::Event(event, offset), &conf);
+        }
+    }
+}
+
+fn positive(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number", v)),
+    }
+}
+
+// chooses a task from the list of recent tasks, either by the number given or by asking
+fn pick(
+    reader: &mut LogController,
+    filter: &Filter,
+    number: Option<usize>,
+    matches: &ArgMatches,
+    conf: &Configuration,
+) -> Option<Event> {
+    let count = matches
+        .value_of("pick")
+        .unwrap_or(PICK)
+        .parse::<usize>()
+        .unwrap()
+        .max(number.unwrap_or(0));
+    let tasks = distinct_tasks(
+        reader.events_from_the_end().filter(|e| filter.matches(e)),
+        count,
+    );
+    if tasks.is_empty() {
+        warn("no event found", conf);
+        return None;
+    }
+    let number = match number {
+        Some(n) => n,
+        None => {
+            display_tasks(&tasks, conf);
+            if !interactive() {
+                warn(
+                    "no task chosen; give its number to resume it: job resume <n>",
+                    conf,
+                );
+                return None;
+            }
+            loop {
+                let answer = prompt("resume which task?", "");
+                if answer.is_empty() {
+                    warn("no task chosen", conf);
+                    return None;
+                }
+                match answer.parse::<usize>() {
+                    Ok(n) if n > 0 && n <= tasks.len() => break n,
+                    _ => println!("please enter a number from 1 to {}", tasks.len()),
+                }
+            }
+        }
+    };
+    if number > tasks.len() {
+        fatal(
+            format!(
+                "there {} only {} task{} to choose from",
+                if tasks.len() == 1 { "is" } else { "are" },
+                tasks.len(),
+                if tasks.len() == 1 { "" } else { "s" }
+            ),
+            conf,
+        );
+    }
+    tasks.into_iter().nth(number - 1)
+}
+
+// chooses a task from the recent tasks by asking for letters to search for until one is picked
+fn fuzzy_pick(reader: &mut LogController, filter: &Filter, conf: &Configuration) -> Option<Event> {
+    if !interactive() {
+        fatal(
+            "cannot ask which task to resume; give words from its description instead",
+            conf,
+        );
+    }
+    let tasks = distinct_tasks(
+        reader.events_from_the_end().filter(|e| filter.matches(e)),
+        FUZZY_PICK,
+    );
+    if tasks.is_empty() {
+        warn("no event found", conf);
+        return None;
+    }
+    crate::util::fuzzy_pick(
+        &tasks,
+        task_text,
+        |found| display_tasks(found, conf),
+        PICK.parse::<usize>().unwrap(),
+        "task",
+        conf,
+    )
+}
+
+// the text --fzf searches: a task's description and tags
+fn task_text(task: &Event) -> String {
+    format!("{} {}", task.description, task.tags.join(" "))
+}
+
+// finds the task whose description most resembles the words and confirms it is the one wanted;
+// with no one to confirm it, the task must be the only one resembling the words or the only one
+// with exactly this description
+fn resembling(
+    reader: &mut LogController,
+    filter: &Filter,
+    words: &str,
+    conf: &Configuration,
+) -> Option<Event> {
+    let tasks = distinct_tasks(
+        reader.events_from_the_end().filter(|e| filter.matches(e)),
+        usize::MAX,
+    );
+    let candidates = tasks
+        .into_iter()
+        .map(|task| (similarity(words, &task.description), task))
+        .filter(|(score, _)| *score >= FUZZY_THRESHOLD)
+        .collect::<Vec<_>>();
+    if !interactive() && candidates.len() > 1 {
+        let exact = candidates
+            .iter()
+            .filter(|(_, task)| same_words(words, &task.description))
+            .collect::<Vec<_>>();
+        if exact.len() == 1 {
+            return Some(exact[0].1.clone());
+        }
+        fatal(
+            format!(
+                "{} tasks resemble '{}'; give the whole description of one or run resume \
+                in a terminal to confirm the best match",
+                candidates.len(),
+                words
+            ),
+            conf,
+        );
+        return None;
+    }
+    // on a tie the more recent task wins
+    let mut best: Option<(f64, Event)> = None;
+    for (score, task) in candidates {
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            best = Some((score, task));
+        }
+    }
+    match best {
+        None => {
+            warn(format!("no task resembles '{}'", words), conf);
+            None
+        }
+        Some((_, task)) => {
+            if interactive()
+                && !yes_or_no(format!(
+                    "resume '{}'{}?",
+                    task.description,
+                    if task.tags.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({})", task.tags.join(", "))
+                    }
+                ))
+            {
+                warn("no task chosen", conf);
+                None
+            } else {
+                Some(task)
+            }
+        }
+    }
+}
+
+// whether the texts have the same words, ignoring case
+fn same_words(a: &str, b: &str) -> bool {
+    let words = |s: &str| {
+        s.split_whitespace()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+    };
+    words(a) == words(b)
+}
+
+// the most recent instances of the first n distinct tasks -- descriptions and tags -- among
+// the events, which are ordered from the most recent
+fn distinct_tasks<I: Iterator<Item = Event>>(events: I, n: usize) -> Vec<Event> {
+    let mut seen = HashSet::new();
+    let mut tasks: Vec<Event> = vec![];
+    for event in events {
+        if tasks.len() == n {
+            break;
+        }
+        if seen.insert((event.description.clone(), event.tags.clone())) {
+            tasks.push(event);
+        }
+    }
+    tasks
+}
+
+fn display_tasks(tasks: &[Event], conf: &Configuration) {
+    let style = Style::new(conf);
+    let data = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            vec![
+                (i + 1).to_string(),
+                e.start.format("%F").to_string(),
+                e.tags.join(" "),
+                e.description.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut table = Colonnade::new(4, conf.width()).expect("insufficient space for task list");
+    table.columns[0].alignment(Alignment::Right);
+    table.columns[3].priority(1);
+    for row in table.macerate(&data).expect("failed to macerate data") {
+        for line in row {
+            for (cell_num, (margin, cell)) in line.iter().enumerate() {
+                let cell = match cell_num {
+                    2 => style.paint("tags", cell),
+                    _ => cell.to_owned(),
+                };
+                print!("{}{}", margin, cell);
+            }
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{fuzzy_matches, fuzzy_score};
+
+    fn event(description: &str, tags: &[&str]) -> Event {
+        Event::coin(
+            description.to_owned(),
+            tags.iter().map(|t| t.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_distinct_tasks() {
+        let events = vec![
+            event("foo", &["a"]),
+            event("bar", &[]),
+            event("foo", &["a"]),
+            event("foo", &["b"]),
+            event("baz", &[]),
+        ];
+        let tasks = distinct_tasks(events.clone().into_iter(), 10);
+        assert_eq!(
+            vec!["foo", "bar", "foo", "baz"],
+            tasks
+                .iter()
+                .map(|e| e.description.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["b"], tasks[2].tags, "tags distinguish tasks");
+        assert_eq!(2, distinct_tasks(events.into_iter(), 2).len());
+    }
+
+    #[test]
+    fn test_fuzzy_matches() {
+        assert_eq!(Some(0), fuzzy_score("", "foo"));
+        assert_eq!(None, fuzzy_score("fp", "parser fix"), "order matters");
+        assert!(
+            fuzzy_score("parse", "fixing the parser") > fuzzy_score("parse", "prepare the set")
+        );
+        assert!(
+            fuzzy_score("FP", "fixing the parser").is_some(),
+            "case is ignored"
+        );
+        let tasks = vec![
+            event("fixing the expression setter", &["bar"]),
+            event("fixing the parser", &["foo"]),
+            event("email", &["e"]),
+        ];
+        let descriptions = |query| {
+            fuzzy_matches(query, &tasks, task_text, 10)
+                .iter()
+                .map(|e| e.description.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["fixing the parser", "fixing the expression setter"],
+            descriptions("fx prs")
+        );
+        assert_eq!(
+            vec!["fixing the expression setter"],
+            descriptions("bar"),
+            "tags are searched too"
+        );
+        assert_eq!(1, fuzzy_matches("fix", &tasks, task_text, 1).len());
+    }
+
+    #[test]
+    fn test_same_words() {
+        assert!(same_words("Team  meeting", "team meeting"));
+        assert!(!same_words("meeting team", "team meeting"));
+        assert!(!same_words("team meetng", "team meeting"));
     }
 }
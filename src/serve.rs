@@ -0,0 +1,771 @@
+extern crate chrono;
+extern crate clap;
+extern crate serde_json;
+extern crate two_timer;
+
+use crate::configure::Configuration;
+use crate::done::done_json;
+use crate::hook::notify;
+use crate::log::{Event, Filter, LogController};
+use crate::util::{
+    clap_error_message, common_search_or_filter_arguments, current_time, expand_period, fatal,
+    hours_string, log_path, some_nws, success, warn,
+};
+use crate::vacation::VacationController;
+use chrono::{Duration, NaiveDateTime};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::Instant;
+use two_timer::parse;
+
+// the environment variable which may hold the token in lieu of --token
+pub const TOKEN_VARIABLE: &str = "JOBLOG_TOKEN";
+// the largest request body the server will read
+const MAX_BODY: usize = 1 << 20;
+// the seconds a client may take to send its whole request and receive the response
+const TIMEOUT: u64 = 10;
+
+fn after_help() -> &'static str {
+    "\
+The serve subcommand exposes the log over HTTP so that other programs -- a browser extension, \
+say, or a shortcut on your phone -- can consult it and log time. Every request must carry the \
+token given with --token, or by the JOBLOG_TOKEN environment variable, in an Authorization header:
+
+  > job serve --token s3cr3t --write
+  > curl -H 'Authorization: Bearer s3cr3t' 'http://127.0.0.1:7734/summary?period=yesterday&tag=foo'
+
+These endpoints are always available:
+
+  GET /status      the last event, and whether it is ongoing
+  GET /summary     the events or notes in a period
+  GET /statistics  the hours, events, and notes in a period, with the hours by tag
+
+The hours are rounded as in a summary; the exact seconds are given as well, so totals reconcile.
+
+/summary and /statistics take a period parameter, 'today' by default, and the filtering parameters \
+of the summary subcommand -- tag, tag-none, tag-some, rx, rx-not, author, no-tags, and notes -- \
+all of which may be repeated; e.g., ?period=last+week&tag=foo&tag=bar.
+
+With --write, these endpoints are also available:
+
+  POST /add   begins an event
+  POST /done  ends the current event
+  POST /note  adds a note
+
+The body of a request to /add or /note is a JSON object with a description and, optionally, \
+a list of tags:
+
+  {\"description\": \"reviewing the plugh PR\", \"tags\": [\"review\", \"plugh\"]}
+
+All responses are JSON. Errors are objects with an \"error\" property. If the log cannot be \
+read the server responds with status 500 and goes on serving. Configured webhooks are \
+notified of changes to the log, as they are by the add, done, and note subcommands.
+
+The server listens only on the loopback interface unless you say otherwise with --address. \
+It does not use TLS, so if you expose it to other machines, do so through a proxy that does.
+
+All prefixes of 'serve' after 's' -- 'se', 'ser', and 'serv' -- are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("serve")
+            .aliases(&["se", "ser", "serv"])
+            .about("Serves the log over HTTP")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("port")
+                .long("port")
+                .short("p")
+                .help("Listens on this port")
+                .default_value("7734")
+                .validator(|v| if v.parse::<u16>().is_ok() {Ok(())} else {Err(format!("{} is not a port number", v))})
+                .value_name("num")
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("address")
+                .long("address")
+                .help("Listens on this address")
+                .long_help("The address to listen on. By default the server is reachable only from this machine.")
+                .default_value("127.0.0.1")
+                .value_name("ip")
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("token")
+                .long("token")
+                .help("Requires this token of clients")
+                .long_help("Clients must provide this token in an 'Authorization: Bearer <token>' header. \
+                If you do not provide it here you must provide it via the JOBLOG_TOKEN environment variable, \
+                which keeps it out of your shell history and process listings.")
+                .validator(|v| if some_nws(&v) && !v.contains(char::is_whitespace) {Ok(())} else {Err(String::from("a token may not contain whitespace"))})
+                .value_name("token")
+                .display_order(2)
+            )
+            .arg(
+                Arg::with_name("write")
+                .long("write")
+                .help("Enables the add, done, and note endpoints")
+                .long_help("Without this option the server is read-only.")
+                .display_order(3)
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let token = matches
+        .value_of("token")
+        .map(|t| t.to_owned())
+        .or_else(|| std::env::var(TOKEN_VARIABLE).ok())
+        .filter(|t| some_nws(t));
+    let token = match token {
+        Some(token) => token,
+        None => {
+            fatal(
+                format!("a token is required: use --token or set {}", TOKEN_VARIABLE),
+                &conf,
+            );
+            unreachable!()
+        }
+    };
+    let address = format!(
+        "{}:{}",
+        matches.value_of("address").unwrap(),
+        matches.value_of("port").unwrap()
+    );
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            fatal(format!("could not listen on {}: {}", address, e), &conf);
+            unreachable!()
+        }
+    };
+    success(
+        format!(
+            "serving {} at http://{}/{}",
+            log_path(conf.directory()).to_str().unwrap(),
+            address,
+            if matches.is_present("write") {
+                ""
+            } else {
+                " (read-only)"
+            }
+        ),
+        &conf,
+    );
+    let server = Server {
+        directory,
+        token,
+        writable: matches.is_present("write"),
+        matches,
+        writing: Mutex::new(()),
+    };
+    // each connection has its own thread so a slow client cannot hold up the others
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let (server, conf) = (&server, &conf);
+                    scope.spawn(move || server.serve(stream, conf));
+                }
+                Err(e) => warn(format!("could not accept connection: {}", e), &conf),
+            }
+        }
+    });
+}
+
+struct Server<'a> {
+    directory: Option<&'a str>,
+    token: String,
+    writable: bool,
+    matches: &'a ArgMatches<'a>,
+    writing: Mutex<()>, // held while a request appends to the log
+}
+
+impl<'a> Server<'a> {
+    // reads a request from the connection and answers it
+    fn serve(&self, mut stream: TcpStream, conf: &Configuration) {
+        let deadline = Instant::now() + std::time::Duration::from_secs(TIMEOUT);
+        let response = match parse_request(Deadline::new(&stream, deadline)) {
+            // a request that trips over a bug gets an error rather than bringing down the server
+            Ok(request) => catch_unwind(AssertUnwindSafe(|| self.respond(&request)))
+                .unwrap_or_else(|_| Response::error(500, "the request could not be handled")),
+            Err(e) => Response::error(400, e),
+        };
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+            .unwrap_or_else(|| std::time::Duration::from_secs(1));
+        let sent = stream
+            .set_write_timeout(Some(remaining))
+            .and_then(|_| response.write_to(&mut stream));
+        if let Err(e) = sent {
+            warn(format!("could not send response: {}", e), conf);
+        }
+    }
+    fn respond(&self, request: &Request) -> Response {
+        if request.method == "OPTIONS" {
+            // a CORS preflight, which carries no credentials
+            return Response::new(204, String::new());
+        }
+        let authorized = request
+            .header("authorization")
+            .map(|h| same_secret(h, &format!("Bearer {}", self.token)))
+            .unwrap_or(false);
+        if !authorized {
+            return Response::error(401, "a valid token is required");
+        }
+        // the configuration and log may have changed since the last request
+        let conf = Configuration::read(None, self.directory);
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/status") => status(&conf),
+            ("GET", "/summary") => summary(request, &conf),
+            ("GET", "/statistics") => statistics(request, &conf),
+            ("POST", "/add") | ("POST", "/done") | ("POST", "/note") if !self.writable => {
+                Response::error(403, "the server is read-only")
+            }
+            ("POST", "/add") => self.add(request, &conf, false),
+            ("POST", "/note") => self.add(request, &conf, true),
+            ("POST", "/done") => self.done(&conf),
+            (_, "/status") | (_, "/summary") | (_, "/statistics") => {
+                Response::error(405, format!("{} requires GET", request.path))
+            }
+            (_, "/add") | (_, "/done") | (_, "/note") => {
+                Response::error(405, format!("{} requires POST", request.path))
+            }
+            _ => Response::error(404, format!("no such endpoint: {}", request.path)),
+        }
+    }
+    // adds an event or a note
+    fn add(&self, request: &Request, conf: &Configuration, note: bool) -> Response {
+        let (description, tags) = match description_and_tags(&request.body) {
+            Ok(pair) => pair,
+            Err(e) => return Response::error(400, e),
+        };
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reader = match open_log(conf) {
+            Ok(reader) => reader,
+            Err(response) => return response,
+        };
+        let json = if note {
+            let (note, _) = reader.append_note(description, tags);
+            note.to_json(&note.time, conf)
+        } else {
+            let (event, _) = reader.append_event(description, tags);
+            event.to_json(&event.start, conf)
+        };
+        notify(if note { "note" } else { "add" }, &json, conf, self.matches);
+        Response::new(201, json)
+    }
+    fn done(&self, conf: &Configuration) -> Response {
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reader = match open_log(conf) {
+            Ok(reader) => reader,
+            Err(response) => return response,
+        };
+        match reader.last_event() {
+            Some(event) if event.ongoing() => {
+                let (done, _) = reader.close_event();
+                let json = done_json(&done, &event, conf);
+                notify("done", &json, conf, self.matches);
+                Response::new(201, json)
+            }
+            Some(_) => Response::error(409, "the most recent event is not ongoing"),
+            None => Response::error(409, "there is currently no event in the log"),
+        }
+    }
+}
+
+// opens the log or explains why it could not be opened
+fn open_log(conf: &Configuration) -> Result<LogController, Response> {
+    LogController::new(None, conf)
+        .map_err(|e| Response::error(500, format!("could not read the log: {}", e)))
+}
+
+// compares a secret in time independent of where the strings first differ
+fn same_secret(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn status(conf: &Configuration) -> Response {
+    let mut reader = match open_log(conf) {
+        Ok(reader) => reader,
+        Err(response) => return response,
+    };
+    let now = current_time();
+    let body = match reader.last_event() {
+        Some(event) => format!(
+            r#"{{"ongoing":{},"event":{}}}"#,
+            event.ongoing(),
+            event.to_json(&now, conf)
+        ),
+        None => String::from(r#"{"ongoing":false,"event":null}"#),
+    };
+    Response::new(200, body)
+}
+
+fn summary(request: &Request, conf: &Configuration) -> Response {
+    let matches = match query_matches(request) {
+        Ok(matches) => matches,
+        Err(e) => return Response::error(400, e),
+    };
+    let mut reader = match open_log(conf) {
+        Ok(reader) => reader,
+        Err(response) => return response,
+    };
+    let (start, end) = match period(&matches, &mut reader, conf) {
+        Ok(range) => range,
+        Err(e) => return Response::error(400, e),
+    };
+    let now = current_time();
+    let filter = Filter::new(&matches);
+    let items: Vec<String> = if matches.is_present("notes") {
+        reader
+            .notes_in_range(&start, &end)
+            .into_iter()
+            .filter(|n| filter.matches(n))
+            .map(|n| n.to_json(&now, conf))
+            .collect()
+    } else {
+        events(&mut reader, &start, &end, &filter, conf)
+            .iter()
+            .map(|e| e.to_json(&now, conf))
+            .collect()
+    };
+    Response::new(200, format!("[{}]", items.join(",")))
+}
+
+fn statistics(request: &Request, conf: &Configuration) -> Response {
+    let matches = match query_matches(request) {
+        Ok(matches) => matches,
+        Err(e) => return Response::error(400, e),
+    };
+    let mut reader = match open_log(conf) {
+        Ok(reader) => reader,
+        Err(response) => return response,
+    };
+    let (start, end) = match period(&matches, &mut reader, conf) {
+        Ok(range) => range,
+        Err(e) => return Response::error(400, e),
+    };
+    let now = current_time();
+    let filter = Filter::new(&matches);
+    let notes = reader
+        .notes_in_range(&start, &end)
+        .iter()
+        .filter(|n| filter.matches(*n))
+        .count();
+    let events = events(&mut reader, &start, &end, &filter, conf);
+    let mut total = 0.0;
+    let mut by_tag: BTreeMap<&str, f32> = BTreeMap::new();
+    for e in &events {
+        let duration = e.duration(&now);
+        total += duration;
+        for t in &e.tags {
+            *by_tag.entry(t).or_insert(0.0) += duration;
+        }
+    }
+    // numbers are rounded and truncated as they are for summaries
+    let hours = |d: f32| -> Value { hours_string(d, conf).parse::<f64>().unwrap().into() };
+    let tags: serde_json::Map<String, Value> = by_tag
+        .iter()
+        .map(|(t, d)| (t.to_string(), hours(*d)))
+        .collect();
+    // and also given exactly, so they reconcile
+    let tag_seconds: serde_json::Map<String, Value> = by_tag
+        .iter()
+        .map(|(t, d)| (t.to_string(), (d.round() as i64).into()))
+        .collect();
+    let body = json!({
+        "start": format!("{}", start),
+        "end": format!("{}", end),
+        "hours": hours(total),
+        "seconds": total.round() as i64,
+        "events": events.iter().filter(|e| e.vacation_type.is_none()).count(),
+        "notes": notes,
+        "tags": tags,
+        "tag-seconds": tag_seconds,
+    });
+    Response::new(200, body.to_string())
+}
+
+// the events in the period, split by day, merged, and supplemented with vacation, as in summaries
+fn events(
+    reader: &mut LogController,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    filter: &Filter,
+    conf: &Configuration,
+) -> Vec<Event> {
+    let events = reader
+        .events_in_range(start, end)
+        .into_iter()
+        .filter(|e| filter.matches(e))
+        .collect();
+    let events = Event::gather_by_day_and_merge(events, end, conf);
+    VacationController::read(None, conf.directory())
+        .add_vacation_times(start, end, events, conf, None, filter)
+}
+
+// interprets the period parameter, narrowing it to the span of the log as the summary subcommand does
+fn period(
+    matches: &ArgMatches,
+    reader: &mut LogController,
+    conf: &Configuration,
+) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let phrase = matches
+        .values_of("period")
+        .map(|values| values.collect::<Vec<_>>().join(" "))
+        .unwrap_or_else(|| String::from("today"));
+    let expression = expand_period(&phrase, false, conf);
+    match parse(&expression, conf.logical_two_timer_config()) {
+        Ok((start, end, _)) => {
+            let (mut start, mut end) = conf.shift_to_day_boundary(start, end);
+            if let Some(time) = reader.first_timestamp() {
+                if time > start {
+                    start = conf.start_of_day(&conf.logical_date(&time));
+                }
+            }
+            let now = current_time();
+            let tomorrow = conf.start_of_day(&(conf.logical_date(&now) + Duration::days(1)));
+            if end > tomorrow {
+                end = tomorrow;
+            }
+            Ok((start, end))
+        }
+        Err(_) => Err(format!("could not parse '{}' as a time expression", phrase)),
+    }
+}
+
+// converts query parameters into the options of the summary subcommand so they are validated
+// and interpreted exactly as they are on the command line
+fn query_matches(request: &Request) -> Result<ArgMatches<'static>, String> {
+    let app = common_search_or_filter_arguments(
+        App::new("query")
+            .setting(AppSettings::ColorNever)
+            .setting(AppSettings::DisableVersion)
+            .arg(Arg::with_name("period").multiple(true)),
+        None,
+    );
+    let mut args = vec![String::from("query")];
+    let mut period = vec![];
+    for (name, value) in &request.query {
+        match name.as_str() {
+            "period" => period.push(value.clone()),
+            "notes" | "no-tags" => {
+                if value != "false" {
+                    args.push(format!("--{}", name))
+                }
+            }
+            "help" => return Err(String::from("unknown parameter: help")),
+            _ => args.push(format!("--{}={}", name, value)),
+        }
+    }
+    if !period.is_empty() {
+        args.push(String::from("--"));
+        args.push(period.join(" "));
+    }
+    app.get_matches_from_safe(args)
+        .map_err(|e| clap_error_message(&e))
+}
+
+// the description and tags of a new event or note
+fn description_and_tags(body: &str) -> Result<(String, Vec<String>), String> {
+    let value: Value =
+        serde_json::from_str(body).map_err(|e| format!("the body is not valid JSON: {}", e))?;
+    let description = match value.get("description").and_then(|d| d.as_str()) {
+        Some(d) if some_nws(d) && !d.contains('\n') => d.trim().to_owned(),
+        _ => return Err(String::from(
+            "the body must have a description: a single line with some non-whitespace character",
+        )),
+    };
+    let tags = match value.get("tags") {
+        None | Some(Value::Null) => vec![],
+        Some(Value::Array(values)) => {
+            let mut tags = Vec::with_capacity(values.len());
+            for v in values {
+                match v.as_str() {
+                    Some(t) if some_nws(t) && !t.contains('\n') => tags.push(t.to_owned()),
+                    _ => {
+                        return Err(format!(
+                            "{} is not a suitable tag: tags must be strings with some non-whitespace character",
+                            v
+                        ))
+                    }
+                }
+            }
+            tags
+        }
+        Some(_) => return Err(String::from("tags must be a list of strings")),
+    };
+    Ok((description, tags))
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>, // names are lowercased
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&String> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+// reads from a connection until a deadline for the whole request, so a client cannot keep a
+// connection open by trickling in bytes
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> Deadline<'a> {
+    fn new(stream: &'a TcpStream, deadline: Instant) -> Deadline<'a> {
+        Deadline { stream, deadline }
+    }
+}
+
+impl<'a> Read for Deadline<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self
+            .deadline
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::TimedOut, "the request took too long")
+            })?;
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+fn parse_request<R: Read>(stream: R) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("could not read request: {}", e))?;
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 3 || !parts[2].starts_with("HTTP/") {
+        return Err(String::from("malformed request line"));
+    }
+    let method = parts[0].to_owned();
+    let (path, query) = match parts[1].find('?') {
+        Some(i) => (&parts[1][..i], parse_query(&parts[1][i + 1..])?),
+        None => (parts[1], vec![]),
+    };
+    let path = decode(path)?;
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| format!("could not read request: {}", e))?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        match line.find(':') {
+            Some(i) => headers.push((
+                line[..i].trim().to_lowercase(),
+                line[i + 1..].trim().to_owned(),
+            )),
+            None => return Err(format!("malformed header: {}", line)),
+        }
+    }
+    let length = match headers.iter().find(|(n, _)| n == "content-length") {
+        Some((_, v)) => v
+            .parse::<usize>()
+            .map_err(|_| format!("bad Content-Length: {}", v))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(String::from("the request body is too large"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("could not read request body: {}", e))?;
+    let body = String::from_utf8(body).map_err(|_| String::from("the body is not UTF-8"))?;
+    Ok(Request {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+fn parse_query(query: &str) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = vec![];
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (name, value) = match pair.find('=') {
+            Some(i) => (&pair[..i], &pair[i + 1..]),
+            None => (pair, ""),
+        };
+        pairs.push((decode(name)?, decode(value)?));
+    }
+    Ok(pairs)
+}
+
+// undoes URL encoding
+fn decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = s
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("bad escape in {}", s))?;
+                decoded.push(byte);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| format!("{} does not decode to UTF-8", s))
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn new(status: u16, body: String) -> Response {
+        Response { status, body }
+    }
+    fn error<T: ToString>(status: u16, message: T) -> Response {
+        Response::new(status, json!({ "error": message.to_string() }).to_string())
+    }
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            _ => "Internal Server Error",
+        }
+    }
+    fn write_to<W: Write>(&self, stream: &mut W) -> std::io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\n\
+            Content-Type: application/json\r\n\
+            Content-Length: {}\r\n\
+            Access-Control-Allow-Origin: *\r\n\
+            Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+            Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+            Connection: close\r\n\
+            \r\n\
+            {}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request(
+            "POST /add?period=last+week&tag=a%20b HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer foo\r\nContent-Length: 17\r\n\r\n{\"description\":1}"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!("POST", request.method);
+        assert_eq!("/add", request.path);
+        assert_eq!(
+            vec![
+                (String::from("period"), String::from("last week")),
+                (String::from("tag"), String::from("a b"))
+            ],
+            request.query
+        );
+        assert_eq!(
+            Some(&String::from("Bearer foo")),
+            request.header("authorization")
+        );
+        assert_eq!("{\"description\":1}", request.body);
+        assert!(parse_request("GET /\r\n\r\n".as_bytes()).is_err());
+        assert!(decode("%E2%9C%93").unwrap() == "✓");
+        assert!(decode("%G0").is_err());
+    }
+
+    #[test]
+    fn test_same_secret() {
+        assert!(same_secret("Bearer foo", "Bearer foo"));
+        assert!(!same_secret("Bearer fop", "Bearer foo"));
+        assert!(!same_secret("Bearer fo", "Bearer foo"));
+        assert!(!same_secret("", "Bearer foo"));
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let request = parse_request(
+            "GET /summary?period=last&period=week&tag=foo&tag=bar&notes HTTP/1.1\r\n\r\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let matches = query_matches(&request).unwrap();
+        assert_eq!(
+            vec!["foo", "bar"],
+            matches.values_of("tag").unwrap().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["last week"],
+            matches.values_of("period").unwrap().collect::<Vec<_>>()
+        );
+        assert!(matches.is_present("notes"));
+        let request = parse_request("GET /summary?plugh=1 HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+        assert!(query_matches(&request).is_err());
+    }
+
+    #[test]
+    fn test_description_and_tags() {
+        assert_eq!(
+            (String::from("foo"), vec![String::from("bar")]),
+            description_and_tags(r#"{"description":" foo ","tags":["bar"]}"#).unwrap()
+        );
+        assert_eq!(
+            (String::from("foo"), vec![]),
+            description_and_tags(r#"{"description":"foo"}"#).unwrap()
+        );
+        assert!(description_and_tags(r#"{"description":" "}"#).is_err());
+        assert!(description_and_tags(r#"{"description":"foo","tags":[1]}"#).is_err());
+        assert!(description_and_tags("foo").is_err());
+    }
+}
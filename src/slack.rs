@@ -0,0 +1,126 @@
+// stuff for keeping your Slack status in step with the log
+extern crate serde_json;
+
+use crate::calendar::quote;
+use crate::configure::Configuration;
+use crate::util::warn;
+use serde_json::{json, Value};
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const URL: &str = "https://slack.com/api/users.profile.set";
+// the seconds allowed for the update
+const TIMEOUT: usize = 10;
+// the longest status Slack permits
+const MAX_LENGTH: usize = 100;
+
+// sets the Slack status to reflect the change to the log, if a token is provided; like the
+// CalDAV password it comes from the environment, so it is never written to config.ini, which
+// sync shares
+pub fn update_status(action: &str, item: &str, conf: &Configuration) {
+    if conf.stale_slack_token {
+        warn(
+            "config.ini holds a Slack token, which job no longer reads; put it in the \
+            JOBLOG_SLACK_TOKEN environment variable and remove it with \
+            `job configure --unset slack-token`",
+            conf,
+        );
+    }
+    let token = match env::var("JOBLOG_SLACK_TOKEN") {
+        Ok(token) if !token.trim().is_empty() => token,
+        _ => return,
+    };
+    let text = match status(action, item) {
+        Some(text) => text,
+        None => return,
+    };
+    let profile = if text.is_empty() {
+        json!({"status_text": "", "status_emoji": "", "status_expiration": 0})
+    } else {
+        json!({"status_text": text, "status_emoji": conf.slack_emoji, "status_expiration": 0})
+    };
+    if let Err(e) = post(token.trim(), &json!({ "profile": profile }).to_string()) {
+        warn(format!("could not update your Slack status: {}", e), conf);
+    }
+}
+
+// the status text for a change to the log: the description of the event begun, an empty string
+// if the status should be cleared, or None if the change has no bearing on the status
+fn status(action: &str, item: &str) -> Option<String> {
+    if action == "done" || action == "cancel" {
+        return Some(String::new());
+    }
+    let item: Value = serde_json::from_str(item).ok()?;
+    let event = match action {
+        "add" | "append" | "resume" => &item,
+        "switch" => &item["event"],
+        _ => return None,
+    };
+    let description = event["description"].as_str()?.trim();
+    Some(description.chars().take(MAX_LENGTH).collect())
+}
+
+// the token goes to curl through its standard input so it doesn't appear among the arguments
+// of a running process; as with webhooks, curl runs in the background so Slack cannot hold up
+// the command, and a failure is reported only if curl cannot be started
+fn post(token: &str, body: &str) -> Result<(), String> {
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            &TIMEOUT.to_string(),
+            "--header",
+            "Content-Type: application/json; charset=utf-8",
+            "--data-binary",
+            body,
+            "--config",
+            "-",
+            URL,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(format!("header = \"Authorization: Bearer {}\"\n", quote(token)).as_bytes())
+        .map_err(|e| format!("could not configure curl: {}", e))?;
+    // the thread is never joined; it only reaps curl so the server does not accumulate zombies
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        let event = r#"{"type":"Event","description":"fixing the parser","tags":["plugh"]}"#;
+        assert_eq!(
+            Some(String::from("fixing the parser")),
+            status("add", event)
+        );
+        assert_eq!(
+            Some(String::from("fixing the parser")),
+            status("resume", event)
+        );
+        assert_eq!(
+            Some(String::from("fixing the parser")),
+            status(
+                "switch",
+                &format!(r#"{{"type":"Switch","ended":null,"event":{}}}"#, event)
+            )
+        );
+        assert_eq!(Some(String::new()), status("done", r#"{"type":"Done"}"#));
+        assert_eq!(None, status("note", event));
+        let long = format!(r#"{{"description":"{}"}}"#, "x".repeat(200));
+        assert_eq!(Some(MAX_LENGTH), status("add", &long).map(|s| s.len()));
+    }
+}
@@ -1,15 +1,24 @@
 extern crate chrono;
 extern crate clap;
 extern crate colonnade;
+extern crate serde_json;
 extern crate two_timer;
 
 use crate::configure::Configuration;
-use crate::log::{Done, Item, ItemsAfter, LogController};
-use crate::util::{fatal, log_path, remainder, Style};
-use chrono::{Local, NaiveDateTime};
+use crate::issue::Issues;
+use crate::log::{elapsed_seconds, Done, Event, Filter, Item, ItemsAfter};
+use crate::pager::page;
+use crate::util::{
+    current_time, display_estimates, duration_string, estimate_rows, estimates, expand_period,
+    fatal, include_archives_argument, info, log_reader, raw_argument, remainder, tsv_row, warn,
+    weekday_argument, weekdays, LogReader, Style,
+};
+use crate::vacation::VacationController;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use colonnade::{Alignment, Colonnade};
-use std::collections::BTreeSet;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
 use two_timer::parse;
 
 fn after_help() -> &'static str {
@@ -21,6 +30,7 @@ If you want aggregate statistics about your job log, this is your subcommand.
   first timestamp     2014-10-06 08:57:29
   last timestamp      2020-01-31 16:50:22
   hours clocked                    10,701
+  vacation hours                    1,152
   events                           14,529
   notes                               202
   distinct event tags               2,337
@@ -29,6 +39,85 @@ If you want aggregate statistics about your job log, this is your subcommand.
   blank lines                           2
   errors                                0
 
+To see statistics for only certain days of the week, use --weekday; e.g., \
+`job statistics --weekday weekend`.
+
+Hours clocked are the hours logged. Vacation hours are those the vacation records add to fill \
+out work days, as in a summary. To see only one or the other, use --no-vacation or \
+--only-vacation.
+
+Hours are rounded for display. With --raw durations are shown as exact numbers of seconds \
+instead. The hours in --json output are never rounded.
+
+With --tsv each row of the tables is written as tab-separated values, the numbers without commas \
+and the rows of every table after the first beginning with the table's name.
+
+With --untagged the statistics include the hours spent on events without tags, followed by \
+the longest such events:
+
+  > job statistics --untagged 'last month'
+  ...
+  untagged hours                       14
+  untagged events                      23
+
+  longest untagged events
+    2020-01-14  3.25  reading the spec
+  ...
+
+The summary subcommand's --untagged option shows all of them.
+
+With --by-issue the statistics are followed by the hours spent on each issue referred to in the \
+descriptions of events, most first:
+
+  > job statistics --by-issue 'this month'
+  ...
+  hours by issue
+    PROJ-123  14.25
+    #412       3.50
+
+References to issues are recognized by the patterns set with `job configure --issue-pattern`. \
+An event referring to several issues counts toward each of them.
+
+With --estimates the statistics are followed by a comparison of the time estimated for events \
+begun in the period, with `job add --estimate`, to the time they took, in all and by tag:
+
+  > job statistics --estimates 'this month'
+  ...
+  estimates  estimated  actual  actual/estimated
+  TOTAL          12.00   15.50              129%
+  auth            4.00    7.25              181%
+
+With --vacation the statistics are followed by the split of the time in the period among work, \
+vacation, and holidays -- the time added by repeating vacation records -- and by the vacation \
+and holiday time taken in each year, by category:
+
+  > job statistics --vacation 'this year'
+  ...
+  time by kind
+    worked    1,412.50  92%
+    vacation     72.00   5%
+    holiday      48.00   3%
+
+  vacation by year
+    year  category  days  hours
+    2020  flex      0.50   4.00
+    2020  holiday   6.00  48.00
+    2020  ordinary  8.50  68.00
+
+A day of vacation is a day's expected hours of it, so half of a flex day counts as half a day. \
+Time off on the days of the week omitted with --weekday is left out, as elsewhere.
+
+If the log was truncated with --carry-forward, the totals recorded at the top of the log are \
+included in the statistics of any period beginning before them. The hours of untagged events \
+and of issues are not carried forward. With --include-archives the heads the truncate \
+subcommand cut from the log are read instead, so nothing is lost.
+
+With --json the same statistics are given as a single JSON object, hours as fractional \
+numbers and timestamps as strings, for scripts and dashboards:
+
+  > job statistics --json
+  {\"blank-lines\":2,\"comments\":1333,\"distinct-event-tags\":2337,...,\"vacation-hours\":1152.0}
+
 All prefixes of 'statistics' after 's' -- 'st', 'sta', 'stat', etc. -- are aliases of \
 this subcommand, as is 'stats'. The 's' prefix is reserved for the summary subcommand.
 "
@@ -55,6 +144,64 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                     .help("Shows counts without the comma group separator")
                     .display_order(1),
             )
+            .arg(
+                Arg::with_name("fiscal")
+                    .long("fiscal")
+                    .help("Interprets 'this year', 'last year', etc. as the fiscal year")
+                    .long_help("If you have set fiscal-year-start in the configuration, this causes 'this year', 'last year', and 'next year' \
+                    to mean the fiscal rather than the calendar year. Regardless of this option, 'this fiscal year' and the like always mean the fiscal year.")
+                    .display_order(2),
+            )
+            .arg(weekday_argument().display_order(3))
+            .arg(
+                Arg::with_name("no-vacation")
+                    .long("no-vacation")
+                    .help("Leaves out vacation hours")
+                    .conflicts_with("only-vacation")
+                    .display_order(4),
+            )
+            .arg(
+                Arg::with_name("only-vacation")
+                    .long("only-vacation")
+                    .help("Shows vacation hours rather than hours clocked")
+                    .display_order(5),
+            )
+            .arg(
+                Arg::with_name("untagged")
+                    .long("untagged")
+                    .help("Reports time spent on events without tags")
+                    .long_help("Adds the hours spent on events without tags and the number of such events, followed \
+                    by a list of the longest of them, so you can find and tag unbillable time.")
+                    .display_order(6),
+            )
+            .arg(
+                Arg::with_name("by-issue")
+                    .long("by-issue")
+                    .help("Reports time spent on each issue")
+                    .long_help("Adds the hours spent on each issue referred to in event descriptions, as \
+                    recognized by the patterns set with `job configure --issue-pattern`.")
+                    .display_order(7),
+            )
+            .arg(
+                Arg::with_name("estimates")
+                    .long("estimates")
+                    .help("Compares estimated to actual time")
+                    .long_help("Adds a comparison of the time estimated for events begun in the period to the \
+                    time they took, in all and by tag. See `job add --estimate`.")
+                    .display_order(8),
+            )
+            .arg(
+                Arg::with_name("vacation")
+                    .long("vacation")
+                    .help("Reports work, vacation, and holiday time")
+                    .long_help("Adds the split of the time in the period among work, vacation, and holidays, \
+                    holidays being the time added by repeating vacation records, followed by the vacation \
+                    and holiday time taken in each year, by category.")
+                    .conflicts_with_all(&["no-vacation", "only-vacation"])
+                    .display_order(9),
+            )
+            .arg(include_archives_argument().display_order(10))
+            .arg(raw_argument().display_order(11))
             .about("Shows overall statistics of the log")
             .setting(AppSettings::TrailingVarArg)
             .arg(
@@ -71,17 +218,20 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
 }
 
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
-    let no_commas = matches.is_present("raw-numbers");
-    let conf = Configuration::read(None, directory);
+    let tsv = matches.is_present("tsv");
+    let no_commas = matches.is_present("raw-numbers") || tsv;
+    let mut conf = Configuration::read(None, directory);
+    conf.raw = matches.is_present("raw");
+    let unit = if conf.raw { "seconds" } else { "hours" };
     let style = Style::new(&conf);
     let mut colonnade =
         Colonnade::new(2, conf.width()).expect("could not build the statistics table");
     colonnade.columns[1].alignment(Alignment::Right);
-    let (start_offset, end_time, mut maybe_start_time) = where_to_begin(matches, &conf);
-    let items = ItemsAfter::new(
-        start_offset,
-        log_path(conf.directory()).as_path().to_str().unwrap(),
-    );
+    let mut reader = log_reader(None, matches, &conf).expect("could not read log");
+    let (start_offset, end_time, mut maybe_start_time) =
+        where_to_begin(matches, &mut reader, &conf);
+    let period_start = maybe_start_time;
+    let items = ItemsAfter::from_storage(start_offset, &reader.storage);
     let mut line_count = 0;
     let mut event_count = 0;
     let mut note_count = 0;
@@ -94,11 +244,26 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let mut last_timestamp: Option<NaiveDateTime> = None;
     let mut duration = 0;
     let mut open_timetamp: Option<NaiveDateTime> = None;
+    let days = weekdays(matches);
+    // whether the current line falls on one of the days considered; lines without timestamps
+    // belong to the day of the timestamp before them
+    let mut on_day = true;
     for item in items {
         if let Some((t, _)) = item.time() {
             if t > &end_time {
                 break;
             }
+            if let Some(days) = &days {
+                on_day = days.contains(&conf.logical_date(t).weekday());
+            }
+        }
+        if !on_day {
+            if let Item::Done(_, _) = item {
+                open_timetamp = None;
+            }
+            continue;
+        }
+        if let Some((t, _)) = item.time() {
             if maybe_start_time.is_none() {
                 maybe_start_time = Some(t.clone());
             }
@@ -131,14 +296,205 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             Item::Comment(_) => comment_count += 1,
             Item::Done(Done(d), _) => {
                 if let Some(t) = open_timetamp {
-                    duration += (d.timestamp() - t.timestamp()) as usize;
+                    duration += elapsed_seconds(&t, &d) as usize;
                 }
                 open_timetamp = None;
             }
             Item::Error(_, _) => error_count += 1,
         }
     }
-    let data = [
+    // the totals truncate carried forward belong to any period reaching from before them into
+    // what remains of the log
+    if first_timestamp.is_some() && days.is_none() {
+        let carried = reader.carried_forward().filter(|c| {
+            c.first
+                .is_some_and(|t| period_start.is_none_or(|start| start <= t))
+        });
+        if let Some(carried) = carried {
+            info(
+                format!(
+                    "including the totals carried forward from before {}",
+                    first_timestamp.unwrap()
+                ),
+                &conf,
+            );
+            duration += (carried.hours * 60.0 * 60.0).round() as usize;
+            event_count += carried.events;
+            note_count += carried.notes;
+            event_tags.extend(carried.tags.into_keys());
+            note_tags.extend(carried.note_tags);
+            first_timestamp = carried.first;
+            maybe_start_time = carried.first;
+        }
+    }
+    let clocked = !matches.is_present("only-vacation");
+    let now = current_time();
+    let vacation = match maybe_start_time {
+        Some(start) if !matches.is_present("no-vacation") => {
+            vacation_seconds(&mut reader, &start, &end_time, days.as_ref(), &conf)
+        }
+        _ => 0,
+    };
+    let kinds = match maybe_start_time {
+        Some(start) if matches.is_present("vacation") => Some(time_by_kind(
+            &with_vacation(&mut reader, &start, &end_time, days.as_ref(), &conf),
+            &now,
+            &conf,
+        )),
+        _ => None,
+    };
+    let untagged = match maybe_start_time {
+        Some(start) if matches.is_present("untagged") => Some(untagged_events(
+            &mut reader,
+            &start,
+            &end_time,
+            days.as_ref(),
+            &now,
+            &conf,
+        )),
+        _ => None,
+    };
+    let by_issue = match maybe_start_time {
+        Some(start) if matches.is_present("by-issue") => {
+            if conf.issue_patterns.is_empty() {
+                warn(
+                    "no issue patterns are configured; see `job configure --issue-pattern`",
+                    &conf,
+                );
+            }
+            Some(issue_seconds(
+                &mut reader,
+                &start,
+                &end_time,
+                days.as_ref(),
+                &now,
+                &conf,
+            ))
+        }
+        _ => None,
+    };
+    let estimates = match maybe_start_time {
+        Some(start) if matches.is_present("estimates") => {
+            let events = estimated_events(&mut reader, &start, &end_time, days.as_ref(), &conf);
+            let estimates = estimates(&events, &now);
+            if estimates.is_none() {
+                warn("no event begun in the period has an estimate", &conf);
+            }
+            estimates
+        }
+        _ => None,
+    };
+    let untagged_seconds = untagged.as_ref().map(|events| {
+        events
+            .iter()
+            .map(|e| e.duration(&now) as usize)
+            .sum::<usize>()
+    });
+    if matches.is_present("json") {
+        let mut json = json!({
+            "lines": line_count,
+            "first-timestamp": first_timestamp.map(|t| format!("{}", t)),
+            "last-timestamp": last_timestamp.map(|t| format!("{}", t)),
+            "hours-clocked": (duration as f64) / (60.0 * 60.0),
+            "vacation-hours": (vacation as f64) / (60.0 * 60.0),
+            "events": event_count,
+            "notes": note_count,
+            "distinct-event-tags": event_tags.len(),
+            "distinct-note-tags": note_tags.len(),
+            "comments": comment_count,
+            "blank-lines": blank_line_count,
+            "errors": error_count,
+        });
+        if !clocked {
+            json.as_object_mut().unwrap().remove("hours-clocked");
+        }
+        if matches.is_present("no-vacation") {
+            json.as_object_mut().unwrap().remove("vacation-hours");
+        }
+        if let Some(events) = &untagged {
+            let json = json.as_object_mut().unwrap();
+            json.insert(
+                String::from("untagged-hours"),
+                json!((untagged_seconds.unwrap() as f64) / (60.0 * 60.0)),
+            );
+            json.insert(String::from("untagged-events"), json!(events.len()));
+            json.insert(
+                String::from("longest-untagged-events"),
+                events
+                    .iter()
+                    .take(LONGEST_UNTAGGED)
+                    .map(|e| serde_json::from_str(&e.to_json(&now, &conf)).unwrap())
+                    .collect::<Vec<serde_json::Value>>()
+                    .into(),
+            );
+        }
+        if let Some(issues) = &by_issue {
+            json.as_object_mut().unwrap().insert(
+                String::from("hours-by-issue"),
+                issues
+                    .iter()
+                    .map(|(issue, seconds)| (issue.clone(), json!(seconds / (60.0 * 60.0))))
+                    .collect::<serde_json::Map<String, serde_json::Value>>()
+                    .into(),
+            );
+        }
+        if let Some(((estimated, actual), by_tag)) = &estimates {
+            let hours = |(estimated, actual): (f32, f32)| {
+                json!({
+                    "estimated": estimated / (60.0 * 60.0),
+                    "actual": actual / (60.0 * 60.0),
+                })
+            };
+            let json = json.as_object_mut().unwrap();
+            json.insert(String::from("estimates"), hours((*estimated, *actual)));
+            json.insert(
+                String::from("estimates-by-tag"),
+                by_tag
+                    .iter()
+                    .map(|(tag, pair)| (tag.clone(), hours(*pair)))
+                    .collect::<serde_json::Map<String, serde_json::Value>>()
+                    .into(),
+            );
+        }
+        if let Some(kinds) = &kinds {
+            let hours = |seconds: f32| json!(seconds / (60.0 * 60.0));
+            let mut by_year = serde_json::Map::new();
+            for ((year, category), (days, seconds)) in kinds.by_year.iter() {
+                by_year
+                    .entry(year.to_string())
+                    .or_insert_with(|| json!({}))
+                    .as_object_mut()
+                    .unwrap()
+                    .insert(
+                        category.clone(),
+                        json!({ "days": days, "hours": hours(*seconds) }),
+                    );
+            }
+            let json = json.as_object_mut().unwrap();
+            json.insert(
+                String::from("time-by-kind"),
+                json!({
+                    "worked": hours(kinds.worked),
+                    "vacation": hours(kinds.vacation),
+                    "holiday": hours(kinds.holiday),
+                }),
+            );
+            json.insert(String::from("vacation-by-year"), by_year.into());
+        }
+        println!("{}", json);
+        return;
+    }
+    let hours = |seconds: usize| {
+        if conf.raw {
+            format_num(seconds, no_commas)
+        } else {
+            format_num(
+                ((seconds as f64) / (60.0 * 60.0)).round() as usize,
+                no_commas,
+            )
+        }
+    };
+    let mut data = vec![
         [String::from("lines"), format_num(line_count, no_commas)],
         [
             String::from("first timestamp"),
@@ -156,16 +512,14 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 String::from("")
             },
         ],
-        [
-            String::from("hours clocked"),
-            format!(
-                "{}",
-                format_num(
-                    ((duration as f64) / (60.0 * 60.0)).round() as usize,
-                    no_commas
-                )
-            ),
-        ],
+    ];
+    if clocked {
+        data.push([format!("{} clocked", unit), hours(duration)]);
+    }
+    if !matches.is_present("no-vacation") {
+        data.push([format!("vacation {}", unit), hours(vacation)]);
+    }
+    data.extend(vec![
         [String::from("events"), format_num(event_count, no_commas)],
         [String::from("notes"), format_num(note_count, no_commas)],
         [
@@ -185,7 +539,69 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             format_num(blank_line_count, no_commas),
         ],
         [String::from("errors"), format_num(error_count, no_commas)],
-    ];
+    ]);
+    if let Some(events) = &untagged {
+        data.push([
+            format!("untagged {}", unit),
+            hours(untagged_seconds.unwrap()),
+        ]);
+        data.push([
+            String::from("untagged events"),
+            format_num(events.len(), no_commas),
+        ]);
+    }
+    if tsv {
+        // each row of the other tables begins with the table's name
+        for row in data.iter() {
+            println!("{}", tsv_row(row));
+        }
+        if let Some(events) = &untagged {
+            for e in events.iter().take(LONGEST_UNTAGGED) {
+                println!(
+                    "{}",
+                    tsv_row(&[
+                        "longest untagged events",
+                        &e.start.format("%F").to_string(),
+                        &duration_string(e.duration(&now), &conf),
+                        &e.description,
+                    ])
+                );
+            }
+        }
+        if let Some(issues) = &by_issue {
+            let section = format!("{} by issue", unit);
+            for (issue, seconds) in issues.iter() {
+                println!(
+                    "{}",
+                    tsv_row(&[&section, issue, &duration_string(*seconds, &conf)])
+                );
+            }
+        }
+        if let Some(estimates) = &estimates {
+            for row in estimate_rows(estimates, &conf) {
+                println!(
+                    "{}",
+                    tsv_row(&[&["estimates".to_owned()], &row[..]].concat())
+                );
+            }
+        }
+        if let Some(kinds) = &kinds {
+            for row in kind_rows(kinds, &conf) {
+                println!(
+                    "{}",
+                    tsv_row(&[&["time by kind".to_owned()], &row[..]].concat())
+                );
+            }
+            for row in year_rows(kinds, &conf) {
+                println!(
+                    "{}",
+                    tsv_row(&[&["vacation by year".to_owned()], &row[..]].concat())
+                );
+            }
+        }
+        return;
+    }
+    let _pager = page(&conf, matches);
     for (i, line) in colonnade
         .tabulate(&data)
         .expect("couild not tabulate data")
@@ -201,19 +617,334 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             }
         );
     }
+    if let Some(events) = untagged.filter(|events| !events.is_empty()) {
+        let mut colonnade =
+            Colonnade::new(3, conf.width()).expect("could not build the untagged events table");
+        colonnade
+            .left_margin(2)
+            .expect("could not build the untagged events table -- setting margin");
+        colonnade.columns[1]
+            .alignment(Alignment::Right)
+            .left_margin(2);
+        colonnade.columns[2].left_margin(2).priority(1);
+        let data = events
+            .iter()
+            .take(LONGEST_UNTAGGED)
+            .map(|e| {
+                [
+                    e.start.format("%F").to_string(),
+                    duration_string(e.duration(&now), &conf),
+                    e.description.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        println!();
+        println!("{}", style.paint("header", "longest untagged events"));
+        for line in colonnade.tabulate(&data).expect("could not tabulate data") {
+            println!("{}", line);
+        }
+    }
+    if let Some(issues) = by_issue.filter(|issues| !issues.is_empty()) {
+        let mut colonnade =
+            Colonnade::new(2, conf.width()).expect("could not build the issue table");
+        colonnade
+            .left_margin(2)
+            .expect("could not build the issue table -- setting margin");
+        colonnade.columns[1]
+            .alignment(Alignment::Right)
+            .left_margin(2);
+        let data = issues
+            .iter()
+            .map(|(issue, seconds)| [issue.clone(), duration_string(*seconds, &conf)])
+            .collect::<Vec<_>>();
+        println!();
+        println!("{}", style.paint("header", format!("{} by issue", unit)));
+        for line in colonnade.tabulate(&data).expect("could not tabulate data") {
+            println!("{}", line);
+        }
+    }
+    if let Some(estimates) = estimates {
+        println!();
+        display_estimates(&estimates, &conf);
+    }
+    if let Some(kinds) = kinds {
+        display_time_by_kind(&kinds, &style, &conf);
+    }
+}
+
+// the seconds of work, vacation, and holiday time in a period, and the days and seconds of
+// vacation and holiday time taken in each year by category
+struct TimeByKind {
+    worked: f32,
+    vacation: f32,
+    holiday: f32,
+    by_year: BTreeMap<(i32, String), (f32, f32)>,
+}
+
+// the events begun in the period, divided by day, with the vacation time added to fill out work
+// days, as in a summary
+fn with_vacation(
+    reader: &mut LogReader,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    days: Option<&Vec<Weekday>>,
+    conf: &Configuration,
+) -> Vec<Event> {
+    let events = Event::gather_by_day(reader.events_in_range(start, end), end, conf);
+    VacationController::read(None, conf.directory())
+        .add_vacation_times(start, end, events, conf, None, &Filter::dummy())
+        .into_iter()
+        .filter(|e| e.start >= *start)
+        .filter(|e| days.is_none_or(|d| d.contains(&conf.logical_date(&e.start).weekday())))
+        .collect()
+}
+
+fn time_by_kind(events: &[Event], now: &NaiveDateTime, conf: &Configuration) -> TimeByKind {
+    let mut kinds = TimeByKind {
+        worked: 0.0,
+        vacation: 0.0,
+        holiday: 0.0,
+        by_year: BTreeMap::new(),
+    };
+    for e in events.iter() {
+        let seconds = e.duration(now);
+        if !e.vacation {
+            kinds.worked += seconds;
+            continue;
+        }
+        let category = if e.holiday {
+            kinds.holiday += seconds;
+            "holiday"
+        } else {
+            kinds.vacation += seconds;
+            match e.vacation_type.as_deref() {
+                Some("") | None => "ordinary",
+                Some(t) => t,
+            }
+        };
+        let date = conf.logical_date(&e.start);
+        let entry = kinds
+            .by_year
+            .entry((date.year(), category.to_owned()))
+            .or_insert((0.0, 0.0));
+        entry.0 += day_fraction(seconds, &date, conf);
+        entry.1 += seconds;
+    }
+    kinds
+}
+
+// the seconds as a portion of the hours expected on the date
+fn day_fraction(seconds: f32, date: &NaiveDate, conf: &Configuration) -> f32 {
+    let hours = conf.day_length_on(date);
+    if hours > 0.0 {
+        seconds / (hours * 60.0 * 60.0)
+    } else {
+        0.0
+    }
+}
+
+// the rows of the time by kind table: the kind, its time, and its share of the whole
+fn kind_rows(kinds: &TimeByKind, conf: &Configuration) -> Vec<[String; 3]> {
+    let total = kinds.worked + kinds.vacation + kinds.holiday;
+    [
+        ("worked", kinds.worked),
+        ("vacation", kinds.vacation),
+        ("holiday", kinds.holiday),
+    ]
+    .iter()
+    .map(|(kind, seconds)| {
+        [
+            kind.to_string(),
+            duration_string(*seconds, conf),
+            if total > 0.0 {
+                format!("{:.0}%", 100.0 * seconds / total)
+            } else {
+                String::new()
+            },
+        ]
+    })
+    .collect()
+}
+
+// the rows of the vacation by year table: the year, category, days, and time
+fn year_rows(kinds: &TimeByKind, conf: &Configuration) -> Vec<[String; 4]> {
+    kinds
+        .by_year
+        .iter()
+        .map(|((year, category), (days, seconds))| {
+            [
+                year.to_string(),
+                category.clone(),
+                format!("{:.2}", days),
+                duration_string(*seconds, conf),
+            ]
+        })
+        .collect()
+}
+
+fn display_time_by_kind(kinds: &TimeByKind, style: &Style, conf: &Configuration) {
+    let mut colonnade =
+        Colonnade::new(3, conf.width()).expect("could not build the time by kind table");
+    colonnade
+        .left_margin(2)
+        .expect("could not build the time by kind table -- setting margin");
+    colonnade.columns[1]
+        .alignment(Alignment::Right)
+        .left_margin(2);
+    colonnade.columns[2]
+        .alignment(Alignment::Right)
+        .left_margin(2);
+    let data = kind_rows(kinds, conf);
+    println!();
+    println!("{}", style.paint("header", "time by kind"));
+    for line in colonnade.tabulate(&data).expect("could not tabulate data") {
+        println!("{}", line);
+    }
+    if kinds.by_year.is_empty() {
+        return;
+    }
+    let mut data = vec![[
+        String::from("year"),
+        String::from("category"),
+        String::from("days"),
+        String::from(if conf.raw { "seconds" } else { "hours" }),
+    ]];
+    data.extend(year_rows(kinds, conf));
+    let mut colonnade =
+        Colonnade::new(4, conf.width()).expect("could not build the vacation by year table");
+    colonnade
+        .left_margin(2)
+        .expect("could not build the vacation by year table -- setting margin");
+    colonnade.columns[1].left_margin(2);
+    colonnade.columns[2]
+        .alignment(Alignment::Right)
+        .left_margin(2);
+    colonnade.columns[3]
+        .alignment(Alignment::Right)
+        .left_margin(2);
+    println!();
+    println!("{}", style.paint("header", "vacation by year"));
+    for (i, line) in colonnade
+        .tabulate(&data)
+        .expect("could not tabulate data")
+        .iter()
+        .enumerate()
+    {
+        if i == 0 {
+            println!("{}", style.paint("header", line));
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+// the events begun in the period, in full, that have estimates
+fn estimated_events(
+    reader: &mut LogReader,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    days: Option<&Vec<Weekday>>,
+    conf: &Configuration,
+) -> Vec<Event> {
+    reader
+        .events_in_range(start, end)
+        .into_iter()
+        .filter(|e| e.estimate.is_some() && e.start >= *start)
+        .filter(|e| days.is_none_or(|d| d.contains(&conf.logical_date(&e.start).weekday())))
+        .collect()
+}
+
+// the seconds spent in the period on each issue referred to in event descriptions, most first
+fn issue_seconds(
+    reader: &mut LogReader,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    days: Option<&Vec<Weekday>>,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> Vec<(String, f32)> {
+    let issues = Issues::new(conf);
+    let mut seconds: BTreeMap<String, f32> = BTreeMap::new();
+    for mut e in reader
+        .events_in_range(start, end)
+        .into_iter()
+        .filter(|e| days.is_none_or(|d| d.contains(&conf.logical_date(&e.start).weekday())))
+    {
+        if e.start < *start {
+            e.start = *start;
+        }
+        if e.end.unwrap_or(*now) > *end {
+            e.end = Some(*end);
+        }
+        for issue in issues.references(&e.description) {
+            *seconds.entry(issue).or_insert(0.0) += e.duration(now);
+        }
+    }
+    let mut seconds = seconds.into_iter().collect::<Vec<_>>();
+    seconds.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    seconds
+}
+
+// the number of untagged events listed by --untagged
+const LONGEST_UNTAGGED: usize = 5;
+
+// the events without tags in the period, trimmed to it, longest first
+fn untagged_events(
+    reader: &mut LogReader,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    days: Option<&Vec<Weekday>>,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> Vec<Event> {
+    let mut events = reader
+        .events_in_range(start, end)
+        .into_iter()
+        .filter(|e| e.tags.is_empty())
+        .filter(|e| days.is_none_or(|d| d.contains(&conf.logical_date(&e.start).weekday())))
+        .map(|mut e| {
+            if e.start < *start {
+                e.start = *start;
+            }
+            if e.end.unwrap_or(*now) > *end {
+                e.end = Some(*end);
+            }
+            e
+        })
+        .collect::<Vec<_>>();
+    events.sort_by(|a, b| b.duration(now).partial_cmp(&a.duration(now)).unwrap());
+    events
 }
 
 fn where_to_begin(
     matches: &ArgMatches,
+    log: &mut LogReader,
     conf: &Configuration,
 ) -> (usize, NaiveDateTime, Option<NaiveDateTime>) {
     if matches.is_present("period") {
         let period = remainder("period", matches);
-        match parse(&period, conf.two_timer_config()) {
+        if matches.is_present("fiscal") && conf.fiscal_year_start.is_none() {
+            fatal(
+                "--fiscal requires that fiscal-year-start be configured",
+                conf,
+            );
+        }
+        match parse(
+            &expand_period(&period, matches.is_present("fiscal"), conf),
+            conf.two_timer_config(),
+        ) {
             Ok((t1, t2, _)) => {
-                let mut log =
-                    LogController::new(None, conf).expect("could not open log for reading");
                 if let Some(item) = log.find_line(&t1) {
+                    info(
+                        format!(
+                            "'{}' is interpreted as {} to {}; it begins at line {} of the log",
+                            period,
+                            t1,
+                            t2,
+                            item.offset() + 1
+                        ),
+                        conf,
+                    );
                     (item.offset(), t2, Some(t1))
                 } else {
                     fatal("the log does not cover the period specified", conf);
@@ -229,10 +960,26 @@ fn where_to_begin(
             }
         }
     } else {
-        (0, Local::now().naive_local(), None)
+        (0, current_time(), None)
     }
 }
 
+// the seconds of vacation time in the period, counted as a summary would count them
+fn vacation_seconds(
+    reader: &mut LogReader,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    days: Option<&Vec<Weekday>>,
+    conf: &Configuration,
+) -> usize {
+    let now = current_time();
+    with_vacation(reader, start, end, days, conf)
+        .iter()
+        .filter(|e| e.vacation)
+        .map(|e| e.duration(&now) as usize)
+        .sum()
+}
+
 fn format_num(n: usize, no_commas: bool) -> String {
     let s1 = n.to_string();
     if no_commas {
@@ -249,3 +996,48 @@ fn format_num(n: usize, no_commas: bool) -> String {
     }
     s.chars().rev().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_time_by_kind() {
+        let dir = TestDirectory::new("test_time_by_kind");
+        let conf = dir.configuration();
+        let event = |date: NaiveDate, hours: u32, kind: Option<&str>, holiday: bool| {
+            let mut e = Event::builder()
+                .start(date.and_hms(9, 0, 0))
+                .end(date.and_hms(9 + hours, 0, 0))
+                .build()
+                .unwrap();
+            e.vacation = kind.is_some();
+            e.vacation_type = kind.map(|k| k.to_owned());
+            e.holiday = holiday;
+            e
+        };
+        let monday = NaiveDate::from_ymd(2019, 12, 30);
+        let events = vec![
+            event(monday, 8, None, false),
+            event(monday.succ(), 8, Some(""), true),
+            event(monday.succ().succ(), 8, Some(""), false),
+            event(monday.succ().succ().succ(), 4, Some("flex"), false),
+        ];
+        let now = monday.and_hms(0, 0, 0) + chrono::Duration::days(7);
+        let kinds = time_by_kind(&events, &now, &conf);
+        let hour = 60.0 * 60.0;
+        assert_eq!(8.0 * hour, kinds.worked);
+        assert_eq!(12.0 * hour, kinds.vacation);
+        assert_eq!(8.0 * hour, kinds.holiday);
+        assert_eq!(
+            vec![
+                ((2019, String::from("holiday")), (1.0, 8.0 * hour)),
+                ((2020, String::from("flex")), (0.5, 4.0 * hour)),
+                ((2020, String::from("ordinary")), (1.0, 8.0 * hour)),
+            ],
+            kinds.by_year.into_iter().collect::<Vec<_>>(),
+            "split by year and category, days in expected hours"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+extern crate clap;
+extern crate serde_json;
+
+use crate::configure::Configuration;
+use crate::log::{parse_line, Done, Event, Item, LogController};
+use crate::storage::MemoryStorage;
+use crate::util::{current_time, fatal, log_path};
+use chrono::NaiveDateTime;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// the placeholders a template may contain
+const PLACEHOLDERS: [&str; 5] = ["description", "elapsed", "start", "tags", "today"];
+// the bytes first read from the end of the log; each further read doubles this
+const TAIL_SIZE: u64 = 16 * 1024;
+
+fn after_help() -> &'static str {
+    "\
+The status subcommand prints a single line describing the ongoing event, for embedding in \
+a tmux status bar, a starship prompt, or the like:
+
+  > job status
+  fixing the parser 1:25
+  > job status --format '{description} [{tags}] {elapsed}, {today} today'
+  fixing the parser [plugh] 1:25, 5:40 today
+
+The template given with --format may contain these placeholders:
+
+  {description}  the description of the ongoing event
+  {elapsed}      how long it has been going, as hours and minutes
+  {start}        when it began, as hours and minutes
+  {tags}         its tags, separated by commas
+  {today}        the time logged today, as hours and minutes
+
+Write {{ and }} for literal braces. If no event is ongoing, the text given with --idle, by \
+default nothing, is printed instead.
+
+Status bars run their commands every few seconds, so the subcommand reads only the end of the \
+log: the last event and the lines for today. It does not ask about events you forgot to end, \
+though events the auto-close policy ends count as ended.
+
+The only alias of the subcommand is 'statu', as the other prefixes of 'status' are aliases of \
+the statistics subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("status")
+            .aliases(&["statu"])
+            .about("Prints a line describing the ongoing task for a status bar")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .short("f")
+                    .help("The template of the line printed")
+                    .long_help(
+                        "A template containing any of the placeholders {description}, {elapsed}, \
+                        {start}, {tags}, and {today}.",
+                    )
+                    .value_name("template")
+                    .default_value("{description} {elapsed}")
+                    .validator(|v| {
+                        let fields = PLACEHOLDERS
+                            .iter()
+                            .map(|p| (*p, String::new()))
+                            .collect::<BTreeMap<_, _>>();
+                        render(&v, &fields).map(|_| ())
+                    })
+                    .display_order(1),
+            )
+            .arg(
+                Arg::with_name("idle")
+                    .long("idle")
+                    .help("What to print when no task is ongoing")
+                    .value_name("text")
+                    .default_value("")
+                    .display_order(2),
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let now = current_time();
+    let today = conf.start_of_day(&conf.logical_date(&now));
+    let mut reader = LogController::with_storage(
+        MemoryStorage::new(&tail(&log_path(conf.directory()), &today)),
+        &conf,
+    );
+    let ongoing = reader.last_event().filter(|e| {
+        let mut e = e.clone();
+        e.ongoing() && !e.auto_close(&now, &conf)
+    });
+    let ongoing = match ongoing {
+        Some(e) => e,
+        None => {
+            if matches.is_present("json") {
+                println!("null");
+            } else {
+                println!("{}", matches.value_of("idle").unwrap());
+            }
+            return;
+        }
+    };
+    let events = reader.events_in_range(&today, &now);
+    let logged: f32 = Event::gather_by_day(events, &now, &conf)
+        .iter()
+        .filter(|e| e.start >= today)
+        .map(|e| e.duration(&now))
+        .sum();
+    let elapsed = ongoing.duration(&now);
+    if matches.is_present("json") {
+        println!(
+            "{}",
+            json!({
+                "description": ongoing.description,
+                "tags": ongoing.tags,
+                "start": format!("{}", ongoing.start),
+                "elapsed": elapsed / (60.0 * 60.0),
+                "today": logged / (60.0 * 60.0),
+            })
+        );
+        return;
+    }
+    let mut fields = BTreeMap::new();
+    fields.insert("description", ongoing.description.clone());
+    fields.insert("elapsed", clock(elapsed));
+    fields.insert("start", ongoing.start.format("%H:%M").to_string());
+    fields.insert("tags", ongoing.tags.join(", "));
+    fields.insert("today", clock(logged));
+    match render(matches.value_of("format").unwrap(), &fields) {
+        Ok(line) => println!("{}", line),
+        Err(e) => fatal(e, &conf),
+    }
+}
+
+// the end of the log, reaching back to some event or DONE at or before the given time, or the
+// whole log if there is none; status bars poll often, so this avoids reading and indexing it all
+fn tail(log: &Path, since: &NaiveDateTime) -> String {
+    let mut file = match File::open(log) {
+        Ok(file) => file,
+        Err(_) => return String::new(),
+    };
+    let length = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut size = TAIL_SIZE;
+    loop {
+        let start = length.saturating_sub(size);
+        let mut bytes = vec![0; (length - start) as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut bytes))
+            .expect("could not read log");
+        let text = String::from_utf8_lossy(&bytes);
+        if start == 0 {
+            return text.into_owned();
+        }
+        // the first line is likely only the end of a line
+        if let Some(i) = text.find('\n') {
+            let lines = &text[i + 1..];
+            let reached = lines.lines().any(|line| match parse_line(line, 0) {
+                Item::Event(e, _) => e.start <= *since,
+                Item::Done(Done(t), _) => t <= *since,
+                _ => false,
+            });
+            if reached {
+                return lines.to_owned();
+            }
+        }
+        size *= 2;
+    }
+}
+
+// seconds as hours and minutes, e.g., 1:05
+fn clock(seconds: f32) -> String {
+    let minutes = (seconds / 60.0).floor() as i64;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+// the template with its placeholders filled in
+fn render(template: &str, fields: &BTreeMap<&str, String>) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unclosed placeholder {{{}", name)),
+                    }
+                }
+                match fields.get(name.as_str()) {
+                    Some(value) => rendered += value,
+                    None => return Err(format!("unknown placeholder {{{}}}", name)),
+                }
+            }
+            '}' => return Err(String::from("unmatched }; write }} for a literal brace")),
+            _ => rendered.push(c),
+        }
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_render() {
+        let mut fields = BTreeMap::new();
+        fields.insert("description", String::from("fixing the parser"));
+        fields.insert("elapsed", clock(85.5 * 60.0));
+        fields.insert("tags", String::from("plugh, xyzzy"));
+        assert_eq!(
+            "fixing the parser [plugh, xyzzy] 1:25 {literal}",
+            render("{description} [{tags}] {elapsed} {{literal}}", &fields).unwrap()
+        );
+        assert!(render("{nothing}", &fields).is_err());
+        assert!(render("{description", &fields).is_err());
+        assert!(render("oops}", &fields).is_err());
+    }
+
+    #[test]
+    fn test_tail() {
+        let mut log = std::env::temp_dir();
+        log.push(format!("jobrog-status-tail-{}", std::process::id()));
+        let mut text = String::from("# job log version 1\n");
+        for month in 1..=6 {
+            for day in 1..=28 {
+                for hour in 9..17 {
+                    text += &format!(
+                        "2020 {:2} {:2} {:2}  0  0:foo:working on something\n",
+                        month, day, hour
+                    );
+                }
+                text += &format!("2020 {:2} {:2} 17  0  0:DONE\n", month, day);
+            }
+        }
+        std::fs::write(&log, &text).unwrap();
+        let since = NaiveDate::from_ymd(2020, 6, 28).and_hms(0, 0, 0);
+        let end = tail(&log, &since);
+        assert!(end.len() < text.len(), "only the end is read");
+        assert!(text.ends_with(&end));
+        assert!(
+            end.starts_with("2020 "),
+            "the tail begins with a whole line"
+        );
+        let first = end.lines().find_map(|l| match parse_line(l, 0) {
+            Item::Event(e, _) => Some(e.start),
+            Item::Done(Done(t), _) => Some(t),
+            _ => None,
+        });
+        assert!(
+            first.unwrap() <= since,
+            "the tail reaches back to the start of the day"
+        );
+        let epoch = NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(
+            text,
+            tail(&log, &epoch),
+            "with nothing that early the whole log is read"
+        );
+        std::fs::remove_file(&log).unwrap();
+    }
+}
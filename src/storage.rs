@@ -0,0 +1,423 @@
+// where a log's lines are kept
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// how many bytes of a log file are read at a time
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+// the lines of a log, read at random by offset or in order from some offset, and appended to
+pub trait Storage {
+    // the number of lines
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // the line at the offset, with its line terminator, if any
+    fn get(&mut self, offset: usize) -> Result<&str>;
+    // the byte offset of the line from the beginning of the log
+    fn byte_offset(&self, offset: usize) -> Result<u64>;
+    // the lines from the offset to the end, without their terminators
+    fn lines_from(&self, offset: usize) -> Result<Box<dyn Iterator<Item = Result<String>>>>;
+    // adds the text to the end of the log; the lines already read need not reflect the addition
+    fn append(&mut self, text: &str) -> Result<()>;
+}
+
+// a log file, read a block of whole lines at a time
+//
+// Lines are mostly read in runs, from the end backwards or from some line forwards, so the
+// block read for a line holds as many of its neighbors in the direction of reading as fit. The
+// lines are indexed lazily, a block at a time from the end of the file backward, so reading the
+// last few lines of a long log does not require indexing all the rest.
+pub struct FileStorage {
+    file: File,
+    path: PathBuf,
+    length: u64,           // the length of the file when it was opened
+    index: RefCell<Index>, // where the lines begin
+    block: Vec<u8>,        // the block most recently read
+    block_start: u64,      // where the block begins in the file
+    last: Option<usize>,   // the line most recently gotten
+}
+
+// the byte offsets of the lines of the file indexed so far
+struct Index {
+    starts: Vec<u64>,     // the beginnings of the indexed lines, the last line first
+    from: u64,            // where the earliest indexed line begins
+    lines: Option<usize>, // the number of lines in the file, once counted
+}
+
+impl FileStorage {
+    pub fn new(path: &Path) -> Result<FileStorage> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        Ok(FileStorage {
+            file,
+            path: path.to_owned(),
+            length,
+            index: RefCell::new(Index {
+                starts: vec![],
+                from: length,
+                lines: None,
+            }),
+            block: vec![],
+            block_start: 0,
+            last: None,
+        })
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    fn out_of_bounds(&self, offset: usize) -> Error {
+        Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("index {} in a log of only {} lines", offset, self.len()),
+        )
+    }
+    // the number of lines, counting those not yet indexed without indexing them
+    fn count(&self) -> Result<usize> {
+        let mut index = self.index.borrow_mut();
+        if let Some(lines) = index.lines {
+            return Ok(lines);
+        }
+        let lines = count_lines(&self.file, index.from)? + index.starts.len();
+        index.lines = Some(lines);
+        Ok(lines)
+    }
+    // the byte offsets of the beginning and end of the line, indexing back to it if need be
+    fn bounds(&self, offset: usize) -> Result<(u64, u64)> {
+        let lines = self.count()?;
+        if offset >= lines {
+            return Err(self.out_of_bounds(offset));
+        }
+        let i = lines - 1 - offset;
+        let mut index = self.index.borrow_mut();
+        while index.starts.len() <= i {
+            index.extend(&self.file)?;
+        }
+        let end = if i == 0 {
+            self.length
+        } else {
+            index.starts[i - 1]
+        };
+        Ok((index.starts[i], end))
+    }
+    // reads the block of whole lines containing the line, extending forward from it if the
+    // lines are being read forward and otherwise backward
+    fn read_block(&mut self, offset: usize) -> Result<()> {
+        let (start, end) = self.bounds(offset)?;
+        let forward = self.last.is_some_and(|l| l + 1 == offset);
+        let index = self.index.get_mut();
+        let (from, to) = if forward {
+            let to = self.length.min(end.max(start + BLOCK_SIZE));
+            // end the block with the last line that fits in it; the lines after this one are
+            // all indexed already
+            let to = if to == self.length {
+                to
+            } else {
+                index.starts[index.starts.partition_point(|s| *s > to)]
+            };
+            (start, to)
+        } else {
+            let from = start.min(end.saturating_sub(BLOCK_SIZE));
+            while index.from > from {
+                index.extend(&self.file)?;
+            }
+            // begin the block with the first line that fits in it
+            (
+                index.starts[index.starts.partition_point(|s| *s >= from) - 1],
+                end,
+            )
+        };
+        self.block.resize((to - from) as usize, 0);
+        self.file.seek(SeekFrom::Start(from))?;
+        self.file.read_exact(&mut self.block)?;
+        self.block_start = from;
+        Ok(())
+    }
+}
+
+impl Index {
+    // indexes the lines in the block before those already indexed
+    fn extend(&mut self, mut file: &File) -> Result<()> {
+        let end = self.from;
+        let mut size = BLOCK_SIZE;
+        loop {
+            let from = end.saturating_sub(size);
+            let mut block = vec![0; (end - from) as usize];
+            file.seek(SeekFrom::Start(from))?;
+            file.read_exact(&mut block)?;
+            // a run of terminators can only be split into lines from its beginning, so the
+            // block is split after its first byte that is not a terminator
+            let mut found = vec![];
+            let mut i = if from == 0 {
+                found.push(0);
+                0
+            } else {
+                match block.iter().position(|b| !is_terminator(*b)) {
+                    Some(i) => i + 1,
+                    None => block.len(),
+                }
+            };
+            while i < block.len() {
+                let b = block[i];
+                i += 1;
+                if is_terminator(b) {
+                    // the line after the block begins a line, so no pair straddles its end
+                    if i < block.len() && is_pair(b, block[i]) {
+                        i += 1;
+                    }
+                    if i < block.len() {
+                        found.push(from + i as u64);
+                    }
+                }
+            }
+            if let Some(&first) = found.first() {
+                self.starts.extend(found.iter().rev());
+                self.from = first;
+                return Ok(());
+            }
+            // a line longer than the block
+            size *= 2;
+        }
+    }
+}
+
+fn is_terminator(b: u8) -> bool {
+    b == b'\n' || b == b'\r'
+}
+
+// whether the terminators together end a single line
+fn is_pair(t1: u8, t2: u8) -> bool {
+    (t1 == b'\r' && t2 == b'\n') || (t1 == b'\n' && t2 == b'\r')
+}
+
+// the number of lines in the file before the given byte offset, which begins a line or is the
+// end of the file; \n, \r, \r\n, and \n\r all end lines
+fn count_lines(mut file: &File, end: u64) -> Result<usize> {
+    if end == 0 {
+        return Ok(0);
+    }
+    file.seek(SeekFrom::Start(0))?;
+    let mut reader = file.take(end);
+    let mut buffer = vec![0; BLOCK_SIZE as usize];
+    let mut lines = 1;
+    let mut last = 0; // the last byte read
+    let mut pending: Option<u8> = None; // a terminator that may be the first of a pair
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        for b in buffer[..bytes_read].iter() {
+            last = *b;
+            if let Some(t) = pending.take() {
+                if is_pair(t, *b) {
+                    continue;
+                }
+            }
+            if is_terminator(*b) {
+                pending = Some(*b);
+                lines += 1;
+            }
+        }
+    }
+    // a line beginning at the end is no line
+    if is_terminator(last) {
+        lines -= 1;
+    }
+    Ok(lines)
+}
+
+impl Storage for FileStorage {
+    fn len(&self) -> usize {
+        self.count().expect("could not count the lines of the log")
+    }
+    fn get(&mut self, offset: usize) -> Result<&str> {
+        let (start, end) = self.bounds(offset)?;
+        let block_end = self.block_start + self.block.len() as u64;
+        if start < self.block_start || end > block_end {
+            self.read_block(offset)?;
+        }
+        self.last = Some(offset);
+        let bytes =
+            &self.block[(start - self.block_start) as usize..(end - self.block_start) as usize];
+        std::str::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+    fn byte_offset(&self, offset: usize) -> Result<u64> {
+        self.bounds(offset).map(|(start, _)| start)
+    }
+    fn lines_from(&self, offset: usize) -> Result<Box<dyn Iterator<Item = Result<String>>>> {
+        let lines = BufReader::new(File::open(&self.path)?).lines().skip(offset);
+        Ok(Box::new(lines))
+    }
+    fn append(&mut self, text: &str) -> Result<()> {
+        let mut log = OpenOptions::new().append(true).open(&self.path)?;
+        log.write_all(text.as_bytes())
+    }
+}
+
+// so a log may be read the same way wherever it is kept
+impl Storage for Box<dyn Storage> {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    fn get(&mut self, offset: usize) -> Result<&str> {
+        (**self).get(offset)
+    }
+    fn byte_offset(&self, offset: usize) -> Result<u64> {
+        (**self).byte_offset(offset)
+    }
+    fn lines_from(&self, offset: usize) -> Result<Box<dyn Iterator<Item = Result<String>>>> {
+        (**self).lines_from(offset)
+    }
+    fn append(&mut self, text: &str) -> Result<()> {
+        (**self).append(text)
+    }
+}
+
+// a log kept in memory, for tests and for programs that keep their logs elsewhere
+#[derive(Default)]
+pub struct MemoryStorage {
+    text: String,
+    starts: Vec<usize>, // the byte offset of each line
+}
+
+impl MemoryStorage {
+    pub fn new(text: &str) -> MemoryStorage {
+        let mut storage = MemoryStorage {
+            text: text.to_owned(),
+            ..Default::default()
+        };
+        storage.index();
+        storage
+    }
+    // the whole log
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    fn index(&mut self) {
+        let mut offset = 0;
+        self.starts = self
+            .text
+            .split_inclusive('\n')
+            .map(|line| {
+                let start = offset;
+                offset += line.len();
+                start
+            })
+            .collect();
+    }
+    fn out_of_bounds(&self, offset: usize) -> Error {
+        Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("index {} in a log of only {} lines", offset, self.len()),
+        )
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+    fn get(&mut self, offset: usize) -> Result<&str> {
+        let start = *self
+            .starts
+            .get(offset)
+            .ok_or_else(|| self.out_of_bounds(offset))?;
+        let end = self
+            .starts
+            .get(offset + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        Ok(&self.text[start..end])
+    }
+    fn byte_offset(&self, offset: usize) -> Result<u64> {
+        self.starts
+            .get(offset)
+            .map(|s| *s as u64)
+            .ok_or_else(|| self.out_of_bounds(offset))
+    }
+    fn lines_from(&self, offset: usize) -> Result<Box<dyn Iterator<Item = Result<String>>>> {
+        let lines = self
+            .text
+            .lines()
+            .skip(offset)
+            .map(|l| Ok(l.to_owned()))
+            .collect::<Vec<_>>();
+        Ok(Box::new(lines.into_iter()))
+    }
+    fn append(&mut self, text: &str) -> Result<()> {
+        self.text += text;
+        self.index();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_storage() {
+        let mut storage = MemoryStorage::new("# a comment\n\n2020  3  4  8  0  0::foo");
+        assert_eq!(3, storage.len());
+        assert_eq!("\n", storage.get(1).unwrap());
+        assert_eq!(13, storage.byte_offset(2).unwrap());
+        assert!(storage.get(3).is_err());
+        storage.append("\n2020  3  4  9  0  0:DONE\n").unwrap();
+        assert_eq!(4, storage.len());
+        assert_eq!("2020  3  4  8  0  0::foo\n", storage.get(2).unwrap());
+        assert_eq!(
+            vec!["2020  3  4  8  0  0::foo", "2020  3  4  9  0  0:DONE"],
+            storage
+                .lines_from(2)
+                .unwrap()
+                .map(|l| l.unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let path = Path::new("test_file_storage.log");
+        let long = "x".repeat(BLOCK_SIZE as usize + 10);
+        let lines = vec![
+            "a\r\n".to_owned(),
+            "b\n".to_owned(),
+            "\n".to_owned(),
+            "c\r".to_owned(),
+            "d\n\r".to_owned(),
+            format!("{}\n", long),
+            "é\n".to_owned(),
+        ];
+        let mut text = lines.concat();
+        for i in 0..10_000 {
+            text += &format!("line {}\n", i);
+        }
+        text += "the end";
+        std::fs::write(path, &text).unwrap();
+        let mut storage = FileStorage::new(path).unwrap();
+        assert_eq!(lines.len() + 10_001, storage.len());
+        // the end is read without indexing the beginning
+        assert_eq!("the end", storage.get(storage.len() - 1).unwrap());
+        assert!(storage.index.borrow().from > BLOCK_SIZE);
+        assert_eq!(6, storage.byte_offset(3).unwrap());
+        // backward, forward, and at random
+        let all = (0..storage.len())
+            .rev()
+            .map(|i| storage.get(i).unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(text, all.iter().rev().cloned().collect::<String>());
+        for (i, line) in lines.iter().enumerate() {
+            assert_eq!(line, storage.get(i).unwrap());
+        }
+        assert_eq!("line 5000\n", storage.get(lines.len() + 5000).unwrap());
+        assert_eq!("the end", storage.get(storage.len() - 1).unwrap());
+        assert_eq!("a\r\n", storage.get(0).unwrap());
+        assert!(storage.get(storage.len()).is_err());
+        std::fs::write(path, "").unwrap();
+        assert!(FileStorage::new(path).unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
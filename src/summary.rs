@@ -1,16 +1,23 @@
 extern crate chrono;
 extern crate clap;
+extern crate serde_json;
 extern crate two_timer;
 
+use crate::budget::warn_of_exceeded_budgets;
 use crate::configure::Configuration;
-use crate::log::{Event, Filter, LogController, Note};
+use crate::log::{elapsed_seconds, Event, Filter, Note};
+use crate::pager::page;
 use crate::util::{
-    check_for_ongoing_event, common_search_or_filter_arguments, display_events, display_notes,
-    fatal, remainder, warn,
+    base_dir, check_for_ongoing_event, common_search_or_filter_arguments, current_time,
+    display_events_with, display_notes, duration_string, expand_period, expand_week_number, fatal,
+    gaps, include_archives_argument, info, limit_and_offset, limit_arguments, limited, log_reader,
+    names_fiscal_year, raw_argument, remainder, tsv_row, warn, warn_of_unknown_tags,
+    weekday_argument, weekdays, Extras, LogReader, Percentages,
 };
 use crate::vacation::VacationController;
-use chrono::{Duration, Local};
+use chrono::{Datelike, Duration, NaiveDateTime};
 use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::json;
 use two_timer::{parsable, parse};
 
 fn after_help() -> &'static str {
@@ -36,6 +43,11 @@ in a particular period:
   o           0.75
   sb          7.25
 
+Contiguous events with the same tags, like the two 'Multi-Floob Review' events after lunch \
+above if lunch had not intervened, are shown as a single row with their descriptions joined \
+by '; '. If you need every event in the log to appear as its own row with its own times, as \
+an auditor might, use --no-merge.
+
 If no time period is provided, the default period is 'today'. See the parse \
 subcommand for more details about acceptable time expressions.
 
@@ -55,6 +67,70 @@ You can also summarize the notes in a particular period:
    10:15  moe, birthday  mechanical pencils
    10:15  moe, birthday  nice book
 
+ISO week numbers are also acceptable time expressions, as in 'week 37' or 'week 37 2019'. \
+With --week-numbers, the day headers of summaries spanning more than one week show the ISO \
+week number:
+
+  > job s --week-numbers last month
+  Monday, 13 January (week 3)
+  ...
+
+With --with-notes the notes taken during the period are shown among the events, each beneath \
+the event during which it was taken:
+
+  > job s --with-notes yesterday
+  Friday, 17 January
+    8:59 -  9:23  0.50  e, o          email
+    9:23 - 10:40  1.25  2609, cs, sb  Error in approved plugh foo for 14068FY19
+       9:41  note: Z923289 Q923525 (sb)
+  ...
+
+With --show-gaps untracked time during work hours between events is shown as well:
+
+  > job s --show-gaps yesterday
+  Friday, 17 January
+    8:59 -  9:23  0.50  e, o          email
+    9:23 - 10:40  1.25  2609, cs, sb  Error in approved plugh foo for 14068FY19
+   10:40 - 11:02  0.25                untracked
+  ...
+
+With --links any references to issues in the descriptions -- #123, say, or PROJ-123 -- become \
+hyperlinks to the issues, provided your terminal supports hyperlinks and you have told job how \
+to recognize references; see `job configure --issue-pattern`.
+
+With --percentages each event's share of the time logged that day is shown after its duration; \
+with --percent-of day-length, its share of the hours you were expected to work.
+
+Vacation time is added to the summary to fill out the work days covered by vacation records; \
+see the vacation subcommand. To leave it out use --no-vacation, and to see nothing but vacation \
+time use --only-vacation.
+
+To consider only certain days of the week, use --weekday:
+
+  > job s --weekday weekend 'this year'
+
+To leave some part of the period out of the summary, use --except, as many times as you need:
+
+  > job s --except 'march 9 - march 13' --except 'march 20' 'this month'
+
+To see only some of the events found, use --offset to skip the first few and --limit to say \
+how many to show after those:
+
+  > job s --offset 20 --limit 10 'this month'
+
+The totals are then those of the events shown.
+
+Durations are rounded for display as your precision and truncation settings say, so the rounded \
+durations of the events need not add up to the rounded total. With --raw durations are shown as \
+exact numbers of seconds, which always add up. In --json output each event has both its rounded \
+duration, in hours, and its exact seconds.
+
+With --tsv the events or notes are written as tab-separated values, one per line, for awk, cut, \
+and the like. See the README for the columns.
+
+If you have truncated the log, --include-archives reads the heads the truncate subcommand cut \
+from it as well, so you can summarize periods before the truncation.
+
 You can provide the time expression as the final arguments, but sometimes you want to filter \
 by tag it's convenient to be able to add tag expressions to the end of the previous command, in \
 which case the time expression is in the way. For this case you can use the --date option instead.
@@ -92,13 +168,103 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
         .short("d")
         .help("Receives the time expression as an option rather than an argument")
         .long_help("If you are frequently reviewing the tasks done in a particular pay period, filtering them by tag, say, it may be convenient for the date not to be at the end of the command line -- better to add filters here. In this case you can use the --date option.")
-        .validator(|v| if parsable(&v) {Ok(())} else {Err(format!("cannot parse '{}' as a time expression", v))} )
+        .validator(|v| if parsable(&expand_week_number(&v)) || names_fiscal_year(&v) {Ok(())} else {Err(format!("cannot parse '{}' as a time expression", v))} )
         .value_name("phrase")
+    ).arg(
+        Arg::with_name("fiscal")
+        .long("fiscal")
+        .help("Interprets 'this year', 'last year', etc. as the fiscal year")
+        .long_help("If you have set fiscal-year-start in the configuration, this causes 'this year', 'last year', and 'next year' \
+        to mean the fiscal rather than the calendar year. Regardless of this option, 'this fiscal year' and the like always mean the fiscal year.")
+    ).arg(
+        Arg::with_name("week-numbers")
+        .long("week-numbers")
+        .short("w")
+        .help("Shows ISO week numbers in day headers")
+        .long_help("If the period summarized spans more than one week, each day header will be followed by the ISO 8601 number of its week. \
+        You can make this the default with configure --week-numbers true.")
     ).arg(
         Arg::with_name("no-merge")
         .long("no-merge")
         .help("Doesn't merge contiguous events with the same tags")
         .long_help("By default contiguous events with the same tags are displayed as a single event with the sub-events' descriptions joined with '; '. --no-merge prevents this.")
+    ).arg(
+        Arg::with_name("with-notes")
+        .long("with-notes")
+        .help("Shows notes among the events")
+        .long_help("Shows each note in the period beneath the event during which it was taken or, if it was taken \
+        between events, at its time, so the summary reads as one account of the day. The other filters apply to the notes \
+        as well as the events. With --json the notes are interleaved with the events in order of time.")
+        .conflicts_with("notes")
+    ).arg(
+        Arg::with_name("show-gaps")
+        .long("show-gaps")
+        .help("Shows untracked time between events")
+        .long_help("Inserts a row for each interval during work hours between the end of one event and the start of the next \
+        on the same day, with its duration, so missing time is obvious. Work hours begin at the configured beginning-work-day \
+        and last the configured day length. Gaps are not counted in the totals.")
+    ).arg(
+        Arg::with_name("links")
+        .long("links")
+        .help("Makes issue references hyperlinks")
+        .long_help("Makes each reference to an issue in a description a hyperlink to the issue, in terminals that \
+        support them. References are recognized by the patterns set with `job configure --issue-pattern`.")
+    ).arg(
+        Arg::with_name("percentages")
+        .long("percentages")
+        .help("Shows each event's share of its day")
+        .long_help("Adds a column giving each event's duration as a percentage of the total time logged that day. \
+        See also --percent-of.")
+    ).arg(
+        Arg::with_name("percent-of")
+        .long("percent-of")
+        .help("Says what --percentages are percentages of")
+        .long_help("With 'total', the default, percentages are of the total time logged each day; with 'day-length', \
+        of the hours you are expected to work that day. This implies --percentages.")
+        .possible_values(&["total", "day-length"])
+        .value_name("whole")
+    ).arg(
+        Arg::with_name("no-vacation")
+        .long("no-vacation")
+        .help("Leaves vacation time out of the summary")
+        .long_help("Shows only the time you actually logged, without the vacation time that would otherwise be added \
+        to fill out the work days covered by vacation records.")
+        .conflicts_with_all(&["only-vacation", "notes"])
+    ).arg(
+        Arg::with_name("only-vacation")
+        .long("only-vacation")
+        .help("Shows only vacation time")
+        .long_help("Shows only the vacation time added to fill out the work days covered by vacation records, leaving \
+        out the time you actually logged.")
+        .conflicts_with("notes")
+    ).arg(
+        weekday_argument()
+    ).arg(
+        Arg::with_name("except")
+        .long("except")
+        .help("Excludes a period from the summary")
+        .long_help("Leaves out of the summary, and out of its totals, the time described by this expression, so you can \
+        summarize 'this month' except the week you were at a conference, say. Events which overlap an excluded period are \
+        trimmed to the time outside it. This option may be repeated.")
+        .multiple(true)
+        .number_of_values(1)
+        .validator(|v| if parsable(&expand_week_number(&v)) || names_fiscal_year(&v) {Ok(())} else {Err(format!("cannot parse '{}' as a time expression", v))} )
+        .value_name("phrase")
+    ).arg(
+        Arg::with_name("no-day-split")
+        .long("no-day-split")
+        .help("Doesn't split events that span the day boundary")
+        .long_help("By default an event which continues past midnight, or the configured day boundary, is split into pieces, \
+        one for each day, and the pieces are marked as overlapping the boundary. With --no-day-split such an event is shown as a single \
+        row with its full duration attributed to the day it began. Events begun before the period summarized are omitted.")
+    ).arg(
+        Arg::with_name("all-profiles")
+        .long("all-profiles")
+        .help("Combines the logs of all profiles")
+        .long_help("Summarizes the events or notes in the log of the base directory together with those in the logs of every profile. \
+        Each event or note from a profile's log is given the profile's name as an additional tag, so you can filter by profile with --tag.")
+    ).arg(
+        include_archives_argument()
     ).arg(
         Arg::with_name("precision")
         .long("precision")
@@ -117,12 +283,9 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
         .possible_values(&["round", "floor", "ceiling"])
         .value_name("function")
     ).arg(
-        Arg::with_name("json")
-        .long("json")
-        .short("j")
-        .help("Returns summarized events/notes as a list of line-delimited JSON objects")
-        .long_help("Should you wish to feed summarized results into some other service this provides easily parsed output.")
-    ))
+        raw_argument()
+        .conflicts_with_all(&["precision", "truncation"])
+    ).args(&limit_arguments()))
 }
 
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
@@ -135,6 +298,18 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     if let Some(identifier) = matches.value_of("truncation") {
         conf.set_truncation(identifier);
     }
+    if matches.is_present("raw") {
+        conf.raw = true;
+    }
+    if matches.is_present("week-numbers") {
+        conf.week_numbers = true;
+    }
+    if matches.is_present("fiscal") && conf.fiscal_year_start.is_none() {
+        fatal(
+            "--fiscal requires that fiscal-year-start be configured",
+            &conf,
+        );
+    }
     if let Some(expression) = matches.value_of("date") {
         if phrase != "today" {
             warn(
@@ -147,62 +322,278 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
         }
         phrase = expression.to_owned();
     }
-    if let Ok((start, end, _)) = parse(&phrase, conf.two_timer_config()) {
-        let mut reader = LogController::new(None, &conf).expect("could not read log");
-        let now = Local::now().naive_local();
-        if let Some(time) = reader.first_timestamp() {
+    let excepted = matches
+        .values_of("except")
+        .map(|values| {
+            values
+                .map(|phrase| {
+                    let expression = expand_period(phrase, matches.is_present("fiscal"), &conf);
+                    match parse(&expression, conf.logical_two_timer_config()) {
+                        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+                        Err(_) => {
+                            fatal(
+                                format!("could not parse '{}' as a time expression", phrase),
+                                &conf,
+                            );
+                            unreachable!()
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let excepted_time =
+        |t: &NaiveDateTime| excepted.iter().any(|(start, end)| start <= t && t < end);
+    let expression = expand_period(&phrase, matches.is_present("fiscal"), &conf);
+    if let Ok((start, end, _)) = parse(&expression, conf.logical_two_timer_config()) {
+        let (start, end) = conf.shift_to_day_boundary(start, end);
+        info(
+            format!("'{}' is interpreted as {} to {}", phrase, start, end),
+            &conf,
+        );
+        let mut readers = readers(matches, &conf);
+        let now = current_time();
+        if let Some(time) = readers
+            .iter()
+            .filter_map(|(_, r)| r.first_timestamp())
+            .min()
+        {
             // narrow the range in to just the dates from the beginning of the lot to the present
             // so that we don't have spurious vacation times
             let start = if time > start {
-                time.date().and_hms(0, 0, 0)
+                conf.start_of_day(&conf.logical_date(&time))
             } else {
                 start
             };
-            let time = now.date().and_hms(0, 0, 0) + Duration::days(1);
+            let time = conf.start_of_day(&(conf.logical_date(&now) + Duration::days(1)));
             let end = if end > time { time } else { end };
 
             let filter = Filter::new(matches);
-            check_for_ongoing_event(&mut reader, &conf);
+            let days = weekdays(matches);
+            for (_, reader) in readers.iter_mut() {
+                check_for_ongoing_event(reader, &conf);
+            }
             if matches.is_present("notes") {
-                let notes: Vec<Note> = reader
-                    .notes_in_range(&start, &end)
-                    .into_iter()
-                    .filter(|n| filter.matches(n))
-                    .collect();
+                let mut notes: Vec<Note> = vec![];
+                for (profile, reader) in readers.iter_mut() {
+                    for mut n in reader.notes_in_range(&start, &end) {
+                        if let Some(profile) = profile {
+                            n.tags.push(profile.clone());
+                        }
+                        if filter.matches(&n) {
+                            notes.push(n);
+                        }
+                    }
+                }
+                notes.sort_by_key(|n| n.time);
+                if let Some(days) = &days {
+                    notes.retain(|n| days.contains(&conf.logical_date(&n.time).weekday()));
+                }
+                notes.retain(|n| !excepted_time(&n.time));
+                let notes = limited(notes, matches);
                 if notes.is_empty() {
-                    warn("no note found", &conf)
+                    warn("no note found", &conf);
+                    warn_of_unknown_tags(&filter.tags(), &conf);
                 } else {
                     if matches.is_present("json") {
                         for n in notes {
                             println!("{}", n.to_json(&now, &conf));
                         }
+                    } else if matches.is_present("tsv") {
+                        for n in notes {
+                            println!("{}", n.to_tsv(&now, &conf));
+                        }
                     } else {
+                        let _pager = page(&conf, matches);
                         display_notes(notes, &start, &end, &conf);
                     }
                 }
             } else {
-                let events = reader
-                    .events_in_range(&start, &end)
-                    .into_iter()
-                    .filter(|n| filter.matches(n))
-                    .collect();
-                let events = if matches.is_present("no-merge") {
-                    Event::gather_by_day(events, &end)
+                let mut notes: Vec<Note> = vec![];
+                if matches.is_present("with-notes") {
+                    for (profile, reader) in readers.iter_mut() {
+                        for mut n in reader.notes_in_range(&start, &end) {
+                            if let Some(profile) = profile {
+                                n.tags.push(profile.clone());
+                            }
+                            if filter.matches(&n) {
+                                notes.push(n);
+                            }
+                        }
+                    }
+                }
+                notes.sort_by_key(|n| n.time);
+                notes.retain(|n| !excepted_time(&n.time));
+                // the events are read, divided, merged, and so forth as they are needed, so only
+                // those displayed are held in memory
+                let sources = readers
+                    .iter_mut()
+                    .map(|(profile, reader)| {
+                        let profile = profile.clone();
+                        reader
+                            .events_in_range_streaming(&start, &end)
+                            .map(move |mut e| {
+                                if let Some(profile) = &profile {
+                                    e.tags.push(profile.clone());
+                                }
+                                e
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                let events = by_start(sources).filter(|e| filter.matches(e));
+                let events: Box<dyn Iterator<Item = Event>> = if matches.is_present("no-day-split")
+                {
+                    // events are attributed entirely to the day they began
+                    let events = events.filter(|e| e.start >= start);
+                    if matches.is_present("no-merge") {
+                        Box::new(events)
+                    } else {
+                        Box::new(Event::merge_contiguous_streaming(events, &conf))
+                    }
+                } else if matches.is_present("no-merge") {
+                    Box::new(Event::gather_by_day_streaming(events, &end, &conf))
                 } else {
-                    Event::gather_by_day_and_merge(events, &end)
+                    Box::new(Event::merge_contiguous_streaming(
+                        Event::gather_by_day_streaming(events, &end, &conf),
+                        &conf,
+                    ))
                 };
-                let events = VacationController::read(None, conf.directory())
-                    .add_vacation_times(&start, &end, events, &conf, None, &filter);
-                if events.is_empty() {
-                    warn("no event found", &conf)
+                let vacations = if matches.is_present("no-vacation") {
+                    None
                 } else {
-                    if matches.is_present("json") {
-                        for e in events {
+                    Some(VacationController::read(None, conf.directory()))
+                };
+                let events = match &vacations {
+                    Some(vacations) => vacations
+                        .add_vacation_times_streaming(&start, &end, events, &conf, None, &filter),
+                    None => events,
+                };
+                let only_vacation = matches.is_present("only-vacation");
+                let on_days = |t: &NaiveDateTime| {
+                    days.as_ref()
+                        .is_none_or(|days| days.contains(&conf.logical_date(t).weekday()))
+                };
+                notes.retain(|n| on_days(&n.time));
+                let (limit, offset) = limit_and_offset(matches);
+                let limits = matches.is_present("limit") || matches.is_present("offset");
+                let events = events
+                    .filter(|e| !only_vacation || e.vacation)
+                    .filter(|e| on_days(&e.start))
+                    .flat_map(|e| Event::without_periods(vec![e], &excepted, &now))
+                    .skip(offset)
+                    .take(limit.unwrap_or(usize::MAX));
+                let tsv = matches.is_present("tsv");
+                if matches.is_present("json") || tsv {
+                    // only with gaps need the events be gathered first
+                    let (events, gaps): (Box<dyn Iterator<Item = Event>>, _) =
+                        if matches.is_present("show-gaps") {
+                            let events = events.collect::<Vec<_>>();
+                            let gaps = gaps(&events, &conf);
+                            (Box::new(events.into_iter()), gaps)
+                        } else {
+                            (Box::new(events), vec![])
+                        };
+                    let note_line = |n: &Note| {
+                        if tsv {
+                            n.to_tsv(&now, &conf)
+                        } else {
+                            n.to_json(&now, &conf)
+                        }
+                    };
+                    let mut notes = notes.into_iter().peekable();
+                    let mut last_end = None;
+                    let mut count = 0;
+                    for (i, e) in events.enumerate() {
+                        if i == 0 && limits {
+                            // only the notes among the events shown
+                            while notes.next_if(|n| n.time < e.start).is_some() {}
+                        }
+                        for (_, from, to) in gaps.iter().filter(|g| g.0 == i) {
+                            if tsv {
+                                let duration = elapsed_seconds(from, to) as f32;
+                                println!(
+                                    "{}",
+                                    tsv_row(&[
+                                        "Gap",
+                                        &from.to_string(),
+                                        &to.to_string(),
+                                        &duration_string(duration, &conf),
+                                        "",
+                                        "",
+                                        "",
+                                        "",
+                                    ])
+                                );
+                                continue;
+                            }
+                            println!(
+                                "{}",
+                                json!({
+                                    "type": "Gap",
+                                    "start": format!("{}", from),
+                                    "end": format!("{}", to),
+                                    "duration": elapsed_seconds(from, to) as f32 / (60.0 * 60.0),
+                                    "seconds": elapsed_seconds(from, to),
+                                })
+                            );
+                        }
+                        while let Some(n) = notes.next_if(|n| n.time < e.start) {
+                            println!("{}", note_line(&n));
+                        }
+                        if tsv {
+                            println!("{}", e.to_tsv(&now, &conf));
+                        } else {
                             println!("{}", e.to_json(&now, &conf));
                         }
+                        last_end = e.end;
+                        count += 1;
+                    }
+                    if count == 0 {
+                        warn("no event found", &conf);
+                        warn_of_unknown_tags(&filter.tags(), &conf);
                     } else {
-                        display_events(events, &start, &end, &conf);
+                        for n in notes {
+                            if limits && last_end.is_some_and(|t| n.time >= t) {
+                                break;
+                            }
+                            println!("{}", note_line(&n));
+                        }
+                    }
+                    return;
+                }
+                let events = events.collect::<Vec<_>>();
+                if limits {
+                    // only the notes among the events shown
+                    let from = events.first().map(|e| e.start);
+                    let to = events.last().and_then(|e| e.end);
+                    notes.retain(|n| {
+                        from.is_some_and(|t| n.time >= t) && to.is_none_or(|t| n.time < t)
+                    });
+                }
+                if events.is_empty() {
+                    warn("no event found", &conf);
+                    warn_of_unknown_tags(&filter.tags(), &conf);
+                } else {
+                    if matches.is_present("links") && conf.issue_patterns.is_empty() {
+                        warn(
+                            "no issue patterns are configured; see `job configure --issue-pattern`",
+                            &conf,
+                        );
                     }
+                    let _pager = page(&conf, matches);
+                    let extras = Extras {
+                        notes,
+                        gaps: matches.is_present("show-gaps"),
+                        percentages: match matches.value_of("percent-of") {
+                            Some("day-length") => Some(Percentages::DayLength),
+                            Some(_) => Some(Percentages::Total),
+                            None if matches.is_present("percentages") => Some(Percentages::Total),
+                            None => None,
+                        },
+                        links: matches.is_present("links"),
+                    };
+                    display_events_with(events, extras, &start, &end, &conf);
+                    warn_of_exceeded_budgets(&start, &end, &now, &conf);
                 }
             }
         } else {
@@ -219,3 +610,57 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
         )
     }
 }
+
+// the logs to summarize, each with the name of its profile, if any
+fn readers(matches: &ArgMatches, conf: &Configuration) -> Vec<(Option<String>, LogReader)> {
+    if !matches.is_present("all-profiles") {
+        let reader = log_reader(None, matches, conf).expect("could not read log");
+        return vec![(None, reader)];
+    }
+    let base = Configuration::base();
+    let mut readers = vec![];
+    let mut seen = vec![];
+    let mut sources = vec![(None, base_dir(None))];
+    for (name, dir) in base.profiles() {
+        sources.push((Some(name), dir));
+    }
+    for (profile, dir) in sources {
+        let mut log = dir.clone();
+        log.push("log");
+        if let Ok(canonical) = log.canonicalize() {
+            if seen.contains(&canonical) {
+                continue;
+            }
+            seen.push(canonical);
+            match log_reader(Some(log), matches, conf) {
+                Ok(reader) => readers.push((profile, reader)),
+                Err(_) => warn(
+                    format!("could not read the log in {}", dir.to_str().unwrap()),
+                    conf,
+                ),
+            }
+        }
+    }
+    readers
+}
+
+// the events of the several logs, each in order, merged into one sequence in order
+fn by_start<'a, I: Iterator<Item = Event> + 'a>(
+    sources: Vec<I>,
+) -> impl Iterator<Item = Event> + 'a {
+    let mut sources = sources
+        .into_iter()
+        .map(|s| s.peekable())
+        .collect::<Vec<_>>();
+    std::iter::from_fn(move || {
+        let mut earliest: Option<(usize, NaiveDateTime)> = None;
+        for (i, s) in sources.iter_mut().enumerate() {
+            if let Some(e) = s.peek() {
+                if earliest.is_none_or(|(_, t)| e.start < t) {
+                    earliest = Some((i, e.start));
+                }
+            }
+        }
+        earliest.and_then(|(i, _)| sources[i].next())
+    })
+}
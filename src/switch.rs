@@ -0,0 +1,126 @@
+extern crate clap;
+extern crate serde_json;
+
+use crate::configure::Configuration;
+use crate::hook::notify;
+use crate::log::{Event, Item, LogController};
+use crate::util::{close_at_argument, close_forgotten_event, describe, some_nws, warn};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+fn after_help() -> &'static str {
+    "\
+The switch subcommand ends the ongoing event and begins a new one at the same instant:
+
+  > job switch --tag doc writing the README
+  switching from fixing the parser to writing the README (doc)
+
+Because a new event implicitly ends the one before it, this is what the add subcommand does \
+as well. The switch subcommand differs in saying what has ended and in warning you if there \
+was no ongoing event to end. Ending one event with done and then beginning another with add, \
+on the other hand, leaves a gap of however many seconds passed between the two commands.
+
+As with the add subcommand, if the ongoing event was begun on a previous day you are asked \
+when it ended, or you can say with --close-at; it is then ended at that time rather than now.
+
+All prefixes of 'switch' after 's' -- 'sw', 'swi', 'swit', and 'switc' -- are aliases of \
+the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("switch")
+            .aliases(&["sw", "swi", "swit", "switc"])
+            .about("Ends the ongoing task and begins another")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("tag")
+                .short("t")
+                .long("tag")
+                .multiple(true)
+                .number_of_values(1)
+                .help("add this tag to the new event")
+                .long_help("A tag is just a short description, like 'fun', or 'overhead'. Add a tag to an event to facilitate finding or grouping similar events.")
+                .value_name("tag")
+                .validator(|v| if some_nws(&v) {Ok(())} else {Err(format!("{:?} is not a suitable tag: it has no non-whitespace character", v))} )
+                .display_order(1)
+            )
+            .arg(
+                Arg::with_name("copy-tags")
+                .short("c")
+                .long("copy-tags")
+                .visible_alias("ct")
+                .help("copy tags from the ended event")
+                .long_help("Copy to the new event all the tags of the event it ends. These tags will be in addition to any tags added via --tag.")
+                .display_order(2)
+            )
+            .arg(close_at_argument().display_order(3))
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("description")
+                    .help("what you are switching to")
+                    .long_help(
+                        "All the <description> arguments are concatenated to produce a description of the new event.",
+                    )
+                    .value_name("description")
+                    .required(true)
+                    .multiple(true)
+            )
+            .display_order(display_order)
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let closed = close_forgotten_event(&mut reader, matches, &conf);
+    let description = matches
+        .values_of("description")
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let mut tags: Vec<String> = if let Some(values) = matches.values_of("tag") {
+        values.map(|s| s.to_owned()).collect()
+    } else {
+        vec![]
+    };
+    let ended = reader.last_event().filter(|e| e.ongoing());
+    if let Some(event) = &ended {
+        if matches.is_present("copy-tags") {
+            tags.extend(event.tags.iter().cloned());
+        }
+    } else if !closed {
+        warn("there was no ongoing event to end", &conf);
+    }
+    let (event, offset) = reader.append_event(description, conf.normalize_tags(&tags));
+    let json = switch_json(ended.as_ref(), &event, &conf);
+    notify("switch", &json, &conf, matches);
+    if matches.is_present("json") {
+        println!("{}", json);
+    } else if let Some(ended) = ended {
+        describe(
+            "switching",
+            Some(&format!("from {} to", ended.description)),
+            Item::Event(event, offset),
+            &conf,
+        );
+    } else {
+        describe("starting", None, Item::Event(event, offset), &conf);
+    }
+}
+
+// the JSON representation of a switch: the event ended, if any, and the event begun
+fn switch_json(ended: Option<&Event>, event: &Event, conf: &Configuration) -> String {
+    let ended = match ended {
+        Some(e) => {
+            let mut e = e.clone();
+            e.end = Some(event.start);
+            e.to_json(&event.start, conf)
+        }
+        None => String::from("null"),
+    };
+    format!(
+        r#"{{"type":"Switch","ended":{},"event":{}}}"#,
+        ended,
+        event.to_json(&event.start, conf)
+    )
+}
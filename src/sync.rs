@@ -0,0 +1,343 @@
+extern crate chrono;
+extern crate clap;
+
+use crate::backup::FILES;
+use crate::configure::Configuration;
+use crate::log::{timestamp, Item, LogController};
+use crate::merge::{conflicts, drop_orphaned_dones, merge, read_blocks, write_blocks, Source};
+use crate::util::{base_dir, fatal, log_path, success, warn};
+use chrono::{Local, NaiveDateTime};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn after_help() -> &'static str {
+    "\
+If you work on more than one machine you may wish to keep your log synchronized among them. \
+The sync subcommand makes the job log directory a git repository, if it isn't one already, \
+commits any changes, and then, if you have configured a remote repository, pulls changes from \
+it and pushes your own changes to it:
+
+  > job configure --sync-remote git@example.com:me/joblog.git
+  > job sync
+
+Sync commits only the log, the vacation file, and the configuration. Backups and the logs of \
+other profiles, which live in the profiles subdirectory, stay out of the repository.
+
+Before committing anything sync validates the log. If it finds errors it stops and asks you to \
+fix them with `job edit --validate`. When both machines have added to the log since they last \
+synchronized, sync interleaves the two logs by timestamp as the merge subcommand does. If the \
+logs disagree -- an event on one machine falls within an event begun on the other -- or some \
+other file conflicts, sync abandons the merge, leaving your log as it was, and tells you which \
+files conflict. You can then combine the two logs with the merge subcommand:
+
+  > git -C ~/.joblog show FETCH_HEAD:log > /tmp/remote-log
+  > job merge /tmp/remote-log
+  > git -C ~/.joblog merge -s ours FETCH_HEAD
+  > job sync
+
+Likewise, it checks that the \
+log remains valid after the pull, since git may interleave the lines of two logs in a way that \
+puts events out of order.
+
+You must have git installed to use this subcommand.
+
+All prefixes of 'sync' after 's' -- 'sy' and 'syn' -- are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("sync")
+            .aliases(&["sy", "syn"])
+            .about("Synchronizes the job log directory via git")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("local")
+                .long("local")
+                .help("Commits changes without pulling or pushing")
+                .long_help("Validates the log and commits any changes to the local repository but does not contact the remote repository.")
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("message")
+                .long("message")
+                .short("m")
+                .help("Uses this commit message")
+                .long_help("The message for the commit of any local changes. By default this is the time of the sync.")
+                .value_name("text")
+                .display_order(1)
+            )
+            .display_order(display_order),
+    )
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    let conf = Configuration::read(None, directory);
+    let dir = base_dir(conf.directory());
+    if let Some(problem) = check_log(&conf) {
+        fatal(
+            format!(
+                "{}; fix this with `job edit --validate` before syncing",
+                problem
+            ),
+            &conf,
+        );
+    }
+    let mut git_dir = dir.clone();
+    git_dir.push(".git");
+    let fresh = !git_dir.as_path().exists();
+    if fresh {
+        git(&dir, &["init", "--quiet"], &conf);
+        let mut ignore = dir.clone();
+        ignore.push(".gitignore");
+        if !ignore.as_path().exists() {
+            let mut file = File::create(ignore).expect("could not create .gitignore");
+            // backups, the scratch files of edit, truncate, and zone, and the logs of other profiles
+            file.write_all(b"*.bak\n*.tmp\n*.validation\nbackups/\nprofiles/\n")
+                .expect("could not write .gitignore");
+        }
+        success(
+            format!("initialized a git repository in {}", dir.to_str().unwrap()),
+            &conf,
+        );
+    }
+    let branch =
+        String::from_utf8_lossy(&git(&dir, &["symbolic-ref", "--short", "HEAD"], &conf).stdout)
+            .trim()
+            .to_owned();
+    let remote = if matches.is_present("local") {
+        None
+    } else {
+        conf.sync_remote.clone()
+    };
+    if let Some(remote) = &remote {
+        // a new machine with nothing yet logged simply adopts the remote log
+        if fresh && log_is_empty(&conf) {
+            let fetch = git_output(&dir, &["fetch", "--quiet", remote, &branch], &conf);
+            if fetch.status.success() {
+                git(&dir, &["reset", "--quiet", "--hard", "FETCH_HEAD"], &conf);
+                success(format!("adopted the log in {}", remote), &conf);
+                return;
+            }
+        }
+    }
+    // stage only the files sync is responsible for so other profiles' logs are never shared
+    let mut args = vec!["add", "--all", "--"];
+    for file in FILES.iter().chain([".gitignore"].iter()) {
+        if dir.join(file).as_path().exists() {
+            args.push(file);
+        }
+    }
+    git(&dir, &args, &conf);
+    if git_output(&dir, &["diff", "--cached", "--quiet"], &conf)
+        .status
+        .success()
+    {
+        success("no local changes to commit", &conf);
+    } else {
+        let message = matches
+            .value_of("message")
+            .map(|m| m.to_owned())
+            .unwrap_or_else(|| format!("job sync {}", Local::now().format("%F %T")));
+        git(&dir, &["commit", "--quiet", "-m", &message], &conf);
+        success("committed local changes", &conf);
+    }
+    if matches.is_present("local") {
+        return;
+    }
+    let remote = match remote {
+        Some(remote) => remote,
+        None => {
+            warn(
+                "no sync-remote is configured so there is nothing to pull or push; see `job configure --help`",
+                &conf,
+            );
+            return;
+        }
+    };
+    let fetch = git_output(&dir, &["fetch", "--quiet", &remote, &branch], &conf);
+    if fetch.status.success() {
+        let merge = git_output(
+            &dir,
+            &[
+                "merge",
+                "--no-edit",
+                "--quiet",
+                "--allow-unrelated-histories",
+                "FETCH_HEAD",
+            ],
+            &conf,
+        );
+        if !merge.status.success() {
+            let conflicts = git(&dir, &["diff", "--name-only", "--diff-filter=U"], &conf);
+            let conflicts = String::from_utf8_lossy(&conflicts.stdout)
+                .split_whitespace()
+                .map(|f| f.to_owned())
+                .collect::<Vec<_>>();
+            // concurrent additions to the log nearly always conflict at its end, so the log is
+            // resolved by interleaving the two versions rather than by giving up
+            if conflicts == ["log"] && merge_logs(&dir, &remote, &conf) {
+                git(&dir, &["add", "--", "log"], &conf);
+                git(&dir, &["commit", "--quiet", "--no-edit"], &conf);
+                success(format!("merged your log with that from {}", remote), &conf);
+            } else {
+                git_output(&dir, &["merge", "--abort"], &conf);
+                let conflicts = conflicts.join(", ");
+                if conflicts.is_empty() {
+                    fatal(
+                        format!(
+                            "could not merge changes from {}: {}",
+                            remote,
+                            String::from_utf8_lossy(&merge.stderr).trim()
+                        ),
+                        &conf,
+                    );
+                } else {
+                    fatal(
+                        format!(
+                            "changes from {} conflict with local changes in {}; the merge has been abandoned",
+                            remote, conflicts
+                        ),
+                        &conf,
+                    );
+                }
+            }
+        }
+        if let Some(problem) = check_log(&conf) {
+            warn(
+                format!(
+                    "after merging changes from {}, {}; fix this with `job edit --validate` and sync again",
+                    remote, problem
+                ),
+                &conf,
+            );
+            return;
+        }
+        success(format!("pulled changes from {}", remote), &conf);
+    } else {
+        // the remote may be empty or lack this branch
+        warn(
+            format!(
+                "could not fetch {} from {}: {}",
+                branch,
+                remote,
+                String::from_utf8_lossy(&fetch.stderr).trim()
+            ),
+            &conf,
+        );
+    }
+    let push = git_output(&dir, &["push", "--quiet", &remote, &branch], &conf);
+    if push.status.success() {
+        success(format!("pushed changes to {}", remote), &conf);
+    } else {
+        fatal(
+            format!(
+                "could not push to {}: {}",
+                remote,
+                String::from_utf8_lossy(&push.stderr).trim()
+            ),
+            &conf,
+        );
+    }
+}
+
+// runs a git command in the given directory, dying if it fails
+fn git(dir: &PathBuf, args: &[&str], conf: &Configuration) -> Output {
+    let output = git_output(dir, args, conf);
+    if !output.status.success() {
+        fatal(
+            format!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            conf,
+        );
+    }
+    output
+}
+
+// runs a git command in the given directory, returning its output however it ends
+fn git_output(dir: &PathBuf, args: &[&str], conf: &Configuration) -> Output {
+    match Command::new("git").arg("-C").arg(dir).args(args).output() {
+        Ok(output) => output,
+        Err(e) => {
+            fatal(format!("could not run git: {}", e), conf);
+            unreachable!()
+        }
+    }
+}
+
+// replaces a conflicted log with the interleaving of our and their versions, returning whether
+// this succeeded; it fails if either version is invalid or the two logs overlap
+fn merge_logs(dir: &PathBuf, remote: &str, conf: &Configuration) -> bool {
+    let mut blocks = vec![];
+    for (rev, source) in [("HEAD", Source::Ours), ("FETCH_HEAD", Source::Theirs)].iter() {
+        let version = git_output(dir, &["show", &format!("{}:log", rev)], conf);
+        if !version.status.success() {
+            return false;
+        }
+        let path = dir.join(format!("log.{}.tmp", rev.to_lowercase()));
+        std::fs::write(&path, &version.stdout).expect("could not write a version of the log");
+        let read = read_blocks(path.to_str().unwrap(), *source);
+        let _ = std::fs::remove_file(&path);
+        match read {
+            Ok(b) => blocks.push(b),
+            Err(e) => {
+                warn(format!("could not merge the log: {}", e), conf);
+                return false;
+            }
+        }
+    }
+    let theirs = blocks.pop().unwrap();
+    let ours = blocks.pop().unwrap();
+    let merged = merge(ours, theirs);
+    let conflicts = conflicts(&merged, remote);
+    if !conflicts.is_empty() {
+        for c in conflicts.iter() {
+            warn(c, conf);
+        }
+        return false;
+    }
+    write_blocks(
+        &drop_orphaned_dones(merged),
+        log_path(conf.directory()).to_str().unwrap(),
+    );
+    true
+}
+
+// whether the log has yet to record anything
+fn log_is_empty(conf: &Configuration) -> bool {
+    let log = LogController::new(None, conf).expect("could not open log");
+    log.items().all(|i| !i.has_time())
+}
+
+// describes the first problem found in the log, if any
+fn check_log(conf: &Configuration) -> Option<String> {
+    let log = LogController::new(None, conf).expect("could not open log for validation");
+    let mut last: Option<NaiveDateTime> = None;
+    for item in log.items() {
+        if let Item::Error(message, offset) = &item {
+            return Some(format!(
+                "line {} of the log is invalid: {}",
+                offset + 1,
+                message
+            ));
+        }
+        if let Some((&t, offset)) = item.time() {
+            if let Some(l) = last {
+                if l > t {
+                    return Some(format!(
+                        "line {} of the log is out of order with the earlier timestamp {}",
+                        offset + 1,
+                        timestamp(&l)
+                    ));
+                }
+            }
+            last = Some(t);
+        }
+    }
+    None
+}
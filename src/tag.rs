@@ -3,12 +3,13 @@ extern crate clap;
 extern crate two_timer;
 
 use crate::configure::Configuration;
-use crate::log::{parse_line, Filter, Item, LogController, LogLine};
+use crate::log::{log_line, parse_line, Filter, Item, LogController};
+use crate::storage::Storage;
 use crate::util::{
-    common_search_or_filter_arguments, display_events, display_notes, fatal, remainder, some_nws,
-    warn,
+    common_search_or_filter_arguments, current_time, display_events, display_notes, fatal, info,
+    remainder, some_nws, success, warn, warn_of_unknown_tags,
 };
-use chrono::{Duration, Local};
+use chrono::{Duration, NaiveDateTime};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use std::fs::{copy, remove_file, File};
 use std::io::{BufReader, BufWriter, Read, Write};
@@ -28,6 +29,32 @@ need to categorize the interruption. You can `job edit` to add the missing tags,
 tag subcommand makes this a little easier. With `job tag --empty --last --add overhead --add communication` or \
 perhaps `job t -el -a o -a c` you're back on your way.
 
+If you have configured tag normalization -- --fold-tag-case or --tag-alias -- the add, note, \
+resume, and switch subcommands apply it to the tags they write. To apply it to the tags already \
+in the log, use --normalize:
+
+    job tag --normalize 'since 2010'
+
+The tag subcommand changes the tags of every event, or with --notes every note, in the period \
+that passes the filters, so you can retag a month's worth of events at once. To see what \
+would change first, use --dry-run:
+
+    job tag --dry-run --rx standup --add meeting 'last month'
+
+The events or notes whose tags change are shown with their new tags and counted. Events and \
+notes are never retagged together, so the tags of notes can be kept as an index of their own. \
+To make a script's intent plain, you may say --notes-only, which is the same as --notes, or \
+--events-only, which is what the subcommand does by default:
+
+    job tag --notes-only --rx 'postgres|psql' --add db 'this year'
+
+If you realize partway through a task that it belongs to some other project, change the tags \
+of the ongoing event with --current. The event goes on; only its line in the log is changed. \
+The words following --current are tag changes, +tag to add a tag and -tag to remove one, so \
+any other options must come before it:
+
+    job tag --dry-run --current +apollo -gemini
+
 All prefixes of 'tag', so 't' and 'ta', are aliases of the subcommand.
 "
 }
@@ -91,27 +118,90 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
         .number_of_values(1)
         .help("Removes tag, if present")
         .value_name("tag")
+    ).arg(
+        Arg::with_name("notes-only")
+        .long("notes-only")
+        .conflicts_with_all(&["events-only", "current"])
+        .help("Changes the tags of notes only; the same as --notes")
+    ).arg(
+        Arg::with_name("events-only")
+        .long("events-only")
+        .conflicts_with("notes")
+        .help("Changes the tags of events only; the default")
+    ).arg(
+        Arg::with_name("dry-run")
+        .long("dry-run")
+        .help("Shows the changes without making them")
+        .long_help("Shows the events or notes whose tags would change, with their new tags, and how many there are, but leaves the log as it is.")
+    ).arg(
+        Arg::with_name("current")
+        .long("current")
+        .conflicts_with_all(&["first", "last"])
+        .help("Changes the tags of the ongoing event")
+        .long_help("Changes the tags of the ongoing event, without ending it, rather than those of the events in a period. \
+        The words following --current are tag changes: +tag adds a tag and -tag removes one. Because these may begin \
+        with a hyphen, other options must precede --current. --add, --remove, --clear, and --normalize work as usual.")
+        .value_name("change")
+        .takes_value(true)
+        .multiple(true)
+        .min_values(0)
+        .allow_hyphen_values(true)
+    ).arg(
+        Arg::with_name("normalize")
+        .long("normalize")
+        .help("Applies the configured case folding and tag aliases")
+        .long_help("Puts the tags in lower case, if tag case is folded, and replaces aliases by the tags they stand for. See job configure --fold-tag-case and --tag-alias.")
     )
 )
 }
 
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
+    let current = matches.is_present("current");
     let mut to_add = if let Some(values) = matches.values_of("add") {
         values.collect::<Vec<_>>()
     } else {
         vec![]
     };
-    to_add.sort_unstable();
-    to_add.dedup();
     let mut to_remove = if let Some(values) = matches.values_of("remove") {
         values.collect::<Vec<_>>()
     } else {
         vec![]
     };
+    if current && matches.occurrences_of("period") > 0 {
+        fatal(
+            "--current changes the ongoing event, so there should be no period",
+            &conf,
+        );
+    }
+    if let Some(words) = matches.values_of("current") {
+        for word in words {
+            match (word.strip_prefix('+'), word.strip_prefix('-')) {
+                (_, Some(option)) if option.starts_with('-') => fatal(
+                    format!(
+                        "{} follows --current and so is taken as a tag change; put options before --current",
+                        word
+                    ),
+                    &conf,
+                ),
+                (Some(tag), _) if some_nws(tag) => to_add.push(tag),
+                (_, Some(tag)) if some_nws(tag) => to_remove.push(tag),
+                _ => fatal(
+                    format!(
+                        "with --current, {:?} should be +tag, to add a tag, or -tag, to remove one",
+                        word
+                    ),
+                    &conf,
+                ),
+            }
+        }
+    }
+    to_add.sort_unstable();
+    to_add.dedup();
     to_remove.sort_unstable();
     to_remove.dedup();
     let clear = matches.is_present("clear");
+    let normalize = matches.is_present("normalize");
     // some sanity checking
     if clear {
         if matches.is_present("no-tags") {
@@ -119,7 +209,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 "there is no point in --clear if you are seeking only items that are --empty",
                 &conf,
             );
-            if to_add.is_empty() {
+            if to_add.is_empty() && !normalize {
                 fatal("no tag changes specified: you must --add a tag if you are seeking only items that are --empty", &conf);
             }
         }
@@ -153,231 +243,326 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 &conf,
             );
         }
-        if to_add.is_empty() && to_remove.is_empty() {
+        if to_add.is_empty() && to_remove.is_empty() && !normalize {
             fatal(
-                "no tag changes specified: you must --clear tags, --add a tag, or --remove a tag",
+                "no tag changes specified: you must --clear tags, --add a tag, --remove a tag, or --normalize tags",
                 &conf,
             );
         }
     }
-    let phrase = remainder("period", matches);
-    if let Ok((start, end, _)) = parse(&phrase, conf.two_timer_config()) {
-        let mut reader = LogController::new(None, &conf).expect("could not read log");
-        let now = Local::now().naive_local();
-        if let Some(time) = reader.first_timestamp() {
-            // narrow the range in to just the dates from the beginning of the log to the present
-            // so that we don't have spurious vacation times
-            let start = if time > start {
-                time.date().and_hms(0, 0, 0)
-            } else {
-                start
-            };
-            let time = now.date().and_hms(0, 0, 0) + Duration::days(1);
-            let end = if end > time { time } else { end };
-
-            let filter = Filter::new(matches);
-            let notes_only = matches.is_present("notes");
-            let mut items = reader
-                .tagable_items_in_range(&start, &end)
-                .into_iter()
-                .filter(|i| match i {
-                    Item::Note(n, _) => {
-                        if notes_only {
-                            filter.matches(n)
-                        } else {
-                            false
-                        }
-                    }
-                    Item::Event(e, _) => {
-                        if notes_only {
-                            false
-                        } else {
-                            filter.matches(e)
-                        }
-                    }
-                    _ => false,
+    let mut reader = LogController::new(None, &conf).expect("could not read log");
+    let now = current_time();
+    let notes_only = notes_only(matches);
+    let (items, start, end) = if current {
+        if notes_only {
+            fatal(
+                "--current changes the tags of the ongoing event, not of notes",
+                &conf,
+            );
+        }
+        match ongoing_event(&mut reader) {
+            Some(item) => {
+                let start = *item.time().unwrap().0;
+                (vec![item], start, now)
+            }
+            None => {
+                warn("there is no ongoing event", &conf);
+                return;
+            }
+        }
+    } else {
+        match items_in_period(&mut reader, matches, &now, &conf) {
+            Some(found) => found,
+            None => return,
+        }
+    };
+    // only the items whose tags change are rewritten and shown
+    let items = items
+        .into_iter()
+        .filter_map(|i| match i {
+            Item::Note(mut n, offset) => {
+                retag(&mut n.tags, clear, &to_add, &to_remove, normalize, &conf)
+                    .then_some(Item::Note(n, offset))
+            }
+            Item::Event(mut e, offset) => {
+                retag(&mut e.tags, clear, &to_add, &to_remove, normalize, &conf)
+                    .then_some(Item::Event(e, offset))
+            }
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    if !items.is_empty() {
+        let dry_run = matches.is_present("dry-run");
+        if !dry_run {
+            rewrite(&items, &mut reader, &conf);
+        }
+        // now display the items
+        if notes_only {
+            let notes = items
+                .iter()
+                .map(|i| match i {
+                    Item::Note(n, _) => n.clone(),
+                    _ => unreachable!(),
                 })
                 .collect::<Vec<_>>();
-            if items.is_empty() {
-                fatal(
-                    format!("no {} found", if notes_only { "note" } else { "event" }),
-                    &conf,
-                );
-            } else if matches.is_present("last") {
-                items = vec![items.remove(items.len() - 1)];
+            if matches.is_present("json") {
+                for n in notes {
+                    println!("{}", n.to_json(&now, &conf));
+                }
+            } else if matches.is_present("tsv") {
+                for n in notes {
+                    println!("{}", n.to_tsv(&now, &conf));
+                }
+            } else {
+                display_notes(notes, &start, &end, &conf);
             }
-            let mut changed = false;
-            items = items
-                .into_iter()
-                .map(|i| match &i {
-                    Item::Note(n, offset) => {
-                        let mut tags = vec![];
-                        if clear {
-                            changed = changed || !n.tags.is_empty();
-                        } else {
-                            for s in &n.tags {
-                                if to_remove.contains(&s.as_str()) {
-                                    changed = true;
-                                } else {
-                                    tags.push(s.clone());
-                                }
-                            }
-                        }
-                        for s in &to_add {
-                            let s = s.to_string();
-                            if !tags.contains(&s) {
-                                changed = true;
-                                tags.push(s);
-                            }
-                        }
-                        let mut n = n.clone();
-                        n.tags = tags;
-                        Item::Note(n, *offset)
-                    }
+        } else {
+            // we need to create events *with end times*
+            let events = items
+                .iter()
+                .map(|i| match i {
                     Item::Event(e, offset) => {
-                        let mut tags = vec![];
-                        if clear {
-                            changed = changed || !e.tags.is_empty();
-                        } else {
-                            for s in &e.tags {
-                                if to_remove.contains(&s.as_str()) {
-                                    changed = true;
-                                } else {
-                                    tags.push(s.clone());
+                        let mut e = e.clone();
+                        // look for end time
+                        for i in offset + 1..reader.storage.len() {
+                            let i = parse_line(
+                                &reader.storage.get(i).expect("could not read the log"),
+                                0,
+                            );
+                            match &i {
+                                Item::Event(_, _) | Item::Done(_, _) => {
+                                    e.end = Some(i.time().unwrap().0.clone())
                                 }
+                                _ => (),
                             }
-                        }
-                        for s in &to_add {
-                            let s = s.to_string();
-                            if !tags.contains(&s) {
-                                changed = true;
-                                tags.push(s);
+                            if e.end.is_some() {
+                                break;
                             }
                         }
-                        let mut e = e.clone();
-                        e.tags = tags;
-                        Item::Event(e, *offset)
+                        e
                     }
                     _ => unreachable!(),
                 })
-                .collect();
-            if changed {
-                // create a copy of the log with the desired changes and replace the current log
-                // this could be more efficient; maybe some day it will be
-                let mut modified_copy = BufWriter::new(modified_copy(&conf));
-                let mut buf_reader = BufReader::new(log_file(&conf));
-                let byte_offset = reader
-                    .larry
-                    .offset(items[0].offset())
-                    .expect("could not obtain line offset of first item")
-                    as usize;
-                let mut bytes_written: usize = 0;
-                // fill up the log copy up to the offset without parsing bytes
-                while bytes_written < byte_offset {
-                    let delta = byte_offset - bytes_written;
-                    let mut buffer: Vec<u8> = if delta < BUFFER_SIZE {
-                        vec![0; delta]
-                    } else {
-                        vec![0; BUFFER_SIZE]
-                    };
-                    buf_reader
-                        .read_exact(&mut buffer)
-                        .expect("could not read from log file");
-                    bytes_written += buffer.len();
-                    modified_copy
-                        .write_all(&buffer)
-                        .expect("could not write to validation file");
-                }
-                // now add the changes and any other lines
-                let mut item_offset = 0;
-                for line_offset in items[0].offset()..reader.larry.len() {
-                    if item_offset == items.len() || items[item_offset].offset() != line_offset {
-                        modified_copy
-                            .write(
-                                reader
-                                    .larry
-                                    .get(line_offset)
-                                    .expect("could not obtain log line")
-                                    .as_bytes(),
-                            )
-                            .expect("could not write log line to log copy");
-                    } else {
-                        let line = match &items[item_offset] {
-                            Item::Event(e, _) => e.to_line(),
-                            Item::Note(n, _) => n.to_line(),
-                            _ => unreachable!(),
-                        };
-                        modified_copy
-                            .write(line.as_bytes())
-                            .expect("could not write log line to log copy");
-                        modified_copy
-                            .write("\n".as_bytes())
-                            .expect("could not add newline to log copy");
-                        item_offset += 1;
-                    }
+                .collect::<Vec<_>>();
+            if matches.is_present("json") {
+                for e in events {
+                    println!("{}", e.to_json(&now, &conf));
                 }
-                modified_copy
-                    .flush()
-                    .expect("could not flush log copy buffer");
-                copy(copy_path(&conf), log_path(&conf))
-                    .expect("could not replace old log with new");
-                remove_file(copy_path(&conf)).expect("could not remove log copy");
-                // now display the items
-                if notes_only {
-                    let notes = items
-                        .iter()
-                        .map(|i| match i {
-                            Item::Note(n, _) => n.clone(),
-                            _ => unreachable!(),
-                        })
-                        .collect::<Vec<_>>();
-                    display_notes(notes, &start, &end, &conf);
-                } else {
-                    // we need to create events *with end times*
-                    let events = items
-                        .iter()
-                        .map(|i| match i {
-                            Item::Event(e, offset) => {
-                                let mut e = e.clone();
-                                // look for end time
-                                for i in offset + 1..reader.larry.len() {
-                                    let i = parse_line(
-                                        &reader.larry.get(i).expect("larry failed us"),
-                                        0,
-                                    );
-                                    match &i {
-                                        Item::Event(_, _) | Item::Done(_, _) => {
-                                            e.end = Some(i.time().unwrap().0.clone())
-                                        }
-                                        _ => (),
-                                    }
-                                    if e.end.is_some() {
-                                        break;
-                                    }
-                                }
-                                e
-                            }
-                            _ => unreachable!(),
-                        })
-                        .collect::<Vec<_>>();
-                    display_events(events, &start, &end, &conf);
+            } else if matches.is_present("tsv") {
+                for e in events {
+                    println!("{}", e.to_tsv(&now, &conf));
                 }
             } else {
-                warn("no change", &conf);
+                display_events(events, &start, &end, &conf);
             }
+        }
+        let count = format!(
+            "{} {}{}",
+            items.len(),
+            if notes_only { "note" } else { "event" },
+            if items.len() == 1 { "" } else { "s" }
+        );
+        if dry_run {
+            success(
+                format!("would retag {}; the log is unchanged", count),
+                &conf,
+            );
         } else {
-            if matches.is_present("notes") {
-                warn("no note found", &conf)
-            } else {
-                warn("no event found", &conf)
-            }
+            success(format!("retagged {}", count), &conf);
         }
     } else {
+        warn("no change", &conf);
+        warn_of_unknown_tags(&to_remove, &conf);
+    }
+}
+
+// whether the tags of notes rather than events are to be changed
+fn notes_only(matches: &ArgMatches) -> bool {
+    matches.is_present("notes") || matches.is_present("notes-only")
+}
+
+// the events or notes in the period matching the filters, with the period's bounds, or None if
+// the log is empty
+fn items_in_period(
+    reader: &mut LogController,
+    matches: &ArgMatches,
+    now: &NaiveDateTime,
+    conf: &Configuration,
+) -> Option<(Vec<Item>, NaiveDateTime, NaiveDateTime)> {
+    let phrase = remainder("period", matches);
+    let (start, end) = match parse(&phrase, conf.two_timer_config()) {
+        Ok((start, end, _)) => (start, end),
+        Err(_) => {
+            fatal(
+                format!("could not parse '{}' as a time expression", phrase),
+                conf,
+            );
+            unreachable!()
+        }
+    };
+    info(
+        format!("'{}' is interpreted as {} to {}", phrase, start, end),
+        conf,
+    );
+    let notes_only = notes_only(matches);
+    let time = match reader.first_timestamp() {
+        Some(time) => time,
+        None => {
+            warn(
+                if notes_only {
+                    "no note found"
+                } else {
+                    "no event found"
+                },
+                conf,
+            );
+            return None;
+        }
+    };
+    // narrow the range in to just the dates from the beginning of the log to the present
+    // so that we don't have spurious vacation times
+    let start = if time > start {
+        time.date().and_hms(0, 0, 0)
+    } else {
+        start
+    };
+    let time = now.date().and_hms(0, 0, 0) + Duration::days(1);
+    let end = if end > time { time } else { end };
+    let filter = Filter::new(matches);
+    let mut items = reader
+        .tagable_items_in_range(&start, &end)
+        .into_iter()
+        .filter(|i| match i {
+            Item::Note(n, _) => notes_only && filter.matches(n),
+            Item::Event(e, _) => !notes_only && filter.matches(e),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        warn_of_unknown_tags(&filter.tags(), conf);
         fatal(
-            format!("could not parse '{}' as a time expression", phrase),
-            &conf,
-        )
+            format!("no {} found", if notes_only { "note" } else { "event" }),
+            conf,
+        );
+    } else if matches.is_present("last") {
+        items = vec![items.remove(items.len() - 1)];
+    }
+    Some((items, start, end))
+}
+
+// the ongoing event, if any, with its line offset
+fn ongoing_event(reader: &mut LogController) -> Option<Item> {
+    for offset in (0..reader.storage.len()).rev() {
+        match parse_line(
+            reader.storage.get(offset).expect("could not read the log"),
+            offset,
+        ) {
+            item @ Item::Event(_, _) => return Some(item),
+            Item::Done(_, _) => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+// applies the tag changes to the tags, returning whether they changed
+fn retag(
+    tags: &mut Vec<String>,
+    clear: bool,
+    to_add: &[&str],
+    to_remove: &[&str],
+    normalize: bool,
+    conf: &Configuration,
+) -> bool {
+    let mut changed = false;
+    let mut retagged = vec![];
+    if clear {
+        changed = !tags.is_empty();
+    } else {
+        for s in tags.iter() {
+            if to_remove.contains(&s.as_str()) {
+                changed = true;
+            } else {
+                retagged.push(s.clone());
+            }
+        }
     }
+    for s in to_add {
+        let s = s.to_string();
+        if !retagged.contains(&s) {
+            changed = true;
+            retagged.push(s);
+        }
+    }
+    if normalize {
+        let normalized = conf.normalize_tags(&retagged);
+        changed = changed || normalized != retagged;
+        retagged = normalized;
+    }
+    *tags = retagged;
+    changed
+}
+
+// replaces the lines of the items in the log with the items as they now are
+fn rewrite(items: &[Item], reader: &mut LogController, conf: &Configuration) {
+    // create a copy of the log with the desired changes and replace the current log
+    // this could be more efficient; maybe some day it will be
+    let mut modified_copy = BufWriter::new(modified_copy(conf));
+    let mut buf_reader = BufReader::new(log_file(conf));
+    let byte_offset = reader
+        .storage
+        .byte_offset(items[0].offset())
+        .expect("could not obtain line offset of first item") as usize;
+    let mut bytes_written: usize = 0;
+    // fill up the log copy up to the offset without parsing bytes
+    while bytes_written < byte_offset {
+        let delta = byte_offset - bytes_written;
+        let mut buffer: Vec<u8> = if delta < BUFFER_SIZE {
+            vec![0; delta]
+        } else {
+            vec![0; BUFFER_SIZE]
+        };
+        buf_reader
+            .read_exact(&mut buffer)
+            .expect("could not read from log file");
+        bytes_written += buffer.len();
+        modified_copy
+            .write_all(&buffer)
+            .expect("could not write to validation file");
+    }
+    // now add the changes and any other lines
+    let mut item_offset = 0;
+    for line_offset in items[0].offset()..reader.storage.len() {
+        if item_offset == items.len() || items[item_offset].offset() != line_offset {
+            modified_copy
+                .write(
+                    reader
+                        .storage
+                        .get(line_offset)
+                        .expect("could not obtain log line")
+                        .as_bytes(),
+                )
+                .expect("could not write log line to log copy");
+        } else {
+            let line = match &items[item_offset] {
+                Item::Event(e, _) => log_line(e, conf),
+                Item::Note(n, _) => log_line(n, conf),
+                _ => unreachable!(),
+            };
+            modified_copy
+                .write(line.as_bytes())
+                .expect("could not write log line to log copy");
+            modified_copy
+                .write("\n".as_bytes())
+                .expect("could not add newline to log copy");
+            item_offset += 1;
+        }
+    }
+    modified_copy
+        .flush()
+        .expect("could not flush log copy buffer");
+    copy(copy_path(conf), log_path(conf)).expect("could not replace old log with new");
+    remove_file(copy_path(conf)).expect("could not remove log copy");
 }
 
 fn copy_path(conf: &Configuration) -> PathBuf {
@@ -401,3 +586,24 @@ fn log_path(conf: &Configuration) -> PathBuf {
 fn log_file(conf: &Configuration) -> File {
     File::open(log_path(conf)).expect("could not produce log file")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+
+    #[test]
+    fn test_retag() {
+        let dir = TestDirectory::new("test_retag");
+        let conf = dir.configuration();
+        let mut tags = vec![String::from("bar"), String::from("foo")];
+        assert!(retag(&mut tags, false, &["baz"], &["foo"], false, &conf));
+        assert_eq!(vec!["bar", "baz"], tags);
+        assert!(
+            !retag(&mut tags, false, &["bar"], &["quux"], false, &conf),
+            "adding a tag already present and removing one absent change nothing"
+        );
+        assert!(retag(&mut tags, true, &["plugh"], &[], false, &conf));
+        assert_eq!(vec!["plugh"], tags);
+    }
+}
@@ -4,14 +4,17 @@ extern crate flate2;
 extern crate two_timer;
 
 use crate::configure::Configuration;
-use crate::log::LogController;
+use crate::log::{log_header, log_version, CarryForward, LogController};
+use crate::storage::{MemoryStorage, Storage};
 use crate::util::remainder;
-use crate::util::{base_dir, fatal, log_path, success, warn, yes_or_no};
+use crate::util::{base_dir, current_time, fatal, log_path, success, warn, yes_or_no};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use std::fs::File;
+use std::fs::{read_dir, File};
 use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use two_timer::{parsable, parse};
 
 const BUFFER_SIZE: usize = 16 * 1024;
@@ -26,6 +29,22 @@ You give it a starting date and it splits your log into two with the active port
 containing all moments on that date or after. The older portion is \
 retained in the hidden directory.
 
+With --carry-forward the truncated log begins with a comment recording the totals of what was \
+cut off -- the hours logged, by tag and in all, and the numbers of events and notes -- so the \
+statistics subcommand can still report on the whole history:
+
+  > job truncate --carry-forward 2020-01-01
+  > head -2 ~/.joblog/log
+  # job log version 1
+  # carried forward: {\"events\":14529,\"first\":\"2014-10-06 08:57:29\",\"hours\":10701.25,...}
+
+Truncating a log that begins with such a comment adds its totals to the new one.
+
+The summary, first, and statistics subcommands search the truncated heads as well as the log \
+if given --include-archives:
+
+  > job first --include-archives --tag projectx
+
 All prefixes of 'truncate' excepting 't' are aliases of the subcommand. The 't' alias belongs \
 to the tag subcommand."
 }
@@ -43,6 +62,14 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .help("Compresses truncated head of log with gzip")
                 .long_help("To conserve space, compress the truncated head of the log with Gzip.")
             )
+            .arg(
+                Arg::with_name("carry-forward")
+                .long("carry-forward")
+                .help("Records the totals of the truncated head in the log")
+                .long_help("Begins the truncated log with a comment recording the hours logged, in all and by tag, \
+                and the numbers of events and notes in the truncated head, including any totals it carried forward \
+                itself, so that statistics about the whole log remain correct.")
+            )
             .setting(AppSettings::TrailingVarArg)
             .arg(
                 Arg::with_name("date")
@@ -90,7 +117,12 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     fatal("could not truncate log", &conf);
                 }
             }
-            let offset = log.larry.offset(item.offset()).unwrap() as usize;
+            let carried = if matches.is_present("carry-forward") {
+                Some(log.carry_forward(item.offset(), &current_time()))
+            } else {
+                None
+            };
+            let offset = log.storage.byte_offset(item.offset()).unwrap() as usize;
             let mut bytes_read = 0;
             let original_file =
                 File::open(log_path(conf.directory())).expect("cannot open log file for reading");
@@ -140,6 +172,12 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             let tail_file = File::create(temp_log_path(conf.directory()))
                 .expect("could not open log.tmp for writing");
             let mut tail_writer = BufWriter::new(tail_file);
+            if log_version(log_path(conf.directory()).to_str().unwrap()) > 0 {
+                writeln!(tail_writer, "{}", log_header()).expect("failed to write to log.tmp");
+            }
+            if let Some(carried) = &carried {
+                writeln!(tail_writer, "{}", carried.to_line()).expect("failed to write to log.tmp");
+            }
             loop {
                 let mut buffer: Vec<u8> = vec![0; BUFFER_SIZE];
                 let bytes_read = reader.read(&mut buffer).expect("failed to read from log");
@@ -148,7 +186,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     break;
                 }
                 tail_writer
-                    .write_all(&buffer)
+                    .write_all(&buffer[..bytes_read])
                     .expect("failed to write to log.tmp");
             }
             std::fs::rename(
@@ -177,8 +215,101 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     }
 }
 
+// the heads truncate has cut from the log at the path, earliest first
+pub fn archives(log: &Path) -> Vec<PathBuf> {
+    let mut archives = log
+        .parent()
+        .and_then(|dir| read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("log.head-to-"))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // the names end in the times of the truncations, so they sort in order
+    archives.sort();
+    archives
+}
+
+// the heads cut from the log followed by the log itself, read into memory as one log; the totals
+// carried forward are left out, since the heads hold what they total
+pub fn archived_log(log: &Path) -> std::io::Result<MemoryStorage> {
+    let mut text = String::new();
+    let mut paths = archives(log);
+    paths.push(log.to_owned());
+    for (i, path) in paths.iter().enumerate() {
+        let mut part = String::new();
+        let file = File::open(path)?;
+        if path.extension().is_some_and(|e| e == "gz") {
+            GzDecoder::new(file).read_to_string(&mut part)?;
+        } else {
+            BufReader::new(file).read_to_string(&mut part)?;
+        }
+        for line in part.lines() {
+            if (i > 0 && line.trim() == log_header()) || CarryForward::from_line(line).is_some() {
+                continue;
+            }
+            text += line;
+            text.push('\n');
+        }
+    }
+    Ok(MemoryStorage::new(&text))
+}
+
 fn temp_log_path(directory: Option<&str>) -> std::path::PathBuf {
     let mut path = base_dir(directory);
     path.push("log.tmp");
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TestDirectory;
+    use std::fs::write;
+
+    #[test]
+    fn test_archived_log() {
+        let dir = TestDirectory::new("test_archived_log");
+        let header = log_header();
+        write(
+            dir.path.join("log.head-to-2020-03-04_00:00:00"),
+            format!("{}\n2020  3  3  9  0  0::one\n", header),
+        )
+        .unwrap();
+        let mut encoder = GzEncoder::new(
+            File::create(dir.path.join("log.head-to-2020-03-05_00:00:00.gz")).unwrap(),
+            Compression::best(),
+        );
+        let carried = CarryForward {
+            events: 1,
+            hours: 1.0,
+            ..Default::default()
+        };
+        write!(
+            encoder,
+            "{}\n{}\n2020  3  4  9  0  0::two\n",
+            header,
+            carried.to_line()
+        )
+        .unwrap();
+        encoder.finish().unwrap();
+        let log = dir.path.join("log");
+        write(&log, format!("{}\n2020  3  5  9  0  0::three\n", header)).unwrap();
+        assert_eq!(2, archives(&log).len());
+        assert_eq!(
+            format!(
+                "{}\n2020  3  3  9  0  0::one\n2020  3  4  9  0  0::two\n2020  3  5  9  0  0::three\n",
+                header
+            ),
+            archived_log(&log).unwrap().text(),
+            "the heads come first, without their headers or the totals carried forward"
+        );
+    }
+}
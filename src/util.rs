@@ -1,34 +1,61 @@
 extern crate ansi_term;
 extern crate chrono;
+#[cfg(feature = "cli")]
 extern crate clap;
+#[cfg(feature = "cli")]
 extern crate colonnade;
 extern crate dirs;
+#[cfg(unix)]
+extern crate libc;
 extern crate pidgin;
 extern crate regex;
+extern crate two_timer;
 
-use crate::configure::Configuration;
-use crate::log::{Event, Item, LogController, Note};
-use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
+use crate::configure::{Configuration, PayPeriod};
+#[cfg(feature = "cli")]
+use crate::issue::Issues;
+#[cfg(feature = "cli")]
+use crate::log::{elapsed_seconds, log_line, parse_line, tag_suggestions, Done, FilterExpression};
+use crate::log::{log_header, Event, Item, LogController, Note};
+#[cfg(feature = "cli")]
+use crate::merge::tmp_path;
+#[cfg(feature = "cli")]
+use crate::storage::FileStorage;
+use crate::storage::Storage;
+#[cfg(feature = "cli")]
+use crate::truncate::archived_log;
+#[cfg(feature = "cli")]
+use crate::vacation::VacationController;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Weekday};
+#[cfg(feature = "cli")]
 use clap::{App, Arg, ArgMatches};
+#[cfg(feature = "cli")]
 use colonnade::{Alignment, Colonnade};
 use dirs::home_dir;
 use pidgin::{Grammar, Matcher};
 use regex::Regex;
 use std::collections::BTreeMap;
-use std::fs::{create_dir, File};
+#[cfg(feature = "cli")]
+use std::collections::BTreeSet;
+use std::fs::{create_dir_all, File};
+#[cfg(feature = "cli")]
+use std::fs::{read_to_string, rename, write};
 use std::io;
 use std::io::Write;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
 
+#[cfg(feature = "cli")]
 const ONGOING: &str = "ongoing";
 
 // a collection of arguments used in many subcommands concerned with searching for or filtering events
+#[cfg(feature = "cli")]
 pub fn common_search_or_filter_arguments(
     app: App<'static, 'static>,
     for_events: Option<bool>,
 ) -> App<'static, 'static> {
-    if for_events.is_none() {
+    let app = if for_events.is_none() {
         app.arg(
             Arg::with_name("notes")
             .short("n")
@@ -108,7 +135,7 @@ pub fn common_search_or_filter_arguments(
         Arg::with_name("no-tags")
         .short("e")
         .long("empty")
-        .visible_alias("no-tags")
+        .visible_aliases(&["no-tags", "untagged"])
         .help(match for_events {
             Some(true) => "Selects events that lack tags",
             Some(false) => "Selects notes that lack tags",
@@ -155,9 +182,151 @@ pub fn common_search_or_filter_arguments(
         .validator(|arg| if Regex::new(&arg).is_ok() {Ok(())} else {Err(format!("'{}' cannot be parsed as a regular expression", &arg))})
         .display_order(6)
     )
+    .arg(
+        Arg::with_name("rx-i")
+        .long("rx-i")
+        .help("Makes --rx and --rx-not patterns case-insensitive")
+        .long_help("Makes the patterns given to --rx and --rx-not ignore case, so 'standup' matches 'Standup' and 'STANDUP'. \
+        This is the same as beginning each pattern with (?i).")
+        .display_order(6)
+    )
+    .arg(
+        Arg::with_name("rx-word")
+        .long("rx-word")
+        .help("Makes --rx and --rx-not patterns match whole words")
+        .long_help("Makes the patterns given to --rx and --rx-not match only whole words, so 'cat' matches 'the cat sat' but \
+        not 'concatenate'. This is the same as surrounding each pattern with \\b(?:...)\\b.")
+        .display_order(6)
+    )
+    .arg(
+        Arg::with_name("author")
+        .long("author")
+        .multiple(true)
+        .number_of_values(1)
+        .help(match for_events {
+            Some(true) => "Skips events not by this author",
+            Some(false) => "Skips notes not by this author",
+            None => "Skips events/notes not by this author"
+        })
+        .long_help(match for_events {
+            Some(true) => "Skips events not recorded by this author; see the user-name configuration parameter. If the option is repeated, events by any of the authors are found.",
+            Some(false) => "Skips notes not recorded by this author; see the user-name configuration parameter. If the option is repeated, notes by any of the authors are found.",
+            None => "Skips events or notes not recorded by this author; see the user-name configuration parameter. If the option is repeated, events or notes by any of the authors are found."
+        })
+        .value_name("name")
+        .display_order(7)
+    )
+    .arg(
+        Arg::with_name("fuzzy")
+        .long("fuzzy")
+        .help(match for_events {
+            Some(true) => "Finds events whose description resembles this text",
+            Some(false) => "Finds notes whose text resembles this text",
+            None => "Finds events/notes whose description/text resembles this text"
+        })
+        .long_help(match for_events {
+            Some(true) => "Finds events whose descriptions resemble this text, forgiving typos and differences of word order.",
+            Some(false) => "Finds notes whose text resembles this text, forgiving typos and differences of word order.",
+            None => "Finds events or notes whose descriptions or text resemble this text, forgiving typos and differences of word order."
+        })
+        .value_name("text")
+        .display_order(8)
+    )
+    .arg(
+        Arg::with_name("filter")
+        .long("filter")
+        .help(match for_events {
+            Some(true) => "Finds events matching this boolean expression",
+            Some(false) => "Finds notes matching this boolean expression",
+            None => "Finds events/notes matching this boolean expression"
+        })
+        .long_help("Combines tests with AND, OR, NOT, and parentheses, as in '(tag:acme OR tag:bigco) AND NOT rx:standup'. \
+        The tests are tag:<tag>, rx:<pattern>, author:<name>, and untagged; a value containing spaces may be quoted, as in \
+        rx:\"daily standup\". NOT binds more tightly than AND and AND more tightly than OR. Tests side by side are ANDed. \
+        The expression is applied in addition to any other filtering options.")
+        .value_name("expression")
+        .validator(|arg| FilterExpression::parse(&arg).map(|_| ()))
+        .display_order(9)
+    );
+    if for_events == Some(false) {
+        return app;
+    }
+    app.arg(
+        Arg::with_name("longer-than")
+        .long("longer-than")
+        .help("Skips events no longer than this")
+        .long_help("Skips events that lasted no longer than this duration, given as, e.g., '30 min', '2h', or '1h 15m'. \
+        This is useful for hiding brief context switches. Notes, which have no duration, are unaffected.")
+        .value_name("duration")
+        .validator(valid_duration)
+        .display_order(10)
+    )
+    .arg(
+        Arg::with_name("shorter-than")
+        .long("shorter-than")
+        .help("Skips events no shorter than this")
+        .long_help("Skips events that lasted this long or longer, the duration being given as, e.g., '30 min', '2h', or \
+        '1h 15m'. Notes, which have no duration, are unaffected.")
+        .value_name("duration")
+        .validator(valid_duration)
+        .display_order(11)
+    )
+}
+
+#[cfg(feature = "cli")]
+pub fn valid_duration(v: String) -> Result<(), String> {
+    if parse_duration(&v).is_some() {
+        Ok(())
+    } else {
+        Err(format!(
+            "cannot parse '{}' as a duration; try something like '30 min' or '1h 15m'",
+            v
+        ))
+    }
+}
+
+// parses a duration such as '30 min', '2h', or '1.5 hours' into a number of seconds
+pub fn parse_duration(expression: &str) -> Option<i64> {
+    lazy_static! {
+        static ref DURATION: Regex = Regex::new(
+            r"(?ix) \A \s* (?: (?: \d+ (?:\.\d*)? | \.\d+ ) \s* (?: hours? | hrs? | h | minutes? | mins? | m | seconds? | secs? | s ) \s* )+ \z"
+        )
+        .unwrap();
+        static ref PART: Regex =
+            Regex::new(r"(?i)(\d+(?:\.\d*)?|\.\d+)\s*([hms])").unwrap();
+    }
+    if !DURATION.is_match(expression) {
+        return None;
+    }
+    let seconds = PART
+        .captures_iter(expression)
+        .map(|c| {
+            let n: f64 = c[1].parse().unwrap();
+            n * match c[2].to_ascii_lowercase().as_str() {
+                "h" => 60.0 * 60.0,
+                "m" => 60.0,
+                _ => 1.0,
+            }
+        })
+        .sum::<f64>();
+    Some(seconds.round() as i64)
+}
+
+// the gist of a clap error, without the usage information that follows it
+#[cfg(feature = "cli")]
+pub fn clap_error_message(e: &clap::Error) -> String {
+    e.message
+        .lines()
+        .take_while(|l| !l.trim().is_empty())
+        .map(|l| l.trim())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_start_matches("error: ")
+        .to_owned()
 }
 
 // concatenate the trailing arguments -- we need to do this often enough it seems worth DRYing up
+#[cfg(feature = "cli")]
 pub fn remainder(argname: &str, matches: &ArgMatches) -> std::string::String {
     matches
         .values_of(argname)
@@ -166,9 +335,18 @@ pub fn remainder(argname: &str, matches: &ArgMatches) -> std::string::String {
         .join(" ")
 }
 
+// environment variables which may specify the directory in lieu of --directory
+pub const DIRECTORY_VARIABLES: [&str; 2] = ["JOBLOG_DIR", "JOBROG_DIRECTORY"];
+
 pub fn base_dir(directory: Option<&str>) -> std::path::PathBuf {
     if let Some(dir) = directory {
         PathBuf::from_str(dir).expect(&format!("could not treat {} as a file path", dir))
+    } else if let Some(dir) = DIRECTORY_VARIABLES
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.is_empty())
+    {
+        PathBuf::from(dir)
     } else {
         let mut dir = home_dir().unwrap();
         dir.push(".joblog");
@@ -176,17 +354,42 @@ pub fn base_dir(directory: Option<&str>) -> std::path::PathBuf {
     }
 }
 
+// the directory to use given the --directory and --profile options and the default profile, if any
+pub fn resolve_directory(directory: Option<&str>, profile: Option<&str>) -> Option<String> {
+    if directory.is_some() {
+        return directory.map(|d| d.to_owned());
+    }
+    let base = if Configuration::config_file(None).as_path().exists() {
+        Configuration::read(None, None)
+    } else if profile.is_some() {
+        Configuration::base()
+    } else {
+        return None;
+    };
+    profile
+        .map(|p| p.to_owned())
+        .or_else(|| base.default_profile.clone())
+        .map(|p| base.profile_directory(&p).to_str().unwrap().to_owned())
+}
+
 pub fn log_path(directory: Option<&str>) -> std::path::PathBuf {
     let mut dir = base_dir(directory);
     dir.push("log");
     dir
 }
 
+#[cfg(feature = "cli")]
 fn time_string(this_time: &Option<NaiveDateTime>, conf: &Configuration) -> String {
     if let Some(this_time) = this_time {
-        let format = if conf.h12 { "%l:%M" } else { "%k:%M" };
+        let format = if let Some(f) = &conf.time_format {
+            f.as_str()
+        } else if conf.h12 {
+            "%l:%M"
+        } else {
+            "%k:%M"
+        };
         // replace a space with non-breaking whitespace that won't be stripped or split by colonnade
-        format!("{}", this_time.format(format))
+        format_time(this_time, format, conf)
             .as_str()
             .replace(" ", "\u{00A0}")
     } else {
@@ -194,7 +397,38 @@ fn time_string(this_time: &Option<NaiveDateTime>, conf: &Configuration) -> Strin
     }
 }
 
+// the duration for display: exact seconds with --raw, otherwise hours rounded as configured
 pub fn duration_string(duration: f32, conf: &Configuration) -> String {
+    if conf.raw {
+        format!("{}", duration.round() as i64)
+    } else {
+        hours_string(duration, conf)
+    }
+}
+
+// the fields as a line of tab-separated values, with backslashes, tabs, and line breaks escaped
+pub fn tsv_row<T: AsRef<str>>(fields: &[T]) -> String {
+    fields
+        .iter()
+        .map(|f| {
+            let mut field = String::new();
+            for c in f.as_ref().chars() {
+                match c {
+                    '\\' => field.push_str("\\\\"),
+                    '\t' => field.push_str("\\t"),
+                    '\n' => field.push_str("\\n"),
+                    '\r' => field.push_str("\\r"),
+                    _ => field.push(c),
+                }
+            }
+            field
+        })
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+// the duration in hours, rounded and truncated as configured even with --raw
+pub fn hours_string(duration: f32, conf: &Configuration) -> String {
     format!(
         "{0:.1$}",
         conf.truncation
@@ -203,14 +437,397 @@ pub fn duration_string(duration: f32, conf: &Configuration) -> String {
     )
 }
 
-fn date_string(date: &NaiveDate, same_year: bool) -> String {
-    if same_year {
-        format!("{}", date.format("%A, %e %B"))
+#[cfg(feature = "cli")]
+fn date_string(date: &NaiveDate, same_year: bool, conf: &Configuration) -> String {
+    if let Some(f) = &conf.date_format {
+        format_date(date, f, conf)
+    } else if same_year {
+        format_date(date, "%A, %e %B", conf)
     } else {
-        format!("{}", date.format("%A, %e %B %Y"))
+        format_date(date, "%A, %e %B %Y", conf)
+    }
+}
+
+// the header of a day in a summary, with the ISO week number if the period spans several weeks
+#[cfg(feature = "cli")]
+fn day_header(
+    date: &NaiveDate,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    conf: &Configuration,
+) -> String {
+    // end is exclusive
+    let first_day = conf.logical_date(start);
+    let last_day = conf.logical_date(&(*end - Duration::seconds(1)));
+    let header = date_string(date, start.year() == end.year(), conf);
+    if conf.week_numbers && first_day.iso_week() != last_day.iso_week() {
+        format!("{} (week {})", header, date.iso_week().week())
+    } else {
+        header
+    }
+}
+
+// two_timer doesn't know about ISO week numbers, so expressions such as "week 37" or
+// "week 37 2019" are converted into the equivalent date ranges before parsing
+pub fn expand_week_number(phrase: &str) -> String {
+    lazy_static! {
+        static ref WEEK: Regex =
+            Regex::new(r"(?i)\A\s*(?:iso\s+)?week\s+(\d{1,2})(?:\s*,?\s*(?:of\s+)?(\d{4}))?\s*\z")
+                .unwrap();
+    }
+    if let Some(captures) = WEEK.captures(phrase) {
+        let week = captures[1].parse::<u32>().unwrap();
+        let year = captures
+            .get(2)
+            .map(|m| m.as_str().parse::<i32>().unwrap())
+            .unwrap_or_else(|| current_time().date().iso_week().year());
+        if let Some(monday) = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon) {
+            let sunday = monday + Duration::days(6);
+            return format!("{} - {}", monday.format("%F"), sunday.format("%F"));
+        }
+    }
+    phrase.to_owned()
+}
+
+lazy_static! {
+    static ref FISCAL_YEAR: Regex =
+        Regex::new(r"(?i)\A\s*(?:(this|last|next|the)\s+)?(fiscal\s+)?year\s*\z").unwrap();
+}
+
+// whether the phrase is an expression such as "last fiscal year"
+pub fn names_fiscal_year(phrase: &str) -> bool {
+    FISCAL_YEAR
+        .captures(phrase)
+        .map(|c| c.get(2).is_some())
+        .unwrap_or(false)
+}
+
+// when a fiscal year is configured, expressions such as "this fiscal year" -- or, if fiscal is
+// true, "this year" -- are converted into the equivalent date ranges before parsing
+pub fn expand_fiscal_year(phrase: &str, fiscal: bool, conf: &Configuration) -> String {
+    if let Some(captures) = FISCAL_YEAR.captures(phrase) {
+        if fiscal || captures.get(2).is_some() {
+            let today = current_time().date();
+            if let Some(start) = conf.fiscal_year_containing(&today) {
+                let offset = match captures.get(1).map(|m| m.as_str().to_lowercase()) {
+                    Some(ref s) if s == "last" => -1,
+                    Some(ref s) if s == "next" => 1,
+                    _ => 0,
+                };
+                let start = NaiveDate::from_ymd(start.year() + offset, start.month(), start.day());
+                let end = NaiveDate::from_ymd(start.year() + 1, start.month(), start.day())
+                    - Duration::days(1);
+                return format!("{} - {}", start.format("%F"), end.format("%F"));
+            }
+        }
+    }
+    phrase.to_owned()
+}
+
+lazy_static! {
+    static ref PAY_PERIOD: Regex =
+        Regex::new(r"(?i)\A\s*(?:(this|last|next|the)\s+)?(?:pay[\s-]*period|pp)\s*\z").unwrap();
+}
+
+// whether the phrase is an expression such as "last pay period"
+pub fn names_pay_period(phrase: &str) -> bool {
+    PAY_PERIOD.is_match(phrase)
+}
+
+// when pay periods are calendar months or half months, which two_timer knows nothing of,
+// expressions such as "last pay period" are converted into the equivalent date ranges
+pub fn expand_pay_period(phrase: &str, conf: &Configuration) -> String {
+    if conf.pay_period == PayPeriod::Fixed {
+        return phrase.to_owned();
+    }
+    if let Some(captures) = PAY_PERIOD.captures(phrase) {
+        let today = conf.logical_date(&current_time());
+        let (mut start, mut end) = conf.pay_period_containing(&today).unwrap();
+        match captures.get(1).map(|m| m.as_str().to_lowercase()) {
+            Some(ref s) if s == "last" => {
+                end = start;
+                start = conf
+                    .pay_period_containing(&(start - Duration::days(1)))
+                    .unwrap()
+                    .0;
+            }
+            Some(ref s) if s == "next" => {
+                start = end;
+                end = conf.pay_period_containing(&end).unwrap().1;
+            }
+            _ => (),
+        }
+        return format!(
+            "{} - {}",
+            start.format("%F"),
+            (end - Duration::days(1)).format("%F")
+        );
+    }
+    phrase.to_owned()
+}
+
+// convert the expressions Job Log understands but two_timer does not into ones two_timer understands
+pub fn expand_period(phrase: &str, fiscal: bool, conf: &Configuration) -> String {
+    let expanded = expand_fiscal_year(&expand_week_number(phrase), fiscal, conf);
+    let expanded = expand_pay_period(&expanded, conf);
+    if expanded != phrase {
+        info(format!("'{}' expands to '{}'", phrase, expanded), conf);
+    }
+    expanded
+}
+
+// weekday names from Monday and month names from January, in full and abbreviated
+struct LocaleNames {
+    weekdays: [&'static str; 7],
+    short_weekdays: [&'static str; 7],
+    months: [&'static str; 12],
+    short_months: [&'static str; 12],
+}
+
+// the languages whose day and month names we know, identified by ISO 639-1 code
+pub const LOCALES: &[&str] = &["de", "en", "es", "fr", "it", "nl", "pt"];
+
+fn locale_names(locale: &str) -> Option<LocaleNames> {
+    // accept things like de_DE.UTF-8 or de-AT
+    let language = locale
+        .split(&['_', '-', '.'][..])
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    match language.as_str() {
+        "de" => Some(LocaleNames {
+            weekdays: [
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+                "Sonntag",
+            ],
+            short_weekdays: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+            months: [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            short_months: [
+                "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+            ],
+        }),
+        "es" => Some(LocaleNames {
+            weekdays: [
+                "lunes",
+                "martes",
+                "miércoles",
+                "jueves",
+                "viernes",
+                "sábado",
+                "domingo",
+            ],
+            short_weekdays: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+            months: [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+            short_months: [
+                "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+            ],
+        }),
+        "fr" => Some(LocaleNames {
+            weekdays: [
+                "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+            ],
+            short_weekdays: ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+            months: [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            short_months: [
+                "janv", "févr", "mars", "avr", "mai", "juin", "juil", "août", "sept", "oct", "nov",
+                "déc",
+            ],
+        }),
+        "it" => Some(LocaleNames {
+            weekdays: [
+                "lunedì",
+                "martedì",
+                "mercoledì",
+                "giovedì",
+                "venerdì",
+                "sabato",
+                "domenica",
+            ],
+            short_weekdays: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+            months: [
+                "gennaio",
+                "febbraio",
+                "marzo",
+                "aprile",
+                "maggio",
+                "giugno",
+                "luglio",
+                "agosto",
+                "settembre",
+                "ottobre",
+                "novembre",
+                "dicembre",
+            ],
+            short_months: [
+                "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+            ],
+        }),
+        "nl" => Some(LocaleNames {
+            weekdays: [
+                "maandag",
+                "dinsdag",
+                "woensdag",
+                "donderdag",
+                "vrijdag",
+                "zaterdag",
+                "zondag",
+            ],
+            short_weekdays: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+            months: [
+                "januari",
+                "februari",
+                "maart",
+                "april",
+                "mei",
+                "juni",
+                "juli",
+                "augustus",
+                "september",
+                "oktober",
+                "november",
+                "december",
+            ],
+            short_months: [
+                "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+            ],
+        }),
+        "pt" => Some(LocaleNames {
+            weekdays: [
+                "segunda-feira",
+                "terça-feira",
+                "quarta-feira",
+                "quinta-feira",
+                "sexta-feira",
+                "sábado",
+                "domingo",
+            ],
+            short_weekdays: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+            months: [
+                "janeiro",
+                "fevereiro",
+                "março",
+                "abril",
+                "maio",
+                "junho",
+                "julho",
+                "agosto",
+                "setembro",
+                "outubro",
+                "novembro",
+                "dezembro",
+            ],
+            short_months: [
+                "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+            ],
+        }),
+        // English is chrono's default
+        _ => None,
+    }
+}
+
+// whether we know the day and month names for this locale
+pub fn known_locale(locale: &str) -> bool {
+    locale_names(locale).is_some() || locale.to_lowercase().starts_with("en")
+}
+
+// replace the name directives in a strftime pattern with the localized names
+fn localize_pattern<T: Datelike>(t: &T, pattern: &str, locale: Option<&str>) -> String {
+    let names = match locale.and_then(locale_names) {
+        Some(names) => names,
+        None => return pattern.to_owned(),
+    };
+    let weekday = t.weekday().num_days_from_monday() as usize;
+    let month = t.month0() as usize;
+    let mut localized = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('A') => localized.push_str(names.weekdays[weekday]),
+            Some('a') => localized.push_str(names.short_weekdays[weekday]),
+            Some('B') => localized.push_str(names.months[month]),
+            Some('b') | Some('h') => localized.push_str(names.short_months[month]),
+            Some(c) => {
+                localized.push('%');
+                localized.push(c);
+            }
+            None => localized.push('%'),
+        }
     }
+    localized
 }
 
+// format a date, localizing day and month names; a pattern which cannot format a date, which
+// could only come from a hand-edited config.ini, falls back to %F
+pub fn format_date(date: &NaiveDate, pattern: &str, conf: &Configuration) -> String {
+    use std::fmt::Write;
+    let mut s = String::new();
+    let pattern = localize_pattern(date, pattern, conf.locale.as_deref());
+    if write!(s, "{}", date.format(&pattern)).is_err() {
+        return date.to_string();
+    }
+    s
+}
+
+// format a time, localizing day and month names; falls back to %F %T like format_date
+pub fn format_time(time: &NaiveDateTime, pattern: &str, conf: &Configuration) -> String {
+    use std::fmt::Write;
+    let mut s = String::new();
+    let pattern = localize_pattern(time, pattern, conf.locale.as_deref());
+    if write!(s, "{}", time.format(&pattern)).is_err() {
+        return time.to_string();
+    }
+    s
+}
+
+#[cfg(feature = "cli")]
 pub fn display_notes(
     notes: Vec<Note>,
     start: &NaiveDateTime,
@@ -218,28 +835,38 @@ pub fn display_notes(
     conf: &Configuration,
 ) {
     let style = Style::new(conf);
-    let same_year = start.year() == end.year();
     let mut last_date: Option<NaiveDate> = None;
+    let show_authors = notes.iter().any(|n| n.author.is_some());
     let data: Vec<Vec<String>> = notes
         .iter()
         .map(|n| {
-            let mut parts = Vec::with_capacity(3);
+            let mut parts = Vec::with_capacity(4);
             parts.push(time_string(&Some(n.time), conf));
             parts.push(n.tags.join(", "));
+            if show_authors {
+                parts.push(n.author.clone().unwrap_or_default());
+            }
             parts.push(n.description.clone());
             parts
         })
         .collect();
-    let mut note_table = Colonnade::new(3, conf.width()).unwrap();
+    let mut note_table = Colonnade::new(if show_authors { 4 } else { 3 }, conf.width()).unwrap();
     note_table.priority(0).left_margin(2).unwrap();
     note_table.columns[0].alignment(Alignment::Right);
     note_table.columns[1].priority(1);
     note_table.columns[2].priority(2);
+    if show_authors {
+        note_table.columns[2].priority(1);
+        note_table.columns[3].priority(2);
+    }
 
     for (offset, row) in note_table.macerate(data).unwrap().iter().enumerate() {
-        let date = notes[offset].time.date();
+        let date = conf.logical_date(&notes[offset].time);
         if last_date.is_none() || last_date.unwrap() != date {
-            println!("{}", style.paint("header", date_string(&date, same_year)));
+            println!(
+                "{}",
+                style.paint("header", day_header(&date, start, end, conf))
+            );
         }
         last_date = Some(date);
         for line in row {
@@ -255,24 +882,65 @@ pub fn display_notes(
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn display_events(
     events: Vec<Event>,
     start: &NaiveDateTime,
     end: &NaiveDateTime,
     conf: &Configuration,
+) {
+    display_events_with(events, Extras::default(), start, end, conf)
+}
+
+// what a summary may show besides the events themselves
+#[derive(Default)]
+#[cfg(feature = "cli")]
+pub struct Extras {
+    pub notes: Vec<Note>, // each shown beneath the event during which it was taken
+    pub gaps: bool,       // whether to show untracked time during work hours
+    pub percentages: Option<Percentages>, // whether to show each event's share of its day
+    pub links: bool,      // whether to make issue references hyperlinks
+}
+
+// what an event's share of its day is a share of
+#[cfg(feature = "cli")]
+pub enum Percentages {
+    Total,     // the time logged that day
+    DayLength, // the hours one is expected to work that day
+}
+
+// like display_events, but with the given extras
+#[cfg(feature = "cli")]
+pub fn display_events_with(
+    events: Vec<Event>,
+    extras: Extras,
+    start: &NaiveDateTime,
+    end: &NaiveDateTime,
+    conf: &Configuration,
 ) {
     lazy_static! {
         static ref ANY_CONTENT: Regex = Regex::new(r"\S").unwrap();
     }
     let style = Style::new(conf);
+    let issues = if extras.links {
+        Some(Issues::new(conf))
+    } else {
+        None
+    };
     let mut last_date: Option<NaiveDate> = None;
     let mut durations: BTreeMap<String, f32> = BTreeMap::new();
     let mut total_duration = 0.0;
     let mut untagged_duration = 0.0;
     let mut vacation_duration = 0.0;
-    let now = Local::now().naive_local();
-    let same_year = start.year() == end.year();
-    let data: Vec<Vec<String>> = events
+    let now = current_time();
+    let show_authors = events.iter().any(|e| e.author.is_some());
+    // the percentage column, if any, follows the duration
+    let pct = extras.percentages.is_some() as usize;
+    let mut day_totals: BTreeMap<NaiveDate, f32> = BTreeMap::new();
+    for e in events.iter() {
+        *day_totals.entry(conf.logical_date(&e.start)).or_insert(0.0) += e.duration(&now);
+    }
+    let mut data: Vec<Vec<String>> = events
         .iter()
         .map(|e| {
             if let Some(&date) = last_date.as_ref() {
@@ -288,6 +956,18 @@ pub fn display_events(
             parts.push(time_string(&e.end, conf));
             let duration = e.duration(&now);
             parts.push(duration_string(duration, conf));
+            if let Some(percentages) = &extras.percentages {
+                let date = conf.logical_date(&e.start);
+                let whole = match percentages {
+                    Percentages::Total => day_totals[&date],
+                    Percentages::DayLength => conf.day_length_on(&date) * 60.0 * 60.0,
+                };
+                parts.push(if whole > 0.0 {
+                    format!("{:.0}%", 100.0 * duration / whole)
+                } else {
+                    String::new()
+                });
+            }
             parts.push(e.tags.join(", "));
             for tag in e.tags.iter() {
                 *durations.entry(tag.clone()).or_insert(0.0) += duration;
@@ -299,12 +979,15 @@ pub fn display_events(
                 vacation_duration += duration;
             }
             total_duration += duration;
+            if show_authors {
+                parts.push(e.author.clone().unwrap_or_default());
+            }
             parts.push(e.description.clone());
             parts
         })
         .collect();
-    let mut event_table =
-        Colonnade::new(6, conf.width()).expect("insufficient space for events table");
+    let mut event_table = Colonnade::new(if show_authors { 7 } else { 6 } + pct, conf.width())
+        .expect("insufficient space for events table");
     event_table
         .priority(0)
         .left_margin(2)
@@ -312,23 +995,97 @@ pub fn display_events(
     event_table.columns[0].alignment(Alignment::Right);
     event_table.columns[1].left_margin(1);
     event_table.columns[2].left_margin(1);
-    event_table.columns[4].priority(1);
-    event_table.columns[5].priority(2);
+    if pct == 1 {
+        event_table.columns[4].alignment(Alignment::Right);
+    }
+    event_table.columns[4 + pct].priority(1);
+    event_table.columns[5 + pct].priority(2);
+    if show_authors {
+        // the description follows the author
+        event_table.columns[5 + pct].priority(1);
+        event_table.columns[6 + pct].priority(2);
+    }
+    // the rows for gaps follow those for events in the table so they share its layout
+    let gaps = if extras.gaps {
+        gaps(&events, conf)
+    } else {
+        vec![]
+    };
+    for (_, s, e) in gaps.iter() {
+        let mut parts = vec![
+            time_string(&Some(*s), conf),
+            String::from("-"),
+            time_string(&Some(*e), conf),
+            duration_string(elapsed_seconds(s, e) as f32, conf),
+            String::new(),
+        ];
+        if pct == 1 {
+            parts.push(String::new());
+        }
+        if show_authors {
+            parts.push(String::new());
+        }
+        parts.push(String::from("untracked"));
+        data.push(parts);
+    }
+    let rows = event_table.macerate(data).expect("failed to macerate data");
 
     last_date = None;
-    for (offset, row) in event_table
-        .macerate(data)
-        .expect("failed to macerate data")
-        .iter()
-        .enumerate()
-    {
+    let mut notes = extras.notes.into_iter().peekable();
+    let show_note = |n: &Note, last_date: &mut Option<NaiveDate>| {
+        let date = conf.logical_date(&n.time);
+        if *last_date != Some(date) {
+            println!(
+                "{}",
+                style.paint("header", day_header(&date, start, end, conf))
+            );
+        }
+        *last_date = Some(date);
+        let tags = if n.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", n.tags.join(", "))
+        };
+        let description = match &issues {
+            Some(issues) => issues.link(&n.description),
+            None => n.description.clone(),
+        };
+        println!(
+            "  {}",
+            style.paint(
+                "note",
+                format!(
+                    "{:>8}  note: {}{}",
+                    time_string(&Some(n.time), conf),
+                    description,
+                    tags
+                )
+            )
+        );
+    };
+    for (offset, row) in rows.iter().take(events.len()).enumerate() {
         let e = events.get(offset).unwrap();
-        let date = e.start.date();
-        if date < start.date() {
+        let date = conf.logical_date(&e.start);
+        if date < conf.logical_date(start) {
             continue;
         }
+        for (i, _) in gaps.iter().enumerate().filter(|(_, g)| g.0 == offset) {
+            for line in rows[events.len() + i].iter() {
+                let line = line
+                    .iter()
+                    .map(|(margin, cell)| format!("{}{}", margin, cell))
+                    .collect::<String>();
+                println!("{}", style.paint("gap", line));
+            }
+        }
+        while let Some(n) = notes.next_if(|n| n.time < e.start) {
+            show_note(&n, &mut last_date);
+        }
         if last_date.is_none() || last_date.unwrap() != date {
-            println!("{}", style.paint("header", date_string(&date, same_year)));
+            println!(
+                "{}",
+                style.paint("header", day_header(&date, start, end, conf))
+            );
         }
         last_date = Some(date);
         for line in row {
@@ -355,13 +1112,24 @@ pub fn display_events(
                             style.paint("duration", cell)
                         }
                     }
-                    4 => style.paint("tags", cell),
+                    4 if pct == 1 => style.paint("duration", cell),
+                    c if c == 4 + pct => style.paint("tags", cell),
+                    c if c == line.len() - 1 && issues.is_some() => {
+                        issues.as_ref().unwrap().link(cell)
+                    }
                     _ => cell.to_owned(),
                 };
                 print!("{}{}", margin, cell);
             }
             println!();
         }
+        let end = e.end.unwrap_or(now);
+        while let Some(n) = notes.next_if(|n| n.time < end) {
+            show_note(&n, &mut last_date);
+        }
+    }
+    for n in notes {
+        show_note(&n, &mut last_date);
     }
     println!();
 
@@ -369,7 +1137,11 @@ pub fn display_events(
         Colonnade::new(2, conf.width()).expect("insufficient space for tags table");
     tags_table.columns[1].alignment(Alignment::Right);
     let mut data = vec![vec![
-        String::from("TOTAL HOURS"),
+        String::from(if conf.raw {
+            "TOTAL SECONDS"
+        } else {
+            "TOTAL HOURS"
+        }),
         duration_string(total_duration, conf),
     ]];
     let mut header_count = 1;
@@ -412,14 +1184,212 @@ pub fn display_events(
             println!();
         }
     }
+    if let Some(estimates) = estimates(&events, &now) {
+        println!();
+        display_estimates(&estimates, conf);
+    }
+}
+
+// the estimated and actual seconds of events, in all and by tag
+#[cfg(feature = "cli")]
+pub type Estimates = ((f32, f32), BTreeMap<String, (f32, f32)>);
+
+// the estimates of the events with estimates, or None if no event has one; an event split at
+// day boundaries counts its estimate only once
+#[cfg(feature = "cli")]
+pub fn estimates(events: &[Event], now: &NaiveDateTime) -> Option<Estimates> {
+    let mut total = None;
+    let mut by_tag: BTreeMap<String, (f32, f32)> = BTreeMap::new();
+    for e in events.iter() {
+        if let Some(estimate) = e.estimate {
+            let estimated = if e.overlaps_start() {
+                0.0
+            } else {
+                estimate as f32
+            };
+            let actual = e.duration(now);
+            let total = total.get_or_insert((0.0, 0.0));
+            total.0 += estimated;
+            total.1 += actual;
+            for tag in e.tags.iter() {
+                let pair = by_tag.entry(tag.clone()).or_insert((0.0, 0.0));
+                pair.0 += estimated;
+                pair.1 += actual;
+            }
+        }
+    }
+    total.map(|total| (total, by_tag))
+}
+
+// the rows of the table comparing the time estimated for events to the time they took: the
+// total, then each tag
+#[cfg(feature = "cli")]
+pub fn estimate_rows(estimates: &Estimates, conf: &Configuration) -> Vec<Vec<String>> {
+    let (total, by_tag) = estimates;
+    let row = |label: &str, (estimated, actual): (f32, f32)| {
+        vec![
+            label.to_owned(),
+            duration_string(estimated, conf),
+            duration_string(actual, conf),
+            if estimated > 0.0 {
+                format!("{:.0}%", 100.0 * actual / estimated)
+            } else {
+                String::new()
+            },
+        ]
+    };
+    let mut rows = vec![row("TOTAL", *total)];
+    for (tag, pair) in by_tag.iter() {
+        rows.push(row(tag, *pair));
+    }
+    rows
+}
+
+// a table comparing the time estimated for events to the time they took
+#[cfg(feature = "cli")]
+pub fn display_estimates(estimates: &Estimates, conf: &Configuration) {
+    let style = Style::new(conf);
+    let mut data = vec![vec![
+        String::from("estimates"),
+        String::from("estimated"),
+        String::from("actual"),
+        String::from("actual/estimated"),
+    ]];
+    data.extend(estimate_rows(estimates, conf));
+    let mut table =
+        Colonnade::new(4, conf.width()).expect("insufficient space for estimates table");
+    for i in 1..4 {
+        table.columns[i].alignment(Alignment::Right).left_margin(2);
+    }
+    for (offset, row) in table
+        .macerate(data)
+        .expect("could not macerate estimate data")
+        .iter()
+        .enumerate()
+    {
+        for line in row {
+            for (cell_num, (margin, cell)) in line.iter().enumerate() {
+                let cell = if offset == 0 {
+                    style.paint("header", cell)
+                } else if cell_num > 0 {
+                    style.paint("duration", cell)
+                } else if offset == 1 {
+                    style.paint("important", cell)
+                } else {
+                    style.paint("tags", cell)
+                };
+                print!("{}{}", margin, cell);
+            }
+            println!();
+        }
+    }
+}
+
+// the untracked intervals within work hours between events on the same day, each with the
+// index of the event it precedes
+pub fn gaps(events: &[Event], conf: &Configuration) -> Vec<(usize, NaiveDateTime, NaiveDateTime)> {
+    let mut gaps = vec![];
+    for (i, pair) in events.windows(2).enumerate() {
+        let (before, after) = (&pair[0], &pair[1]);
+        if before.vacation || after.vacation {
+            continue;
+        }
+        let date = conf.logical_date(&after.start);
+        let end_before = match before.end {
+            Some(t) if conf.logical_date(&before.start) == date => t,
+            _ => continue,
+        };
+        let (hour, minute) = conf.beginning_work_day_on(&date);
+        let work_start = date.and_hms(hour as u32, minute as u32, 0);
+        let work_end =
+            work_start + Duration::seconds((conf.day_length_on(&date) * 60.0 * 60.0) as i64);
+        let gap_start = end_before.max(work_start);
+        let gap_end = after.start.min(work_end);
+        if gap_start < gap_end {
+            gaps.push((i + 1, gap_start, gap_end));
+        }
+    }
+    gaps
+}
+
+// how much to say: 0 is --quiet, 1 the default, 2 --verbose
+static VERBOSITY: AtomicUsize = AtomicUsize::new(1);
+
+// set from the global --quiet and --verbose options
+pub fn set_verbosity(quiet: bool, verbose: bool) {
+    let level = if quiet {
+        0
+    } else if verbose {
+        2
+    } else {
+        1
+    };
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+// whether only errors should be reported
+pub fn quiet() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) == 0
+}
+
+// whether to report the details of what job is doing
+pub fn verbose() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) == 2
+}
+
+// a diagnostic message shown only with --verbose
+pub fn info<T: ToString>(msg: T, conf: &Configuration) {
+    if verbose() {
+        let style = Style::new(conf);
+        eprintln!("{} {}", style.paint("info", "info:"), msg.to_string());
+    }
+}
+
+// the present as fixed by --now or JOBLOG_NOW, in seconds; i64::MIN if the present is unfixed
+static NOW: AtomicI64 = AtomicI64::new(i64::MIN);
+
+// fixes the present at the beginning of the given time expression
+pub fn set_now(expression: &str, directory: Option<&str>) {
+    let conf = Configuration::read(None, directory);
+    match two_timer::parse(
+        &expand_period(expression, false, &conf),
+        conf.two_timer_config(),
+    ) {
+        Ok((time, _, _)) => {
+            info(format!("treating {} as the present", time), &conf);
+            NOW.store(time.timestamp(), Ordering::Relaxed);
+        }
+        Err(e) => fatal(
+            format!(
+                "could not parse '{}' as the present: {}",
+                expression,
+                e.msg()
+            ),
+            &conf,
+        ),
+    }
+}
+
+// the time relative to which expressions are interpreted and ongoing events measured
+pub fn current_time() -> NaiveDateTime {
+    match NOW.load(Ordering::Relaxed) {
+        i64::MIN => Local::now().naive_local(),
+        seconds => NaiveDateTime::from_timestamp(seconds, 0),
+    }
 }
 
 pub fn success<T: ToString>(msg: T, conf: &Configuration) {
+    if quiet() {
+        return;
+    }
     let style = Style::new(&conf);
     eprintln!("{} {}", style.paint("success", "ok:"), msg.to_string());
 }
 
 pub fn warn<T: ToString>(msg: T, conf: &Configuration) {
+    if quiet() {
+        return;
+    }
     let style = Style::new(&conf);
     eprintln!("{} {}", style.paint("warning", "warning:"), msg.to_string());
 }
@@ -431,6 +1401,16 @@ pub fn fatal<T: ToString>(msg: T, conf: &Configuration) {
 }
 
 pub fn describe(action: &str, extra: Option<&str>, item: Item, conf: &Configuration) {
+    info(
+        format!(
+            "appended to {}",
+            log_path(conf.directory()).to_str().unwrap()
+        ),
+        conf,
+    );
+    if quiet() {
+        return;
+    }
     let style = Style::new(conf);
     let mut s = style.paint("success", action);
     s += " ";
@@ -479,7 +1459,7 @@ pub fn describe(action: &str, extra: Option<&str>, item: Item, conf: &Configurat
 }
 
 // this is really a check for ongoing *multi-day* events
-pub fn check_for_ongoing_event(reader: &mut LogController, conf: &Configuration) {
+pub fn check_for_ongoing_event<S: Storage>(reader: &mut LogController<S>, conf: &Configuration) {
     if reader.forgot_to_end_last_event() {
         warn(
             "it appears an event begun on a previous day is ongoing",
@@ -489,10 +1469,499 @@ pub fn check_for_ongoing_event(reader: &mut LogController, conf: &Configuration)
     }
 }
 
+// the --weekday option of subcommands that report on periods
+#[cfg(feature = "cli")]
+pub fn weekday_argument() -> Arg<'static, 'static> {
+    Arg::with_name("weekday")
+        .long("weekday")
+        .help("Considers only these days of the week")
+        .long_help("Considers only time on the given days of the week, given as a comma-separated list of names or \
+        abbreviations, e.g., 'Mon,Tue' or 'saturday, sunday'. 'weekend' and 'weekdays' are also understood. The option \
+        may be repeated. Days are divided at the configured day boundary.")
+        .value_name("days")
+        .multiple(true)
+        .number_of_values(1)
+        .validator(|v| parse_weekdays(&v).map(|_| ()))
+}
+
+// parses a comma-separated list of days of the week such as 'Mon,Tue' or 'weekend'
+pub fn parse_weekdays(list: &str) -> Result<Vec<Weekday>, String> {
+    let mut days = vec![];
+    for day in list.split(',').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        match day.to_lowercase().as_str() {
+            "weekend" | "weekends" => days.extend(&[Weekday::Sat, Weekday::Sun]),
+            "weekdays" => days.extend(&[
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ]),
+            d => match Weekday::from_str(d) {
+                Ok(w) => days.push(w),
+                Err(_) => return Err(format!("'{}' is not a day of the week", day)),
+            },
+        }
+    }
+    if days.is_empty() {
+        Err(format!("'{}' names no day of the week", list))
+    } else {
+        Ok(days)
+    }
+}
+
+// the days of the week given by --weekday, if any
+#[cfg(feature = "cli")]
+pub fn weekdays(matches: &ArgMatches) -> Option<Vec<Weekday>> {
+    matches
+        .values_of("weekday")
+        .map(|values| values.flat_map(|v| parse_weekdays(v).unwrap()).collect())
+}
+
+// the --limit and --offset options of subcommands that list events or notes
+#[cfg(feature = "cli")]
+pub fn limit_arguments() -> [Arg<'static, 'static>; 2] {
+    let count = |v: String| match v.parse::<usize>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("'{}' is not a whole number", v)),
+    };
+    [
+        Arg::with_name("limit")
+            .long("limit")
+            .help("Shows at most this many")
+            .long_help(
+                "Shows at most this many of the events or notes found, after skipping those set aside by --offset.",
+            )
+            .value_name("n")
+            .validator(count),
+        Arg::with_name("offset")
+            .long("offset")
+            .help("Skips this many first")
+            .long_help("Skips this many of the events or notes found before showing any.")
+            .value_name("n")
+            .validator(count),
+    ]
+}
+
+// the numbers given by --limit, if any, and --offset
+#[cfg(feature = "cli")]
+pub fn limit_and_offset(matches: &ArgMatches) -> (Option<usize>, usize) {
+    let number = |name| matches.value_of(name).map(|v| v.parse::<usize>().unwrap());
+    (number("limit"), number("offset").unwrap_or(0))
+}
+
+// the items --limit and --offset select
+#[cfg(feature = "cli")]
+pub fn limited<T>(items: Vec<T>, matches: &ArgMatches) -> Vec<T> {
+    let (limit, offset) = limit_and_offset(matches);
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+// warns of any of the tags that appear nowhere in the log, suggesting what may have been meant
+#[cfg(feature = "cli")]
+pub fn warn_of_unknown_tags(tags: &[&str], conf: &Configuration) {
+    if tags.is_empty() {
+        return;
+    }
+    let known = match LogController::new(None, conf) {
+        Ok(log) => log.tag_inventory(),
+        Err(_) => return,
+    };
+    for tag in tags.iter().filter(|t| !known.contains(**t)) {
+        warn(
+            format!(
+                "nothing in the log is tagged '{}'{}",
+                tag,
+                did_you_mean(tag, &known)
+            ),
+            conf,
+        );
+    }
+}
+
+// a question suggesting the known tags like the one given, if there are any
+#[cfg(feature = "cli")]
+fn did_you_mean(tag: &str, known: &BTreeSet<String>) -> String {
+    let suggestions = tag_suggestions(tag, known);
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "; did you mean {}?",
+            suggestions
+                .iter()
+                .map(|s| format!("'{}'", s))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )
+    }
+}
+
+// the options of add and note that guard against misspelled tags
+#[cfg(feature = "cli")]
+pub fn new_tag_arguments() -> [Arg<'static, 'static>; 2] {
+    [
+        Arg::with_name("strict-tags")
+            .long("strict-tags")
+            .help("Refuses tags not already in the log unless confirmed")
+            .long_help(
+                "Refuses to add a tag found nowhere in the log, suggesting any similar tags, unless it is given \
+                with --new-tag rather than --tag.",
+            ),
+        Arg::with_name("new-tag")
+            .long("new-tag")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Adds this tag, confirming it is new")
+            .long_help(
+                "Adds a tag as --tag does, but without the warning or, with --strict-tags, the refusal a tag not \
+                yet in the log otherwise provokes.",
+            )
+            .value_name("tag")
+            .validator(|v| {
+                if some_nws(&v) {
+                    Ok(())
+                } else {
+                    Err(format!("tag {:?} needs some non-whitespace character", v))
+                }
+            }),
+    ]
+}
+
+// with warn-new-tags configured, warns of tags appearing nowhere in the log; with --strict-tags,
+// refuses them; tags given with --new-tag are exempt
+#[cfg(feature = "cli")]
+pub fn check_new_tags(
+    tags: &[String],
+    reader: &LogController,
+    matches: &ArgMatches,
+    conf: &Configuration,
+) {
+    let strict = matches.is_present("strict-tags");
+    if !(strict || conf.warn_new_tags) || tags.is_empty() {
+        return;
+    }
+    let confirmed = matches
+        .values_of("new-tag")
+        .map(|values| values.collect::<Vec<_>>())
+        .unwrap_or_default();
+    let known = reader.tag_inventory();
+    let new = tags
+        .iter()
+        .filter(|t| !confirmed.contains(&t.as_str()) && !known.contains(*t))
+        .collect::<Vec<_>>();
+    for tag in new.iter() {
+        warn(
+            format!("'{}' is a new tag{}", tag, did_you_mean(tag, &known)),
+            conf,
+        );
+    }
+    if strict && !new.is_empty() {
+        fatal(
+            "the log is unchanged; give new tags with --new-tag to confirm them",
+            conf,
+        );
+    }
+}
+
+// with require-tags configured, asks for tags if there are none and someone is there to ask,
+// or else refuses to go on
+#[cfg(feature = "cli")]
+pub fn require_tags(tags: &mut Vec<String>, conf: &Configuration) {
+    if !conf.require_tags || !tags.is_empty() {
+        return;
+    }
+    if !interactive() {
+        fatal(
+            "the log is unchanged; require-tags is set, so the event needs at least one tag",
+            conf,
+        );
+    }
+    while tags.is_empty() {
+        let answer = prompt(
+            "the event needs at least one tag (separate tags with commas)",
+            "",
+        );
+        *tags = conf.normalize_tags(
+            &answer
+                .split(',')
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_owned())
+                .collect::<Vec<_>>(),
+        );
+    }
+}
+
+// the --include-archives option of subcommands that search the log's history
+#[cfg(feature = "cli")]
+pub fn include_archives_argument() -> Arg<'static, 'static> {
+    Arg::with_name("include-archives")
+        .long("include-archives")
+        .help("Searches the heads truncate has cut from the log as well")
+        .long_help(
+            "Reads the heads of the log set aside by the truncate subcommand, compressed or not, \
+        together with the log itself, as though the log had never been truncated.",
+        )
+}
+
+// a log read from wherever it is kept
+#[cfg(feature = "cli")]
+pub type LogReader = LogController<Box<dyn Storage>>;
+
+// the log at the path or, with --include-archives, the heads truncate has cut from it followed
+// by the log itself
+#[cfg(feature = "cli")]
+pub fn log_reader(
+    log: Option<PathBuf>,
+    matches: &ArgMatches,
+    conf: &Configuration,
+) -> std::io::Result<LogReader> {
+    let log = log.unwrap_or_else(|| log_path(conf.directory()));
+    info(format!("reading log {}", log.display()), conf);
+    let storage: Box<dyn Storage> = if matches.is_present("include-archives") {
+        Box::new(archived_log(&log)?)
+    } else {
+        Box::new(FileStorage::new(&log)?)
+    };
+    Ok(LogController::with_storage(storage, conf))
+}
+
+// the --close-at option of subcommands that begin events
+#[cfg(feature = "cli")]
+pub fn close_at_argument() -> Arg<'static, 'static> {
+    Arg::with_name("close-at")
+        .long("close-at")
+        .help("Ends an event left open on a previous day at this time")
+        .long_help("If the last event in the log was begun on a previous day and never ended, ends it at this time \
+        before doing anything else. The time is understood relative to the day the event began, so '5pm' is 5 PM that day.")
+        .value_name("time")
+}
+
+// the --shorten-vacation option of subcommands that begin events
+#[cfg(feature = "cli")]
+pub fn shorten_vacation_argument() -> Arg<'static, 'static> {
+    Arg::with_name("shorten-vacation")
+        .long("shorten-vacation")
+        .help("Makes the vacation record covering today flex")
+        .long_help("If an ordinary vacation record covers today, makes it flex for today, so it only fills out \
+        the hours of the work day you do not work and your time is not counted twice. The record's other days \
+        are left as they were.")
+}
+
+// the --raw option of subcommands that show durations
+#[cfg(feature = "cli")]
+pub fn raw_argument() -> Arg<'static, 'static> {
+    Arg::with_name("raw")
+        .long("raw")
+        .help("Shows durations as exact seconds")
+        .long_help("Shows durations as whole numbers of seconds, neither rounded nor truncated, so they add up \
+        exactly. This overrides the precision and truncation in the configuration.")
+}
+
+// warns of work begun on a day an ordinary vacation record covers and, with --shorten-vacation,
+// makes the record flex for the day
+#[cfg(feature = "cli")]
+pub fn check_vacation(matches: &ArgMatches, conf: &Configuration) {
+    let today = conf.logical_date(&current_time());
+    let mut vacations = VacationController::read(None, conf.directory());
+    let (index, description) = match vacations.ordinary_vacation_on(&today, conf) {
+        Some(found) => found,
+        None => {
+            if matches.is_present("shorten-vacation") {
+                warn(
+                    "no ordinary vacation record covers today; ignoring --shorten-vacation",
+                    conf,
+                );
+            }
+            return;
+        }
+    };
+    if !matches.is_present("shorten-vacation") {
+        warn(
+            format!(
+                "today is a vacation day, by the {}; to count only the hours you do not work, \
+                use --shorten-vacation",
+                description
+            ),
+            conf,
+        );
+        return;
+    }
+    match vacations.shorten(index, &today, conf) {
+        Ok(description) => {
+            vacations.write();
+            success(
+                format!("today is now covered by the flex {}", description),
+                conf,
+            );
+        }
+        Err(e) => warn(e, conf),
+    }
+}
+
+// if an event begun on a previous day is ongoing, ends it at the time given by --close-at or,
+// failing that, at a time the user is asked for; otherwise just warns about it; returns
+// whether the event was ended
+#[cfg(feature = "cli")]
+pub fn close_forgotten_event(
+    reader: &mut LogController,
+    matches: &ArgMatches,
+    conf: &Configuration,
+) -> bool {
+    let close_at = matches.value_of("close-at");
+    if !reader.forgot_to_end_last_event() {
+        if close_at.is_some() {
+            warn(
+                "no event begun on a previous day is ongoing; ignoring --close-at",
+                conf,
+            );
+        }
+        return false;
+    }
+    let event = reader.last_event().unwrap();
+    let time = if let Some(expression) = close_at {
+        match end_of_forgotten_event(&event, expression, conf) {
+            Ok(time) => time,
+            Err(e) => {
+                fatal(e, conf);
+                unreachable!()
+            }
+        }
+    } else if interactive() {
+        warn(
+            format!(
+                "'{}', begun {}, is still ongoing",
+                event.description,
+                event.start.format("%F %T")
+            ),
+            conf,
+        );
+        loop {
+            let answer = prompt("when did it end? (leave blank to leave it ongoing)", "");
+            if answer.is_empty() {
+                println!();
+                return false;
+            }
+            match end_of_forgotten_event(&event, &answer, conf) {
+                Ok(time) => break time,
+                Err(e) => warn(e, conf),
+            }
+        }
+    } else {
+        check_for_ongoing_event(reader, conf);
+        return false;
+    };
+    insert_dones(&[time], conf);
+    success(
+        format!("ended {} at {}", event.description, time.format("%F %T")),
+        conf,
+    );
+    *reader = LogController::new(None, conf).expect("could not read log");
+    true
+}
+
+// adds DONE lines at the given times to the log, each after the last timestamped line no later
+// than it
+#[cfg(feature = "cli")]
+pub(crate) fn insert_dones(times: &[NaiveDateTime], conf: &Configuration) {
+    let path = log_path(conf.directory());
+    let text = read_to_string(&path).expect("could not read log");
+    let mut lines = text.lines().map(|l| l.to_owned()).collect::<Vec<_>>();
+    for time in times {
+        lines = with_done(
+            &lines.iter().map(|l| l.as_str()).collect::<Vec<_>>(),
+            time,
+            log_line(&Done(*time), conf),
+        );
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    let tmp = tmp_path(conf);
+    write(&tmp, text).expect("could not write temporary log");
+    rename(&tmp, &path).expect("failed to move the new log into place");
+}
+
+// parses the time at which an event left open ended, making sure it falls between the event's
+// start and the present
+#[cfg(feature = "cli")]
+fn end_of_forgotten_event(
+    event: &Event,
+    expression: &str,
+    conf: &Configuration,
+) -> Result<NaiveDateTime, String> {
+    let day = conf.logical_date(&event.start);
+    let config = conf
+        .two_timer_config()
+        .map(|c| c.now(day.and_hms(23, 59, 59)));
+    let time = match two_timer::parse(&expand_period(expression, false, conf), config) {
+        Ok((time, _, _)) => time,
+        Err(e) => return Err(e.msg().to_owned()),
+    };
+    if time <= event.start {
+        Err(format!(
+            "{} is not after the event began at {}",
+            time.format("%F %T"),
+            event.start.format("%F %T")
+        ))
+    } else if time > current_time() {
+        Err(format!("{} is in the future", time.format("%F %T")))
+    } else {
+        Ok(time)
+    }
+}
+
+// the log lines with the DONE line inserted after the last timestamped line no later than its
+// time, and preceded by a date comment if its date differs from that of the line before it
+#[cfg(feature = "cli")]
+fn with_done(lines: &[&str], time: &NaiveDateTime, done: String) -> Vec<String> {
+    let mut after = None;
+    for (offset, line) in lines.iter().enumerate() {
+        if let Some((t, _)) = parse_line(line, offset).time() {
+            if t > time {
+                break;
+            }
+            after = Some((offset, t.date()));
+        }
+    }
+    let comment = format!("# {}/{}/{}", time.year(), time.month(), time.day());
+    let (mut index, mut needs_comment) = match after {
+        Some((offset, date)) => (offset + 1, date != time.date()),
+        None => (0, true),
+    };
+    // a date comment for the day may already follow the preceding line
+    for (offset, line) in lines.iter().enumerate().skip(index) {
+        match parse_line(line, offset) {
+            Item::Blank(_) | Item::Comment(_) => {
+                if line.trim() == comment {
+                    index = offset + 1;
+                    needs_comment = false;
+                    break;
+                }
+            }
+            _ => break,
+        }
+    }
+    let mut with = lines[..index]
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>();
+    if needs_comment {
+        with.push(comment);
+    }
+    with.push(done);
+    with.extend(lines[index..].iter().map(|l| l.to_string()));
+    with
+}
+
 // make sure base directory and its files are present
 pub fn init(directory: Option<&str>) {
     if !base_dir(directory).as_path().exists() {
-        create_dir(base_dir(directory).to_str().unwrap()).expect(&format!(
+        create_dir_all(base_dir(directory).to_str().unwrap()).expect(&format!(
             "could not create base directory {}",
             base_dir(directory).to_str().unwrap()
         ));
@@ -503,17 +1972,18 @@ pub fn init(directory: Option<&str>) {
             .to_str()
             .unwrap()
             .starts_with(".");
-        println!(
-            "initialized {}directory {} for Job Log",
-            if hidden { "hidden " } else { "" },
-            base_dir(directory).to_str().unwrap()
-        );
+        if !quiet() {
+            println!(
+                "initialized {}directory {} for Job Log",
+                if hidden { "hidden " } else { "" },
+                base_dir(directory).to_str().unwrap()
+            );
+        }
     }
     if !log_path(directory).as_path().exists() {
         let mut log =
             File::create(log_path(directory).to_str().unwrap()).expect("could not create log file");
-        log.write_all(b"# job log\n")
-            .expect("could not write comment to log file");
+        writeln!(log, "{}", log_header()).expect("could not write comment to log file");
     }
     let mut readme_path = base_dir(directory);
     readme_path.push("README");
@@ -643,6 +2113,17 @@ pub fn some_nws(s: &str) -> bool {
     return false;
 }
 
+// whether the standard input and output are both terminals, so there is someone to ask questions
+#[cfg(unix)]
+pub fn interactive() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+#[cfg(not(unix))]
+pub fn interactive() -> bool {
+    false
+}
+
 // ask a yes or no question and await an answer
 pub fn yes_or_no<T: ToString>(msg: T) -> bool {
     loop {
@@ -666,10 +2147,213 @@ pub fn yes_or_no<T: ToString>(msg: T) -> bool {
     }
 }
 
+// asks a question, returning the trimmed answer or, if the answer is empty, the default
+pub fn prompt<T: ToString>(msg: T, default: &str) -> String {
+    prompt_until_eof(msg, default).unwrap_or_else(|| default.to_owned())
+}
+
+// like prompt, but None when the input is exhausted, so callers that would otherwise ask again
+// can give up
+pub fn prompt_until_eof<T: ToString>(msg: T, default: &str) -> Option<String> {
+    if default.is_empty() {
+        print!("{}: ", msg.to_string());
+    } else {
+        print!("{} [{}]: ", msg.to_string(), default);
+    }
+    io::stdout().flush().expect("could not flush stdout");
+    let mut buffer = String::new();
+    let read = io::stdin()
+        .read_line(&mut buffer)
+        .expect("failed to read response");
+    if read == 0 {
+        println!();
+        return None;
+    }
+    let answer = buffer.trim();
+    if answer.is_empty() {
+        Some(default.to_owned())
+    } else {
+        Some(answer.to_owned())
+    }
+}
+
+// asks for letters to search the items for, as a fuzzy finder would, listing the best matches
+// until one is chosen by its number or, if only one is listed, by giving nothing
+pub fn fuzzy_pick<T, F, D>(
+    items: &[T],
+    text: F,
+    display: D,
+    limit: usize,
+    what: &str,
+    conf: &Configuration,
+) -> Option<T>
+where
+    T: Clone,
+    F: Fn(&T) -> String,
+    D: Fn(&[T]),
+{
+    let mut found: Vec<T> = vec![];
+    loop {
+        let answer = prompt(format!("type to search, or give a {}'s number", what), "");
+        if answer.is_empty() {
+            if found.len() == 1 {
+                return found.pop();
+            }
+            warn(format!("no {} chosen", what), conf);
+            return None;
+        }
+        if let Ok(n) = answer.parse::<usize>() {
+            if n > 0 && n <= found.len() {
+                return found.into_iter().nth(n - 1);
+            }
+        }
+        found = fuzzy_matches(&answer, items, &text, limit);
+        if found.is_empty() {
+            println!("no {} matches '{}'", what, answer);
+        } else {
+            display(&found);
+        }
+    }
+}
+
+// the items whose text the query matches, at most limit of them, the best matches first and,
+// among equally good matches, those earlier among the items
+pub fn fuzzy_matches<T: Clone, F: Fn(&T) -> String>(
+    query: &str,
+    items: &[T],
+    text: F,
+    limit: usize,
+) -> Vec<T> {
+    let mut matches = items
+        .iter()
+        .filter_map(|i| fuzzy_score(query, &text(i)).map(|score| (score, i)))
+        .collect::<Vec<_>>();
+    // the sort is stable, so order breaks ties
+    matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, i)| i.clone())
+        .collect()
+}
+
+// how well the query matches the text, as in a fuzzy finder: every character of the query other
+// than whitespace must occur in the text, ignoring case, in order; characters following the one
+// matched before them or beginning words score more
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = position + text[position..].iter().position(|t| *t == c)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == i) {
+            score += 2;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 1;
+        }
+        last = Some(i);
+        position = i + 1;
+    }
+    Some(score)
+}
+
+// a scratch job directory for a test, with an empty configuration, which is deleted when the
+// test ends, whether or not it passes
+#[cfg(test)]
+pub(crate) struct TestDirectory {
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+impl TestDirectory {
+    // the name should be unique to the test, as tests run in parallel
+    pub fn new(name: &str) -> TestDirectory {
+        let path = PathBuf::from(name);
+        create_dir_all(&path).expect("could not create test directory");
+        File::create(path.join("config.ini")).expect("could not create configuration file");
+        TestDirectory { path }
+    }
+    pub fn configuration(&self) -> Configuration {
+        Configuration::read(
+            Some(self.path.join("config.ini")),
+            Some(self.path.to_str().unwrap()),
+        )
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn week_numbers() {
+        assert_eq!(
+            "2019-09-09 - 2019-09-15",
+            expand_week_number("week 37 2019")
+        );
+        assert_eq!(
+            "2019-09-09 - 2019-09-15",
+            expand_week_number(" Week 37 of 2019 ")
+        );
+        assert_eq!(
+            "2020-12-28 - 2021-01-03",
+            expand_week_number("week 53 2020")
+        );
+        assert_eq!(
+            "week 53 2019",
+            expand_week_number("week 53 2019"),
+            "2019 had 52 weeks"
+        );
+        assert_eq!("last week", expand_week_number("last week"));
+        assert!(Regex::new(r"\A\d{4}-\d\d-\d\d - \d{4}-\d\d-\d\d\z")
+            .unwrap()
+            .is_match(&expand_week_number("week 1")));
+    }
+
+    #[test]
+    fn localized_names() {
+        let date = NaiveDate::from_ymd(2020, 3, 4);
+        let pattern = "%A, %e %B (%a %b) 100%%";
+        assert_eq!(
+            "Wednesday,  4 March (Wed Mar) 100%",
+            format!("{}", date.format(&localize_pattern(&date, pattern, None)))
+        );
+        assert_eq!(
+            "Wednesday,  4 March (Wed Mar) 100%",
+            format!(
+                "{}",
+                date.format(&localize_pattern(&date, pattern, Some("en_US")))
+            )
+        );
+        assert_eq!(
+            "Mittwoch,  4 März (Mi Mär) 100%",
+            format!(
+                "{}",
+                date.format(&localize_pattern(&date, pattern, Some("de_DE.UTF-8")))
+            )
+        );
+        assert_eq!(
+            "mercredi,  4 mars (mer mars) 100%",
+            format!(
+                "{}",
+                date.format(&localize_pattern(&date, pattern, Some("fr")))
+            )
+        );
+        assert!(known_locale("en"));
+        assert!(known_locale("pt-BR"));
+        assert!(!known_locale("xx"));
+    }
+
     #[test]
     fn styles_that_match() {
         for style in &[
@@ -717,4 +2401,136 @@ mod tests {
         assert!(parses[2].has("color"));
         assert!(parses[2].has("fixed"));
     }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_with_done() {
+        let t = NaiveDate::from_ymd(2020, 1, 2).and_hms(17, 0, 0);
+        let done = String::from("2020  1  2 17  0  0:DONE");
+        let lines = vec!["# 2020/1/2", "2020  1  2  9  0  0::foo"];
+        assert_eq!(
+            vec![
+                "# 2020/1/2",
+                "2020  1  2  9  0  0::foo",
+                "2020  1  2 17  0  0:DONE"
+            ],
+            with_done(&lines, &t, done.clone()),
+            "appended"
+        );
+        let lines = vec![
+            "# 2020/1/2",
+            "2020  1  2  9  0  0::foo",
+            "# 2020/1/3",
+            "2020  1  3  8  0  0<NOTE>::a note",
+        ];
+        assert_eq!(
+            vec![
+                "# 2020/1/2",
+                "2020  1  2  9  0  0::foo",
+                "2020  1  2 17  0  0:DONE",
+                "# 2020/1/3",
+                "2020  1  3  8  0  0<NOTE>::a note",
+            ],
+            with_done(&lines, &t, done.clone()),
+            "inserted before later note"
+        );
+        let lines = vec![
+            "# 2020/1/1",
+            "2020  1  1 20  0  0::foo",
+            "# 2020/1/2",
+            "2020  1  2 18  0  0<NOTE>::a note",
+        ];
+        assert_eq!(
+            vec![
+                "# 2020/1/1",
+                "2020  1  1 20  0  0::foo",
+                "# 2020/1/2",
+                "2020  1  2 17  0  0:DONE",
+                "2020  1  2 18  0  0<NOTE>::a note",
+            ],
+            with_done(&lines, &t, done),
+            "existing date comment reused"
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let dir = TestDirectory::new("test_gaps");
+        let conf = dir.configuration();
+        let day = NaiveDate::from_ymd(2020, 1, 2);
+        let event = |start: (u32, u32), end: (u32, u32)| {
+            let mut e = Event::coin(String::from("foo"), vec![]);
+            e.start = day.and_hms(start.0, start.1, 0);
+            e.end = Some(day.and_hms(end.0, end.1, 0));
+            e
+        };
+        let events = vec![
+            event((8, 0), (10, 0)),
+            event((11, 0), (12, 0)),
+            event((12, 0), (16, 0)),
+            event((18, 0), (19, 0)),
+        ];
+        assert_eq!(
+            vec![
+                (1, day.and_hms(10, 0, 0), day.and_hms(11, 0, 0)),
+                (3, day.and_hms(16, 0, 0), day.and_hms(17, 0, 0)),
+            ],
+            gaps(&events, &conf),
+            "contiguous events have no gap and gaps end with the work day"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Some(30 * 60), parse_duration("30 min"));
+        assert_eq!(Some(2 * 60 * 60), parse_duration("2h"));
+        assert_eq!(Some(90 * 60), parse_duration("1.5 hours"));
+        assert_eq!(Some(75 * 60), parse_duration("1h15m"));
+        assert_eq!(Some(75 * 60 + 10), parse_duration(" 1 hr 15 mins 10s "));
+        assert_eq!(None, parse_duration("30"));
+        assert_eq!(None, parse_duration("2 weeks"));
+        assert_eq!(None, parse_duration(""));
+    }
+
+    #[test]
+    fn test_tsv_row() {
+        assert_eq!("a\tb c\t", tsv_row(&["a", "b c", ""]));
+        assert_eq!(
+            "tab\\tand\\nbreak\t\\\\",
+            tsv_row(&["tab\tand\nbreak", "\\"]),
+            "fields cannot contain tabs or line breaks"
+        );
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(
+            Ok(vec![Weekday::Mon, Weekday::Tue]),
+            parse_weekdays("Mon,Tue")
+        );
+        assert_eq!(
+            Ok(vec![Weekday::Sat, Weekday::Sun, Weekday::Fri]),
+            parse_weekdays("weekend, friday")
+        );
+        assert!(parse_weekdays("Mon,Funday").is_err());
+        assert!(parse_weekdays(",").is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_matches_items() {
+        let hits = [(1, "floob prep"), (2, "lunch"), (3, "floob review")];
+        let matches = fuzzy_matches("flrv", &hits, |h| h.1.to_owned(), 10);
+        assert_eq!(vec![(3, "floob review")], matches);
+        let matches = fuzzy_matches("floob", &hits, |h| h.1.to_owned(), 10);
+        assert_eq!(
+            vec![1, 3],
+            matches.iter().map(|h| h.0).collect::<Vec<_>>(),
+            "ties keep order"
+        );
+        assert_eq!(
+            1,
+            fuzzy_matches("floob", &hits, |h| h.1.to_owned(), 1).len()
+        );
+        assert!(fuzzy_matches("zzz", &hits, |h| h.1.to_owned(), 10).is_empty());
+    }
 }
@@ -1,24 +1,45 @@
+// much of the vacation controller serves only the vacation subcommand
+#![cfg_attr(not(feature = "cli"), allow(dead_code))]
 extern crate chrono;
+#[cfg(feature = "cli")]
 extern crate clap;
+#[cfg(feature = "cli")]
 extern crate colonnade;
 extern crate pidgin;
 extern crate regex;
+extern crate serde_json;
 extern crate two_timer;
 
 use crate::configure::Configuration;
-use crate::log::{parse_tags, parse_timestamp, tags, timestamp, Event, Filter};
-use crate::util::{base_dir, fatal, remainder, some_nws, success, warn, Style};
-use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use crate::log::{
+    check_line_parts, elapsed_seconds, parse_tags, parse_timestamp, tags, timestamp, Event, Filter,
+};
+#[cfg(feature = "cli")]
+use crate::pager::page;
+use crate::util::{base_dir, current_time};
+#[cfg(feature = "cli")]
+use crate::util::{fatal, remainder, some_nws, success, warn, Style};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+#[cfg(feature = "cli")]
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+#[cfg(feature = "cli")]
 use colonnade::{Alignment, Colonnade};
 use pidgin::{Grammar, Matcher};
+#[cfg(feature = "cli")]
 use regex::Regex;
+use serde_json::json;
 use std::cmp::Ordering;
 use std::fs::{copy, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::PathBuf;
+#[cfg(feature = "cli")]
 use two_timer::{parsable, parse};
 
+// the default display formats for vacation records
+const DATE_FORMAT: &str = "%F";
+const TIME_FORMAT: &str = "%I:%M %p";
+
+#[cfg(feature = "cli")]
 fn after_help() -> &'static str {
     "\
 Vacation time is the dark matter of the log. It is not stored in the log and it can be simultaneous with \
@@ -55,21 +76,27 @@ Because the vacation format is so complex it should not be edited by hand but on
 Generally this just means adding and subtracting vacation days. For the latter you will be presented with an \
 enumerated list of known vacations. You delete them by their number in the list.
 
-If two vacation periods overlap repeating periods will be preferred to non-repeating, narrower periods to wider, and \
-ordinary over fixed over flex. In any case, a particular vacation moment will only be counted once.
+If two vacation periods overlap ordinary periods will be preferred to fixed and fixed to flex, repeating periods to \
+non-repeating, those beginning earlier to those beginning later, and narrower periods to wider. You can change the order \
+of these rules, or reverse them, with `job configure --vacation-precedence`. In any case, a particular vacation moment will only be counted once. When you add a \
+vacation record that overlaps some existing record you will receive a warning listing the overlaps and which record \
+takes precedence. If you know what you are doing, use --force to suppress this warning.
 
 Note, the Rust version of JobLog is adding some features to vacations: on and off times for repeating vacations. \
-Because of this you will not be able to use the vacation file with the Perl client after you add repeating vacations.
+Because of this you will not be able to use the vacation file with the Perl client after you add repeating vacations \
+unless you first turn these times off with `job configure --perl-compatible true`.
 
 All prefixes of 'vacation' are aliases of the subcommand.
 "
 }
 
 // used in three places, so it's factored out
+#[cfg(feature = "cli")]
 fn over_as_of_rx() -> Regex {
     Regex::new(r"\A(\d+)(?:\s+(\S.*?)\s*)?\z").unwrap()
 }
 
+#[cfg(feature = "cli")]
 fn number_date_validator(v: String) -> Result<(), String> {
     if let Some(captures) = over_as_of_rx().captures(&v) {
         let index = captures[1].to_owned();
@@ -95,6 +122,7 @@ fn number_date_validator(v: String) -> Result<(), String> {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
     mast.subcommand(
         SubCommand::with_name("vacation")
@@ -109,6 +137,14 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                 .conflicts_with_all(&["delete", "over-as-of", "list", "clear"])
                 .display_order(0)
             )
+            .arg(
+                Arg::with_name("force")
+                .long("force")
+                .help("Adds a vacation record without warning about overlaps")
+                .long_help("Normally when you add a vacation record which overlaps existing records you will be warned which records overlap and which will take precedence. This suppresses the warning.")
+                .conflicts_with_all(&["delete", "over-as-of", "effective-as-of", "list", "clear"])
+                .display_order(0)
+            )
             .arg(
                 Arg::with_name("list")
                 .short("l")
@@ -216,12 +252,27 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
     )
 }
 
+#[cfg(feature = "cli")]
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
     let mut controller = VacationController::read(None, conf.directory());
+    controller.perl_compatible = conf.perl_compatible;
+    if conf.perl_compatible
+        && (matches.is_present("over-as-of") || matches.is_present("effective-as-of"))
+    {
+        fatal(
+            "the Perl client cannot read the times at which repeating vacations take effect or cease; \
+            see `job configure --perl-compatible`",
+            &conf,
+        );
+    }
     if matches.is_present("list") {
         if controller.vacations.is_empty() {
             warn("no vacation records", &conf);
+        } else if matches.is_present("json") {
+            for (i, v) in controller.vacations.iter().enumerate() {
+                println!("{}", v.to_json(i + 1));
+            }
         } else {
             let mut data = vec![vec![
                 String::from(""),
@@ -234,17 +285,19 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 String::from("started"),
                 String::from("ended"),
             ]];
+            let date_format = conf.date_format.as_deref().unwrap_or(DATE_FORMAT);
+            let time_format = conf.time_format.as_deref().unwrap_or(TIME_FORMAT);
             for (i, v) in controller.vacations.iter().enumerate() {
                 let mut row = Vec::with_capacity(9);
                 row.push((i + 1).to_string());
                 row.push(v.description.to_owned());
                 row.push(v.tags.join(", "));
-                row.push(v.start_description());
-                row.push(v.end_description());
+                row.push(v.start_description(date_format, time_format));
+                row.push(v.end_description(date_format, time_format));
                 row.push(v.kind.to_s().to_owned());
                 row.push(v.repetition.to_s().to_owned());
-                row.push(v.effective_as_of_description());
-                row.push(v.over_as_of_description());
+                row.push(v.effective_as_of_description(date_format));
+                row.push(v.over_as_of_description(date_format));
                 data.push(row);
             }
             let style = Style::new(&conf);
@@ -257,6 +310,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             table.columns[0].alignment(Alignment::Right).left_margin(0);
             table.columns[1].priority(1);
             table.columns[2].priority(2);
+            let _pager = page(&conf, matches);
             println!();
             for (row_num, row) in table
                 .macerate(data)
@@ -388,6 +442,20 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             );
             if recorded {
                 success(format!("added {}", description), &conf);
+                if !matches.is_present("force") {
+                    let overlaps = controller.overlaps(controller.vacations.len() - 1, &conf);
+                    if !overlaps.is_empty() {
+                        warn(
+                            format!(
+                                "the new record overlaps {} existing record{}:\n{}",
+                                overlaps.len(),
+                                if overlaps.len() == 1 { "" } else { "s" },
+                                overlaps.join("\n")
+                            ),
+                            &conf,
+                        );
+                    }
+                }
             } else {
                 fatal(description, &conf)
             }
@@ -398,9 +466,43 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
             )
         }
     }
+    if conf.perl_compatible && controller.changed {
+        let extended = controller.vacations.iter().filter(|v| v.extended()).count();
+        if extended > 0 {
+            warn(
+                format!(
+                    "{} vacation record{} the Perl client cannot read",
+                    extended,
+                    if extended == 1 {
+                        " has times"
+                    } else {
+                        "s have times"
+                    }
+                ),
+                &conf,
+            );
+        }
+    }
     controller.write();
 }
 
+// adds to the vacation file the records in another vacation file it lacks, returning the
+// number added
+pub(crate) fn import(path: PathBuf, conf: &Configuration) -> usize {
+    let theirs = VacationController::read(Some(path), None);
+    let mut ours = VacationController::read(None, conf.directory());
+    let mut added = 0;
+    for vacation in theirs.vacations {
+        if !ours.contains(&vacation) {
+            ours.vacations.push(vacation);
+            ours.changed = true;
+            added += 1;
+        }
+    }
+    ours.write();
+    added
+}
+
 fn vacation_path(directory: Option<&str>) -> PathBuf {
     let mut path = base_dir(directory);
     path.push("vacation");
@@ -412,6 +514,7 @@ pub struct VacationController {
     vacations: Vec<Vacation>,
     changed: bool,
     path: String,
+    perl_compatible: bool, // whether to omit what the Perl client can't read
 }
 
 impl VacationController {
@@ -432,12 +535,14 @@ impl VacationController {
                 vacations,
                 changed: false,
                 path: path_str,
+                perl_compatible: false,
             }
         } else {
             VacationController {
                 vacations: vec![],
                 changed: false,
                 path: path_str,
+                perl_compatible: false,
             }
         }
     }
@@ -467,7 +572,7 @@ impl VacationController {
         &self,
         start: &NaiveDateTime,
         end: &NaiveDateTime,
-        mut events: Vec<Event>, // these events *must be grouped by day*
+        events: Vec<Event>, // these events *must be grouped by day*
         conf: &Configuration,
         now: Option<NaiveDateTime>,
         filter: &Filter,
@@ -475,29 +580,67 @@ impl VacationController {
         if self.vacations.is_empty() {
             return events;
         }
-        let mut new_events = Vec::new();
-        let mut date = start.date();
-        let end_date = end.date();
-        let now = now.unwrap_or(Local::now().naive_local());
-        let end_date = if now.date() < end_date { now.date() + Duration::days(1) } else { end_date };
-        let sorted_records = self.sorted_vacation_records();
-        while date < end_date {
+        self.add_vacation_times_streaming(start, end, events, conf, now, filter)
+            .collect()
+    }
+    // like add_vacation_times, but reading the events as they are needed, so only a day's
+    // events are held at a time
+    pub fn add_vacation_times_streaming<'a, I>(
+        &'a self,
+        start: &NaiveDateTime,
+        end: &NaiveDateTime,
+        events: I, // these events *must be grouped by day*
+        conf: &'a Configuration,
+        now: Option<NaiveDateTime>,
+        filter: &'a Filter,
+    ) -> Box<dyn Iterator<Item = Event> + 'a>
+    where
+        I: IntoIterator<Item = Event>,
+        I::IntoIter: 'a,
+    {
+        if self.vacations.is_empty() {
+            return Box::new(events.into_iter());
+        }
+        let mut events = events.into_iter().peekable();
+        let end = *end;
+        let mut date = conf.logical_date(start);
+        let end_date = conf.logical_date(&end);
+        let now = now.unwrap_or(current_time());
+        let today = conf.logical_date(&now);
+        let end_date = if today < end_date {
+            today + Duration::days(1)
+        } else {
+            end_date
+        };
+        let sorted_records = self.sorted_vacation_records(conf);
+        let mut day = vec![].into_iter();
+        Box::new(std::iter::from_fn(move || loop {
+            if let Some(e) = day.next() {
+                return Some(e);
+            }
+            if date >= end_date {
+                return None;
+            }
+            let mut new_events = Vec::new();
             let mut seconds_worked = 0;
-            while events.len() > 0 && events[0].start.date() == date {
-                seconds_worked += events[0].duration(&now) as usize;
-                new_events.push(events.remove(0));
+            // any events from earlier days are passed along as they are
+            while let Some(e) = events.next_if(|e| conf.logical_date(&e.start) <= date) {
+                if conf.logical_date(&e.start) == date {
+                    seconds_worked += e.duration(&now) as usize;
+                }
+                new_events.push(e);
             }
             if conf.is_workday(&date) {
                 // only check for vacation time on workdays
-                let s = date.and_hms(0, 0, 0);
+                let s = conf.start_of_day(&date);
                 let e = s + Duration::days(1);
                 // make sure we don't fetch in vacation time beyond the end of the last moment
-                let e = if &e > end { end } else { &e };
+                let e = if e > end { &end } else { &e };
                 let start_workday = start_workday(&s, conf);
-                let end_workday = start_workday + Duration::hours(conf.day_length as i64);
+                let end_workday = start_workday + day_duration(&date, conf);
                 // and the end of the workday won't be past the last moment either
                 let end_workday = if &end_workday > e { e } else { &end_workday };
-                let delta = (end_workday.timestamp() - start_workday.timestamp()) as usize;
+                let delta = elapsed_seconds(&start_workday, end_workday) as usize;
                 let mut unworked_seconds = if seconds_worked > delta {
                     0
                 } else {
@@ -528,24 +671,25 @@ impl VacationController {
                 }
             }
             date = date + Duration::days(1);
-        }
-        new_events.sort_by(|a, b| {
-            if a.start == b.start {
-                (a.duration(&now) as usize).cmp(&(b.duration(&now) as usize))
-            } else {
-                a.start.cmp(&b.start)
-            }
-        });
-        new_events
+            new_events.sort_by(|a, b| {
+                if a.start == b.start {
+                    (a.duration(&now) as usize).cmp(&(b.duration(&now) as usize))
+                } else {
+                    a.start.cmp(&b.start)
+                }
+            });
+            day = new_events.into_iter();
+        }))
     }
-    fn sorted_vacation_records(&self) -> Vec<&Vacation> {
+    // the records in order of precedence
+    fn sorted_vacation_records(&self, conf: &Configuration) -> Vec<&Vacation> {
         let mut sorted = self.vacations.iter().collect::<Vec<&Vacation>>();
-        sorted.sort_by(|a, b| a.cmp(b));
+        sorted.sort_by(|a, b| a.precedence(b, conf).0);
         sorted
     }
     // serialize vacation records back to file
     // returns whether there was any change to the file system
-    fn write(&self) -> bool {
+    pub fn write(&self) -> bool {
         if !self.changed {
             return false;
         }
@@ -580,6 +724,67 @@ impl VacationController {
             true
         }
     }
+    // the index and description of an ordinary vacation record covering some of the work day on
+    // the date, if any
+    pub fn ordinary_vacation_on(
+        &self,
+        date: &NaiveDate,
+        conf: &Configuration,
+    ) -> Option<(usize, String)> {
+        if !conf.is_workday(date) {
+            return None;
+        }
+        let start = conf.start_of_day(date);
+        let end = start + Duration::days(1);
+        let seconds = (conf.day_length_on(date) * 60.0 * 60.0) as usize;
+        self.vacations
+            .iter()
+            .position(|v| {
+                v.kind == Type::Ordinary && v.overlap(&start, &end, seconds, conf).is_some()
+            })
+            .map(|i| (i, self.vacations[i].describe()))
+    }
+    // makes the record at the index flex on the date, so it only fills out the work day; the
+    // record's other days are left as they were; returns a description of the flex record
+    pub fn shorten(
+        &mut self,
+        index: usize,
+        date: &NaiveDate,
+        conf: &Configuration,
+    ) -> Result<String, String> {
+        let v = &self.vacations[index];
+        if v.repeating() {
+            return Err(format!(
+                "a repeating record cannot be made flex: {}",
+                v.describe()
+            ));
+        }
+        let day_start = conf.start_of_day(date);
+        let day_end = conf.start_of_day(&(*date + Duration::days(1)));
+        let piece = |kind: Type, start: NaiveDateTime, end: NaiveDateTime| Vacation {
+            description: v.description.clone(),
+            tags: v.tags.clone(),
+            kind,
+            repetition: Repetition::Never,
+            start,
+            end,
+            effective_as_of: None,
+            over_as_of: None,
+        };
+        let flex = piece(Type::Flex, v.start.max(day_start), v.end.min(day_end));
+        let description = flex.describe();
+        let mut pieces = vec![];
+        if v.start < day_start {
+            pieces.push(piece(Type::Ordinary, v.start, day_start));
+        }
+        pieces.push(flex);
+        if v.end > day_end {
+            pieces.push(piece(Type::Ordinary, day_end, v.end));
+        }
+        self.vacations.splice(index..=index, pieces);
+        self.changed = true;
+        Ok(description)
+    }
     // remove a particular vacation record
     fn destroy(&mut self, index: usize) -> Result<Vacation, String> {
         if index == 0 {
@@ -598,48 +803,72 @@ impl VacationController {
             .iter()
             .any(|v| v.start == new.start && v.end == new.end)
     }
+    // describe the records overlapping the record at the given index and which takes precedence
+    fn overlaps(&self, index: usize, conf: &Configuration) -> Vec<String> {
+        let new = &self.vacations[index];
+        self.vacations
+            .iter()
+            .enumerate()
+            .filter(|&(i, v)| i != index && new.conflicts(v))
+            .map(|(i, v)| {
+                let (winner, reason) = new.precedence(v, conf);
+                format!(
+                    "  {}) {}; {} takes precedence because {}",
+                    i + 1,
+                    v.describe(),
+                    if winner == Ordering::Less {
+                        "the new record"
+                    } else {
+                        "the old record"
+                    },
+                    reason
+                )
+            })
+            .collect()
+    }
     // create a new vacation record
     // returns a description and whether any event was recorded
     fn record(
         &mut self,
         description: String,
-        mut tags: Vec<String>,
+        tags: Vec<String>,
         start: NaiveDateTime,
         end: NaiveDateTime,
         kind: Option<&str>,
         repetition: Option<&str>,
     ) -> (String, bool) {
-        tags.sort_unstable();
-        tags.dedup();
-        let mut vacation = Vacation::new(description, tags, start, end);
+        let mut builder = Vacation::builder()
+            .description(description)
+            .tags(&tags)
+            .start(start)
+            .end(end);
         if let Some(k) = kind {
-            vacation.kind = Type::from_str(k);
+            builder = builder.kind(k);
         }
         if let Some(r) = repetition {
-            vacation.repetition = Repetition::from_str(r);
-            match vacation.repetition {
-                Repetition::Never => (),
-                _ => vacation.effective_as_of = Some(Local::now().naive_local()),
-            }
+            builder = builder.repetition(r);
         }
-        let description = vacation.describe();
-        let period = vacation.period();
-        match vacation.valid() {
-            Ok(()) => {
-                if self.contains(&vacation) {
-                    (
-                        format!("there is already a record for the {}", period),
-                        false,
-                    )
-                } else {
-                    self.vacations.push(vacation);
-                    self.changed = true;
-                    (description, true)
-                }
-            }
+        match builder.build().and_then(|vacation| self.add(vacation)) {
+            Ok(description) => (description, true),
             Err(s) => (s, false),
         }
     }
+    // adds a vacation record, returning its description, unless there is one for the same period
+    pub fn add(&mut self, mut vacation: Vacation) -> Result<String, String> {
+        if vacation.repeating() && !self.perl_compatible {
+            vacation.effective_as_of = Some(current_time());
+        }
+        if self.contains(&vacation) {
+            return Err(format!(
+                "there is already a record for the {}",
+                vacation.period()
+            ));
+        }
+        let description = vacation.describe();
+        self.vacations.push(vacation);
+        self.changed = true;
+        Ok(description)
+    }
     fn set_over_as_of(&mut self, index: usize, date: &NaiveDateTime) -> Result<String, String> {
         if index == 0 {
             return Err(format!("there is no vacation record number {}", index));
@@ -838,7 +1067,7 @@ impl PartialEq for Repetition {
 impl Eq for Repetition {}
 
 #[derive(Debug)]
-struct Vacation {
+pub struct Vacation {
     description: String,
     tags: Vec<String>,
     kind: Type,
@@ -935,6 +1164,30 @@ fn escape_description(description: &str) -> String {
 }
 
 impl Vacation {
+    // the JSON representation of a vacation record; the number is its position in the list
+    fn to_json(&self, number: usize) -> String {
+        json!({
+            "number": number,
+            "description": self.description,
+            "tags": self.tags,
+            "start": format!("{}", self.start),
+            "end": format!("{}", self.end),
+            "type": match self.kind {
+                Type::Ordinary => "ordinary",
+                _ => self.kind.to_s(),
+            },
+            "repetition": match self.repetition {
+                Repetition::Never => "never",
+                _ => self.repetition.to_s(),
+            },
+            "effective-as-of": self.effective_as_of.map(|t| format!("{}", t)),
+            "over-as-of": self.over_as_of.map(|t| format!("{}", t)),
+        })
+        .to_string()
+    }
+    pub fn builder() -> VacationBuilder {
+        VacationBuilder::default()
+    }
     // create an ordinary vacation record
     fn new(
         description: String,
@@ -966,38 +1219,42 @@ impl Vacation {
         }
     }
 
-    fn start_description(&self) -> String {
+    fn start_description(&self, date_format: &str, time_format: &str) -> String {
         match self.kind {
-            Type::Fixed => format!("{}", self.start.format("%F %I:%M %p")),
-            _ => format!("{}", self.start.format("%F")),
+            Type::Fixed => format!(
+                "{} {}",
+                self.start.format(date_format),
+                self.start.format(time_format)
+            ),
+            _ => format!("{}", self.start.format(date_format)),
         }
     }
 
-    fn end_description(&self) -> String {
+    fn end_description(&self, date_format: &str, time_format: &str) -> String {
         match self.kind {
-            Type::Fixed => format!("{}", self.end.format("%I:%M %p")),
+            Type::Fixed => format!("{}", self.end.format(time_format)),
             _ => {
                 let d = (self.end - Duration::seconds(1)).date();
                 if self.start.date() == d {
                     String::from("")
                 } else {
-                    format!("{}", d.format("%F"))
+                    format!("{}", d.format(date_format))
                 }
             }
         }
     }
 
-    fn effective_as_of_description(&self) -> String {
+    fn effective_as_of_description(&self, date_format: &str) -> String {
         if let Some(t) = self.effective_as_of {
-            format!("{}", t.format("%F"))
+            format!("{}", t.format(date_format))
         } else {
             String::from("")
         }
     }
 
-    fn over_as_of_description(&self) -> String {
+    fn over_as_of_description(&self, date_format: &str) -> String {
         if let Some(t) = self.over_as_of {
-            format!("{}", t.format("%F"))
+            format!("{}", t.format(date_format))
         } else {
             String::from("")
         }
@@ -1010,6 +1267,11 @@ impl Vacation {
         }
     }
 
+    // whether the record has times the Perl client can't read
+    fn extended(&self) -> bool {
+        self.effective_as_of.is_some() || self.over_as_of.is_some()
+    }
+
     fn deserialize(line: &str) -> Option<Vacation> {
         lazy_static! {
             static ref VACATION: Grammar = grammar!{
@@ -1074,7 +1336,7 @@ impl Vacation {
         line.push_str(&tags(&self.tags));
         line.push_str(":");
         line.push_str(&escape_description(&self.description));
-        if self.effective_as_of.is_some() || self.over_as_of.is_some() {
+        if self.extended() {
             line.push_str(":");
             if let Some(t) = self.effective_as_of {
                 line.push_str(&timestamp(&t));
@@ -1096,13 +1358,102 @@ impl Vacation {
     fn period(&self) -> String {
         format!(
             "period {} - {}",
-            self.start_description(),
-            self.end_description()
+            self.start_description(DATE_FORMAT, TIME_FORMAT),
+            self.end_description(DATE_FORMAT, TIME_FORMAT)
         )
     }
     fn duration(&self) -> Duration {
         self.end - self.start
     }
+    // the period of a repeating record projected onto the year or month of the given time
+    fn projected_onto(&self, time: &NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let date = match self.repetition {
+            Repetition::Never => return Some((self.start, self.end)),
+            Repetition::Annual => {
+                NaiveDate::from_ymd_opt(time.year(), self.start.month(), self.start.day())
+            }
+            Repetition::Monthly => {
+                NaiveDate::from_ymd_opt(time.year(), time.month(), self.start.day())
+            }
+        };
+        date.map(|d| {
+            let s = d.and_time(self.start.time());
+            (s, s + self.duration())
+        })
+    }
+    // whether the two records might ever claim the same moment
+    fn conflicts(&self, other: &Vacation) -> bool {
+        match (self.repeating(), other.repeating()) {
+            (false, false) => any_overlap((&self.start, &self.end), (&other.start, &other.end)),
+            (false, true) => other.recurrence_overlaps(self),
+            (true, false) => self.recurrence_overlaps(other),
+            (true, true) => {
+                // compare the two repetitions within a year of the later start
+                let base = if self.start < other.start {
+                    other.start
+                } else {
+                    self.start
+                };
+                let mut moment = base;
+                while moment < base + Duration::days(366) {
+                    if let (Some((s1, e1)), Some((s2, e2))) =
+                        (self.projected_onto(&moment), other.projected_onto(&moment))
+                    {
+                        if any_overlap((&s1, &e1), (&s2, &e2)) {
+                            return true;
+                        }
+                    }
+                    moment += Duration::days(28);
+                }
+                false
+            }
+        }
+    }
+    // whether some repetition of this record overlaps the given non-repeating record
+    fn recurrence_overlaps(&self, other: &Vacation) -> bool {
+        if let Some(t) = self.over_as_of {
+            if t < other.start {
+                return false;
+            }
+        }
+        let mut moment = other.start - Duration::days(31);
+        while moment < other.end + Duration::days(31) {
+            if let Some((s, e)) = self.projected_onto(&moment) {
+                if any_overlap((&s, &e), (&other.start, &other.end)) {
+                    return true;
+                }
+            }
+            moment += Duration::days(28);
+        }
+        false
+    }
+    // which of the two records is preferred when they overlap, and why
+    fn precedence(&self, other: &Vacation, conf: &Configuration) -> (Ordering, &'static str) {
+        for (rule, reversed) in conf.vacation_precedence_rules() {
+            let order = match rule {
+                "kind" => self.kind.cmp(&other.kind),
+                "repeating" => self.repetition.cmp(&other.repetition),
+                "earlier" => self.start.cmp(&other.start),
+                "narrower" => self.duration().cmp(&other.duration()),
+                _ => unreachable!(),
+            };
+            if order == Ordering::Equal {
+                continue;
+            }
+            let reason = match (rule, reversed) {
+                ("kind", false) => "ordinary records are preferred to fixed and fixed to flex",
+                ("kind", true) => "flex records are preferred to fixed and fixed to ordinary",
+                ("repeating", false) => "repeating records are preferred to non-repeating",
+                ("repeating", true) => "non-repeating records are preferred to repeating",
+                ("earlier", false) => "it begins earlier",
+                ("earlier", true) => "it begins later",
+                ("narrower", false) => "it is narrower",
+                _ => "it is wider",
+            };
+            return (if reversed { order.reverse() } else { order }, reason);
+        }
+        (self.cmp(other), "of its description")
+    }
     // return an "event" representing an overlap of a vacation record with this span of time
     fn overlap(
         &self,
@@ -1114,7 +1465,9 @@ impl Vacation {
         let range: Option<(NaiveDateTime, NaiveDateTime)> = match self.kind {
             Type::Fixed => available_overlap((&self.start, &self.end), (start, end)),
             Type::Flex => {
-                if let Some((s, e)) = available_overlap((&self.start, &self.end), (start, end)) {
+                // the days of flex time are shifted to the day boundary
+                let (vs, ve) = conf.shift_to_day_boundary(self.start, self.end);
+                if let Some((s, e)) = available_overlap((&vs, &ve), (start, end)) {
                     let (s, e) = fit_range_to_workday(&s, &e, conf);
                     let end_available = s + Duration::seconds(available_seconds as i64);
                     // we don't want the flex end time to be greater than the end parameter, though
@@ -1168,6 +1521,8 @@ impl Vacation {
                     }
                 };
                 if let Some((adjusted_start, adjusted_end)) = maybe_range {
+                    let (adjusted_start, adjusted_end) =
+                        conf.shift_to_day_boundary(adjusted_start, adjusted_end);
                     if let Some((s, e)) =
                         available_overlap((&adjusted_start, &adjusted_end), (start, end))
                     {
@@ -1191,8 +1546,11 @@ impl Vacation {
                 start: s,
                 end: Some(e),
                 vacation_type: Some(self.kind.to_s().to_owned()),
+                holiday: self.repeating(),
                 start_overlap: false,
                 end_overlap: false,
+                author: None,
+                estimate: None,
             })
         } else {
             None
@@ -1203,10 +1561,82 @@ impl Vacation {
         match self.kind {
             Type::Ordinary | Type::Flex => true,
             _ => {
-                let duration = (self.end.timestamp() - self.start.timestamp()) as u32;
-                (conf.day_length as u32) * (60 * 60) <= duration
+                let duration = elapsed_seconds(&self.start, &self.end) as u32;
+                (conf.day_length_on(&self.start.date()) * (60.0 * 60.0)) as u32 <= duration
+            }
+        }
+    }
+}
+
+// builds a vacation record, by default an ordinary one that does not repeat, and makes sure the
+// vacation file can hold it
+#[derive(Default)]
+pub struct VacationBuilder {
+    description: String,
+    tags: Vec<String>,
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    kind: Option<String>,
+    repetition: Option<String>,
+}
+
+impl VacationBuilder {
+    pub fn description<T: ToString>(mut self, description: T) -> Self {
+        self.description = description.to_string();
+        self
+    }
+    pub fn tags<T: ToString>(mut self, tags: &[T]) -> Self {
+        self.tags.extend(tags.iter().map(|t| t.to_string()));
+        self
+    }
+    pub fn tag<T: ToString>(mut self, tag: T) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+    pub fn start(mut self, start: NaiveDateTime) -> Self {
+        self.start = Some(start);
+        self
+    }
+    pub fn end(mut self, end: NaiveDateTime) -> Self {
+        self.end = Some(end);
+        self
+    }
+    // ordinary, fixed, or flex
+    pub fn kind<T: ToString>(mut self, kind: T) -> Self {
+        self.kind = Some(kind.to_string());
+        self
+    }
+    // never, annual, or monthly
+    pub fn repetition<T: ToString>(mut self, repetition: T) -> Self {
+        self.repetition = Some(repetition.to_string());
+        self
+    }
+    pub fn build(mut self) -> Result<Vacation, String> {
+        check_line_parts(&self.description, &self.tags)?;
+        let (start, end) = match (self.start, self.end) {
+            (Some(start), Some(end)) if start < end => (start, end),
+            (Some(_), Some(_)) => return Err(String::from("a vacation must end after it begins")),
+            _ => return Err(String::from("a vacation needs both a start and an end")),
+        };
+        self.tags.sort_unstable();
+        self.tags.dedup();
+        let mut vacation = Vacation::new(self.description, self.tags, start, end);
+        if let Some(kind) = self.kind {
+            match kind.as_str() {
+                "ordinary" | "fixed" | "flex" => vacation.kind = Type::from_str(&kind),
+                _ => return Err(format!("unknown type of vacation: {}", kind)),
             }
         }
+        if let Some(repetition) = self.repetition {
+            match repetition.as_str() {
+                "never" | "annual" | "monthly" => {
+                    vacation.repetition = Repetition::from_str(&repetition)
+                }
+                _ => return Err(format!("unknown repetition: {}", repetition)),
+            }
+        }
+        vacation.valid()?;
+        Ok(vacation)
     }
 }
 
@@ -1251,21 +1681,24 @@ fn fit_range_to_workday(
     conf: &Configuration,
 ) -> (NaiveDateTime, NaiveDateTime) {
     let wd_start = start_workday(start, conf);
-    let wd_end = wd_start + Duration::hours(conf.day_length as i64);
+    let wd_end = wd_start + day_duration(&start.date(), conf);
     available_overlap((start, end), (&wd_start, &wd_end)).unwrap()
 }
 
+// the expected length of the workday on the given date
+fn day_duration(date: &NaiveDate, conf: &Configuration) -> Duration {
+    Duration::seconds((conf.day_length_on(date) * (60.0 * 60.0)) as i64)
+}
+
 fn start_workday(time: &NaiveDateTime, conf: &Configuration) -> NaiveDateTime {
-    time.date().and_hms(
-        conf.beginning_work_day.0 as u32,
-        conf.beginning_work_day.1 as u32,
-        0,
-    )
+    let (hour, minute) = conf.beginning_work_day_on(&time.date());
+    time.date().and_hms(hour as u32, minute as u32, 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configure::parse_vacation_precedence;
     use crate::log::{Done, Event, LogController};
     use std::str::FromStr;
 
@@ -1314,8 +1747,8 @@ mod tests {
     }
 
     fn test_time(phrase: &str) -> (NaiveDateTime, NaiveDateTime) {
-        let (start, end, _) =
-            parse(phrase, None).expect(&format!("could not make test time from '{}'", phrase));
+        let (start, end, _) = two_timer::parse(phrase, None)
+            .expect(&format!("could not make test time from '{}'", phrase));
         (start, end)
     }
 
@@ -1358,8 +1791,11 @@ mod tests {
     }
 
     fn add_event(log: &mut LogController, time: &NaiveDateTime, description: &str) {
-        let mut event = Event::coin(description.to_owned(), Vec::new());
-        event.start = time.clone();
+        let event = Event::builder()
+            .start(*time)
+            .description(description)
+            .build()
+            .unwrap();
         log.append_to_log(event, "could not add event");
     }
 
@@ -1435,6 +1871,57 @@ mod tests {
         cleanup(disambiguator);
     }
 
+    #[test]
+    fn streaming() {
+        let disambiguator = "streaming";
+        let conf = test_configuration(disambiguator);
+        let mut log = test_log_controller(true, disambiguator, &conf);
+        let mut vacation = test_vacation_controller(true, disambiguator);
+        let now = test_now();
+        let filter = Filter::dummy();
+        let (boxing_day_starts, boxing_day_ends) = test_time("Dec 26, 2000");
+        add_vacation(
+            &mut vacation,
+            "Boxing Day",
+            vec![],
+            &boxing_day_starts,
+            &boxing_day_ends,
+            None,
+            None,
+        );
+        // an event begun the day before continues into the vacation day
+        add_event(
+            &mut log,
+            &(boxing_day_starts - Duration::hours(2)),
+            "overnight",
+        );
+        end_event(&mut log, &(boxing_day_starts + Duration::hours(2)));
+        let mut log = test_log_controller(false, disambiguator, &conf);
+        let events = log.events_in_range(&boxing_day_starts, &boxing_day_ends);
+        let events = Event::gather_by_day_streaming(events, &boxing_day_ends, &conf);
+        let events = vacation
+            .add_vacation_times_streaming(
+                &boxing_day_starts,
+                &boxing_day_ends,
+                events,
+                &conf,
+                Some(now),
+                &filter,
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![false, false, true],
+            events.iter().map(|e| e.vacation).collect::<Vec<_>>(),
+            "the part of the event on the day before is passed along"
+        );
+        assert_eq!(
+            conf.day_length * (60.0 * 60.0),
+            events[2].duration(&now),
+            "vacation lasts one work day"
+        );
+        cleanup(disambiguator);
+    }
+
     #[test]
     fn tags() {
         let disambiguator = "tags";
@@ -2034,4 +2521,287 @@ mod tests {
         assert_eq!(0, events[0].tags.len(), "no tags");
         cleanup(disambiguator);
     }
+
+    #[test]
+    fn overlap_warnings() {
+        let disambiguator = "overlap_warnings";
+        let conf = test_configuration(disambiguator);
+        test_log_controller(true, disambiguator, &conf);
+        let mut vacation = test_vacation_controller(true, disambiguator);
+        let (christmas_starts, christmas_ends) = test_time("Dec 25, 1999");
+        add_vacation(
+            &mut vacation,
+            "Christmas",
+            vec![],
+            &christmas_starts,
+            &christmas_ends,
+            None,
+            Some("annual"),
+        );
+        let (break_starts, break_ends) = test_time("Dec 23, 2000 - Dec 31, 2000");
+        add_vacation(
+            &mut vacation,
+            "winter break",
+            vec![],
+            &break_starts,
+            &break_ends,
+            None,
+            None,
+        );
+        let overlaps = vacation.overlaps(1, &conf);
+        assert_eq!(1, overlaps.len(), "break overlaps Christmas");
+        assert!(
+            overlaps[0].contains("the old record takes precedence"),
+            "repeating record preferred"
+        );
+        let (eve_starts, _) = test_time("Dec 24, 2000");
+        let eve_starts = eve_starts + Duration::hours(10);
+        add_vacation(
+            &mut vacation,
+            "Christmas Eve afternoon",
+            vec![],
+            &eve_starts,
+            &(eve_starts + Duration::hours(4)),
+            Some("fixed"),
+            None,
+        );
+        let overlaps = vacation.overlaps(2, &conf);
+        assert_eq!(1, overlaps.len(), "Christmas Eve overlaps only the break");
+        assert!(overlaps[0].contains("'winter break'"), "found the break");
+        let (other_starts, other_ends) = test_time("Jan 5, 2001");
+        add_vacation(
+            &mut vacation,
+            "day off",
+            vec![],
+            &other_starts,
+            &other_ends,
+            None,
+            None,
+        );
+        assert!(vacation.overlaps(3, &conf).is_empty(), "no overlap");
+        cleanup(disambiguator);
+    }
+
+    #[test]
+    fn shortened_vacation() {
+        let disambiguator = "shortened_vacation";
+        let conf = test_configuration(disambiguator);
+        test_log_controller(true, disambiguator, &conf);
+        let mut vacation = test_vacation_controller(true, disambiguator);
+        let (trip_starts, trip_ends) = test_time("Mar 2, 2020 - Mar 5, 2020");
+        add_vacation(
+            &mut vacation,
+            "trip",
+            vec![],
+            &trip_starts,
+            &trip_ends,
+            None,
+            None,
+        );
+        let (christmas_starts, christmas_ends) = test_time("Dec 25, 2019");
+        add_vacation(
+            &mut vacation,
+            "Christmas",
+            vec![],
+            &christmas_starts,
+            &christmas_ends,
+            None,
+            Some("annual"),
+        );
+        let tuesday = NaiveDate::from_ymd(2020, 3, 3);
+        assert_eq!(
+            None,
+            vacation.ordinary_vacation_on(&(tuesday + Duration::days(5)), &conf)
+        );
+        let (index, _) = vacation
+            .ordinary_vacation_on(&tuesday, &conf)
+            .expect("the trip covers Tuesday");
+        assert_eq!(0, index);
+        vacation.shorten(index, &tuesday, &conf).unwrap();
+        let records = vacation
+            .vacations
+            .iter()
+            .map(|v| (v.kind.to_s(), v.start.date(), v.end.date()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("", tuesday.pred(), tuesday),
+                ("flex", tuesday, tuesday.succ()),
+                ("", tuesday.succ(), NaiveDate::from_ymd(2020, 3, 6)),
+                (
+                    "",
+                    NaiveDate::from_ymd(2019, 12, 25),
+                    NaiveDate::from_ymd(2019, 12, 26)
+                ),
+            ],
+            records,
+            "only Tuesday is flex"
+        );
+        assert_eq!(
+            None,
+            vacation.ordinary_vacation_on(&tuesday, &conf),
+            "Tuesday is no longer an ordinary vacation day"
+        );
+        let christmas = NaiveDate::from_ymd(2030, 12, 25);
+        let (index, _) = vacation.ordinary_vacation_on(&christmas, &conf).unwrap();
+        assert!(
+            vacation.shorten(index, &christmas, &conf).is_err(),
+            "repeating records cannot be flex"
+        );
+        cleanup(disambiguator);
+    }
+
+    #[test]
+    fn shortened_vacation_with_day_boundary() {
+        let disambiguator = "shortened_vacation_with_day_boundary";
+        let mut conf = test_configuration(disambiguator);
+        conf.day_boundary = (4, 0);
+        test_log_controller(true, disambiguator, &conf);
+        let mut vacation = test_vacation_controller(true, disambiguator);
+        let (trip_starts, trip_ends) = test_time("Mar 2, 2020 - Mar 5, 2020");
+        add_vacation(
+            &mut vacation,
+            "trip",
+            vec![],
+            &trip_starts,
+            &trip_ends,
+            None,
+            None,
+        );
+        let tuesday = NaiveDate::from_ymd(2020, 3, 3);
+        let (index, _) = vacation.ordinary_vacation_on(&tuesday, &conf).unwrap();
+        vacation.shorten(index, &tuesday, &conf).unwrap();
+        let records = vacation
+            .vacations
+            .iter()
+            .map(|v| (v.kind.to_s(), v.start, v.end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("", trip_starts, tuesday.and_hms(4, 0, 0)),
+                (
+                    "flex",
+                    tuesday.and_hms(4, 0, 0),
+                    tuesday.succ().and_hms(4, 0, 0)
+                ),
+                ("", tuesday.succ().and_hms(4, 0, 0), trip_ends),
+            ],
+            records,
+            "the flex record covers Tuesday's logical day"
+        );
+        assert_eq!(
+            None,
+            vacation.ordinary_vacation_on(&tuesday, &conf),
+            "Tuesday is no longer an ordinary vacation day"
+        );
+        cleanup(disambiguator);
+    }
+
+    #[test]
+    fn configured_precedence() {
+        let disambiguator = "configured_precedence";
+        let mut conf = test_configuration(disambiguator);
+        test_log_controller(true, disambiguator, &conf);
+        let mut vacation = test_vacation_controller(true, disambiguator);
+        let (christmas_starts, christmas_ends) = test_time("Dec 25, 1999");
+        add_vacation(
+            &mut vacation,
+            "Christmas",
+            vec![],
+            &christmas_starts,
+            &christmas_ends,
+            None,
+            Some("annual"),
+        );
+        let (break_starts, break_ends) = test_time("Dec 23, 2000 - Dec 31, 2000");
+        add_vacation(
+            &mut vacation,
+            "winter break",
+            vec![],
+            &break_starts,
+            &break_ends,
+            None,
+            None,
+        );
+        let (eve_starts, _) = test_time("Dec 24, 2000");
+        let eve_starts = eve_starts + Duration::hours(10);
+        add_vacation(
+            &mut vacation,
+            "Christmas Eve afternoon",
+            vec![],
+            &eve_starts,
+            &(eve_starts + Duration::hours(4)),
+            Some("fixed"),
+            None,
+        );
+        let order = |vacation: &VacationController, conf: &Configuration| {
+            vacation
+                .sorted_vacation_records(conf)
+                .iter()
+                .map(|v| v.description.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["Christmas", "winter break", "Christmas Eve afternoon"],
+            order(&vacation, &conf),
+            "the default precedence"
+        );
+        conf.vacation_precedence = parse_vacation_precedence("-repeating").unwrap();
+        assert_eq!(
+            vec!["winter break", "Christmas Eve afternoon", "Christmas"],
+            order(&vacation, &conf),
+            "the rule listed comes first"
+        );
+        let overlaps = vacation.overlaps(1, &conf);
+        assert!(
+            overlaps[0].contains("the new record takes precedence because non-repeating"),
+            "non-repeating record preferred"
+        );
+        conf.vacation_precedence = parse_vacation_precedence("narrower, -kind").unwrap();
+        assert_eq!(
+            vec!["Christmas Eve afternoon", "Christmas", "winter break"],
+            order(&vacation, &conf)
+        );
+        cleanup(disambiguator);
+    }
+
+    #[test]
+    fn builder() {
+        let (start, end) = test_time("Dec 25, 1999");
+        let vacation = Vacation::builder()
+            .description("Christmas")
+            .start(start)
+            .end(end)
+            .kind("fixed")
+            .build()
+            .unwrap();
+        assert!(matches!(vacation.kind, Type::Fixed));
+        assert!(
+            Vacation::builder().start(start).build().is_err(),
+            "needs an end"
+        );
+        assert!(
+            Vacation::builder().start(end).end(start).build().is_err(),
+            "ends after it begins"
+        );
+        assert!(
+            Vacation::builder()
+                .start(start)
+                .end(end)
+                .kind("sabbatical")
+                .build()
+                .is_err(),
+            "knows the kinds"
+        );
+        assert!(
+            Vacation::builder()
+                .start(start)
+                .end(end)
+                .kind("fixed")
+                .repetition("annual")
+                .build()
+                .is_err(),
+            "fixed vacations do not repeat"
+        );
+    }
 }
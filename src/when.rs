@@ -1,16 +1,18 @@
 extern crate chrono;
 extern crate clap;
+extern crate serde_json;
 extern crate two_timer;
 
 use std::collections::BTreeMap;
 
-use crate::configure::Configuration;
-use crate::log::{Event, Filter, LogController};
-use crate::util::{fatal, Style, duration_string};
+use crate::configure::{BudgetPeriod, Configuration};
+use crate::log::{advance_by, Event, Filter, LogController};
+use crate::util::{current_time, duration_string, expand_period, fatal, format_time, info, Style};
 use crate::vacation::VacationController;
-use chrono::{Duration, Local, NaiveDateTime};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use colonnade::{Alignment, Colonnade};
+use serde_json::{json, Map, Value};
 use two_timer::parse;
 
 fn after_help() -> &'static str {
@@ -36,6 +38,34 @@ completed in each budget will also be displayed.
  budget  budgeted  completed
  insp       15.00       1.00
 
+Vacation counts toward what you are expected to work, today's included even if it is yet to \
+come, so on a day off, or an afternoon off, you need not work a full day:
+
+  > job when
+  when: today
+  you will be finished at  1:00:00 PM, 2.00 hours from now
+  counting 4.00 hours of vacation
+
+If vacation alone meets the expectation you are told so:
+
+  > job when
+  when: today
+  you have already met the target for today with 8.00 hours of vacation
+
+With --week the subcommand looks ahead instead: for each workday remaining in the week it says \
+how much you must work and when you may leave to meet the week's expectation, spreading what \
+remains evenly over the days left. Scheduled vacation counts toward the week and shortens the \
+days it falls on.
+
+  > job when --week
+  day                  hours  leave at
+  Wednesday 14 October  4.25   5:15:00 PM
+  Thursday 15 October   8.00   5:00:00 PM
+  Friday 16 October     4.00   1:00:00 PM
+
+The times for days yet to come assume you begin at the beginning of your work day and take no \
+breaks.
+
 All prefixes of 'when' are aliases of the subcommand.
 "
 }
@@ -57,21 +87,49 @@ pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 's
                     .default_value("today")
                     .multiple(true),
             )
+            .arg(
+                Arg::with_name("week")
+                    .long("week")
+                    .help("Says when to leave each workday remaining this week to stay on pace")
+                    .long_help(
+                        "Spreads the hours yet to be worked this week over the workdays remaining, \
+                        taking scheduled vacations into account, and says when you may leave on each. \
+                        This option takes no period.",
+                    ),
+            )
             .display_order(display_order),
     )
 }
 
 pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     let conf = Configuration::read(None, directory);
+    if matches.is_present("week") {
+        if matches.occurrences_of("period") > 0 {
+            fatal("--week takes no period", &conf);
+        }
+        week(&conf, matches.is_present("json"));
+        return;
+    }
     let phrase = matches
         .values_of("period")
         .unwrap()
         .collect::<Vec<&str>>()
         .join(" ");
-    println!("when: {}", phrase);
-    match parse(&phrase, conf.two_timer_config()) {
+    let json = matches.is_present("json");
+    if !json {
+        println!("when: {}", phrase);
+    }
+    match parse(
+        &expand_period(&phrase, false, &conf),
+        conf.logical_two_timer_config(),
+    ) {
         Ok((start, end, _)) => {
-            let now = Local::now().naive_local();
+            let (start, end) = conf.shift_to_day_boundary(start, end);
+            info(
+                format!("'{}' is interpreted as {} to {}", phrase, start, end),
+                &conf,
+            );
+            let now = current_time();
             if now <= start {
                 fatal(
                     format!(
@@ -92,29 +150,29 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 let mut reader = LogController::new(None, &conf).expect("could not read log");
                 let events = reader.events_in_range(&start, &now);
                 // first figure out how much you *should* work during the period
-                let mut start_date = start.date();
+                let mut start_date = conf.logical_date(&start);
                 let end_time = if now < end { now } else { end };
                 let mut hours_required = 0.0;
-                while start_date.and_hms(0, 0, 0) < end_time {
-                    if conf.is_workday(&start_date) {
-                        hours_required += conf.day_length;
-                    }
+                while conf.start_of_day(&start_date) < end_time {
+                    hours_required += conf.day_length_on(&start_date);
                     start_date += Duration::days(1);
                 }
                 // then figure out how much you have worked
-                let events = Event::gather_by_day(events, &end);
+                let events = Event::gather_by_day(events, &end, &conf);
                 let filter = Filter::dummy();
                 let events = VacationController::read(None, conf.directory())
                     .add_vacation_times(&start, &end, events, &conf, None, &filter);
                 let mut seconds_worked = 0.0;
+                let mut seconds_vacation = 0.0;
                 let mut last_moment = None;
                 let mut budget_counter: Option<BTreeMap<String, (f32, f32)>> =
                     if let Some(budgets) = &conf.budgets {
                         let mut bc: BTreeMap<String, (f32, f32)> = BTreeMap::new();
-                        for pair in budgets {
+                        // only the pay period budgets; see the budget subcommand for the others
+                        for pair in budgets.iter().filter(|b| b.2 == BudgetPeriod::PayPeriod) {
                             bc.insert(pair.0.clone(), (pair.1 * 60.0 * 60.0, 0.0));
                         }
-                        Some(bc)
+                        Some(bc).filter(|bc| !bc.is_empty())
                     } else {
                         None
                     };
@@ -122,7 +180,7 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                 for e in events {
                     let seconds = e.duration(&now);
                     if let Some(bc) = &mut budget_counter {
-                        let d = e.start.date();
+                        let d = conf.logical_date(&e.start);
                         if d >= next_threshold.unwrap() {
                             // fresh budgets
                             for (_, tuple) in bc.iter_mut() {
@@ -137,26 +195,81 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                         }
                     }
                     seconds_worked += seconds;
-                    last_moment = e.end.clone();
+                    if e.vacation {
+                        seconds_vacation += seconds;
+                    } else {
+                        last_moment = e.end.clone();
+                    }
                 }
                 // now do the math
                 let seconds_required = hours_required * (60.0 * 60.0);
                 let delta = seconds_required - seconds_worked;
+                // whether the target is only met thanks to vacation
+                let met_with_vacation = delta <= 0.0
+                    && seconds_vacation > 0.0
+                    && seconds_worked - seconds_vacation < seconds_required;
+                if json {
+                    let completion_time = if delta > 0.0 {
+                        advance_by(&now, delta as i64)
+                    } else {
+                        advance_by(&last_moment.unwrap_or(now), delta as i64)
+                    };
+                    let budgets = budget_counter.map(|bc| {
+                        bc.into_iter()
+                            .map(|(tag, (budgeted, completed))| {
+                                (
+                                    tag,
+                                    json!({
+                                        "budgeted": budgeted / (60.0 * 60.0),
+                                        "completed": completed / (60.0 * 60.0),
+                                    }),
+                                )
+                            })
+                            .collect::<Map<String, Value>>()
+                    });
+                    println!(
+                        "{}",
+                        json!({
+                            "period": phrase,
+                            "start": format!("{}", start),
+                            "end": format!("{}", end),
+                            "hours-required": hours_required,
+                            "hours-worked": seconds_worked / (60.0 * 60.0),
+                            "hours-vacation": seconds_vacation / (60.0 * 60.0),
+                            "done": delta <= 0.0,
+                            "met-with-vacation": met_with_vacation,
+                            "completion-time": completion_time.format("%F %T").to_string(),
+                            "budgets": budgets,
+                        })
+                    );
+                    return;
+                }
                 let style = Style::new(&conf);
                 if delta > 0.0 {
-                    let completion_time = now + Duration::seconds(delta as i64);
+                    let completion_time = advance_by(&now, delta as i64);
                     let delta_hours = delta / (60.0 * 60.0);
                     println!(
                         "you will be finished at {}, {:.2} hours from now",
-                        style.paint("important", tell_time(&now, &completion_time)),
+                        style.paint("important", tell_time(&now, &completion_time, &conf)),
                         delta_hours
                     );
+                    if seconds_vacation > 0.0 {
+                        println!(
+                            "counting {} hours of vacation",
+                            duration_string(seconds_vacation, &conf)
+                        );
+                    }
+                } else if met_with_vacation {
+                    println!(
+                        "you have already met the target for {} with {} hours of vacation",
+                        phrase,
+                        duration_string(seconds_vacation, &conf)
+                    );
                 } else {
-                    let completion_time =
-                        last_moment.unwrap_or(now) + Duration::seconds(delta as i64);
+                    let completion_time = advance_by(&last_moment.unwrap_or(now), delta as i64);
                     println!(
                         "you were done at {}",
-                        style.paint("important", tell_time(&now, &completion_time))
+                        style.paint("important", tell_time(&now, &completion_time, &conf))
                     );
                 }
                 if let Some(bc) = budget_counter {
@@ -177,7 +290,12 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
                     table.columns[1].alignment(Alignment::Right).left_margin(2);
                     table.columns[2].alignment(Alignment::Right).left_margin(2);
                     println!("");
-                    for (offset, row) in table.macerate(&lines).expect("failed to macerate data").iter().enumerate() {
+                    for (offset, row) in table
+                        .macerate(&lines)
+                        .expect("failed to macerate data")
+                        .iter()
+                        .enumerate()
+                    {
                         if offset == 0 {
                             for line in row.iter() {
                                 for (margin, content) in line.iter() {
@@ -204,10 +322,203 @@ pub fn run(directory: Option<&str>, matches: &ArgMatches) {
     }
 }
 
-fn tell_time(now: &NaiveDateTime, then: &NaiveDateTime) -> String {
+fn tell_time(now: &NaiveDateTime, then: &NaiveDateTime, conf: &Configuration) -> String {
     if now.date() == then.date() {
         format!("{}", then.format("%l:%M:%S %p"))
     } else {
-        format!("{}", then.format("%l:%M:%S %p on %A, %e %B %Y"))
+        format_time(then, "%l:%M:%S %p on %A, %e %B %Y", conf)
+    }
+}
+
+// for each workday remaining this week, how much must be worked and when one may leave to
+// meet the week's expectation
+fn week(conf: &Configuration, json: bool) {
+    let (start, end) = match parse("this week", conf.logical_two_timer_config()) {
+        Ok((start, end, _)) => conf.shift_to_day_boundary(start, end),
+        Err(e) => {
+            fatal(e.msg(), conf);
+            unreachable!()
+        }
+    };
+    let now = current_time();
+    let today = conf.logical_date(&now);
+    let mut reader = LogController::new(None, conf).expect("could not read log");
+    let mut events = reader.events_in_range(&start, &now);
+    // so scheduled vacations can be sought to the end of the week without stretching the
+    // ongoing event to meet it
+    for e in events.iter_mut().filter(|e| e.end.is_none()) {
+        e.end = Some(now);
+    }
+    let events = Event::gather_by_day(events, &end, conf);
+    let events = VacationController::read(None, conf.directory()).add_vacation_times(
+        &start,
+        &end,
+        events,
+        conf,
+        Some(end),
+        &Filter::dummy(),
+    );
+    let mut days = vec![];
+    let mut date = conf.logical_date(&start);
+    while conf.start_of_day(&date) < end {
+        days.push(Day {
+            date,
+            expected: conf.day_length_on(&date) * (60.0 * 60.0),
+            worked: 0.0,
+            vacation: 0.0,
+        });
+        date += Duration::days(1);
+    }
+    for e in events.iter() {
+        let date = conf.logical_date(&e.start);
+        if let Some(day) = days.iter_mut().find(|d| d.date == date) {
+            if e.vacation {
+                day.vacation += e.duration(&now);
+            } else {
+                day.worked += e.duration(&now);
+            }
+        }
+    }
+    let required: f32 = days.iter().map(|d| d.expected).sum();
+    let worked: f32 = days.iter().map(|d| d.worked + d.vacation).sum();
+    // when one may leave on each day yet to be worked
+    let paces = pace(&days, today)
+        .into_iter()
+        .map(|(day, seconds)| {
+            let leave = if day.date == today {
+                advance_by(&now, seconds as i64)
+            } else {
+                let (hour, minute) = conf.beginning_work_day_on(&day.date);
+                let beginning = day.date.and_hms(hour as u32, minute as u32, 0);
+                advance_by(&beginning, (day.vacation + seconds) as i64)
+            };
+            (day.date, seconds, leave)
+        })
+        .collect::<Vec<_>>();
+    if json {
+        let days = paces
+            .iter()
+            .map(|(date, seconds, leave)| {
+                json!({
+                    "date": format!("{}", date),
+                    "hours": seconds / (60.0 * 60.0),
+                    "leave-at": leave.format("%F %T").to_string(),
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            json!({
+                "period": "this week",
+                "start": format!("{}", start),
+                "end": format!("{}", end),
+                "hours-required": required / (60.0 * 60.0),
+                "hours-worked": worked / (60.0 * 60.0),
+                "done": worked >= required,
+                "days": days,
+            })
+        );
+        return;
+    }
+    println!("when: this week");
+    if worked >= required {
+        println!("you have worked all the hours expected this week");
+        return;
+    }
+    if paces.is_empty() {
+        println!(
+            "no workdays remain this week; you are {} hours short",
+            duration_string(required - worked, conf)
+        );
+        return;
+    }
+    let style = Style::new(conf);
+    let mut data = vec![vec![
+        String::from("day"),
+        String::from("hours"),
+        String::from("leave at"),
+    ]];
+    for (date, seconds, leave) in paces.iter() {
+        data.push(vec![
+            date.format("%A %e %B").to_string(),
+            duration_string(*seconds, conf),
+            leave.format("%l:%M:%S %p").to_string(),
+        ]);
+    }
+    let mut table = Colonnade::new(3, conf.width()).expect("could not build the week table");
+    table.columns[1].alignment(Alignment::Right).left_margin(2);
+    table.columns[2].left_margin(2);
+    for (i, line) in table
+        .tabulate(&data)
+        .expect("could not tabulate data")
+        .iter()
+        .enumerate()
+    {
+        if i == 0 {
+            println!("{}", style.paint("header", line));
+        } else {
+            println!("{}", style.paint("important", line));
+        }
+    }
+}
+
+// a day of the week and the seconds expected, worked, and taken as vacation in it
+struct Day {
+    date: NaiveDate,
+    expected: f32,
+    worked: f32,
+    vacation: f32,
+}
+
+// the seconds yet to be worked on each workday from today on to meet the week's expectation;
+// what remains is spread over the days in proportion to the time not taken as vacation
+fn pace(days: &[Day], today: NaiveDate) -> Vec<(&Day, f32)> {
+    let required: f32 = days.iter().map(|d| d.expected).sum();
+    let done: f32 = days.iter().map(|d| d.worked + d.vacation).sum();
+    let owed = (required - done).max(0.0);
+    let remaining = days
+        .iter()
+        .filter(|d| d.date >= today)
+        .map(|d| (d, (d.expected - d.vacation).max(0.0)))
+        .filter(|(_, available)| *available > 0.0)
+        .collect::<Vec<_>>();
+    let available: f32 = remaining.iter().map(|(_, a)| a).sum();
+    remaining
+        .into_iter()
+        .map(|(d, a)| (d, owed * a / available))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pace() {
+        let hours = 60.0 * 60.0;
+        let monday = NaiveDate::from_ymd(2020, 3, 2);
+        let days = (0..7)
+            .map(|i| Day {
+                date: monday + Duration::days(i),
+                expected: if i < 5 { 8.0 * hours } else { 0.0 },
+                worked: if i < 2 { 7.0 * hours } else { 0.0 },
+                vacation: if i == 4 { 4.0 * hours } else { 0.0 },
+            })
+            .collect::<Vec<_>>();
+        let today = monday + Duration::days(2);
+        let paces = pace(&days, today)
+            .into_iter()
+            .map(|(d, s)| (d.date, s / hours))
+            .collect::<Vec<_>>();
+        // 40 hours less 14 worked and 4 of vacation leaves 22, spread over 8, 8, and 4 hours
+        assert_eq!(
+            vec![
+                (today, 8.8),
+                (today + Duration::days(1), 8.8),
+                (today + Duration::days(2), 4.4)
+            ],
+            paces
+        );
+        assert!(pace(&days, monday + Duration::days(5)).is_empty());
     }
 }
@@ -0,0 +1,162 @@
+extern crate chrono;
+extern crate clap;
+extern crate regex;
+
+use crate::configure::Configuration;
+use crate::log::{parse_timestamp, utc_offset};
+use crate::util::{base_dir, fatal, info, log_path, success, warn, yes_or_no};
+use chrono::Local;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+fn after_help() -> &'static str {
+    "\
+Ordinarily the timestamps in the log are naive local times. If you move between time zones \
+this will distort the durations of events that span a move. If you set the utc-offsets \
+configuration parameter, each new line of the log will record the UTC offset in force when it \
+was written:
+
+  2022  8  6 15  4  5 -0400:foo:an event with a UTC offset
+
+When the log is read such times are converted into the current local time zone.
+
+The zone subcommand without any options tells you the current UTC offset and how many lines in \
+the log have offsets. With --migrate it adds offsets to all the timestamped lines in the log \
+that lack them and turns on the utc-offsets configuration parameter. The original log is kept \
+in log.pre-zone.bak in the job log directory. By default the offset added \
+is the one your system's time zone would have had at that moment, but if the log was \
+written somewhere else you can provide a fixed offset with --offset.
+
+  > job zone --migrate --offset -0500
+
+All prefixes of 'zone' are aliases of the subcommand."
+}
+
+pub fn cli(mast: App<'static, 'static>, display_order: usize) -> App<'static, 'static> {
+    mast.subcommand(
+        SubCommand::with_name("zone")
+            .aliases(&["z", "zo", "zon"])
+            .about("Reports or migrates the UTC offsets recorded in the log")
+            .after_help(after_help())
+            .arg(
+                Arg::with_name("migrate")
+                .long("migrate")
+                .help("Adds UTC offsets to all timestamped lines lacking them")
+                .long_help("Rewrites the log, adding a UTC offset to every timestamp lacking one, and sets the utc-offsets configuration parameter so that subsequent lines also receive offsets.")
+                .display_order(0)
+            )
+            .arg(
+                Arg::with_name("offset")
+                .long("offset")
+                .help("Uses this offset rather than the local one when migrating")
+                .long_help("A fixed UTC offset in the form +hhmm or -hhmm to add to lines lacking an offset. By default the offset is the one the local time zone had at the moment in question.")
+                .validator(|v| if offset_rx().is_match(&v) {Ok(())} else {Err(format!("'{}' is not of the form +hhmm or -hhmm", v))})
+                .allow_hyphen_values(true)
+                .requires("migrate")
+                .value_name("offset")
+                .display_order(1)
+            )
+            .display_order(display_order),
+    )
+}
+
+fn offset_rx() -> Regex {
+    Regex::new(r"\A[+-]\d{4}\z").unwrap()
+}
+
+pub fn run(directory: Option<&str>, matches: &ArgMatches) {
+    lazy_static! {
+        // a timestamp with the rest of the line
+        static ref TIMESTAMP: Regex =
+            Regex::new(r"\A(\s*[1-9]\d{3}(?:\s+\d{1,2}){5})(\s+[+-]\d{4})?(:|<NOTE>)").unwrap();
+    }
+    let mut conf = Configuration::read(None, directory);
+    let log = log_path(conf.directory());
+    let reader = BufReader::new(File::open(&log).expect("could not open log file"));
+    if matches.is_present("migrate") {
+        let fixed_offset = matches.value_of("offset");
+        let mut path = base_dir(conf.directory());
+        path.push("log.tmp");
+        if path.as_path().exists()
+            && !yes_or_no(format!(
+                "the temporary log file {} already exists; overwrite?",
+                path.to_str().unwrap()
+            ))
+        {
+            fatal("could not migrate log", &conf);
+        }
+        let mut writer = BufWriter::new(File::create(&path).expect("could not create log.tmp"));
+        let mut changed = 0;
+        for line in reader.lines() {
+            let line = line.expect("could not read log line");
+            let migrated = match TIMESTAMP.captures(&line) {
+                Some(captures) if captures.get(2).is_none() => {
+                    let ts = captures.get(1).unwrap();
+                    match parse_timestamp(ts.as_str()) {
+                        Ok(time) => {
+                            changed += 1;
+                            let offset = fixed_offset
+                                .map(|s| s.to_owned())
+                                .unwrap_or_else(|| utc_offset(&time));
+                            format!("{} {}{}", ts.as_str(), offset, &line[ts.end()..])
+                        }
+                        // leave errors for the edit subcommand to sort out
+                        Err(_) => line,
+                    }
+                }
+                _ => line,
+            };
+            writeln!(writer, "{}", migrated).expect("could not write to log.tmp");
+        }
+        writer.flush().expect("could not flush log.tmp");
+        let mut backup = base_dir(conf.directory());
+        backup.push("log.pre-zone.bak");
+        info(
+            format!("copying the log to {}", backup.to_str().unwrap()),
+            &conf,
+        );
+        std::fs::copy(&log, &backup).expect("could not back up the log");
+        std::fs::rename(&path, &log).expect("failed to copy migrated log into place");
+        success(
+            format!(
+                "added UTC offsets to {} lines; your original log is in {}",
+                changed,
+                backup.to_str().unwrap()
+            ),
+            &conf,
+        );
+        if !conf.utc_offsets {
+            conf.utc_offsets = true;
+            conf.write();
+            success("setting utc-offsets to true!", &conf);
+        }
+    } else {
+        let mut with = 0;
+        let mut without = 0;
+        for line in reader.lines() {
+            let line = line.expect("could not read log line");
+            if let Some(captures) = TIMESTAMP.captures(&line) {
+                if captures.get(2).is_some() {
+                    with += 1;
+                } else {
+                    without += 1;
+                }
+            }
+        }
+        println!(
+            "current UTC offset: {}",
+            utc_offset(&Local::now().naive_local())
+        );
+        println!("utc-offsets: {}", conf.utc_offsets);
+        println!("timestamped lines with a UTC offset: {}", with);
+        println!("timestamped lines without a UTC offset: {}", without);
+        if without > 0 && conf.utc_offsets {
+            warn(
+                "some lines lack UTC offsets; consider running job zone --migrate",
+                &conf,
+            );
+        }
+    }
+}